@@ -0,0 +1,118 @@
+import test from 'ava'
+
+import { CanvasAnimation, createCanvas, Image } from '../index'
+
+function drawFrames(animation: CanvasAnimation) {
+  const canvas = createCanvas(4, 4)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 4, 4)
+  animation.addFrame(canvas)
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 4, 4)
+  animation.addFrame(ctx)
+}
+
+test('raw frames', async (t) => {
+  const animation = new CanvasAnimation(4, 4, 24)
+  drawFrames(animation)
+  t.is(animation.frameCount, 2)
+
+  const output = await animation.finalize('raw')
+  t.is(output.length, 2 * 4 * 4 * 4)
+  t.deepEqual([...output.subarray(0, 4)], [255, 0, 0, 255])
+  t.deepEqual([...output.subarray(64, 68)], [0, 0, 255, 255])
+  t.is(animation.frameCount, 0)
+})
+
+test('apng', async (t) => {
+  const animation = new CanvasAnimation(4, 4, 24)
+  drawFrames(animation)
+
+  const output = await animation.finalize('apng', { repeat: 3 })
+  const actl = output.indexOf('acTL')
+  t.true(actl > 0)
+  t.is(output.readUInt32BE(actl + 4), 2)
+  t.is(output.readUInt32BE(actl + 8), 3)
+  const fctl = output.indexOf('fcTL')
+  // delay 1/24s
+  t.is(output.readUInt16BE(fctl + 24), 1)
+  t.is(output.readUInt16BE(fctl + 26), 24)
+  t.true(output.includes('fdAT'))
+
+  // decoders without APNG support show the first frame
+  const image = new Image()
+  image.src = output
+  t.is(image.width, 4)
+  t.is(image.height, 4)
+})
+
+test('should reject the invalid frames', (t) => {
+  const animation = new CanvasAnimation(4, 4)
+  t.is(animation.fps, 30)
+  t.throws(() => animation.finalize('apng'), { code: 'InvalidArg' })
+  t.throws(() => animation.addFrame(createCanvas(8, 8)), { code: 'InvalidArg' })
+  drawFrames(animation)
+  // @ts-expect-error
+  t.throws(() => animation.finalize('gif'), { code: 'InvalidArg' })
+  t.throws(() => new CanvasAnimation(4, 4, 0), { code: 'InvalidArg' })
+})
+
+test('frame stream', async (t) => {
+  const canvas = createCanvas(4, 4)
+  const ctx = canvas.getContext('2d')
+  const stream = canvas.createFrameStream('raw')
+  const frames: Buffer[] = []
+  const consumed = (async () => {
+    for await (const frame of stream) {
+      frames.push(frame)
+    }
+  })()
+
+  for (const color of ['red', 'lime', 'blue']) {
+    ctx.fillStyle = color
+    ctx.fillRect(0, 0, 4, 4)
+    const committed = stream.commitFrame()
+    // the pixels are copied, drawing right after the commit doesn't change the frame
+    ctx.clearRect(0, 0, 4, 4)
+    await committed
+  }
+  await stream.end()
+  await consumed
+
+  t.is(frames.length, 3)
+  t.deepEqual([...frames[0].subarray(0, 4)], [255, 0, 0, 255])
+  t.deepEqual([...frames[1].subarray(0, 4)], [0, 255, 0, 255])
+  t.deepEqual([...frames[2].subarray(0, 4)], [0, 0, 255, 255])
+  await t.throwsAsync(() => stream.commitFrame())
+})
+
+test('frame stream backpressure', async (t) => {
+  const canvas = createCanvas(4, 4)
+  const stream = canvas.createFrameStream('png')
+  // nothing reads the stream, the second frame fills the buffer and waits for the consumer
+  await stream.commitFrame()
+  let resolved = false
+  const second = stream.commitFrame().then(() => {
+    resolved = true
+  })
+  await new Promise((resolve) => setTimeout(resolve, 50))
+  t.false(resolved)
+  t.true(stream.read().includes('IHDR'))
+  await second
+  t.true(resolved)
+})
+
+test('frame stream destroyed by the consumer', async (t) => {
+  const canvas = createCanvas(4, 4)
+  const stream = canvas.createFrameStream('raw')
+  const committed = Promise.all([stream.commitFrame(), stream.commitFrame(), stream.commitFrame(), stream.commitFrame()])
+  for await (const frame of stream) {
+    t.is(frame.length, 64)
+    break
+  }
+  t.true(stream.destroyed)
+  // the frames waiting for the consumer are dropped instead of hanging
+  await committed
+  await t.throwsAsync(() => stream.commitFrame())
+})
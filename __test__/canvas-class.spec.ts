@@ -5,3 +5,108 @@ import { createCanvas, Canvas } from '../index'
 test('Canvas constructor should be equal to createCanvas', (t) => {
   t.true(new Canvas(100, 100) instanceof createCanvas(100, 100).constructor)
 })
+
+test('setting the size should resize the canvas and reset the context', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.translate(10, 10)
+  ctx.fillRect(0, 0, 100, 100)
+
+  canvas.width = 200
+  t.is(canvas.width, 200)
+  t.is(canvas.height, 100)
+  t.is(canvas.getContext('2d'), ctx)
+  t.is(ctx.fillStyle, '#000000')
+  t.true(ctx.getTransform().isIdentity)
+  t.deepEqual([...ctx.getImageData(50, 50, 1, 1).data], [0, 0, 0, 0])
+  t.is(canvas.toBuffer('raw').length, 200 * 100 * 4)
+
+  ctx.fillRect(0, 0, 200, 100)
+  // the pixels are cleared even if the size is not changed
+  canvas.height = 100
+  t.deepEqual([...ctx.getImageData(150, 50, 1, 1).data], [0, 0, 0, 0])
+})
+
+test('resize should preserve the contents and the states', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 50, 50)
+  ctx.translate(10, 10)
+
+  canvas.resize(200, 200, { preserveContents: true })
+  t.is(canvas.width, 200)
+  t.is(canvas.height, 200)
+  t.is(ctx.fillStyle, '#ff0000')
+  t.is(ctx.getTransform().e, 10)
+  t.deepEqual([...ctx.getImageData(25, 25, 1, 1).data], [255, 0, 0, 255])
+  t.deepEqual([...ctx.getImageData(75, 75, 1, 1).data], [0, 0, 0, 0])
+
+  canvas.resize(100, 100, { preserveContents: true, scale: true })
+  t.deepEqual([...ctx.getImageData(10, 10, 1, 1).data], [255, 0, 0, 255])
+  t.deepEqual([...ctx.getImageData(40, 40, 1, 1).data], [0, 0, 0, 0])
+
+  canvas.resize(50, 50)
+  t.is(ctx.fillStyle, '#000000')
+  t.deepEqual([...ctx.getImageData(10, 10, 1, 1).data], [0, 0, 0, 0])
+})
+
+test('resize with preserveContents should keep the layer', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.saveLayer({ alpha: 0.5 })
+  canvas.resize(200, 200, { preserveContents: true })
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 200, 200)
+  ctx.restore()
+  // the red is composited with the alpha of the layer
+  t.deepEqual([...ctx.getImageData(150, 150, 1, 1).data], [255, 0, 0, 128])
+  const [r, g, b, a] = ctx.getImageData(50, 50, 1, 1).data
+  t.true(Math.abs(r - 128) <= 1 && g === 0 && Math.abs(b - 127) <= 1 && a === 255, `got rgba(${r}, ${g}, ${b}, ${a})`)
+})
+
+test('extract should copy the region to a new canvas', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(50, 50, 50, 50)
+  ctx.translate(10, 10)
+
+  const tile = canvas.extract(40, 40, 80, 80)
+  t.true(tile instanceof Canvas)
+  t.is(tile.width, 80)
+  t.is(tile.height, 80)
+  const tileCtx = tile.getContext('2d')
+  t.not(tileCtx, ctx)
+  t.true(tileCtx.getTransform().isIdentity)
+  t.deepEqual([...tileCtx.getImageData(5, 5, 1, 1).data], [0, 0, 0, 0])
+  t.deepEqual([...tileCtx.getImageData(15, 15, 1, 1).data], [255, 0, 0, 255])
+  // outside of the source canvas
+  t.deepEqual([...tileCtx.getImageData(70, 70, 1, 1).data], [0, 0, 0, 0])
+
+  tileCtx.fillStyle = 'blue'
+  tileCtx.fillRect(0, 0, 80, 80)
+  t.deepEqual([...ctx.getImageData(60, 60, 1, 1).data], [255, 0, 0, 255])
+})
+
+test('Canvas.from should copy the pixels to a new canvas', (t) => {
+  const pixels = Buffer.from([255, 0, 0, 128, 0, 0, 255, 255, 0, 255, 0, 255, 0, 0, 0, 0])
+  const canvas = Canvas.from(pixels, 2, 2)
+  t.is(canvas.width, 2)
+  t.is(canvas.height, 2)
+  const ctx = canvas.getContext('2d')
+  t.deepEqual([...ctx.getImageData(0, 0, 2, 2).data], [...pixels])
+
+  ctx.fillStyle = 'white'
+  ctx.fillRect(1, 1, 1, 1)
+  t.deepEqual([...ctx.getImageData(1, 1, 1, 1).data], [255, 255, 255, 255])
+  t.is(pixels[15], 0)
+
+  const bgra = Canvas.from(new Uint8Array([128, 0, 0, 128]), 1, 1, { colorType: 'bgra', premultiplied: true })
+  t.deepEqual([...bgra.getContext('2d').getImageData(0, 0, 1, 1).data], [0, 0, 255, 128])
+
+  t.throws(() => Canvas.from(pixels, 3, 2), { code: 'InvalidArg' })
+})
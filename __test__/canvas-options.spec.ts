@@ -0,0 +1,41 @@
+import test from 'ava'
+
+import { createCanvas, CanvasOptions } from '../index'
+
+function alphaValues(options: CanvasOptions) {
+  const canvas = createCanvas(100, 100, options)
+  const ctx = canvas.getContext('2d')
+  ctx.beginPath()
+  ctx.arc(50, 50, 40, 0, Math.PI * 2)
+  ctx.fill()
+  const { data } = ctx.getImageData(0, 0, 100, 100)
+  return new Set(data.filter((_, i) => i % 4 === 3))
+}
+
+test('should antialias the edges by default', (t) => {
+  t.true(alphaValues({}).size > 2)
+})
+
+test('should be able to turn off the antialiasing', (t) => {
+  t.deepEqual(alphaValues({ antialias: false }), new Set([0, 255]))
+})
+
+test('should not antialias the clip', (t) => {
+  const canvas = createCanvas(100, 100, { antialias: false })
+  const ctx = canvas.getContext('2d')
+  ctx.beginPath()
+  ctx.arc(50, 50, 40, 0, Math.PI * 2)
+  ctx.save()
+  ctx.clip()
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.restore()
+  const { data } = ctx.getImageData(0, 0, 100, 100)
+  t.deepEqual(new Set(data.filter((_, i) => i % 4 === 3)), new Set([0, 255]))
+})
+
+test('should accept the surface properties', (t) => {
+  t.notThrows(() => createCanvas(100, 100, { msaa: 4, pixelGeometry: 'rgb' }))
+  t.notThrows(() => createCanvas(100, 100, { gpu: true, msaa: 8, pixelGeometry: 'vbgr' }))
+  // @ts-expect-error
+  t.throws(() => createCanvas(100, 100, { pixelGeometry: 'rgb-h' }))
+})
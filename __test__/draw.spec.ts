@@ -1,5 +1,5 @@
 import { promises, readFileSync } from 'fs'
-import { platform } from 'os'
+import { platform, tmpdir } from 'os'
 import { join } from 'path'
 
 import ava, { TestFn } from 'ava'
@@ -373,6 +373,53 @@ test('draw-image-svg-noto-emoji', async (t) => {
   await snapshotImage(t)
 })
 
+const RECT_SVG = `<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="5" height="10" fill="#ff0000"/></svg>`
+
+function pixelAt(ctx: SKRSContext2D, x: number, y: number) {
+  return Array.from(ctx.getImageData(x, y, 1, 1).data)
+}
+
+test('drawSvg should render at the intrinsic size by default', (t) => {
+  const { ctx } = t.context
+  ctx.drawSvg(RECT_SVG, 100, 100)
+  t.deepEqual(pixelAt(ctx, 102, 105), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(ctx, 107, 105), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 102, 112), [0, 0, 0, 0])
+})
+
+test('drawSvg should scale the vectors to the destination size', (t) => {
+  const { ctx } = t.context
+  ctx.globalAlpha = 0.5
+  ctx.drawSvg(Buffer.from(RECT_SVG), 0, 0, 400, 200)
+  t.deepEqual(pixelAt(ctx, 190, 150), [255, 0, 0, 128])
+  t.deepEqual(pixelAt(ctx, 210, 150), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 190, 210), [0, 0, 0, 0])
+})
+
+test('drawSvg should apply the composite operation and the shadow', (t) => {
+  const { ctx } = t.context
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 512, 512)
+  ctx.globalCompositeOperation = 'destination-in'
+  ctx.drawSvg(RECT_SVG, 100, 100)
+  t.deepEqual(pixelAt(ctx, 102, 105), [0, 0, 255, 255])
+  t.deepEqual(pixelAt(ctx, 107, 105), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 50, 50), [0, 0, 0, 0])
+
+  ctx.globalCompositeOperation = 'source-over'
+  ctx.shadowColor = 'lime'
+  ctx.shadowOffsetX = 20
+  ctx.drawSvg(RECT_SVG, 300, 100)
+  t.deepEqual(pixelAt(ctx, 302, 105), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(ctx, 322, 105), [0, 255, 0, 255])
+})
+
+test('drawSvg should throw on the invalid SVG', (t) => {
+  const { ctx } = t.context
+  t.throws(() => ctx.drawSvg('<svg xmlns="http://www.w3.org/2000/svg"></svg>', 0, 0))
+  t.throws(() => ctx.drawSvg(RECT_SVG, 0, 0, 100))
+})
+
 test('drawImage-another-Canvas', async (t) => {
   const { ctx } = t.context
 
@@ -395,6 +442,75 @@ test('drawImage-another-Canvas', async (t) => {
   await snapshotImage(t)
 })
 
+test('drawAtlas', async (t) => {
+  const { ctx } = t.context
+  const atlas = createCanvas(40, 20)
+  const atlasContext = atlas.getContext('2d')
+  atlasContext.fillStyle = 'hotpink'
+  atlasContext.fillRect(0, 0, 20, 20)
+  atlasContext.fillStyle = 'white'
+  atlasContext.beginPath()
+  atlasContext.arc(30, 10, 10, 0, Math.PI * 2)
+  atlasContext.fill()
+  const count = 100
+  const xforms = new Float32Array(count * 4)
+  const srcRects = new Float32Array(count * 4)
+  const colors: string[] = []
+  for (let i = 0; i < count; i++) {
+    const angle = (i / count) * Math.PI * 2
+    xforms.set([Math.cos(angle), Math.sin(angle), 250 + Math.cos(angle) * 150, 250 + Math.sin(angle) * 150], i * 4)
+    srcRects.set([(i % 2) * 20, 0, 20, 20], i * 4)
+    colors.push(`hsl(${(i / count) * 360}, 80%, 60%)`)
+  }
+  ctx.drawAtlas(atlas, xforms, srcRects, colors)
+  t.throws(() => ctx.drawAtlas(atlas, xforms, srcRects.subarray(4)), {
+    message: 'xforms and srcRects must have 4 values for every sprite',
+  })
+  await snapshotImage(t)
+
+  const sprites = createCanvas(140, 50).getContext('2d')
+  sprites.drawAtlas(
+    atlas,
+    // unscaled, scaled by 2 and rotated by 90 degrees
+    new Float32Array([1, 0, 10, 10, 2, 0, 50, 10, 0, 1, 120, 10]),
+    new Float32Array([0, 0, 20, 20, 20, 0, 20, 20, 0, 0, 20, 20]),
+    new Uint32Array([0xffffffff, 0xffffffff, 0xff0000ff]),
+  )
+  t.deepEqual(pixelAt(sprites, 20, 20), [255, 105, 180, 255])
+  t.deepEqual(pixelAt(sprites, 35, 20), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(sprites, 70, 30), [255, 255, 255, 255])
+  t.deepEqual(pixelAt(sprites, 52, 12), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(sprites, 110, 20), [0, 0, 180, 255])
+  t.deepEqual(pixelAt(sprites, 125, 20), [0, 0, 0, 0])
+})
+
+test('drawImageNine', async (t) => {
+  const { ctx } = t.context
+  const bubble = createCanvas(60, 60)
+  const bubbleContext = bubble.getContext('2d')
+  bubbleContext.fillStyle = 'hotpink'
+  bubbleContext.beginPath()
+  bubbleContext.arc(20, 20, 20, Math.PI, Math.PI * 1.5)
+  bubbleContext.arc(40, 20, 20, Math.PI * 1.5, 0)
+  bubbleContext.arc(40, 40, 20, 0, Math.PI * 0.5)
+  bubbleContext.arc(20, 40, 20, Math.PI * 0.5, Math.PI)
+  bubbleContext.fill()
+  ctx.drawImageNine(bubble, { x: 20, y: 20, width: 20, height: 20 }, 50, 50, 400, 150)
+  t.throws(() => ctx.drawImageNine(bubble, { x: 20, y: 20, width: 50, height: 20 }, 0, 0, 100, 100), {
+    message: 'The center rect must be inside of the image',
+  })
+  await snapshotImage(t)
+  // the corners are not scaled
+  t.deepEqual(pixelAt(ctx, 55, 60), [255, 105, 180, 255])
+  t.deepEqual(pixelAt(ctx, 52, 52), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 445, 190), [255, 105, 180, 255])
+  t.deepEqual(pixelAt(ctx, 448, 198), [0, 0, 0, 0])
+  // the center is stretched to fill the rest
+  t.deepEqual(pixelAt(ctx, 100, 51), [255, 105, 180, 255])
+  t.deepEqual(pixelAt(ctx, 250, 125), [255, 105, 180, 255])
+  t.deepEqual(pixelAt(ctx, 250, 201), [0, 0, 0, 0])
+})
+
 test('ellipse', async (t) => {
   const { ctx } = t.context
   // Draw the ellipse
@@ -935,6 +1051,407 @@ test('raw output', async (t) => {
   t.deepEqual(output, pngOutput.data)
 })
 
+test('PNG compression level and filters', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const fast = canvas.toBuffer('image/png', { compressionLevel: 0, filters: ['none'] })
+  const small = await canvas.encode('png', { compressionLevel: 9 })
+  t.true(fast.length > small.length)
+  t.deepEqual(canvas.toBuffer('image/png', { compressionLevel: 6, filters: ['all'] }), canvas.toBuffer('image/png'))
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('image/png', { filters: ['median'] }), { code: 'InvalidArg' })
+})
+
+test('PNG density and text metadata', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const output = await canvas.encode('png', { density: 300, text: { Software: '@napi-rs/canvas' } })
+  // pHYs right after IHDR, 300 dpi = 11811 pixels per meter
+  t.is(output.toString('latin1', 37, 41), 'pHYs')
+  t.is(output.readUInt32BE(41), 11811)
+  t.is(output.readUInt32BE(45), 11811)
+  t.is(output[49], 1)
+  t.true(output.includes(Buffer.from('tEXtSoftware\0@napi-rs/canvas', 'latin1')))
+  t.deepEqual(canvas.toBuffer('image/png', { density: 300, text: { Software: '@napi-rs/canvas' } }), output)
+  t.throws(() => canvas.toBuffer('image/png', { text: { '': 'empty keyword' } }), { code: 'InvalidArg' })
+})
+
+test('indexed color PNG', async (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 256, 512)
+  ctx.fillStyle = 'rgba(0, 0, 255, 0.5)'
+  ctx.fillRect(256, 0, 128, 512)
+
+  const output = await canvas.encode('png', { colors: 16 })
+  // the 3 colors fit in 2 bits per pixel, color type 3
+  t.is(output[24], 2)
+  t.is(output[25], 3)
+  t.true(output.length < canvas.toBuffer('image/png').length)
+  const decoded = png.decoders['image/png'](output)
+  t.deepEqual([...decoded.data.subarray(0, 4)], [255, 0, 0, 255])
+  t.deepEqual([...decoded.data.subarray(300 * 4, 300 * 4 + 4)], [0, 0, 255, 128])
+  t.deepEqual([...decoded.data.subarray(400 * 4, 400 * 4 + 4)], [0, 0, 0, 0])
+  t.deepEqual(canvas.toBuffer('image/png', { colors: 16 }), output)
+
+  const gradient = ctx.createLinearGradient(0, 0, 512, 0)
+  gradient.addColorStop(0, 'black')
+  gradient.addColorStop(1, 'white')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 512, 512)
+  for (const dither of ['none', 'ordered', 'floyd-steinberg'] as const) {
+    const output = canvas.toBuffer('image/png', { colors: 8, dither })
+    t.is(output[24], 4)
+  }
+  t.notDeepEqual(
+    canvas.toBuffer('image/png', { colors: 8, dither: 'floyd-steinberg' }),
+    canvas.toBuffer('image/png', { colors: 8 }),
+  )
+  t.throws(() => canvas.toBuffer('image/png', { colors: 1 }), { code: 'InvalidArg' })
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('image/png', { colors: 8, dither: 'atkinson' }), { code: 'InvalidArg' })
+  t.throws(() => canvas.createPNGStream({ colors: 8 }), { code: 'InvalidArg' })
+})
+
+test('embed ICC profile', async (t) => {
+  const { canvas } = t.context
+  const ctx = canvas.getContext('2d', { colorSpace: 'display-p3' })
+  drawTranslate(ctx)
+
+  const pngOutput = await canvas.encode('png')
+  // iCCP right after IHDR, sRGB is removed
+  t.is(pngOutput.toString('latin1', 37, 41), 'iCCP')
+  t.false(pngOutput.includes('sRGB'))
+  t.true(canvas.toBuffer('image/jpeg').includes('ICC_PROFILE\0'))
+  const webpOutput = canvas.toBuffer('image/webp', { lossless: true })
+  t.is(webpOutput.toString('latin1', 12, 16), 'VP8X')
+  t.is(webpOutput.toString('latin1', 30, 34), 'ICCP')
+
+  const profile = Buffer.from('custom icc profile')
+  const jpegOutput = await canvas.encode('jpeg', { quality: 90, iccProfile: profile })
+  t.true(jpegOutput.includes(Buffer.concat([Buffer.from('ICC_PROFILE\0\x01\x01', 'latin1'), profile])))
+  t.throws(() => canvas.toBuffer('image/png', { iccProfile: Buffer.alloc(0) }), { code: 'InvalidArg' })
+
+  const srgb = createCanvas(16, 16)
+  srgb.getContext('2d')
+  t.false(srgb.toBuffer('image/png').includes('iCCP'))
+})
+
+test('draw in the display-p3 color space', (t) => {
+  const canvas = createCanvas(2, 1, { colorSpace: 'display-p3' })
+  const ctx = canvas.getContext('2d')
+  const near = (actual: Uint8ClampedArray, expected: number[]) =>
+    t.true(expected.every((value, i) => Math.abs(actual[i] - value) <= 1), `${[...actual]}`)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 1, 1)
+  // the sRGB red is converted into the P3 pixels
+  near(ctx.getImageData(0, 0, 1, 1).data, [234, 51, 35, 255])
+  near(ctx.getImageData(0, 0, 1, 1, { colorSpace: 'srgb' }).data, [255, 0, 0, 255])
+  t.is(ctx.createImageData(1, 1).colorSpace, 'display-p3')
+  const srgbRed = new ImageData(new Uint8ClampedArray([255, 0, 0, 255]), 1, 1, { colorSpace: 'srgb' })
+  ctx.putImageData(srgbRed, 1, 0)
+  near(ctx.getImageData(1, 0, 1, 1).data, [234, 51, 35, 255])
+  t.is(canvas.toBuffer('image/png').toString('latin1', 37, 41), 'iCCP')
+  // @ts-expect-error
+  t.throws(() => createCanvas(1, 1, { colorSpace: 'rec2020' }))
+})
+
+test('float16 canvas', (t) => {
+  const canvas = createCanvas(2, 1, { colorType: 'float16' })
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 1, 1)
+  const raw = canvas.toBuffer('raw', { colorType: 'rgba16f' })
+  t.is(raw.length, 2 * 8)
+  // 1.0 and 0.0 in half floats
+  t.deepEqual([0, 2, 4, 6].map((offset) => raw.readUInt16LE(offset)), [0x3c00, 0, 0, 0x3c00])
+  t.deepEqual([...canvas.toBuffer('raw').subarray(0, 4)], [255, 0, 0, 255])
+  // the bit depth in IHDR
+  t.is(canvas.toBuffer('image/png')[24], 16)
+  t.is(canvas.toBuffer('image/png', { bitDepth: 8 })[24], 8)
+  t.is(createCanvas(1, 1).toBuffer('image/png', { bitDepth: 16 })[24], 16)
+  t.throws(() => canvas.toBuffer('image/png', { bitDepth: 16, colors: 8 }), { code: 'InvalidArg' })
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('image/png', { bitDepth: 12 }), { code: 'InvalidArg' })
+  // @ts-expect-error
+  t.throws(() => createCanvas(1, 1, { colorType: 'float32' }), { code: 'InvalidArg' })
+})
+
+test('alpha8 mask canvas', (t) => {
+  const mask = createCanvas(20, 10, { colorType: 'alpha8' })
+  const maskCtx = mask.getContext('2d')
+  maskCtx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  maskCtx.fillRect(0, 0, 10, 10)
+  // only the alpha is kept
+  t.deepEqual([...maskCtx.getImageData(5, 5, 1, 1).data], [0, 0, 0, 128])
+  t.is(mask.data().length, 20 * 10)
+  const canvas = createCanvas(20, 10)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'blue'
+  ctx.drawImage(mask, 0, 0)
+  t.deepEqual([...ctx.getImageData(5, 5, 1, 1).data], [0, 0, 255, 128])
+  t.deepEqual([...ctx.getImageData(15, 5, 1, 1).data], [0, 0, 0, 0])
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 20, 10)
+  ctx.globalCompositeOperation = 'destination-in'
+  ctx.drawImage(mask, 0, 0)
+  t.deepEqual([...ctx.getImageData(5, 5, 1, 1).data], [255, 0, 0, 128])
+  t.deepEqual([...ctx.getImageData(15, 5, 1, 1).data], [0, 0, 0, 0])
+})
+
+test('raw output with color type', (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  ctx.fillRect(0, 0, 1, 1)
+
+  t.deepEqual([...canvas.toBuffer('raw').subarray(0, 4)], [255, 0, 0, 128])
+  t.deepEqual([...canvas.toBuffer('raw', { colorType: 'bgra' }).subarray(0, 4)], [0, 0, 255, 128])
+  t.deepEqual([...canvas.toBuffer('raw', { premultiplied: true }).subarray(0, 4)], [128, 0, 0, 128])
+  t.deepEqual(canvas.toBuffer('raw', { premultiplied: true }), canvas.data())
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('raw', { colorType: 'argb' }), { code: 'InvalidArg' })
+})
+
+test('getImageData with color type and premultiplied', (t) => {
+  const { ctx } = t.context
+  ctx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  ctx.fillRect(0, 0, 1, 1)
+
+  t.deepEqual([...ctx.getImageData(0, 0, 1, 1).data], [255, 0, 0, 128])
+  t.deepEqual([...ctx.getImageData(0, 0, 1, 1, { colorType: 'bgra' }).data], [0, 0, 255, 128])
+  t.deepEqual([...ctx.getImageData(0, 0, 1, 1, { premultiplied: true }).data], [128, 0, 0, 128])
+  t.deepEqual([...ctx.getImageData(0, 0, 1, 1, { colorType: 'bgra', premultiplied: true }).data], [0, 0, 128, 128])
+  // @ts-expect-error
+  t.throws(() => ctx.getImageData(0, 0, 1, 1, { colorType: 'argb' }), { code: 'InvalidArg' })
+})
+
+test('encode should accept mime types and raw', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  t.deepEqual(await canvas.encode('image/png'), canvas.toBuffer('image/png'))
+  t.deepEqual(await canvas.encode('image/jpeg', 80), canvas.toBuffer('image/jpeg', 80))
+  t.deepEqual(canvas.encodeSync('image/webp', 80), await canvas.encode('webp', 80))
+  t.deepEqual(await canvas.encode('raw', { colorType: 'bgra' }), canvas.toBuffer('raw', { colorType: 'bgra' }))
+})
+
+test('createPNGStream', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const chunks: Buffer[] = []
+  for await (const chunk of canvas.createPNGStream({ density: 144 })) {
+    chunks.push(chunk)
+  }
+  t.true(chunks.length > 1)
+  t.deepEqual(Buffer.concat(chunks), await canvas.encode('png', { density: 144 }))
+})
+
+test('createPNGStream of a flat color canvas', async (t) => {
+  // zlib compresses many rows into nothing, the stream must not stall on them
+  const canvas = createCanvas(2048, 2048)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 2048, 2048)
+
+  const chunks: Buffer[] = []
+  for await (const chunk of canvas.createPNGStream()) {
+    t.true(chunk.length > 0)
+    chunks.push(chunk)
+  }
+  t.deepEqual(Buffer.concat(chunks), await canvas.encode('png'))
+})
+
+test('createJPEGStream', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const readAll = async (stream: NodeJS.ReadableStream) => {
+    const chunks: Buffer[] = []
+    for await (const chunk of stream) {
+      chunks.push(chunk as Buffer)
+    }
+    return Buffer.concat(chunks)
+  }
+  const output = await readAll(canvas.createJPEGStream({ quality: 80 }))
+  t.deepEqual(output, await canvas.encode('jpeg', 80))
+  t.notDeepEqual(await readAll(canvas.createJPEGStream({ quality: 80, chromaSubsampling: false })), output)
+})
+
+test('createJPEGStream of a flat color canvas', async (t) => {
+  const canvas = createCanvas(2048, 2048)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 2048, 2048)
+
+  for (const chromaSubsampling of [true, false]) {
+    const chunks: Buffer[] = []
+    for await (const chunk of canvas.createJPEGStream({ chromaSubsampling })) {
+      t.true(chunk.length > 0)
+      chunks.push(chunk)
+    }
+    t.deepEqual(Buffer.concat(chunks), canvas.toBuffer('image/jpeg', { chromaSubsampling }))
+  }
+})
+
+test('JPEG background matte', async (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  ctx.fillRect(0, 0, 256, 512)
+
+  const decode = (data: Buffer) => {
+    const image = new Image()
+    image.src = data
+    const output = createCanvas(image.width, image.height)
+    const outputCtx = output.getContext('2d')
+    outputCtx.drawImage(image, 0, 0)
+    return outputCtx.getImageData(0, 0, image.width, image.height).data
+  }
+  const pixelAt = (data: Uint8ClampedArray, x: number, y: number) =>
+    [...data.subarray((y * 512 + x) * 4, (y * 512 + x) * 4 + 4)]
+  const closeTo = (actual: number[], expected: number[]) =>
+    actual.every((value, i) => Math.abs(value - expected[i]) <= 4)
+
+  const output = await canvas.encode('jpeg', { quality: 100, background: '#fff' })
+  const pixels = decode(output)
+  t.true(closeTo(pixelAt(pixels, 128, 256), [255, 128, 128, 255]))
+  t.true(closeTo(pixelAt(pixels, 384, 256), [255, 255, 255, 255]))
+  t.deepEqual(canvas.toBuffer('image/jpeg', { quality: 100, background: 'white' }), output)
+  t.true(closeTo(pixelAt(decode(canvas.toBuffer('image/jpeg', { quality: 100 })), 384, 256), [0, 0, 0, 255]))
+  t.throws(() => canvas.toBuffer('image/jpeg', { background: 'not a color' }), { code: 'InvalidArg' })
+})
+
+test('JPEG EXIF and density', async (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 512, 512)
+  const config = { density: 300, orientation: 6, software: 'canvas', copyright: 'CC0' }
+
+  const output = await canvas.encode('jpeg', config)
+  const jfif = output.indexOf('JFIF\0')
+  // dots per inch
+  t.is(output[jfif + 7], 1)
+  t.is(output.readUInt16BE(jfif + 8), 300)
+  t.is(output.readUInt16BE(jfif + 10), 300)
+  const exif = output.indexOf('Exif\0\0')
+  t.is(output.readUInt16BE(exif - 4), 0xffe1)
+  t.true(output.includes('canvas\0'))
+  t.true(output.includes('CC0\0'))
+  t.deepEqual(canvas.toBuffer('image/jpeg', config), output)
+
+  const image = new Image()
+  image.src = output
+  t.is(image.width, 512)
+  t.throws(() => canvas.encode('jpeg', { orientation: 9 }), { code: 'InvalidArg' })
+  t.throws(() => canvas.toBuffer('image/jpeg', { density: 0 }), { code: 'InvalidArg' })
+  t.throws(() => canvas.createJPEGStream({ density: Infinity }), { code: 'InvalidArg' })
+})
+
+test('convertToBlob', async (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 512, 512)
+
+  const blob = await canvas.convertToBlob()
+  t.is(blob.type, 'image/png')
+  t.deepEqual(Buffer.from(await blob.arrayBuffer()), await canvas.encode('png'))
+
+  const jpeg = await canvas.convertToBlob({ type: 'image/jpeg', quality: 0.5 })
+  t.is(jpeg.type, 'image/jpeg')
+  t.is(jpeg.size, (await canvas.encode('jpeg', 50)).length)
+
+  // unsupported types fall back to PNG
+  const fallback = await canvas.convertToBlob({ type: 'image/gif' })
+  t.is(fallback.type, 'image/png')
+  t.is(fallback.size, blob.size)
+})
+
+test('blurhash and thumbhash', (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 512, 512)
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(256, 0, 256, 512)
+
+  const blurhash = canvas.blurhash()
+  // 4x3 components
+  t.is(blurhash.length, 6 + 11 * 2)
+  t.is(blurhash[0], 'L')
+  t.is(canvas.blurhash(1, 1).length, 6)
+  t.throws(() => canvas.blurhash(10, 3), { code: 'InvalidArg' })
+
+  const thumbhash = canvas.thumbhash()
+  t.true(thumbhash.length <= 25)
+  t.deepEqual(canvas.thumbhash(), thumbhash)
+})
+
+test('toYUV', (t) => {
+  const canvas = createCanvas(3, 2)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 3, 2)
+
+  const i420 = canvas.toYUV()
+  // 3x2 luma and 2x1 chroma planes
+  t.is(i420.length, 6 + 2 + 2)
+  t.deepEqual([...i420.subarray(0, 6)], [81, 81, 81, 81, 81, 81])
+  t.deepEqual([...i420.subarray(6)], [90, 90, 240, 240])
+  const nv12 = canvas.toYUV({ format: 'nv12' })
+  t.deepEqual([...nv12.subarray(6)], [90, 240, 90, 240])
+  t.deepEqual([...canvas.toYUV({ matrix: 'bt709', fullRange: true }).subarray(0, 1)], [54])
+  // @ts-expect-error
+  t.throws(() => canvas.toYUV({ format: 'yv12' }), { code: 'InvalidArg' })
+})
+
+test('resize at export', async (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 512, 256)
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 256, 512, 256)
+
+  const decode = (data: Buffer) => {
+    const image = new Image()
+    image.src = data
+    return image
+  }
+  const thumbnail = decode(await canvas.encode('png', { width: 64, height: 32 }))
+  t.is(thumbnail.width, 64)
+  t.is(thumbnail.height, 32)
+  const half = decode(canvas.toBuffer('image/png', { width: 256 }))
+  t.is(half.width, 256)
+  t.is(half.height, 256)
+  t.is(decode(canvas.toBuffer('image/jpeg', { quality: 90, height: 100 })).width, 100)
+
+  const raw = canvas.toBuffer('raw', { width: 2, height: 2 })
+  t.deepEqual([...raw], [255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255])
+  t.deepEqual(await canvas.encode('raw', { width: 2, height: 2 }), raw)
+  // letterboxed with transparent pixels
+  const contain = canvas.toBuffer('raw', { width: 4, height: 2, fit: 'contain' })
+  t.deepEqual([...contain.subarray(0, 16)], [0, 0, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 0, 0])
+  t.deepEqual(canvas.toBuffer('raw', { width: 512, height: 512 }), canvas.toBuffer('raw'))
+  t.throws(() => canvas.toBuffer('image/png', { width: 0 }), { code: 'InvalidArg' })
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('image/png', { width: 10, fit: 'stretch' }), { code: 'InvalidArg' })
+})
+
+test('saveAs', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const dir = await promises.mkdtemp(join(tmpdir(), 'canvas-'))
+  await canvas.saveAs(join(dir, 'output.png'))
+  t.deepEqual(await promises.readFile(join(dir, 'output.png')), await canvas.encode('png'))
+  await canvas.saveAs(join(dir, 'output.jpg'), { quality: 80 })
+  t.deepEqual(await promises.readFile(join(dir, 'output.jpg')), await canvas.encode('jpeg', 80))
+  await canvas.saveAs(join(dir, 'output'), { format: 'webp', lossless: true })
+  t.deepEqual(await promises.readFile(join(dir, 'output')), await canvas.encode('webp', { lossless: true }))
+  t.throws(() => canvas.saveAs(join(dir, 'output')), { code: 'InvalidArg' })
+  await promises.rm(dir, { recursive: true })
+})
+
 test('toDataURL', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -947,6 +1464,24 @@ test('toDataURL', async (t) => {
   t.deepEqual(pngBuffer, await canvas.encode('png'))
 })
 
+test('toDataURL should fallback to PNG for unsupported types', (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  // @ts-expect-error
+  t.is(canvas.toDataURL('image/bmp'), canvas.toDataURL('image/png'))
+  // @ts-expect-error
+  t.true(canvas.toDataURL('IMAGE/JPEG').startsWith('data:image/jpeg;base64,'))
+})
+
+test('toDataURL should ignore the quality out of range', (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  t.is(canvas.toDataURL('image/jpeg', 2), canvas.toDataURL('image/jpeg'))
+  t.is(canvas.toDataURL('image/webp', -1), canvas.toDataURL('image/webp'))
+})
+
 test('JPEG toDataURL with quality', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -959,6 +1494,14 @@ test('JPEG toDataURL with quality', async (t) => {
   t.deepEqual(pngBuffer, await canvas.encode('jpeg', 20))
 })
 
+test('JPEG quality should be clamped to 100', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  t.deepEqual(canvas.toBuffer('image/jpeg', 300), await canvas.encode('jpeg', 100))
+  t.deepEqual(canvas.encodeSync('jpeg', 300), await canvas.encode('jpeg', 100))
+})
+
 test('WebP toDataURL with quality', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -971,6 +1514,22 @@ test('WebP toDataURL with quality', async (t) => {
   t.deepEqual(webpBuffer, await canvas.encode('webp', 100))
 })
 
+test('WebP lossless encoding', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  const lossless = await canvas.encode('webp', { lossless: true })
+  t.deepEqual(canvas.toBuffer('image/webp', { lossless: true }), lossless)
+  t.notDeepEqual(lossless, await canvas.encode('webp', 100))
+})
+
+test('WebP quality in config', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+
+  t.deepEqual(await canvas.encode('webp', { quality: 50 }), await canvas.encode('webp', 50))
+})
+
 test('toDataURLAsync', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -992,6 +1551,14 @@ test('shadowOffsetX', async (t) => {
   ctx.fillStyle = 'blue'
   ctx.fillRect(20, 20, 150, 100)
   await snapshotImage(t)
+  // the shadow is offset to 45 ~ 195, blurred by the sigma of shadowBlur / 2
+  t.deepEqual(pixelAt(ctx, 100, 70), [0, 0, 255, 255])
+  t.deepEqual(pixelAt(ctx, 175, 70), [255, 0, 0, 255])
+  const [r, g, b, a] = pixelAt(ctx, 195, 70)
+  t.deepEqual([r, g, b], [255, 0, 0])
+  t.true(a > 90 && a < 150, `the alpha of the blurred edge is ${a}`)
+  t.deepEqual(pixelAt(ctx, 250, 70), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 10, 70), [0, 0, 0, 0])
 })
 
 test('should not throw while fill/stroke style is invalid', (t) => {
@@ -1018,6 +1585,14 @@ test('shadowOffsetY', async (t) => {
   ctx.fillStyle = 'blue'
   ctx.fillRect(20, 20, 150, 80)
   await snapshotImage(t)
+  // the shadow is offset to 45 ~ 125, blurred by the sigma of shadowBlur / 2
+  t.deepEqual(pixelAt(ctx, 95, 60), [0, 0, 255, 255])
+  t.deepEqual(pixelAt(ctx, 95, 105), [255, 0, 0, 255])
+  const [r, g, b, a] = pixelAt(ctx, 95, 125)
+  t.deepEqual([r, g, b], [255, 0, 0])
+  t.true(a > 90 && a < 150, `the alpha of the blurred edge is ${a}`)
+  t.deepEqual(pixelAt(ctx, 95, 180), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(ctx, 95, 10), [0, 0, 0, 0])
 })
 
 function drawTranslate(ctx: SKRSContext2D) {
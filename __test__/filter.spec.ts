@@ -3,7 +3,7 @@ import { join } from 'path'
 
 import ava, { TestFn } from 'ava'
 
-import { createCanvas, Canvas, SKRSContext2D, Image } from '../index'
+import { createCanvas, Canvas, ColorFilter, ImageFilter, SKRSContext2D, Image } from '../index'
 
 import { snapshotImage } from './image-snapshot'
 
@@ -120,6 +120,162 @@ test('filter-save-restore', async (t) => {
   await snapshotImage(t)
 })
 
+test('filter-drop-shadow-chain', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = 'white'
+  // the shadow is inverted with the image, unlike the shadow of the shadow properties
+  ctx.filter = 'drop-shadow(rgb(255, 0, 0) 20px 0) invert(100%)'
+  ctx.fillRect(0, 0, 20, 20)
+  t.deepEqual(pixel(10, 10), [0, 0, 0, 255])
+  t.deepEqual(pixel(30, 10), [0, 255, 255, 255])
+  // the invisible shadow doesn't drop the other filters
+  ctx.filter = 'invert(100%) drop-shadow(0 0 transparent)'
+  ctx.fillRect(100, 0, 20, 20)
+  t.deepEqual(pixel(110, 10), [0, 0, 0, 255])
+})
+
+test('filter-invalid-value', (t) => {
+  const { ctx } = t.context
+  ctx.filter = 'invert(100%)'
+  // the invalid values are ignored, the current filter is kept
+  for (const filter of ['invert(100%) foo', 'blur(5px', 'foo', '']) {
+    ctx.filter = filter
+    t.is(ctx.filter, 'invert(100%)')
+  }
+  ctx.fillStyle = 'white'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 255])
+  ctx.filter = 'none'
+  t.is(ctx.filter, 'none')
+})
+
+test('color-filter', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  t.is(ctx.colorFilter, null)
+  // the red goes to the blue
+  // prettier-ignore
+  const swap = ColorFilter.matrix([
+    0, 0, 0, 0, 0,
+    0, 1, 0, 0, 0,
+    1, 0, 0, 0, 0,
+    0, 0, 0, 1, 0,
+  ])
+  ctx.colorFilter = swap
+  t.true(ctx.colorFilter instanceof ColorFilter)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixel(5, 5), [0, 0, 255, 255])
+  ctx.save()
+  ctx.colorFilter = ColorFilter.blend('lime', 'source-in')
+  ctx.fillRect(10, 0, 10, 10)
+  t.deepEqual(pixel(15, 5), [0, 255, 0, 255])
+  ctx.restore()
+  ctx.fillRect(20, 0, 10, 10)
+  t.deepEqual(pixel(25, 5), [0, 0, 255, 255])
+  ctx.colorFilter = null
+  ctx.fillStyle = 'white'
+  ctx.colorFilter = ColorFilter.lighting('red', 'blue')
+  ctx.fillRect(30, 0, 10, 10)
+  t.deepEqual(pixel(35, 5), [255, 0, 255, 255])
+  // the red is dropped after the swap, the other way around is black
+  // prettier-ignore
+  const dropRed = ColorFilter.matrix([
+    0, 0, 0, 0, 0,
+    0, 1, 0, 0, 0,
+    0, 0, 1, 0, 0,
+    0, 0, 0, 1, 0,
+  ])
+  ctx.colorFilter = dropRed.compose(swap)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(40, 0, 10, 10)
+  t.deepEqual(pixel(45, 5), [0, 0, 255, 255])
+  ctx.colorFilter = ColorFilter.luma()
+  ctx.fillStyle = 'white'
+  ctx.fillRect(50, 0, 10, 10)
+  t.deepEqual(pixel(55, 5), [0, 0, 0, 255])
+  t.throws(() => ColorFilter.matrix([1, 0, 0, 0]))
+  t.throws(() => ColorFilter.blend('lime', 'foo'))
+  t.throws(() => ColorFilter.lighting('foo', 'red'))
+})
+
+test('color-filter-save-layer', (t) => {
+  const { ctx } = t.context
+  ctx.fillStyle = 'red'
+  ctx.saveLayer({ colorFilter: ColorFilter.blend('blue', 'source-in') })
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [255, 0, 0, 255])
+  ctx.restore()
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 255, 255])
+})
+
+test('apply-filter', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(100, 0, 100, 100)
+  // the rect is not transformed
+  ctx.translate(100, 0)
+  ctx.applyFilter('invert(100%)', 0, 0, 50, 100)
+  t.deepEqual(pixel(25, 50), [0, 255, 255, 255])
+  t.deepEqual(pixel(75, 50), [255, 0, 0, 255])
+  t.deepEqual(pixel(125, 50), [0, 0, 255, 255])
+  ctx.applyFilter(ColorFilter.blend('lime', 'source-in'), 200, 100, -50, -100)
+  t.deepEqual(pixel(175, 50), [0, 255, 0, 255])
+  t.deepEqual(pixel(125, 50), [0, 0, 255, 255])
+  // the blur samples the pixels outside of the rect
+  ctx.applyFilter('blur(10px)', 90, 0, 20, 100)
+  const [red, , blue] = pixel(99, 50)
+  t.true(red > 0 && blue > 0)
+  t.deepEqual(pixel(85, 50), [255, 0, 0, 255])
+  t.throws(() => ctx.applyFilter('foo', 0, 0, 10, 10))
+})
+
+test('image-filter', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = 'red'
+  const moved = ImageFilter.offset(20, 0)
+  ctx.filter = moved
+  t.true(ctx.filter instanceof ImageFilter)
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixel(5, 5), [0, 0, 0, 0])
+  t.deepEqual(pixel(25, 5), [255, 0, 0, 255])
+  ctx.save()
+  // the input pixels and the moved ones
+  ctx.filter = ImageFilter.merge([null, moved])
+  ctx.fillRect(0, 20, 10, 10)
+  t.deepEqual(pixel(5, 25), [255, 0, 0, 255])
+  t.deepEqual(pixel(25, 25), [255, 0, 0, 255])
+  ctx.filter = 'none'
+  ctx.restore()
+  t.true(ctx.filter instanceof ImageFilter)
+  ctx.filter = ImageFilter.compose(ImageFilter.colorFilter(ColorFilter.blend('lime', 'source-in')), moved)
+  ctx.fillRect(0, 40, 10, 10)
+  t.deepEqual(pixel(25, 45), [0, 255, 0, 255])
+  ctx.filter = ImageFilter.dropShadow(20, 0, 0, 0, 'blue')
+  ctx.fillRect(0, 60, 10, 10)
+  t.deepEqual(pixel(5, 65), [255, 0, 0, 255])
+  t.deepEqual(pixel(25, 65), [0, 0, 255, 255])
+  ctx.filter = 'blur(2px)'
+  t.is(ctx.filter, 'blur(2px)')
+  ctx.filter = ImageFilter.displacementMap('r', 'g', 10, ImageFilter.blur(2, 2))
+  t.throws(() => ImageFilter.displacementMap('x' as any, 'g', 10, moved))
+})
+
+test('image-filter-save-layer', (t) => {
+  const { ctx } = t.context
+  ctx.fillStyle = 'red'
+  ctx.saveLayer({ filter: ImageFilter.offset(20, 0) })
+  ctx.fillRect(0, 0, 10, 10)
+  ctx.restore()
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 0])
+  t.deepEqual(Array.from(ctx.getImageData(25, 5, 1, 1).data), [255, 0, 0, 255])
+})
+
 async function createImage(name: string) {
   const i = new Image()
   i.src = await fs.readFile(join(__dirname, 'fixtures', name))
@@ -0,0 +1,222 @@
+import test from 'ava'
+
+import { createCanvas, ExternalTexture, GpuContext } from '../index'
+
+// the canvas falls back to the CPU if Skia is built without the GPU backend or there is no GPU
+test('should draw on the GPU canvas', (t) => {
+  const canvas = createCanvas(100, 100, { gpu: true })
+  t.is(typeof canvas.gpu, 'boolean')
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 50, 50)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [255, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 75, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should encode the GPU canvas', async (t) => {
+  const canvas = createCanvas(100, 100, { gpu: true })
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#00ff00'
+  ctx.fillRect(0, 0, 100, 100)
+  const png = await canvas.encode('png')
+  // the draws after `encode` is called are not encoded
+  ctx.fillStyle = '#0000ff'
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(png.subarray(0, 4), Buffer.from([0x89, 0x50, 0x4e, 0x47]))
+  t.deepEqual(canvas.encodeSync('png').subarray(0, 4), Buffer.from([0x89, 0x50, 0x4e, 0x47]))
+})
+
+test('should stream the GPU canvas', async (t) => {
+  const canvas = createCanvas(512, 512, { gpu: true })
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#00ff00'
+  ctx.fillRect(0, 0, 256, 512)
+  const readAll = async (stream: NodeJS.ReadableStream) => {
+    const chunks: Buffer[] = []
+    for await (const chunk of stream) {
+      chunks.push(chunk as Buffer)
+    }
+    return Buffer.concat(chunks)
+  }
+  t.deepEqual(await readAll(canvas.createPNGStream()), await canvas.encode('png'))
+  t.deepEqual(
+    await readAll(canvas.createJPEGStream({ background: 'white' })),
+    canvas.toBuffer('image/jpeg', { background: 'white' }),
+  )
+})
+
+test('should select the GPU backend', (t) => {
+  for (const gpu of ['auto', 'gl', 'metal', 'vulkan'] as const) {
+    const canvas = createCanvas(100, 100, { gpu })
+    const ctx = canvas.getContext('2d')
+    ctx.fillStyle = '#0000ff'
+    ctx.fillRect(0, 0, 100, 100)
+    t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 255, 255])
+  }
+  // @ts-expect-error
+  t.throws(() => createCanvas(100, 100, { gpu: 'directx' }))
+})
+
+test('should select the Vulkan device', (t) => {
+  for (const device of [0, '8d0c2c0a-7e1f-4b2a-9b51-116d2fe80ac3']) {
+    const canvas = createCanvas(100, 100, { gpu: 'vulkan', device })
+    const ctx = canvas.getContext('2d')
+    ctx.fillStyle = '#0000ff'
+    ctx.fillRect(0, 0, 100, 100)
+    t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 255, 255])
+  }
+  t.throws(() => createCanvas(100, 100, { gpu: 'vulkan', device: 'not a uuid' }))
+  t.throws(() => createCanvas(100, 100, { gpu: 'gl', device: 0 }))
+})
+
+test('should not use the GPU by default', (t) => {
+  t.false(createCanvas(100, 100).gpu)
+  t.false(createCanvas(100, 100, { gpu: false }).gpu)
+})
+
+test('should share the GPU context between the canvases', (t) => {
+  let context: GpuContext
+  try {
+    context = new GpuContext({ gpu: true })
+  } catch {
+    // there is no GPU
+    t.false(createCanvas(100, 100, { gpu: true }).gpu)
+    return
+  }
+  const tiles = Array.from({ length: 4 }, () => createCanvas(256, 256, { gpu: true }))
+  for (const tile of tiles) {
+    tile.getContext('2d').fillRect(0, 0, 256, 256)
+  }
+  t.true(context.resourceCacheUsage > 0)
+  context.resourceCacheLimit = 64 * 1024 * 1024
+  t.is(context.resourceCacheLimit, 64 * 1024 * 1024)
+  context.freeResources()
+  // the canvases keep their pixels
+  t.deepEqual(Array.from(tiles[0].getContext('2d').getImageData(0, 0, 1, 1).data), [0, 0, 0, 255])
+})
+
+test('should read the pixels back asynchronously', async (t) => {
+  for (const gpu of [false, true]) {
+    const canvas = createCanvas(100, 100, { gpu })
+    const ctx = canvas.getContext('2d')
+    ctx.fillStyle = '#ff0000'
+    ctx.fillRect(0, 0, 50, 50)
+    const pending = ctx.getImageDataAsync(0, 0, 100, 100)
+    // the pixels are the ones at the time of the call
+    ctx.fillStyle = '#0000ff'
+    ctx.fillRect(0, 0, 100, 100)
+    const imageData = await pending
+    t.is(imageData.width, 100)
+    t.deepEqual(Array.from(imageData.data.subarray(0, 4)), [255, 0, 0, 255])
+    t.deepEqual(Array.from(imageData.data.subarray(-4)), [0, 0, 0, 0])
+    // the rect outside of the canvas is transparent, like `getImageData`
+    const outside = await ctx.getImageDataAsync(90, 90, 20, 20)
+    t.deepEqual(outside.data, ctx.getImageData(90, 90, 20, 20).data)
+    const png = await canvas.encodeAsync('png')
+    t.deepEqual(png.subarray(0, 4), Buffer.from([0x89, 0x50, 0x4e, 0x47]))
+  }
+})
+
+test('should not wait for the readback taken already', (t) => {
+  for (const gpu of [false, true]) {
+    // the readback behind `getImageDataAsync`
+    const ctx = createCanvas(100, 100, { gpu }).getContext('2d') as any
+    const readback = ctx.readPixelsAsync(0, 0, 100, 100)
+    while (!readback.poll()) {}
+    t.is(readback.takeImageData().width, 100)
+    t.true(readback.poll())
+    t.throws(() => readback.takeImageData(), { message: 'Read pixels from canvas failed' })
+  }
+})
+
+test('should share the texture of the GPU canvas', (t) => {
+  t.throws(() => createCanvas(100, 100).exportTexture())
+  let texture: ExternalTexture
+  try {
+    texture = createCanvas(100, 100, { gpu: true }).exportTexture()
+  } catch {
+    // there is no GPU, or the driver can't export the texture
+    t.pass()
+    return
+  }
+  const canvas = createCanvas(100, 100, { texture })
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 100, 100)
+  t.true(canvas.gpu)
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [255, 0, 0, 255])
+  // the canvas can't be detached from the texture
+  t.throws(() => {
+    canvas.width = 200
+  })
+  t.throws(() => canvas.resize(200, 200, { preserveContents: true }))
+  t.is(canvas.width, 100)
+  // setting the same size clears it
+  canvas.width = 100
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 0])
+  canvas.getContext('2d', { willReadFrequently: true })
+  t.true(canvas.gpu)
+})
+
+test('should validate the external texture', (t) => {
+  // @ts-expect-error
+  t.throws(() => createCanvas(100, 100, { texture: { type: 'dmabuf', fd: 0 } }))
+  t.throws(() => createCanvas(100, 100, { texture: { type: 'dmabuf', fd: 0, stride: 400, fourcc: 'RGBA8' } }))
+  t.throws(() => createCanvas(100, 100, { gpu: 'metal', texture: { type: 'dmabuf', fd: 0, stride: 400 } }))
+  t.throws(() => createCanvas(100, 100, { texture: { type: 'iosurface', ioSurface: 0 } }))
+})
+
+test('should move the GPU canvas to the CPU if it will read frequently', (t) => {
+  const canvas = createCanvas(100, 100, { gpu: true })
+  const ctx = canvas.getContext('2d', { willReadFrequently: true })
+  t.false(canvas.gpu)
+  t.true(ctx.getContextAttributes().willReadFrequently)
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 50, 50)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [255, 0, 0, 255])
+  // the later calls don't move it back
+  canvas.getContext('2d')
+  t.false(canvas.gpu)
+})
+
+test('should keep the pixels and the transform when moving to the CPU', (t) => {
+  const canvas = createCanvas(100, 100, { gpu: true })
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#0000ff'
+  ctx.fillRect(0, 0, 50, 50)
+  ctx.translate(50, 50)
+  canvas.getContext('2d', { willReadFrequently: true })
+  ctx.fillRect(0, 0, 50, 50)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 255, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 75, 1, 1).data), [0, 0, 255, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 25, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should encode the desynchronized frames', async (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d', { desynchronized: true })
+  t.true(ctx.getContextAttributes().desynchronized)
+  ctx.fillRect(0, 0, 100, 100)
+  const png = await canvas.encodeFrame('png')
+  t.deepEqual(png.subarray(0, 4), Buffer.from([0x89, 0x50, 0x4e, 0x47]))
+})
+
+test('should keep the clips when moving to the CPU', (t) => {
+  const canvas = createCanvas(100, 100, { gpu: true })
+  const ctx = canvas.getContext('2d')
+  ctx.rect(0, 0, 50, 100)
+  ctx.clip()
+  ctx.save()
+  ctx.beginPath()
+  ctx.rect(0, 0, 100, 50)
+  ctx.clip()
+  canvas.getContext('2d', { willReadFrequently: true })
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(25, 75, 1, 1).data), [0, 0, 0, 0])
+  // the clip of the saved state is still there after restore
+  ctx.restore()
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(Array.from(ctx.getImageData(25, 75, 1, 1).data), [0, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 75, 1, 1).data), [0, 0, 0, 0])
+})
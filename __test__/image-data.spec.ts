@@ -1,6 +1,6 @@
 import test from 'ava'
 
-import { ImageData } from '../index'
+import { ImageData, createCanvas } from '../index'
 
 test('should be able to create ImageData', (t) => {
   t.notThrows(() => new ImageData(1024, 768))
@@ -51,3 +51,50 @@ test('properties should be readonly', (t) => {
   // @ts-expect-error
   t.throws(() => (imageData.height = 514), expectation)
 })
+
+test('ImageData.from should wrap the buffer without copying', (t) => {
+  const buffer = Buffer.alloc(4 * 4 * 4, 255)
+  const imageData = ImageData.from(buffer, 4, 4)
+  t.is(imageData.width, 4)
+  t.is(imageData.height, 4)
+  t.is(imageData.data.buffer, buffer.buffer)
+  buffer[0] = 1
+  t.is(imageData.data[0], 1)
+})
+
+test('ImageData.from should validate the size', (t) => {
+  t.throws(() => ImageData.from(new Uint8ClampedArray(4 * 4 * 4), 4, 3), {
+    code: 'InvalidArg',
+    message: 'Index or size is negative or greater than the allowed amount',
+  })
+})
+
+test('should store the color space', (t) => {
+  t.is(new ImageData(1, 1).colorSpace, 'srgb')
+  t.is(new ImageData(1, 1, { colorSpace: 'display-p3' }).colorSpace, 'display-p3')
+  const u8array = new Uint8ClampedArray(4 * 4 * 4)
+  t.is(new ImageData(u8array, 4, 4, { colorSpace: 'display-p3' }).colorSpace, 'display-p3')
+  // @ts-expect-error
+  t.is(new ImageData(u8array, 4, { colorSpace: 'display-p3' }).colorSpace, 'display-p3')
+  // @ts-expect-error
+  t.throws(() => new ImageData(1, 1, { colorSpace: 'rec2020' }), { message: /^TypeError: / })
+})
+
+test('should convert between the color spaces in getImageData and putImageData', (t) => {
+  const ctx = createCanvas(2, 1).getContext('2d')
+  const p3Red = ctx.createImageData(1, 1, { colorSpace: 'display-p3' })
+  p3Red.data.set([234, 51, 35, 255])
+  ctx.putImageData(p3Red, 0, 0)
+  const srgb = ctx.getImageData(0, 0, 1, 1)
+  t.is(srgb.colorSpace, 'srgb')
+  t.true([255, 0, 0, 255].every((value, i) => Math.abs(srgb.data[i] - value) <= 1), `${[...srgb.data]}`)
+  const p3 = ctx.getImageData(0, 0, 1, 1, { colorSpace: 'display-p3' })
+  t.true([234, 51, 35, 255].every((value, i) => Math.abs(p3.data[i] - value) <= 1), `${[...p3.data]}`)
+  // the size and the color space are copied, the pixels are not
+  const copied = ctx.createImageData(p3)
+  t.is(copied.colorSpace, 'display-p3')
+  t.is(copied.width, 1)
+  t.deepEqual([...copied.data], [0, 0, 0, 0])
+  // @ts-expect-error
+  t.throws(() => ctx.getImageData(0, 0, 1, 1, { colorSpace: 'rec2020' }), { message: /^TypeError: / })
+})
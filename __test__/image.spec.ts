@@ -41,6 +41,47 @@ test('complete state should be ok', async (t) => {
   t.is(image.complete, true)
 })
 
+test('should be able to resize image', async (t) => {
+  const file = await loadImageFile()
+  const image = new Image()
+  image.src = file
+  const thumbnail = image.resize(100, 100, { fit: 'contain' })
+  t.is(thumbnail.width, 100)
+  t.is(thumbnail.height, 100)
+  t.is(image.width, 300)
+  t.throws(() => image.resize(100, 100, { fit: 'stretch' as any }), { message: /Invalid resize fit: stretch/ })
+})
+
+test('colorSpaceConversion should be ok', (t) => {
+  const image = new Image()
+  t.is(image.colorSpaceConversion, 'default')
+  image.colorSpaceConversion = 'none'
+  t.is(image.colorSpaceConversion, 'none')
+  // @ts-expect-error
+  image.colorSpaceConversion = 'invalid'
+  t.is(image.colorSpaceConversion, 'none')
+})
+
+test('should convert the image from the embedded ICC profile', async (t) => {
+  // 4x4 PNG filled with rgb(200, 100, 50) in Display P3
+  const file = await fs.readFile(join(__dirname, 'fixtures', 'display-p3.png'))
+  const pixelOf = (image: Image) => {
+    const ctx = createCanvas(4, 4).getContext('2d')
+    ctx.drawImage(image, 0, 0)
+    return Array.from(ctx.getImageData(1, 1, 1, 1).data)
+  }
+  const image = new Image()
+  image.src = file
+  const [r, g, b, a] = pixelOf(image)
+  t.true(Math.abs(r - 215) <= 2 && Math.abs(g - 93) <= 2 && Math.abs(b - 31) <= 2, `got rgb(${r}, ${g}, ${b})`)
+  t.is(a, 255)
+
+  const raw = new Image()
+  raw.colorSpaceConversion = 'none'
+  raw.src = file
+  t.deepEqual(pixelOf(raw), [200, 100, 50, 255])
+})
+
 test('alt state should be ok', (t) => {
   const image = new Image()
   t.is(image.alt, '')
@@ -1,6 +1,6 @@
 import ava, { TestFn } from 'ava'
 
-import { createCanvas, Path2D, Canvas, SKRSContext2D } from '../index'
+import { createCanvas, Path2D, Canvas, FillType, SKRSContext2D, SKRSCanvasGradient, DOMMatrix } from '../index'
 
 import { snapshotImage } from './image-snapshot'
 
@@ -15,6 +15,12 @@ test.beforeEach((t) => {
   t.context.ctx = canvas.getContext('2d')!
 })
 
+// the 2D values of the `DOMMatrix` returned by `getTransform`
+function transformValues(ctx: SKRSContext2D) {
+  const { a, b, c, d, e, f } = ctx.getTransform()
+  return { a, b, c, d, e, f }
+}
+
 test('should be able to create Path2D', (t) => {
   t.notThrows(() => new Path2D())
   t.notThrows(
@@ -26,6 +32,178 @@ test('should be able to create Path2D', (t) => {
   t.notThrows(() => new Path2D(new Path2D()))
 })
 
+test('should ignore the non-finite arguments of Path2D', (t) => {
+  const path = new Path2D()
+  path.moveTo(10, 10)
+  path.lineTo(NaN, 20)
+  path.lineTo(Infinity, 20)
+  path.bezierCurveTo(0, 0, 10, 10, -Infinity, 0)
+  path.quadraticCurveTo(0, NaN, 10, 10)
+  path.arc(50, 50, NaN, 0, Math.PI)
+  path.rect(0, 0, Infinity, 10)
+  path.lineTo(20, 20)
+  t.is(path.toSVGString(), 'M10 10L20 20')
+})
+
+test('should add the transformed path to Path2D', (t) => {
+  const square = new Path2D()
+  square.rect(0, 0, 10, 10)
+  const path = new Path2D()
+  path.addPath(square)
+  path.addPath(square, { a: 2, b: 0, c: 0, d: 2, e: 20, f: 0 })
+  // the path with the non-finite transform is not added
+  path.addPath(square, { a: NaN, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  t.is(path.toSVGString(), 'M0 0L10 0L10 10L0 10L0 0ZM20 0L40 0L40 20L20 20L20 0Z')
+})
+
+test('should add the rounded rect to Path2D', (t) => {
+  const path = new Path2D()
+  path.roundRect(0, 0, 100, 100, 20)
+  t.deepEqual(path.bounds(), { x: 0, y: 0, width: 100, height: 100 })
+  t.false(path.contains(2, 2))
+  t.true(path.contains(50, 50))
+  // the radii are swapped like the rect is flipped
+  const flipped = new Path2D()
+  flipped.roundRect(100, 0, -100, 100, [0, 20, 0, 0])
+  t.false(flipped.contains(2, 2))
+  t.true(flipped.contains(98, 2))
+  const elliptical = new Path2D()
+  elliptical.roundRect(0, 0, 100, 100, [{ x: 50, y: 10 }])
+  t.false(elliptical.contains(10, 1))
+  t.true(elliptical.contains(1, 10))
+  t.throws(() => path.roundRect(0, 0, 10, 10, []))
+  t.throws(() => path.roundRect(0, 0, 10, 10, [1, 2, 3, 4, 5]))
+  t.throws(() => path.roundRect(0, 0, 10, 10, -1))
+  t.notThrows(() => path.roundRect(0, 0, 10, 10, NaN))
+})
+
+test('should fill the rounded rect', (t) => {
+  const { ctx } = t.context
+  ctx.beginPath()
+  ctx.roundRect(0, 0, 100, 100, [20])
+  ctx.fill()
+  t.deepEqual(Array.from(ctx.getImageData(1, 1, 1, 1).data), [0, 0, 0, 0])
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 255])
+})
+
+test('should round the rect of the context like the spec', (t) => {
+  const { ctx } = t.context
+  // the radii exceeding the rect are scaled down together, 100 to 25 here
+  ctx.beginPath()
+  ctx.roundRect(0, 0, 100, 50, [100])
+  t.true(ctx.isPointInPath(50, 1))
+  t.false(ctx.isPointInPath(3, 3))
+  t.true(ctx.isPointInPath(25, 25))
+  // the corners of [upper left, upper right, lower right, lower left]
+  ctx.beginPath()
+  ctx.roundRect(0, 0, 100, 100, [0, 20, { x: 20, y: 20 }, { x: 0, y: 20 }])
+  t.true(ctx.isPointInPath(1, 1))
+  t.false(ctx.isPointInPath(99, 1))
+  t.false(ctx.isPointInPath(99, 99))
+  t.true(ctx.isPointInPath(1, 99))
+  t.throws(() => ctx.roundRect(0, 0, 100, 100, [10, { x: -1, y: 10 }]))
+  t.throws(() => ctx.roundRect(0, 0, 100, 100, [1, 2, 3, 4, 5]))
+})
+
+test('should ignore the non-finite angle of rotate', (t) => {
+  const { ctx } = t.context
+  ctx.rotate(NaN)
+  ctx.rotate(Infinity)
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  ctx.rotate(Math.PI / 2)
+  const { a, b, c, d } = ctx.getTransform()
+  t.deepEqual([a, b, c, d].map(Math.round), [0, 1, -1, 0])
+})
+
+test('should multiply the current transform', (t) => {
+  const { ctx } = t.context
+  ctx.translate(10, 20)
+  ctx.transform(2, 0, 0, 2, 5, 5)
+  t.deepEqual(transformValues(ctx), { a: 2, b: 0, c: 0, d: 2, e: 15, f: 25 })
+  // the non-finite arguments are ignored
+  ctx.transform(NaN, 0, 0, 1, 0, 0)
+  ctx.transform(1, 0, 0, 1, Infinity, 0)
+  t.deepEqual(transformValues(ctx), { a: 2, b: 0, c: 0, d: 2, e: 15, f: 25 })
+})
+
+test('should keep the current path after resetTransform', (t) => {
+  const { ctx } = t.context
+  ctx.translate(50, 50)
+  ctx.beginPath()
+  ctx.rect(0, 0, 10, 10)
+  ctx.resetTransform()
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  t.true(ctx.isPointInPath(55, 55))
+  t.false(ctx.isPointInPath(5, 5))
+  ctx.fill()
+  t.deepEqual(Array.from(ctx.getImageData(55, 55, 1, 1).data), [0, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should sweep the ellipse like the spec', (t) => {
+  const bounds = (startAngle: number, endAngle: number, anticlockwise?: boolean) => {
+    const path = new Path2D()
+    path.ellipse(50, 50, 40, 20, 0, startAngle, endAngle, anticlockwise)
+    const { x, y, width, height } = path.bounds(true)
+    return [x, y, width, height].map(Math.round)
+  }
+  t.deepEqual(bounds(0, Math.PI / 2), [50, 50, 40, 20])
+  // the sweep is clamped to the full ellipse
+  t.deepEqual(bounds(0, 4 * Math.PI), [10, 30, 80, 40])
+  t.deepEqual(bounds(0, Math.PI / 2, true), [10, 30, 80, 40])
+  const { ctx } = t.context
+  t.throws(() => ctx.ellipse(50, 50, -1, 20, 0, 0, Math.PI))
+  t.throws(() => new Path2D().ellipse(50, 50, 40, -1, 0, 0, Math.PI))
+  t.notThrows(() => ctx.ellipse(50, 50, -1, 20, 0, 0, NaN))
+})
+
+test('should sweep the arc like the spec', (t) => {
+  const { ctx } = t.context
+  const bounds = (startAngle: number, endAngle: number, anticlockwise?: boolean) => {
+    const path = new Path2D()
+    path.arc(50, 50, 40, startAngle, endAngle, anticlockwise)
+    const { x, y, width, height } = path.bounds(true)
+    return [x, y, width, height].map(Math.round)
+  }
+  // the reversed sweep goes around the other way
+  t.deepEqual(bounds(Math.PI / 2, 0), [10, 10, 80, 80])
+  t.deepEqual(bounds(Math.PI / 2, 0, true), [50, 50, 40, 40])
+  // the sweeps of 2π or more are the full circle
+  t.deepEqual(bounds(0, 2 * Math.PI), [10, 10, 80, 80])
+  t.deepEqual(bounds(0, -3 * Math.PI, true), [10, 10, 80, 80])
+  t.deepEqual(bounds(0, 0), [90, 50, 0, 0])
+  t.throws(() => ctx.arc(50, 50, -1, 0, Math.PI))
+  t.throws(() => new Path2D().arc(50, 50, -1, 0, Math.PI))
+  t.notThrows(() => ctx.arc(50, 50, 40, 0, Infinity))
+})
+
+test('should throw the typed errors for the invalid geometry', (t) => {
+  const { ctx } = t.context
+  const path = new Path2D()
+  for (const fn of [
+    () => ctx.arc(0, 0, -1, 0, Math.PI),
+    () => ctx.arcTo(0, 0, 10, 10, -1),
+    () => ctx.ellipse(0, 0, 10, -1, 0, 0, Math.PI),
+    () => path.arcTo(0, 0, 10, 10, -1),
+    () => ctx.createRadialGradient(0, 0, -1, 10, 10, 10),
+  ]) {
+    t.throws(fn, { message: /^IndexSizeError: / })
+  }
+  t.throws(() => ctx.roundRect(0, 0, 10, 10, -1), { message: /^RangeError: / })
+  t.throws(() => ctx.createLinearGradient(0, 0, NaN, 10), { message: /^TypeError: / })
+  t.throws(() => ctx.createRadialGradient(0, 0, 1, 10, 10, Infinity), { message: /^TypeError: / })
+  // the path methods ignore the non-finite arguments
+  t.notThrows(() => ctx.arcTo(0, 0, NaN, 10, -1))
+})
+
+test('should create Path2D from the SVG path data', (t) => {
+  t.is(new Path2D('M10 10 H 90 V 90 Z').toSVGString(), 'M10 10L90 10L90 90Z')
+  t.is(new Path2D('m10,10 l80-0 v80 h-80z').toSVGString(), 'M10 10L90 10L90 90L10 90Z')
+  // the segments before the error are kept, like the browsers
+  t.is(new Path2D('M10 10 L20 20 L30').toSVGString(), 'M10 10L20 20')
+  t.is(new Path2D('L10 10').toSVGString(), '')
+})
+
 test('miterLimit state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.miterLimit, 10)
@@ -47,6 +225,27 @@ test('globalCompositeOperation state should be ok', (t) => {
   t.is(ctx.globalCompositeOperation, 'xor')
 })
 
+test('should composite the whole canvas with the destination and the source operations', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  for (const operation of ['source-in', 'source-out', 'destination-in', 'destination-atop', 'copy'] as const) {
+    ctx.globalCompositeOperation = 'source-over'
+    ctx.fillStyle = '#0000ff'
+    ctx.fillRect(0, 0, 200, 200)
+    ctx.globalCompositeOperation = operation
+    ctx.fillStyle = '#ff0000'
+    ctx.fillRect(0, 0, 50, 50)
+    // the pixels outside of the drawn rect are cleared
+    t.deepEqual(pixel(150, 150), [0, 0, 0, 0], operation)
+  }
+  ctx.fillStyle = '#0000ff'
+  ctx.globalCompositeOperation = 'source-over'
+  ctx.fillRect(0, 0, 200, 200)
+  ctx.globalCompositeOperation = 'destination-in'
+  ctx.fillRect(0, 0, 50, 50)
+  t.deepEqual(pixel(25, 25), [0, 0, 255, 255])
+})
+
 test('imageSmoothingEnabled state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.imageSmoothingEnabled, true)
@@ -91,16 +290,52 @@ test('lineWidth state should be ok', (t) => {
 
 test('fillStyle state should be ok', (t) => {
   const { ctx } = t.context
-  t.is(ctx.fillStyle, '#000')
+  t.is(ctx.fillStyle, '#000000')
   ctx.fillStyle = 'hotpink'
-  t.is(ctx.fillStyle, 'hotpink')
+  t.is(ctx.fillStyle, '#ff69b4')
+  ctx.fillStyle = 'rgba(255,0,0,.5)'
+  t.is(ctx.fillStyle, 'rgba(255, 0, 0, 0.5)')
+  const gradient = ctx.createLinearGradient(0, 0, 100, 0)
+  ctx.fillStyle = gradient
+  t.is(ctx.fillStyle, gradient)
+  // the color of the restored state is read back
+  ctx.fillStyle = '#00ff00'
+  ctx.save()
+  ctx.fillStyle = '#0000ff'
+  ctx.restore()
+  t.is(ctx.fillStyle, '#00ff00')
+})
+
+test('should resolve currentcolor to the currentColor', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.currentColor, null)
+  ctx.fillStyle = 'currentcolor'
+  t.is(ctx.fillStyle, '#000000')
+  t.throws(() => ctx.createLinearGradient(0, 0, 10, 0).addColorStop(0, 'currentcolor'))
+  ctx.currentColor = 'hotpink'
+  t.is(ctx.currentColor, '#ff69b4')
+  ctx.currentColor = 'not a color'
+  t.is(ctx.currentColor, '#ff69b4')
+  ctx.fillStyle = 'currentcolor'
+  ctx.strokeStyle = 'currentColor'
+  ctx.shadowColor = 'currentcolor'
+  t.is(ctx.fillStyle, '#ff69b4')
+  t.is(ctx.strokeStyle, '#ff69b4')
+  t.is(ctx.shadowColor, '#ff69b4')
+  const gradient = ctx.createLinearGradient(0, 0, 10, 0)
+  gradient.addColorStop(0, 'currentcolor')
+  gradient.addColorStop(1, 'currentcolor')
+  ctx.currentColor = null
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [255, 105, 180, 255])
 })
 
 test('strokeStyle state should be ok', (t) => {
   const { ctx } = t.context
-  t.is(ctx.strokeStyle, '#000')
+  t.is(ctx.strokeStyle, '#000000')
   ctx.strokeStyle = 'hotpink'
-  t.is(ctx.strokeStyle, 'hotpink')
+  t.is(ctx.strokeStyle, '#ff69b4')
 })
 
 test('shadowBlur state should be ok', (t) => {
@@ -110,11 +345,38 @@ test('shadowBlur state should be ok', (t) => {
   t.is(ctx.shadowBlur, 10)
 })
 
+test('should draw the offset only and the blur only shadows', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.shadowColor = '#ff0000'
+  ctx.shadowOffsetX = 100
+  ctx.fillRect(0, 0, 50, 50)
+  t.deepEqual(pixel(125, 25), [255, 0, 0, 255])
+  t.deepEqual(pixel(125, 75), [0, 0, 0, 0])
+  // the offset is not transformed
+  ctx.scale(2, 2)
+  ctx.fillRect(0, 50, 25, 25)
+  t.deepEqual(pixel(125, 125), [255, 0, 0, 255])
+  t.deepEqual(pixel(175, 125), [0, 0, 0, 0])
+  ctx.resetTransform()
+  ctx.shadowOffsetX = 0
+  ctx.shadowBlur = 20
+  ctx.fillRect(300, 300, 50, 50)
+  t.deepEqual(pixel(325, 325), [0, 0, 0, 255])
+  const [r, g, , a] = pixel(355, 325)
+  t.true(r > g)
+  t.true(a > 0 && a < 255)
+})
+
 test('shadowColor state should be ok', (t) => {
   const { ctx } = t.context
-  t.is(ctx.shadowColor, '#000000')
-  ctx.shadowColor = 'hotpink'
-  t.is(ctx.shadowColor, 'hotpink')
+  t.is(ctx.shadowColor, 'rgba(0, 0, 0, 0)')
+  ctx.shadowColor = 'HotPink'
+  t.is(ctx.shadowColor, '#ff69b4')
+  ctx.shadowColor = 'rgba(255, 0, 0, 0.25)'
+  t.is(ctx.shadowColor, 'rgba(255, 0, 0, 0.25)')
+  ctx.shadowColor = 'not a color'
+  t.is(ctx.shadowColor, 'rgba(255, 0, 0, 0.25)')
 })
 
 test('shadowOffsetX state should be ok', (t) => {
@@ -136,6 +398,17 @@ test('lineDash state should be ok', (t) => {
   const lineDash = [1, 2, 4.5, 7]
   ctx.setLineDash(lineDash)
   t.deepEqual(ctx.getLineDash(), lineDash)
+  // the returned list is a copy
+  ctx.getLineDash().push(10)
+  t.deepEqual(ctx.getLineDash(), lineDash)
+  // the odd list is repeated
+  ctx.setLineDash([1, 2, 3])
+  t.deepEqual(ctx.getLineDash(), [1, 2, 3, 1, 2, 3])
+  // the list with the negative or non-finite values is ignored
+  ctx.setLineDash([1, -2])
+  ctx.setLineDash([1, NaN])
+  ctx.setLineDash([Infinity])
+  t.deepEqual(ctx.getLineDash(), [1, 2, 3, 1, 2, 3])
 })
 
 test('textAlign state should be ok', (t) => {
@@ -152,16 +425,286 @@ test('textBaseline state should be ok', (t) => {
   t.is(ctx.textBaseline, 'hanging')
 })
 
+test('should stroke with the lineJoin', (t) => {
+  const { ctx } = t.context
+  const outerCorner = (lineJoin: CanvasLineJoin) => {
+    ctx.clearRect(0, 0, 200, 200)
+    ctx.lineJoin = lineJoin
+    ctx.lineWidth = 20
+    ctx.beginPath()
+    ctx.moveTo(50, 100)
+    ctx.lineTo(100, 100)
+    ctx.lineTo(100, 50)
+    ctx.stroke()
+    return ctx.getImageData(108, 108, 1, 1).data[3]
+  }
+  t.is(outerCorner('miter'), 255)
+  t.is(outerCorner('bevel'), 0)
+  t.is(outerCorner('round'), 0)
+})
+
+test('should restore the line and compositing attributes', (t) => {
+  const { ctx } = t.context
+  ctx.save()
+  ctx.lineWidth = 5
+  ctx.lineCap = 'round'
+  ctx.lineJoin = 'bevel'
+  ctx.miterLimit = 2
+  ctx.globalAlpha = 0.5
+  ctx.globalCompositeOperation = 'multiply'
+  ctx.restore()
+  t.is(ctx.lineWidth, 1)
+  t.is(ctx.lineCap, 'butt')
+  t.is(ctx.lineJoin, 'miter')
+  t.is(ctx.miterLimit, 10)
+  t.is(ctx.globalAlpha, 1)
+  t.is(ctx.globalCompositeOperation, 'source-over')
+  // the restored attributes are used by the next drawings
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [255, 0, 0, 255])
+})
+
+test('should keep the current path in place on setTransform and restore', (t) => {
+  const { ctx } = t.context
+  ctx.save()
+  ctx.setTransform(2, 0, 0, 2, 10, 10)
+  ctx.beginPath()
+  ctx.rect(0, 0, 10, 10)
+  ctx.restore()
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  ctx.setTransform(1, 0, 0, 1, 100, 0)
+  ctx.resetTransform()
+  ctx.fill()
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 0])
+  // the saved singular transform doesn't break restore
+  ctx.scale(0, 1)
+  ctx.save()
+  ctx.resetTransform()
+  ctx.restore()
+  t.deepEqual(transformValues(ctx), { a: 0, b: 0, c: 0, d: 1, e: 0, f: 0 })
+})
+
+test('should keep the path through the singular transform', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.translate(10, 10)
+  ctx.rect(0, 0, 20, 20)
+  ctx.setTransform(0, 0, 0, 0, 0, 0)
+  ctx.setTransform(1, 0, 0, 1, 0, 0)
+  ctx.fill()
+  t.deepEqual(pixel(20, 20), [0, 0, 0, 255])
+  t.deepEqual(pixel(5, 5), [0, 0, 0, 0])
+  ctx.beginPath()
+  ctx.rect(50, 50, 20, 20)
+  ctx.scale(0, 1)
+  ctx.resetTransform()
+  ctx.fillStyle = 'red'
+  ctx.fill()
+  t.deepEqual(pixel(60, 60), [255, 0, 0, 255])
+})
+
+test('should intersect the clips and restore them with the state', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.save()
+  ctx.rect(0, 0, 100, 100)
+  ctx.clip()
+  ctx.beginPath()
+  ctx.translate(50, 50)
+  ctx.rect(0, 0, 100, 100)
+  ctx.clip()
+  ctx.resetTransform()
+  ctx.fillRect(0, 0, 200, 200)
+  t.deepEqual(pixel(75, 75), [0, 0, 0, 255])
+  t.deepEqual(pixel(25, 25), [0, 0, 0, 0])
+  t.deepEqual(pixel(125, 125), [0, 0, 0, 0])
+  // the images are clipped too
+  const image = createCanvas(200, 200)
+  const imageCtx = image.getContext('2d')
+  imageCtx.fillStyle = '#ff0000'
+  imageCtx.fillRect(0, 0, 200, 200)
+  ctx.drawImage(image, 0, 0)
+  t.deepEqual(pixel(75, 75), [255, 0, 0, 255])
+  t.deepEqual(pixel(125, 125), [0, 0, 0, 0])
+  ctx.restore()
+  ctx.fillRect(100, 100, 100, 100)
+  t.deepEqual(pixel(125, 125), [0, 0, 0, 255])
+})
+
+test('should not change the fill rule of the clip path', (t) => {
+  const { ctx } = t.context
+  const path = new Path2D()
+  path.rect(0, 0, 100, 100)
+  ctx.clip(path, 'evenodd')
+  t.is(path.getFillType(), FillType.Winding)
+})
+
+test('should composite the layer as a whole', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.saveLayer({ alpha: 0.5 })
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.fillRect(50, 50, 100, 100)
+  ctx.restore()
+  // the overlapping part is not blended twice
+  t.deepEqual(pixel(25, 25), pixel(75, 75))
+  t.is(pixel(75, 75)[3], 128)
+  ctx.saveLayer({ bounds: { x: 200, y: 0, width: 50, height: 50 }, filter: 'invert(1)' })
+  ctx.fillStyle = '#ffffff'
+  ctx.fillRect(200, 0, 100, 100)
+  ctx.restore()
+  t.deepEqual(pixel(225, 25), [0, 0, 0, 255])
+  t.deepEqual(pixel(275, 75), [0, 0, 0, 0])
+  t.throws(() => ctx.saveLayer({ blendMode: 'not-a-mode' as GlobalCompositeOperation }))
+})
+
+test('should reset the canvas and the state', (t) => {
+  const { ctx } = t.context
+  ctx.fillStyle = '#ff0000'
+  ctx.lineWidth = 10
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.save()
+  ctx.translate(10, 10)
+  ctx.rect(0, 0, 50, 50)
+  ctx.clip()
+  ctx.reset()
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 0, 0])
+  t.is(ctx.fillStyle, '#000000')
+  t.is(ctx.lineWidth, 1)
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  // the path and the clip are dropped
+  ctx.fill()
+  ctx.fillRect(100, 100, 50, 50)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 0, 0])
+  t.deepEqual(Array.from(ctx.getImageData(125, 125, 1, 1).data), [0, 0, 0, 255])
+})
+
+test('should clear the pixels in the transform and the clip', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillRect(0, 0, 200, 200)
+  // the global alpha, the compositing and the shadow don't apply
+  ctx.globalAlpha = 0.5
+  ctx.globalCompositeOperation = 'destination-over'
+  ctx.shadowColor = 'red'
+  ctx.shadowOffsetX = 50
+  ctx.translate(50, 0)
+  ctx.rect(0, 0, 100, 25)
+  ctx.clip()
+  ctx.clearRect(0, 0, 50, 50)
+  t.deepEqual(pixel(75, 10), [0, 0, 0, 0])
+  t.deepEqual(pixel(25, 10), [0, 0, 0, 255])
+  t.deepEqual(pixel(125, 10), [0, 0, 0, 255])
+  t.deepEqual(pixel(75, 40), [0, 0, 0, 255])
+})
+
+test('should spread the gradient with the spread method', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const gradient = ctx.createLinearGradient(0, 0, 50, 0)
+  gradient.addColorStop(0, 'red')
+  gradient.addColorStop(0.5, 'red')
+  gradient.addColorStop(0.5, 'blue')
+  gradient.addColorStop(1, 'blue')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 100, 10)
+  t.deepEqual(pixel(60, 5), [0, 0, 255, 255])
+  t.deepEqual(pixel(90, 5), [0, 0, 255, 255])
+  gradient.setSpreadMethod('repeat')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 100, 10)
+  t.deepEqual(pixel(60, 5), [255, 0, 0, 255])
+  t.deepEqual(pixel(90, 5), [0, 0, 255, 255])
+  gradient.setSpreadMethod('reflect')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 100, 10)
+  t.deepEqual(pixel(60, 5), [0, 0, 255, 255])
+  t.deepEqual(pixel(90, 5), [255, 0, 0, 255])
+  // @ts-expect-error
+  t.throws(() => gradient.setSpreadMethod('mirror'), { message: /^TypeError: / })
+})
+
+test('should interpolate the gradient in the color space', (t) => {
+  const { ctx } = t.context
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const fillGradient = (from: string, to: string, interpolate: (gradient: SKRSCanvasGradient) => void) => {
+    const gradient = ctx.createLinearGradient(0, 0, 100, 0)
+    gradient.addColorStop(0, from)
+    gradient.addColorStop(1, to)
+    interpolate(gradient)
+    ctx.fillStyle = gradient
+    ctx.fillRect(0, 0, 100, 10)
+    return pixel(50, 5)
+  }
+  const [gray] = fillGradient('black', 'white', (gradient) => gradient.setInterpolation('srgb-linear'))
+  t.true(Math.abs(gray - 188) <= 2)
+  const [r, g, b] = fillGradient('red', 'blue', (gradient) => gradient.setInterpolation('oklch'))
+  t.true(r > g && b > g)
+  const [longerR, longerG] = fillGradient('red', 'blue', (gradient) => gradient.setInterpolation('oklch', 'longer'))
+  t.true(longerG > longerR)
+  const gradient = ctx.createLinearGradient(0, 0, 100, 0)
+  // @ts-expect-error
+  t.throws(() => gradient.setInterpolation('oklab', 'longer'), { message: /^TypeError: / })
+  // @ts-expect-error
+  t.throws(() => gradient.setInterpolation('hsl'), { message: /^TypeError: / })
+})
+
+test('should keep the opaque canvas opaque', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d', { alpha: false })
+  t.false(ctx.getContextAttributes().alpha)
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [255, 255, 255, 255])
+  ctx.clearRect(0, 0, 50, 50)
+  ctx.globalCompositeOperation = 'destination-out'
+  ctx.fillRect(50, 50, 50, 50)
+  t.deepEqual(Array.from(ctx.getImageData(25, 25, 1, 1).data), [0, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 75, 1, 1).data), [0, 0, 0, 255])
+  // the later calls don't make it transparent
+  canvas.getContext('2d')
+  t.false(ctx.getContextAttributes().alpha)
+  ctx.reset()
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [255, 255, 255, 255])
+})
+
+test('should set the transform from the DOMMatrix', (t) => {
+  const { ctx } = t.context
+  ctx.setTransform(new DOMMatrix().translate(10, 20).scale(2))
+  t.deepEqual(transformValues(ctx), { a: 2, b: 0, c: 0, d: 2, e: 10, f: 20 })
+  ctx.transform(new DOMMatrix([1, 2, 3, 4, 5, 6]))
+  t.deepEqual(transformValues(ctx), { a: 2, b: 4, c: 6, d: 8, e: 20, f: 32 })
+  ctx.setTransform({ m11: 3, m22: 3, m41: 5 })
+  t.deepEqual(transformValues(ctx), { a: 3, b: 0, c: 0, d: 3, e: 5, f: 0 })
+  ctx.transform({ b: 1, m12: 1, is2D: true })
+  t.deepEqual(transformValues(ctx), { a: 3, b: 3, c: 0, d: 3, e: 5, f: 0 })
+  ctx.setTransform()
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+  t.throws(() => ctx.setTransform({ a: 2, m11: 3 }), { message: /^TypeError: / })
+  t.throws(() => ctx.setTransform({ is2D: true, m33: 2 }), { message: /^TypeError: / })
+  t.throws(() => ctx.transform(new DOMMatrix().rotate(30, 30, 0)), { message: /^TypeError: / })
+  // the infinite value is ignored
+  ctx.setTransform({ a: Infinity })
+  t.deepEqual(transformValues(ctx), { a: 1, b: 0, c: 0, d: 1, e: 0, f: 0 })
+})
+
 test('getTransform', (t) => {
   const { ctx } = t.context
-  t.deepEqual(ctx.getTransform(), {
-    a: 1,
-    b: 0,
-    c: 0,
-    d: 1,
-    e: 0,
-    f: 0,
-  })
+  const matrix = ctx.getTransform()
+  t.true(matrix instanceof DOMMatrix)
+  t.true(matrix.is2D)
+  t.true(matrix.isIdentity)
+  ctx.translate(10, 20)
+  ctx.scale(2, 4)
+  t.deepEqual(Array.from(ctx.getTransform().toFloat64Array()), [2, 0, 0, 0, 0, 4, 0, 0, 0, 0, 1, 0, 10, 20, 0, 1])
+  t.deepEqual(ctx.getTransform().transformPoint({ x: 1, y: 1 }).toJSON(), { x: 12, y: 24, z: 0, w: 1 })
+  // the returned matrix is a copy, changing it doesn't change the context
+  matrix.translateSelf(100, 100)
+  t.deepEqual(transformValues(ctx), { a: 2, b: 0, c: 0, d: 4, e: 10, f: 20 })
+  // it round trips through setTransform
+  ctx.setTransform(ctx.getTransform().inverse())
+  t.deepEqual(transformValues(ctx), { a: 0.5, b: 0, c: 0, d: 0.25, e: -5, f: -5 })
 })
 
 test('stroke-and-filling-jpeg', async (t) => {
@@ -16,6 +16,21 @@ test('should load file stream', async (t) => {
   t.is(img instanceof Image, true)
 })
 
+test('should decode image from stream', async (t) => {
+  const progresses: number[] = []
+  const img = await Image.fromStream(
+    fs.createReadStream(join(__dirname, '../example/simple.png'), { highWaterMark: 1024 }),
+    {
+      onProgress: ({ decodedRows }) => progresses.push(decodedRows),
+    },
+  )
+  t.is(img instanceof Image, true)
+  t.is(img.width, 300)
+  t.is(img.height, 320)
+  t.true(progresses.length > 1)
+  t.is(progresses[progresses.length - 1], 320)
+})
+
 test('should load image with alt', async (t) => {
   const img = await loadImage(join(__dirname, '../example/simple.png'), {
     alt: 'demo-image',
@@ -15,6 +15,39 @@ test('should be able to call toSVGString', (t) => {
   t.is(path.toSVGString(), 'M0 0L100 0L100 100L0 100L0 0Z')
 })
 
+test('should accept the names of the path operations', (t) => {
+  const square = () => {
+    const path = new Path2D()
+    path.rect(0, 0, 20, 20)
+    return path
+  }
+  const other = new Path2D()
+  other.rect(10, 10, 20, 20)
+  for (const [name, op] of [
+    ['difference', PathOp.Difference],
+    ['intersect', PathOp.Intersect],
+    ['union', PathOp.Union],
+    ['xor', PathOp.Xor],
+    ['reverseDifference', PathOp.ReverseDifference],
+  ] as const) {
+    t.is(square().op(other, name).toSVGString(), square().op(other, op).toSVGString())
+  }
+  // @ts-expect-error
+  t.throws(() => square().op(other, 'subtract'))
+})
+
+test('should create the same path from toSVGString', (t) => {
+  const path = new Path2D()
+  path.rect(0, 0, 100, 100)
+  path.transform({ a: 2, b: 0, c: 0, d: 2, e: 10, f: 10 })
+  const corner = new Path2D()
+  corner.rect(200, 200, 50, 50)
+  path.op(corner, PathOp.Union)
+  const svg = path.toSVGString()
+  t.true(new Path2D(svg).equals(path))
+  t.is(new Path2D(svg).toSVGString(), svg)
+})
+
 test('should be able to create mountain via op', (t) => {
   const pathOne = new Path2D()
   const pathTwo = new Path2D()
@@ -228,6 +261,40 @@ test('StrokeJoin.Round', (t) => {
   )
 })
 
+test('should stroke the dashed outline', (t) => {
+  const line = () => {
+    const path = new Path2D()
+    path.moveTo(0, 0)
+    path.lineTo(100, 0)
+    return path
+  }
+  const dashed = line().stroke({ width: 2, cap: StrokeCap.Butt, dash: [10, 10] })
+  t.is(dashed.toSVGString().split('M').length - 1, 5)
+  t.deepEqual(dashed.bounds(), { x: 0, y: -1, width: 90, height: 2 })
+  // the odd dash list is repeated
+  const odd = line().stroke({ width: 2, dash: [10] })
+  t.is(odd.toSVGString(), dashed.toSVGString())
+  const offset = line().stroke({ width: 2, dash: [10, 10], dashOffset: 10 })
+  t.deepEqual(offset.bounds(), { x: 10, y: -1, width: 90, height: 2 })
+  // the invalid dash list is ignored
+  t.is(line().stroke({ width: 2, dash: [-1, 10] }).toSVGString(), line().stroke({ width: 2 }).toSVGString())
+})
+
+test('should bake the dash intervals into the path', (t) => {
+  const line = () => {
+    const path = new Path2D()
+    path.moveTo(0, 0)
+    path.lineTo(100, 0)
+    return path
+  }
+  t.is(line().dash([10, 10]).toSVGString(), line().dash(10, 10, 0).toSVGString())
+  t.is(line().dash([10, 20, 30, 40]).toSVGString(), 'M0 0L10 0M30 0L60 0')
+  // the odd intervals are repeated
+  t.is(line().dash([10, 20, 30], 5).toSVGString(), line().dash([10, 20, 30, 10, 20, 30], 5).toSVGString())
+  t.is(line().dash([10, 90], 5).toSVGString(), 'M0 0L5 0M95 0L100 0')
+  t.is(line().dash([-1, 10]).toSVGString(), 'M0 0L100 0')
+})
+
 test('computeTightBounds', (t) => {
   const p = new Path2D()
   t.deepEqual(p.computeTightBounds(), [0, 0, 0, 0])
@@ -235,6 +302,50 @@ test('computeTightBounds', (t) => {
   t.deepEqual(p.computeTightBounds(), [25, 20, 75, 70])
 })
 
+test('bounds', (t) => {
+  const p = new Path2D()
+  t.deepEqual(p.bounds(), { x: 0, y: 0, width: 0, height: 0 })
+  p.moveTo(10, 20)
+  p.quadraticCurveTo(60, 120, 110, 20)
+  t.deepEqual(p.bounds(), { x: 10, y: 20, width: 100, height: 100 })
+  t.deepEqual(p.bounds(true), { x: 10, y: 20, width: 100, height: 50 })
+})
+
+test('should measure the path', (t) => {
+  const path = new Path2D()
+  t.is(path.length(), 0)
+  t.is(path.pointAt(0), null)
+  path.moveTo(0, 0)
+  path.lineTo(100, 0)
+  path.moveTo(100, 100)
+  path.lineTo(100, 200)
+  t.is(path.length(), 200)
+  t.deepEqual(path.pointAt(50), { x: 50, y: 0 })
+  t.deepEqual(path.tangentAt(50), { x: 1, y: 0 })
+  // the distance is measured along the second contour after the first one
+  t.deepEqual(path.pointAt(150), { x: 100, y: 150 })
+  t.deepEqual(path.tangentAt(150), { x: 0, y: 1 })
+  t.deepEqual(path.pointAt(-10), { x: 0, y: 0 })
+  t.deepEqual(path.pointAt(1000), { x: 100, y: 200 })
+})
+
+test('contains', (t) => {
+  // the inner square is a hole by the evenodd rule
+  const path = new Path2D()
+  path.rect(0, 0, 100, 100)
+  path.rect(25, 25, 50, 50)
+  t.true(path.contains(10, 10))
+  t.true(path.contains(50, 50))
+  t.false(path.contains(50, 50, 'evenodd'))
+  t.false(path.contains(150, 50))
+  t.false(path.contains(NaN, 50))
+  path.setFillType(FillType.EvenOdd)
+  t.false(path.contains(50, 50))
+  t.true(path.contains(50, 50, 'nonzero'))
+  // @ts-expect-error
+  t.throws(() => path.contains(50, 50, 'inside'))
+})
+
 test('Transform', (t) => {
   const p = new Path2D()
   p.transform({ a: 1, b: 0.2, c: 0.8, d: 1, e: 0, f: 0 })
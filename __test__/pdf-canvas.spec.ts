@@ -0,0 +1,125 @@
+import { join } from 'path'
+import { Writable } from 'stream'
+
+import test from 'ava'
+
+import { createCanvas, GlobalFonts } from '../index'
+
+GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'SourceSerifPro-Regular.ttf'), 'pdf-source-serif')
+
+function countPages(pdf: Buffer) {
+  return pdf.toString('latin1').match(/\/Type \/Page\b(?!s)/g)?.length ?? 0
+}
+
+test('should be able to draw multiple pages', (t) => {
+  const canvas = createCanvas(200, 100, 'pdf')
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.addPage()
+  ctx.fillText('second page', 10, 50)
+  ctx.addPage(300, 400)
+  ctx.strokeRect(10, 10, 100, 100)
+  const pdf = canvas.toBuffer()
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  t.true(pdf.toString('latin1').trimEnd().endsWith('%%EOF'))
+  t.is(countPages(pdf), 3)
+  t.true(pdf.toString('latin1').includes('/MediaBox [0 0 300 400]'))
+  // the document is closed
+  t.throws(() => ctx.addPage())
+  t.deepEqual(canvas.toBuffer(), pdf)
+})
+
+test('should be able to stream to a Writable', async (t) => {
+  const chunks: Buffer[] = []
+  const writable = new Writable({
+    write(chunk, _encoding, callback) {
+      chunks.push(chunk)
+      callback()
+    },
+  })
+  const canvas = createCanvas(200, 100, 'pdf', writable)
+  const ctx = canvas.getContext('2d')
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.addPage()
+  ctx.fillRect(100, 0, 100, 100)
+  await canvas.flush()
+  await canvas.end()
+  const pdf = Buffer.concat(chunks)
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  t.is(countPages(pdf), 2)
+})
+
+test('addPage should throw on the raster canvas', (t) => {
+  const canvas = createCanvas(200, 100)
+  t.throws(() => canvas.getContext('2d').addPage())
+})
+
+test('should write the document metadata', (t) => {
+  const canvas = createCanvas(200, 100, 'pdf', {
+    title: 'Invoice 42',
+    author: 'Accounting',
+    subject: 'Monthly invoice',
+    keywords: 'invoice, 2024',
+    creationDate: new Date(Date.UTC(2024, 1, 29, 13, 45, 30)),
+    pdfA: true,
+  })
+  canvas.getContext('2d').fillRect(0, 0, 100, 100)
+  const pdf = canvas.toBuffer().toString('latin1')
+  t.true(pdf.includes('/Title (Invoice 42)'))
+  t.true(pdf.includes('/Author (Accounting)'))
+  t.true(pdf.includes('/Subject (Monthly invoice)'))
+  t.true(pdf.includes('/Keywords (invoice, 2024)'))
+  t.true(pdf.includes("/CreationDate (D:20240229134530+00'00')"))
+  // PDF/A writes the XMP metadata stream
+  t.true(pdf.includes('pdfaid:part'))
+})
+
+test('should reject the invalid date', (t) => {
+  t.throws(() => createCanvas(200, 100, 'pdf', { creationDate: new Date(NaN) }))
+})
+
+test('should write the text as selectable text', (t) => {
+  const canvas = createCanvas(300, 100, 'pdf')
+  const ctx = canvas.getContext('2d')
+  ctx.font = '24px pdf-source-serif'
+  ctx.fillText('Invoice total', 10, 50)
+  const pdf = canvas.toBuffer().toString('latin1')
+  t.true(pdf.includes('/Type /Font'))
+  // the font is embedded, and the glyphs are mapped back to the text for searching and copying
+  t.regex(pdf, /\/FontFile/)
+  t.true(pdf.includes('/ToUnicode'))
+})
+
+test('should be able to outline the text', (t) => {
+  const canvas = createCanvas(300, 100, 'pdf', { textToPaths: true })
+  const ctx = canvas.getContext('2d')
+  ctx.font = '24px pdf-source-serif'
+  ctx.fillText('Invoice total', 10, 50)
+  ctx.addPage()
+  ctx.strokeText('Invoice total', 10, 50)
+  const pdf = canvas.toBuffer().toString('latin1')
+  t.false(pdf.includes('/Type /Font'))
+  t.is(countPages(Buffer.from(pdf, 'latin1')), 2)
+})
+
+test('should write the link annotations', (t) => {
+  const canvas = createCanvas(300, 200, 'pdf')
+  const ctx = canvas.getContext('2d')
+  ctx.fillText('Website', 10, 20)
+  ctx.addLink({ x: 10, y: 10, width: 80, height: 20 }, 'https://example.com/')
+  ctx.addLink({ x: 10, y: 40, width: 80, height: 20 }, '#totals')
+  ctx.addPage()
+  ctx.addDestination('totals', 0, 0)
+  const pdf = canvas.toBuffer().toString('latin1')
+  t.true(pdf.includes('/Subtype /Link'))
+  t.true(pdf.includes('/URI (https://example.com/)'))
+  t.true(pdf.includes('/Dests'))
+  t.true(pdf.includes('/totals'))
+})
+
+test('links should be ignored by the raster canvas', (t) => {
+  const canvas = createCanvas(300, 200)
+  const ctx = canvas.getContext('2d')
+  t.notThrows(() => ctx.addLink({ x: 10, y: 10, width: 80, height: 20 }, 'https://example.com/'))
+})
@@ -0,0 +1,61 @@
+import test from 'ava'
+
+import { createCanvas, DOMMatrix, Picture } from '../index'
+
+test('should serialize the recording as skp', (t) => {
+  const canvas = createCanvas(200, 100, 'picture')
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.fillText('recorded', 10, 50)
+  const skp = canvas.toBuffer()
+  t.is(skp.subarray(0, 8).toString('latin1'), 'skiapict')
+  // the recording is finished
+  ctx.fillRect(100, 0, 100, 100)
+  t.deepEqual(canvas.toBuffer(), skp)
+})
+
+test('should serialize the empty recording', (t) => {
+  const canvas = createCanvas(200, 100, 'picture')
+  t.is(canvas.toBuffer().subarray(0, 8).toString('latin1'), 'skiapict')
+})
+
+function recordSquare() {
+  const canvas = createCanvas(20, 20, 'picture')
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 10, 10)
+  return canvas
+}
+
+test('should replay the picture', (t) => {
+  const picture = recordSquare().toPicture()
+  t.is(picture.width, 20)
+  t.is(picture.height, 20)
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.translate(50, 50)
+  ctx.drawPicture(picture)
+  t.deepEqual(Array.from(ctx.getImageData(55, 55, 1, 1).data), [255, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(45, 45, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should replay the serialized picture with the transform', (t) => {
+  const skp = recordSquare().toBuffer()
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  const picture = new Picture(skp)
+  t.is(picture.width, 20)
+  t.is(picture.height, 20)
+  ctx.drawPicture(picture, new DOMMatrix().scale(5))
+  t.deepEqual(Array.from(ctx.getImageData(45, 45, 1, 1).data), [255, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(55, 55, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should throw on the invalid skp', (t) => {
+  t.throws(() => new Picture(Buffer.from('not a picture')))
+  const ctx = createCanvas(100, 100).getContext('2d')
+  // the untrusted bytes are only deserialized by `new Picture`
+  // @ts-expect-error
+  t.throws(() => ctx.drawPicture(Buffer.from('not a picture')))
+})
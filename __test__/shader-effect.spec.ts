@@ -0,0 +1,113 @@
+import test from 'ava'
+
+import { createCanvas } from '../index'
+
+const SOLID = `
+uniform float4 color;
+
+half4 main(float2 p) {
+  return half4(color);
+}
+`
+
+// the left half is red and the right half is blue
+const SPLIT = `
+uniform float width;
+
+half4 main(float2 p) {
+  return p.x < width / 2 ? half4(1, 0, 0, 1) : half4(0, 0, 1, 1);
+}
+`
+
+const STEPS = `
+uniform int steps;
+
+half4 main(float2 p) {
+  return steps == 2 ? half4(0, 1, 0, 1) : half4(1, 0, 0, 1);
+}
+`
+
+const INVERT = `
+uniform shader image;
+
+half4 main(float2 p) {
+  half4 color = sample(image, p);
+  return half4(color.a - color.rgb, color.a);
+}
+`
+
+test('should fill with the shader effect', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = ctx.createShaderEffect(SPLIT, { width: 100 })
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(Array.from(ctx.getImageData(25, 50, 1, 1).data), [255, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 50, 1, 1).data), [0, 0, 255, 255])
+})
+
+test('should stroke with the shader effect', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.strokeStyle = ctx.createShaderEffect(SOLID, { color: [0, 1, 0, 1] })
+  ctx.lineWidth = 10
+  ctx.strokeRect(20, 20, 60, 60)
+  t.deepEqual(Array.from(ctx.getImageData(20, 50, 1, 1).data), [0, 255, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 0])
+})
+
+test('should keep the uniforms of the assigned style', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  const effect = ctx.createShaderEffect(SOLID, { color: [1, 0, 0, 1] })
+  ctx.fillStyle = effect
+  effect.setUniforms({ color: [0, 0, 1, 1] })
+  ctx.fillRect(0, 0, 50, 100)
+  ctx.fillStyle = effect
+  ctx.fillRect(50, 0, 50, 100)
+  t.deepEqual(Array.from(ctx.getImageData(25, 50, 1, 1).data), [255, 0, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 50, 1, 1).data), [0, 0, 255, 255])
+})
+
+test('should set the int uniforms', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  const effect = ctx.createShaderEffect(STEPS, { steps: 2 })
+  ctx.fillStyle = effect
+  ctx.fillRect(0, 0, 50, 100)
+  effect.setUniforms({ steps: 3 })
+  ctx.fillStyle = effect
+  ctx.fillRect(50, 0, 50, 100)
+  t.deepEqual(Array.from(ctx.getImageData(25, 50, 1, 1).data), [0, 255, 0, 255])
+  t.deepEqual(Array.from(ctx.getImageData(75, 50, 1, 1).data), [255, 0, 0, 255])
+})
+
+test('should filter the canvas with the shader effect', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = '#ff0000'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.translate(50, 50)
+  ctx.applyShaderEffect(ctx.createShaderEffect(INVERT))
+  t.deepEqual(Array.from(ctx.getImageData(0, 0, 1, 1).data), [0, 255, 255, 255])
+  t.deepEqual(Array.from(ctx.getImageData(99, 99, 1, 1).data), [0, 255, 255, 255])
+})
+
+test('should throw on the invalid shader effect', (t) => {
+  const ctx = createCanvas(100, 100).getContext('2d')
+  t.throws(() => ctx.createShaderEffect('half4 main(float2 p) { return undefined; }'), {
+    message: /Compile the shader effect failed/,
+  })
+  t.throws(() => ctx.createShaderEffect(SOLID, { color: [1, 0, 0] }))
+  t.throws(() => ctx.createShaderEffect(SOLID, { time: 1 }))
+  t.throws(
+    () =>
+      ctx.createShaderEffect(`
+        uniform colorFilter tint;
+
+        half4 main(float2 p) {
+          return sample(tint, half4(1));
+        }
+      `),
+    { message: /only the shader children are supported, tint is a colorFilter/ },
+  )
+})
@@ -1,4 +1,7 @@
+import { promises as fs } from 'fs'
+import { tmpdir } from 'os'
 import { join } from 'path'
+import { Writable } from 'stream'
 
 import ava, { TestFn } from 'ava'
 
@@ -38,3 +41,81 @@ test('should be able to export text', (t) => {
   ctx.strokeText('@napi-rs/canvas', 50, 300)
   t.snapshot(canvas.getContent().toString('utf8'))
 })
+
+test('should be able to create the svg backend by type', (t) => {
+  const canvas = createCanvas(200, 100, 'svg')
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 100, 100)
+  ctx.font = '20px sans-serif'
+  ctx.fillText('chart', 120, 50)
+  const svg = canvas.toBuffer().toString('utf8')
+  t.true(svg.startsWith('<?xml'))
+  t.true(svg.endsWith('</svg>'))
+  t.true(svg.includes('<rect'))
+  t.true(svg.includes('<text'))
+  // it doesn't consume the document
+  t.is(canvas.toBuffer().toString('utf8'), svg)
+})
+
+test('should be able to stream to a file', async (t) => {
+  const path = join(tmpdir(), `svg-canvas-${process.pid}.svg`)
+  const canvas = createCanvas(200, 100, SvgExportFlag.NoPrettyXML, path)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 100, 100)
+  await canvas.end()
+  const content = await fs.readFile(path, 'utf8')
+  await fs.unlink(path)
+  t.true(content.startsWith('<?xml'))
+  t.true(content.trimEnd().endsWith('</svg>'))
+  t.true(content.includes('fill="#FF69B4"'))
+  t.throws(() => canvas.getContent())
+})
+
+test('should be able to stream to a Writable', async (t) => {
+  const chunks: Buffer[] = []
+  const writable = new Writable({
+    write(chunk, _encoding, callback) {
+      chunks.push(chunk)
+      callback()
+    },
+  })
+  const canvas = createCanvas(200, 100, SvgExportFlag.NoPrettyXML, writable)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, 100, 100)
+  await canvas.flush()
+  t.true(chunks.length > 0)
+  ctx.fillStyle = 'yellow'
+  ctx.fillRect(100, 0, 100, 100)
+  await canvas.end()
+  const content = Buffer.concat(chunks).toString('utf8')
+  t.true(content.startsWith('<?xml'))
+  t.is(content.match(/<svg/g)?.length, 1)
+  t.true(content.trimEnd().endsWith('</svg>'))
+  t.is(content.match(/<rect/g)?.length, 2)
+})
+
+test('should be able to embed the fonts', (t) => {
+  GlobalFonts.registerFromPath(join(__dirname, 'fonts-dir', 'iosevka-curly-regular.woff2'), 'i-curly')
+  const canvas = createCanvas(300, 100, 'svg', { text: 'embed' })
+  const ctx = canvas.getContext('2d')
+  ctx.font = '30px i-curly'
+  ctx.fillText('embedded', 10, 50)
+  const svg = canvas.toBuffer().toString('utf8')
+  t.true(svg.includes('<text'))
+  t.regex(svg, /@font-face\{font-family:"[^"]+";font-weight:400;font-style:normal;src:url\(data:font\/woff2;base64,/)
+  t.true(svg.endsWith(']]></style></svg>'))
+})
+
+test('should be able to outline the text by the text policy', (t) => {
+  const canvas = createCanvas(300, 100, 'svg', { text: 'paths' })
+  const ctx = canvas.getContext('2d')
+  ctx.font = '30px sans-serif'
+  ctx.fillText('outlined', 10, 50)
+  const svg = canvas.toBuffer().toString('utf8')
+  t.false(svg.includes('<text'))
+  t.false(svg.includes('@font-face'))
+  t.throws(() => createCanvas(300, 100, 'svg', { text: 'glyphs' as any }))
+})
@@ -7,9 +7,11 @@ use std::process;
 fn main() {
   println!("cargo:rerun-if-env-changed=SKIA_DIR");
   println!("cargo:rerun-if-env-changed=SKIA_LIB_DIR");
+  println!("cargo:rerun-if-env-changed=SKIA_GPU");
 
   println!("cargo:rerun-if-changed=skia-c/skia_c.cpp");
   println!("cargo:rerun-if-changed=skia-c/skia_c.hpp");
+  println!("cargo:rerun-if-changed=skia-c/skia_c_metal.mm");
 
   let compile_target = env::var("TARGET").expect("TARGET");
   let compile_target_os = env::var("CARGO_CFG_TARGET_OS").expect("CARGO_CFG_TARGET_OS");
@@ -35,6 +37,36 @@ fn main() {
 
   build.cpp(true).file("skia-c/skia_c.cpp");
 
+  // Skia is built with the GPU backends by `SKIA_GPU=1 node scripts/build-skia.js`,
+  // Metal on macOS, OpenGL and Vulkan on Linux
+  if env::var("SKIA_GPU").is_ok() {
+    build.define("SKIAC_GPU", None);
+    match compile_target_os.as_str() {
+      "linux" => {
+        build
+          .define("SKIAC_GL", None)
+          .define("SK_GL", None)
+          .define("SKIAC_VULKAN", None)
+          .define("SK_VULKAN", None)
+          .include(skia_path.join("include/third_party/vulkan"));
+        println!("cargo:rustc-link-lib=EGL");
+        println!("cargo:rustc-link-lib=GLESv2");
+        // libvulkan is opened at runtime
+        println!("cargo:rustc-link-lib=dl");
+      }
+      "macos" => {
+        build
+          .define("SKIAC_METAL", None)
+          .define("SK_METAL", None)
+          .file("skia-c/skia_c_metal.mm");
+        println!("cargo:rustc-link-lib=framework=Metal");
+        println!("cargo:rustc-link-lib=framework=Foundation");
+        println!("cargo:rustc-link-lib=framework=IOSurface");
+      }
+      _ => {}
+    }
+  }
+
   match compile_target.as_str() {
     "aarch64-unknown-linux-musl" => {
       let gcc_version = String::from_utf8(
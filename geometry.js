@@ -770,11 +770,8 @@ class DOMMatrix {
     return this
   }
 
-  transformPoint(point) {
-    const x = point.x
-    const y = point.y
-    const z = point.z
-    const w = point.w
+  transformPoint(point = {}) {
+    const { x = 0, y = 0, z = 0, w = 1 } = point
 
     const values = this[VALUES]
 
@@ -181,15 +181,28 @@ export class ImageData {
    * Returns the actual dimensions of the data in the ImageData object, in pixels.
    */
   readonly width: number
+  /** The color space of the pixels */
+  readonly colorSpace: ColorSpace
 
   constructor(sw: number, sh: number, attr?: { colorSpace?: ColorSpace })
   constructor(imageData: ImageData, attr?: { colorSpace?: ColorSpace })
-  constructor(data: Uint8ClampedArray, sw: number, sh?: number)
+  constructor(data: Uint8ClampedArray, sw: number, sh?: number, attr?: { colorSpace?: ColorSpace })
+  /**
+   * Create an ImageData backed by the memory of `data` without copying it.
+   * Changes to `data` are visible to the ImageData and vice versa.
+   */
+  static from(
+    data: Uint8ClampedArray | Uint8Array | Buffer,
+    sw: number,
+    sh?: number,
+    attr?: { colorSpace?: ColorSpace },
+  ): ImageData
 }
 
 export class Image {
   constructor()
   // attrs only affects SVG
+  // width and height select the closest embedded image of ICO files
   constructor(width: number, height: number, attrs?: { colorSpace?: ColorSpace })
   width: number
   height: number
@@ -197,7 +210,45 @@ export class Image {
   readonly naturalHeight: number
   readonly complete: boolean
   alt: string
+  /**
+   * `none` keeps the raw pixel values instead of converting them from the embedded ICC profile.
+   * Must be set before `src`.
+   * @default 'default'
+   */
+  colorSpaceConversion: 'default' | 'none'
   src: Buffer
+
+  /**
+   * Resample the image into a new Image, the source Image is not changed.
+   * @param options.fit `fill` by default, `contain` letterboxes with transparent pixels and `cover` crops the overflow.
+   * @param options.filter `lanczos` by default.
+   */
+  resize(
+    width: number,
+    height: number,
+    options?: { fit?: 'fill' | 'contain' | 'cover'; filter?: 'nearest' | 'linear' | 'mitchell' | 'lanczos' },
+  ): Image
+
+  /**
+   * Decode the image while the data arrives, the `src` of the returned Image is empty.
+   * PNG, GIF, BMP and WBMP are decoded row by row, other formats are decoded once the stream ends.
+   * Only PNG drops the bytes it has decoded, the other formats keep the whole input in memory until the Image is decoded.
+   */
+  static fromStream(
+    readable: import('stream').Readable,
+    options?: { alt?: string; onProgress?: (progress: ImageDecodeProgress) => void },
+  ): Promise<Image>
+}
+
+export interface ImageDecodeProgress {
+  width: number
+  height: number
+  decodedRows: number
+  complete: boolean
+  /**
+   * Bytes received from the stream so far
+   */
+  loaded: number
 }
 
 export class Path2D {
@@ -222,9 +273,10 @@ export class Path2D {
   moveTo(x: number, y: number): void
   quadraticCurveTo(cpx: number, cpy: number, x: number, y: number): void
   rect(x: number, y: number, w: number, h: number): void
+  roundRect(x: number, y: number, w: number, h: number, radii?: number | DOMPointInit | (number | DOMPointInit)[]): void
 
   // PathKit methods
-  op(path: Path2D, operation: PathOp): Path2D
+  op(path: Path2D, operation: PathOp | 'difference' | 'intersect' | 'union' | 'xor' | 'reverseDifference'): Path2D
   toSVGString(): string
   getFillType(): FillType
   getFillTypeString(): string
@@ -235,9 +287,18 @@ export class Path2D {
   transform(transform: DOMMatrix2DInit): Path2D
   getBounds(): [left: number, top: number, right: number, bottom: number]
   computeTightBounds(): [left: number, top: number, right: number, bottom: number]
+  bounds(tight?: boolean): { x: number; y: number; width: number; height: number }
+  length(): number
+  /** The distance out of the path is pinned to its ends, `null` if the path has no length */
+  pointAt(distance: number): { x: number; y: number } | null
+  tangentAt(distance: number): { x: number; y: number } | null
   trim(start: number, end: number, isComplement?: boolean): Path2D
+  /** The odd intervals are repeated like `setLineDash`, the path is kept if the intervals are invalid */
+  dash(intervals: number[], phase?: number): Path2D
   dash(on: number, off: number, phase: number): Path2D
   equals(path: Path2D): boolean
+  /** The fill type of the path is used if `fillRule` is not set */
+  contains(x: number, y: number, fillRule?: CanvasFillRule): boolean
 }
 
 export interface StrokeOptions {
@@ -245,19 +306,86 @@ export interface StrokeOptions {
   miterLimit?: number
   cap?: StrokeCap
   join?: StrokeJoin
+  /** The dash list like `setLineDash`, the odd list is repeated */
+  dash?: number[]
+  dashOffset?: number
+}
+
+/**
+ * The numbers or the arrays of numbers by the names of the uniforms, the unset uniforms are zeros.
+ * The `int` uniforms take the integer part of the numbers
+ */
+export type ShaderUniforms = Record<string, number | number[]>
+
+/**
+ * The SkSL shader created by `createShaderEffect`.
+ * It's assigned to `fillStyle` and `strokeStyle`, or filters the canvas by `applyShaderEffect`
+ */
+export interface ShaderEffect {
+  /** Update the uniforms, the styles assigned before are not changed */
+  setUniforms(uniforms: ShaderUniforms): void
+}
+
+export interface SKRSCanvasGradient extends CanvasGradient {
+  /** Non-standard, how the colors are drawn outside of the gradient, it's `pad` by default */
+  setSpreadMethod(spread: 'pad' | 'repeat' | 'reflect'): void
+  /**
+   * Non-standard, the color space the colors are interpolated in, it's `srgb` by default.
+   * The hue interpolation method is only valid for `oklch`, it's `shorter` by default
+   */
+  setInterpolation(colorSpace: 'oklch', hue?: HueInterpolationMethod): void
+  setInterpolation(colorSpace: 'srgb' | 'srgb-linear' | 'linear-rgb' | 'oklab'): void
 }
 
+export type HueInterpolationMethod = 'shorter' | 'longer' | 'increasing' | 'decreasing'
+
 export interface SKRSContext2D
   extends Omit<
     CanvasRenderingContext2D,
-    'drawImage' | 'createPattern' | 'getTransform' | 'drawFocusIfNeeded' | 'scrollPathIntoView'
+    | 'drawImage'
+    | 'createPattern'
+    | 'getTransform'
+    | 'drawFocusIfNeeded'
+    | 'scrollPathIntoView'
+    | 'fillStyle'
+    | 'strokeStyle'
+    | 'filter'
+    | 'createLinearGradient'
+    | 'createRadialGradient'
+    | 'getImageData'
   > {
+  fillStyle: string | CanvasGradient | CanvasPattern | ShaderEffect
+  strokeStyle: string | CanvasGradient | CanvasPattern | ShaderEffect
+  /**
+   * Non-standard, the color `currentcolor` is resolved to in `fillStyle`, `strokeStyle`, `shadowColor` and the gradients created after it's set.
+   * `currentcolor` is rejected if it's `null`, the default
+   */
+  currentColor: string | null
+  /** Non-standard, the color filter of the later drawings, a `ColorFilter` of the same filter is read back */
+  colorFilter: ColorFilter | null
+  /** The CSS filter, or the `ImageFilter`, an `ImageFilter` of the same filter is read back for it */
+  filter: string | ImageFilter
   /**
    * @param startAngle The angle at which to begin the gradient, in radians. Angle measurements start vertically above the centre and move around clockwise.
    * @param x The x-axis coordinate of the centre of the gradient.
    * @param y The y-axis coordinate of the centre of the gradient.
    */
-  createConicGradient(startAngle: number, x: number, y: number): CanvasGradient
+  createConicGradient(startAngle: number, x: number, y: number): SKRSCanvasGradient
+  createLinearGradient(x0: number, y0: number, x1: number, y1: number): SKRSCanvasGradient
+  createRadialGradient(x0: number, y0: number, r0: number, x1: number, y1: number, r1: number): SKRSCanvasGradient
+  /**
+   * Start a new page of the PDF canvas, the size defaults to the size of the canvas.
+   * The transform is kept, the clip is reset. It throws on the other canvas types
+   */
+  addPage(width?: number, height?: number): void
+  /**
+   * Make the rect a clickable link in the PDF, `#name` links to the destination added by `addDestination`.
+   * The rect is transformed like the drawings, and ignored by the other canvas types
+   */
+  addLink(rect: { x: number; y: number; width: number; height: number }, url: string): void
+  /** Add a named destination in the PDF at the point of the current page */
+  addDestination(name: string, x: number, y: number): void
+  roundRect(x: number, y: number, w: number, h: number, radii?: number | DOMPointInit | (number | DOMPointInit)[]): void
   drawImage(image: Image | Canvas, dx: number, dy: number): void
   drawImage(image: Image | Canvas, dx: number, dy: number, dw: number, dh: number): void
   drawImage(
@@ -271,26 +399,129 @@ export interface SKRSContext2D
     dw: number,
     dh: number,
   ): void
+  /**
+   * Draw the image as a nine-patch, the corners keep their size, the edges are stretched in one direction and the center is stretched in both directions.
+   * @param center The stretchable part of the image, must be inside of the image.
+   */
+  drawImageNine(
+    image: Image | Canvas,
+    center: { x: number; y: number; width: number; height: number },
+    dx: number,
+    dy: number,
+    dw: number,
+    dh: number,
+  ): void
+  /**
+   * Render the SVG document as vectors, so it stays sharp at any scale, and stays vector in the SVG and PDF canvases.
+   * The size defaults to the `width` and `height`, or the `viewBox` of the SVG.
+   * It's composited with the `globalAlpha`, `globalCompositeOperation`, `filter` and shadow like `drawImage`.
+   */
+  drawSvg(svg: string | Buffer, dx: number, dy: number, dw?: number, dh?: number): void
+  /**
+   * Replay the picture recorded by the picture canvas, or loaded from the `.skp` bytes by `new Picture`.
+   * It stays vector in the SVG and PDF canvases.
+   * @param transform Applied on top of the current transform.
+   */
+  drawPicture(picture: Picture, transform?: DOMMatrix2DInit): void
+  /**
+   * Draw sprites from the same image in one call.
+   * @param xforms `[scos, ssin, tx, ty]` for every sprite, the sprite is rotated and scaled by `scos` and `ssin`, then translated by `tx` and `ty`.
+   * @param srcRects `[x, y, width, height]` of every sprite in the image.
+   * @param colors One color for every sprite, as CSS colors or `0xAARRGGBB` values.
+   * @param blendMode Blends the colors with the sprites, `modulate` by default.
+   */
+  drawAtlas(
+    image: Image | Canvas,
+    xforms: Float32Array,
+    srcRects: Float32Array,
+    colors?: Uint32Array | string[],
+    blendMode?: GlobalCompositeOperation | 'modulate',
+  ): void
+  /**
+   * Compile the SkSL shader like `half4 main(float2 p) { ... }`, it throws with the errors of the compiler.
+   * The coordinates are transformed like the shapes drawn by it
+   */
+  createShaderEffect(sksl: string, uniforms?: ShaderUniforms): ShaderEffect
+  /**
+   * Replace the pixels of the canvas with the output of the effect, the clip is applied but the transform is not.
+   * The first child shader like `uniform shader image;` samples the pixels before it
+   */
+  applyShaderEffect(effect: ShaderEffect): void
+  /**
+   * Filter the pixels in the rect of the canvas by the CSS filter like `filter`, or the `ColorFilter`.
+   * The rect is in the pixels of the canvas like `putImageData`, the clip is applied.
+   * The filter samples the pixels around the rect too, like the CSS `backdrop-filter`
+   */
+  applyFilter(filter: string | ColorFilter, x: number, y: number, width: number, height: number): void
+  /** Clear the canvas, and reset the path, the transform, the clip and the state stack to the defaults */
+  reset(): void
+  /**
+   * Like `save`, but the drawings until the paired `restore` go to a layer,
+   * then the layer is composited onto the canvas as a whole with the options.
+   * @param options.bounds The layer is clipped to the rect, it's unbounded by default.
+   */
+  saveLayer(options?: {
+    alpha?: number
+    blendMode?: GlobalCompositeOperation
+    bounds?: { x: number; y: number; width: number; height: number }
+    filter?: string | ImageFilter
+    /** Applied after the `filter` */
+    colorFilter?: ColorFilter
+  }): void
   createPattern(
     image: Image | ImageData,
     repeat: 'repeat' | 'repeat-x' | 'repeat-y' | 'no-repeat' | null,
   ): CanvasPattern
-  getContextAttributes(): { alpha: boolean; desynchronized: boolean }
-  getTransform(): {
-    a: number
-    b: number
-    c: number
-    d: number
-    e: number
-    f: number
-  }
+  getContextAttributes(): { alpha: boolean; desynchronized: boolean; willReadFrequently: boolean }
+  /**
+   * @param settings.colorType Non-standard, the pixels are `rgba` by default or `bgra`.
+   * @param settings.premultiplied Non-standard, the colors are multiplied by the alpha, `false` by default.
+   * The pixels in the other formats are for the native consumers, `putImageData` treats them as RGBA unpremultiplied.
+   */
+  getImageData(
+    sx: number,
+    sy: number,
+    sw: number,
+    sh: number,
+    settings?: { colorSpace?: ColorSpace; colorType?: 'rgba' | 'bgra'; premultiplied?: boolean },
+  ): ImageData
+  /**
+   * Like `getImageData`, but the GPU canvas reads the pixels back without blocking the thread until the GPU finishes.
+   * The pixels are the ones at the time of the call.
+   */
+  getImageDataAsync(
+    sx: number,
+    sy: number,
+    sw: number,
+    sh: number,
+    settings?: { colorSpace?: ColorSpace },
+  ): Promise<ImageData>
+  /** A copy of the current transform, changing it doesn't change the context */
+  getTransform(): DOMMatrix
+  transform(a: number, b: number, c: number, d: number, e: number, f: number): void
+  /** Multiply the current transform by the 2D `DOMMatrix` or its init dictionary, it throws on the 3D one */
+  transform(transform: DOMMatrixInit): void
+  /** The `DOMMatrix` or its init dictionary, it throws on the 3D one */
+  setTransform(transform?: DOMMatrixInit): void
+  setTransform(a: number, b: number, c: number, d: number, e: number, f: number): void
 }
 
 export type ColorSpace = 'srgb' | 'display-p3'
 
 export interface ContextAttributes {
+  /**
+   * `false` makes the canvas opaque, the pixels are drawn onto the white background and can't be transparent.
+   * The later calls don't make it transparent again
+   */
   alpha?: boolean
   colorSpace?: ColorSpace
+  /**
+   * Move the GPU canvas to the CPU, so reading the pixels doesn't wait for the GPU.
+   * The pixels, the transform and the clip are kept
+   */
+  willReadFrequently?: boolean
+  /** `encodeFrame` and the frame streams encode the pixels without copying them, the later draws could be encoded */
+  desynchronized?: boolean
 }
 
 export interface SvgCanvas {
@@ -299,9 +530,146 @@ export interface SvgCanvas {
   getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
 
   getContent(): Buffer
+  /** The SVG document drawn so far, it can be called repeatedly while drawing */
+  toBuffer(): Buffer
+  /**
+   * Write the document drawn so far to the output passed to `createCanvas`.
+   * Only the Writable output needs it, the file output is written while drawing
+   */
+  flush(): Promise<void>
+  /** Close the document and the output passed to `createCanvas`, the canvas can't be drawn after it */
+  end(): Promise<void>
+}
+
+export interface SvgConfig {
+  /**
+   * `family` references the font family names by default, `embed` writes the fonts as `@font-face` data urls
+   * so the SVG renders without the fonts installed, `paths` outlines the glyphs like `SvgExportFlag.ConvertTextToPaths`
+   */
+  text?: 'family' | 'embed' | 'paths'
+}
+
+export interface PdfConfig {
+  title?: string
+  author?: string
+  subject?: string
+  keywords?: string
+  /** The application created the content, default is `@napi-rs/canvas` */
+  creator?: string
+  /** The application converted the content to PDF, default is the version of Skia */
+  producer?: string
+  creationDate?: Date | number
+  modifiedDate?: Date | number
+  /** Conform to PDF/A-2b for archiving, default is false */
+  pdfA?: boolean
+  /**
+   * The text is selectable and searchable with the fonts embedded by default,
+   * draw it as paths instead, like `SvgExportFlag.ConvertTextToPaths`
+   */
+  textToPaths?: boolean
 }
 
-export interface AvifConfig {
+export interface PdfCanvas {
+  width: number
+  height: number
+  getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
+
+  /** Close the document and return the whole PDF, the later draws are ignored */
+  toBuffer(): Buffer
+  /**
+   * Write the pages finished so far to the Writable passed to `createCanvas`.
+   * The file output is written while drawing
+   */
+  flush(): Promise<void>
+  /** Close the document and the output passed to `createCanvas`, the later draws are ignored */
+  end(): Promise<void>
+}
+
+export interface PictureCanvas {
+  width: number
+  height: number
+  getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
+
+  /**
+   * Finish the recording and serialize it as `.skp` bytes, the fonts are embedded.
+   * The later draws are ignored
+   */
+  toBuffer(): Buffer
+  /** Finish the recording and return the picture without serializing it, the later draws are ignored */
+  toPicture(): Picture
+}
+
+/** The Skia color filter, it's assigned to `colorFilter` or the layer of `saveLayer` */
+export class ColorFilter {
+  private constructor()
+  /** The 4x5 row major matrix, the unpremultiplied colors and the offsets in the fifth column are 0 ~ 1 */
+  static matrix(matrix: number[]): ColorFilter
+  /** Blend the CSS color onto the colors */
+  static blend(color: string, blendMode: GlobalCompositeOperation | 'modulate'): ColorFilter
+  /** Multiply the RGB by the CSS color `multiply`, then add `add`, the alpha is not changed */
+  static lighting(multiply: string, add: string): ColorFilter
+  /** The luminance of the colors becomes the alpha, the RGB are black */
+  static luma(): ColorFilter
+  /** The filter applying `inner` first, then this one */
+  compose(inner: ColorFilter): ColorFilter
+}
+
+export type ColorChannel = 'r' | 'g' | 'b' | 'a'
+
+/**
+ * The Skia image filter built from the other filters, it's assigned to `filter` or the layer of `saveLayer`.
+ * The missing `input` is the pixels drawn with the filter
+ */
+export class ImageFilter {
+  private constructor()
+  /** The Gaussian blur, the sigmas are the standard deviations */
+  static blur(sigmaX: number, sigmaY: number, input?: ImageFilter | null): ImageFilter
+  /** The input drawn over its shadow in the CSS color */
+  static dropShadow(
+    dx: number,
+    dy: number,
+    sigmaX: number,
+    sigmaY: number,
+    color: string,
+    input?: ImageFilter | null,
+  ): ImageFilter
+  static colorFilter(colorFilter: ColorFilter, input?: ImageFilter | null): ImageFilter
+  static offset(dx: number, dy: number, input?: ImageFilter | null): ImageFilter
+  /** Draw the outputs of the filters over each other in order, `null` is the input pixels */
+  static merge(filters: (ImageFilter | null)[]): ImageFilter
+  /** The filter applying `inner` first, then `outer` */
+  static compose(outer: ImageFilter, inner: ImageFilter): ImageFilter
+  /** Move the pixels of the input by the channels of the displacement, 0.5 doesn't move them */
+  static displacementMap(
+    xChannel: ColorChannel,
+    yChannel: ColorChannel,
+    scale: number,
+    displacement: ImageFilter,
+    input?: ImageFilter | null,
+  ): ImageFilter
+}
+
+export class Picture {
+  /** Load the `.skp` bytes serialized by `PictureCanvas.toBuffer`, only load the pictures from the trusted sources */
+  constructor(data: Buffer)
+  /** The size of the recording */
+  readonly width: number
+  readonly height: number
+  toBuffer(): Buffer
+}
+
+export interface ExportSizeConfig {
+  /** Resize before encoding, the other side keeps the aspect ratio if only one of width and height is set */
+  width?: number
+  height?: number
+  /** `fill` by default, `contain` letterboxes with transparent pixels and `cover` crops the overflow */
+  fit?: 'fill' | 'contain' | 'cover'
+}
+export interface IccConfig {
+  /** Embedded in the output, the profile of the canvas color space is embedded by default if it isn't sRGB */
+  iccProfile?: Buffer
+}
+export interface AvifConfig extends ExportSizeConfig {
   /** 0-100 scale, 100 is lossless */
   quality?: number
   /** 0-100 scale */
@@ -313,6 +681,61 @@ export interface AvifConfig {
   /** set to '4:2:0' to use chroma subsampling, default '4:4:4' */
   chromaSubsampling?: ChromaSubsampling
 }
+export interface WebpConfig extends ExportSizeConfig, IccConfig {
+  /** 0-100 scale, default is 80 */
+  quality?: number
+  /** Encode without any loss, `quality` is ignored */
+  lossless?: boolean
+  /** 0 (fastest) - 6 (slowest but smallest), only used by the lossless compression, default is 4 */
+  effort?: number
+}
+export interface PngConfig extends ExportSizeConfig, IccConfig {
+  /** zlib compression level, 0 (fastest) - 9 (smallest), default is 6 */
+  compressionLevel?: number
+  /** Row filters the encoder could choose from, default is ['all'] */
+  filters?: ('none' | 'sub' | 'up' | 'avg' | 'paeth' | 'all')[]
+  /** Pixels per inch, written to the pHYs chunk */
+  density?: number
+  /** Written to tEXt chunks, or iTXt chunks if the value isn't Latin-1 */
+  text?: Record<string, string>
+  /** Encode as an indexed color PNG with at most this many colors, 2 - 256. Not supported by `createPNGStream` */
+  colors?: number
+  /** Used with `colors`, default is 'none' */
+  dither?: 'none' | 'ordered' | 'floyd-steinberg'
+  /** Bits per channel, default is 16 for the `'float16'` canvas and 8 for the others */
+  bitDepth?: 8 | 16
+}
+export interface JpegConfig extends ExportSizeConfig, IccConfig {
+  /** 0-100 scale, default is 92 */
+  quality?: number
+  /** Skia only writes baseline JPEG, accepted for the compatibility with node-canvas and ignored */
+  progressive?: boolean
+  /** Use 4:2:0 chroma subsampling, or 4:4:4 if false. Default is true */
+  chromaSubsampling?: boolean
+  /** CSS color the transparent pixels are composited over, they become black without it */
+  background?: string
+  /** Pixels per inch, written to the JFIF header and the EXIF X/Y resolution */
+  density?: number
+  /** EXIF orientation, 1 - 8 */
+  orientation?: number
+  /** EXIF Software */
+  software?: string
+  /** EXIF Artist */
+  artist?: string
+  /** EXIF Copyright */
+  copyright?: string
+  /** EXIF ImageDescription */
+  description?: string
+}
+export interface SaveAsConfig extends PngConfig, JpegConfig, WebpConfig, AvifConfig, RawConfig {
+  format?: 'png' | 'jpeg' | 'webp' | 'avif' | 'raw' | 'image/png' | 'image/jpeg' | 'image/webp' | 'image/avif'
+}
+export interface RawConfig extends ExportSizeConfig {
+  /** default is 'rgba', 'rgba16f' is 4 little endian half floats per pixel */
+  colorType?: 'rgba' | 'bgra' | 'rgba16f'
+  /** Keep the color components multiplied by alpha, default is false */
+  premultiplied?: boolean
+}
 /**
  * https://en.wikipedia.org/wiki/Chroma_subsampling#Types_of_sampling_and_subsampling
  * https://developer.mozilla.org/en-US/docs/Web/Media/Formats/Video_concepts
@@ -356,39 +779,275 @@ export enum ChromaSubsampling {
   Yuv400 = 3,
 }
 
+export type GpuBackend = 'auto' | 'gl' | 'metal' | 'vulkan'
+
+/**
+ * The GPU memory shared with the video decoders and the compositors without copying it.
+ * The dmabuf is imported and exported by the `'gl'` backend on Linux, the BGRA IOSurface by the `'metal'` backend on macOS
+ */
+export type ExternalTexture =
+  | {
+      type: 'dmabuf'
+      /** The imported fd is not closed by the canvas, the exported one is owned by the caller */
+      fd: number
+      /** DRM fourcc code, default is `'AB24'`, the RGBA bytes */
+      fourcc?: string
+      /** Bytes of a row */
+      stride: number
+      offset?: number
+      /** DRM format modifier, decimal or `0x` prefixed hex, like `BigInt(modifier).toString()` */
+      modifier?: string
+    }
+  | {
+      type: 'iosurface'
+      /** `IOSurfaceGetID` of the IOSurface, the size of it must be the size of the canvas */
+      ioSurface: number
+    }
+
+export interface CanvasOptions {
+  /**
+   * Render on the GPU, falls back to the CPU if there is no GPU, default is `false`.
+   * `true` is the same as `'auto'`, which is Metal on macOS and OpenGL on the others, then Vulkan if OpenGL is unavailable
+   */
+  gpu?: boolean | GpuBackend
+  /**
+   * The physical device of the `'vulkan'` backend, the index in the enumerated devices or the UUID of it,
+   * like `'8d0c2c0a-7e1f-4b2a-9b51-116d2fe80ac3'` printed by `vulkaninfo`. The first usable device by default
+   */
+  device?: number | string
+  /** MSAA samples of the GPU canvas like `4` or `8`, clamped to the maximum of the GPU. Default is `1`, which is off */
+  msaa?: number
+  /**
+   * Antialias the shapes, the clips and the images, default is `true`.
+   * `false` makes the deterministic images for the tests, the text is still antialiased
+   */
+  antialias?: boolean
+  /** The subpixel layout of the LCD text, default is `'unknown'` */
+  pixelGeometry?: 'unknown' | 'rgb' | 'bgr' | 'vrgb' | 'vbgr'
+  /**
+   * Draw into the external texture without copying it, the canvas throws if it can't be imported.
+   * The size of the canvas can't be changed, and `willReadFrequently` doesn't move it to the CPU
+   */
+  texture?: ExternalTexture
+  /**
+   * The color space the canvas draws in, default is `'srgb'`. The sRGB colors and images are converted into it,
+   * the pixels are read in it and the encoded images are tagged with it
+   */
+  colorSpace?: ColorSpace
+  /**
+   * The pixel format, `'float16'` keeps the half float pixels for HDR and the high precision compositing.
+   * `'alpha8'` only keeps the alpha for the masks, `drawImage` fills it with the `fillStyle`.
+   * Default is `'unorm8'`, the external texture keeps the format of it
+   */
+  colorType?: 'unorm8' | 'float16' | 'alpha8'
+}
+
+/**
+ * The GPU context and the resource cache shared by all the canvases created with the same `gpu` and `device` options
+ * on the current thread. Throws if the GPU backend is unavailable
+ */
+export class GpuContext {
+  constructor(options?: CanvasOptions)
+  /** Budget of the cached GPU resources in bytes, the least recently used ones are purged above it */
+  resourceCacheLimit: number
+  /** Bytes of the GPU resources in the cache, including the ones used by the canvases */
+  readonly resourceCacheUsage: number
+  /** Free the cached GPU resources not used by the canvases */
+  freeResources(): void
+}
+
 export class Canvas {
-  constructor(width: number, height: number, flag?: SvgExportFlag)
+  constructor(width: number, height: number, flag?: SvgExportFlag | 'svg' | 'pdf' | 'picture' | CanvasOptions)
+  /**
+   * A canvas of the copy of the pixels, so the decoded frames or the data arrays could be drawn on directly.
+   * @param data `width * height * 4` bytes of the pixels
+   * @param options.colorType `rgba` by default or `bgra`.
+   * @param options.premultiplied Whether the colors are multiplied by the alpha, `false` by default.
+   * @param options.colorSpace The color space of the pixels and the canvas, `srgb` by default.
+   */
+  static from(
+    data: ArrayBufferView | ArrayBuffer,
+    width: number,
+    height: number,
+    options?: { colorType?: 'rgba' | 'bgra'; premultiplied?: boolean; colorSpace?: ColorSpace },
+  ): Canvas
 
   width: number
   height: number
+  /** Whether the canvas is rendered on the GPU, `false` if it fell back to the CPU */
+  readonly gpu: boolean
+  /**
+   * Share the texture of the GPU canvas, the draws are finished on the GPU before it returns.
+   * It throws on the raster canvas, and the Metal canvas not created from an IOSurface
+   */
+  exportTexture(): ExternalTexture
   getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
+  /**
+   * Resize the canvas, it clears the pixels and resets the context like setting `width` and `height` by default.
+   * @param options.preserveContents Copy the pixels to the top left corner of the resized canvas and keep the context states.
+   * @param options.scale Scale the preserved pixels to the new size with the `imageSmoothingEnabled` and `imageSmoothingQuality` of the context.
+   */
+  resize(width: number, height: number, options?: { preserveContents?: boolean; scale?: boolean }): void
+  /**
+   * A new canvas of the pixels in the rect, without the `getImageData` and `putImageData` round trip.
+   * It has the same backend, pixel format and context attributes, the context states are not copied.
+   * The parts outside of the canvas are transparent, or white on the opaque canvas.
+   */
+  extract(x: number, y: number, width: number, height: number): Canvas
   encodeSync(format: 'webp' | 'jpeg', quality?: number): Buffer
-  encodeSync(format: 'png'): Buffer
+  encodeSync(format: 'png', cfg?: PngConfig): Buffer
   encodeSync(format: 'avif', cfg?: AvifConfig): Buffer
+  encodeSync(format: 'webp', cfg?: WebpConfig): Buffer
+  encodeSync(format: 'raw', cfg?: RawConfig): Buffer
+  // mime types are accepted as well
+  encodeSync(
+    mime: 'image/png' | 'image/jpeg' | 'image/webp' | 'image/avif',
+    cfg?: number | PngConfig | WebpConfig | AvifConfig,
+  ): Buffer
   encode(format: 'webp' | 'jpeg', quality?: number): Promise<Buffer>
-  encode(format: 'png'): Promise<Buffer>
+  encode(format: 'png', cfg?: PngConfig): Promise<Buffer>
   encode(format: 'avif', cfg?: AvifConfig): Promise<Buffer>
+  encode(format: 'webp', cfg?: WebpConfig): Promise<Buffer>
+  encode(format: 'raw', cfg?: RawConfig): Promise<Buffer>
+  // mime types are accepted as well
+  encode(
+    mime: 'image/png' | 'image/jpeg' | 'image/webp' | 'image/avif',
+    cfg?: number | PngConfig | WebpConfig | AvifConfig,
+  ): Promise<Buffer>
 
-  toBuffer(mime: 'image/png'): Buffer
+  toBuffer(mime: 'image/png', cfg?: PngConfig): Buffer
   toBuffer(mime: 'image/jpeg' | 'image/webp', quality?: number): Buffer
+  toBuffer(mime: 'image/jpeg', cfg?: JpegConfig): Buffer
   toBuffer(mime: 'image/avif', cfg?: AvifConfig): Buffer
+  toBuffer(mime: 'image/webp', cfg?: WebpConfig): Buffer
+  // raw pixels without encoding, unpremultiplied RGBA by default
+  toBuffer(mime: 'raw', cfg?: RawConfig): Buffer
   // raw pixels
   data(): Buffer
+  // encode the pixels at the time of the call, the chunks are produced as the stream is read
+  createPNGStream(cfg?: PngConfig): import('stream').Readable
+  createJPEGStream(cfg?: JpegConfig): import('stream').Readable
+  // like encode, but the pixels are copied when it's called, so the next frame could be drawn while encoding
+  encodeFrame(format: 'png' | 'jpeg' | 'webp' | 'avif' | 'raw', cfg?: number | SaveAsConfig): Promise<Buffer>
+  // like encodeFrame, but the GPU canvas reads the pixels back without blocking the thread, used by the frame streams
+  encodeAsync(format: 'png' | 'jpeg' | 'webp' | 'avif' | 'raw', cfg?: number | SaveAsConfig): Promise<Buffer>
+  // a Readable of the frames encoded every time commitFrame is called, it's also an AsyncIterable
+  createFrameStream(format: 'png' | 'jpeg' | 'webp' | 'avif' | 'raw', cfg?: number | SaveAsConfig): FrameStream
+  // encode and write on the thread pool, the format is inferred from the extension of path if not specified
+  saveAs(path: string, cfg?: SaveAsConfig): Promise<void>
   toDataURL(mime?: 'image/png'): string
   toDataURL(mime: 'image/jpeg' | 'image/webp', quality?: number): string
   toDataURL(mime?: 'image/jpeg' | 'image/webp' | 'image/png', quality?: number): string
   toDataURL(mime?: 'image/avif', cfg?: AvifConfig): string
+  toDataURL(mime: 'image/webp', cfg?: WebpConfig): string
 
   toDataURLAsync(mime?: 'image/png'): Promise<string>
   toDataURLAsync(mime: 'image/jpeg' | 'image/webp', quality?: number): Promise<string>
   toDataURLAsync(mime?: 'image/jpeg' | 'image/webp' | 'image/png', quality?: number): Promise<string>
   toDataURLAsync(mime?: 'image/avif', cfg?: AvifConfig): Promise<string>
+  toDataURLAsync(mime: 'image/webp', cfg?: WebpConfig): Promise<string>
+  // planar YUV 4:2:0 pixels, the Y plane followed by the U and V planes, or the interleaved UV plane of NV12
+  toYUV(cfg?: YuvConfig): Buffer
+  // placeholder hashes of the pixels, the canvas is downscaled to fit 100x100 for thumbhash
+  blurhash(componentsX?: number, componentsY?: number): string
+  thumbhash(): Buffer
+  // OffscreenCanvas compatible, resolves to a `Blob` if the Node.js version supports it
+  convertToBlob(options?: ImageEncodeOptions): Promise<CanvasBlob>
 }
 
-export function createCanvas(width: number, height: number): Canvas
+type Readable = import('stream').Readable
+
+export interface FrameStream extends Readable {
+  /**
+   * Copy the pixels of the canvas and encode them on the thread pool.
+   * Resolves once the frame is pushed and the consumer keeps up, so awaiting it applies backpressure
+   * The frames not pushed yet when the stream is destroyed, e.g. by a `break` in `for await`, are dropped
+   */
+  commitFrame(): Promise<void>
+  /** Ends the stream after the committed frames */
+  end(): Promise<void>
+}
+
+export interface YuvConfig {
+  /** Default is 'i420' */
+  format?: 'i420' | 'nv12'
+  /** Default is 'bt601' */
+  matrix?: 'bt601' | 'bt709'
+  /** Use 0 - 255 instead of the limited range, default is false */
+  fullRange?: boolean
+}
+
+export interface ImageEncodeOptions {
+  /** 'image/png' | 'image/jpeg' | 'image/webp' | 'image/avif', the unsupported types fall back to 'image/png' */
+  type?: string
+  /** 0 - 1 scale, used by the lossy formats */
+  quality?: number
+}
+
+export interface CanvasBlob {
+  readonly size: number
+  readonly type: string
+  arrayBuffer(): Promise<ArrayBuffer>
+  text(): Promise<string>
+}
+
+export interface AnimationConfig {
+  /** How many times the animation plays, 0 means looping forever. Default is 0 */
+  repeat?: number
+}
+
+export class CanvasAnimation {
+  /** `fps` is 30 by default */
+  constructor(width: number, height: number, fps?: number)
+  readonly width: number
+  readonly height: number
+  readonly fps: number
+  readonly frameCount: number
+  /** Copy the current pixels of the canvas as the next frame, the size must be the same as the animation */
+  addFrame(source: Canvas | SKRSContext2D): void
+  /**
+   * Encode the frames on the thread pool, the animation is empty after it's called.
+   * `raw` concatenates the unpremultiplied RGBA pixels of the frames, e.g. for `ffmpeg -f rawvideo -pix_fmt rgba`
+   */
+  finalize(format: 'apng' | 'raw', cfg?: AnimationConfig): Promise<Buffer>
+}
+
+export function createCanvas(width: number, height: number, options?: CanvasOptions): Canvas
 
 export function createCanvas(width: number, height: number, svgExportFlag: SvgExportFlag): SvgCanvas
 
+export function createCanvas(
+  width: number,
+  height: number,
+  svgExportFlag: SvgExportFlag | 'svg',
+  config: SvgConfig,
+): SvgCanvas
+
+export function createCanvas(width: number, height: number, type: 'svg'): SvgCanvas
+
+export function createCanvas(width: number, height: number, type: 'pdf', config?: PdfConfig): PdfCanvas
+
+/** Record the draw commands instead of rasterizing them */
+export function createCanvas(width: number, height: number, type: 'picture'): PictureCanvas
+
+/** Stream the SVG document to a file path or a Writable instead of keeping it in memory */
+export function createCanvas(
+  width: number,
+  height: number,
+  svgExportFlag: SvgExportFlag | 'svg',
+  output: string | import('stream').Writable,
+  config?: SvgConfig,
+): SvgCanvas
+
+/** Stream the PDF document to a file path or a Writable instead of keeping it in memory */
+export function createCanvas(
+  width: number,
+  height: number,
+  type: 'pdf',
+  output: string | import('stream').Writable,
+  config?: PdfConfig,
+): PdfCanvas
+
 interface IGlobalFonts {
   readonly families: {
     family: string
@@ -1,21 +1,31 @@
 const { platform, homedir } = require('os')
 const { join } = require('path')
+const { Readable } = require('stream')
+const { Blob } = require('buffer')
 
 const {
   clearAllCache,
   CanvasRenderingContext2D,
   CanvasElement,
   SVGCanvas,
+  PDFCanvas,
+  PictureCanvas,
+  Picture,
+  GpuContext,
   Path: Path2D,
   ImageData,
   Image,
+  ImageDecoder,
   CanvasPattern,
+  ColorFilter,
+  ImageFilter,
   GlobalFonts,
   PathOp,
   FillType,
   StrokeJoin,
   StrokeCap,
   convertSVGTextToPath,
+  CanvasAnimation,
 } = require('./js-binding')
 
 const { DOMPoint, DOMMatrix, DOMRect } = require('./geometry')
@@ -46,14 +56,395 @@ Object.defineProperty(GlobalFonts, 'has', {
   writable: false,
 })
 
-function createCanvas(width, height, flag) {
-  const isSvgBackend = typeof flag !== 'undefined'
-  return isSvgBackend ? new SVGCanvas(width, height, flag) : new CanvasElement(width, height)
+Object.defineProperty(ImageData, 'from', {
+  value: function from(data, width, height, settings = {}) {
+    // view the same memory as Uint8ClampedArray, so Buffer and other typed arrays are not copied
+    const pixels =
+      data instanceof Uint8ClampedArray ? data : new Uint8ClampedArray(data.buffer, data.byteOffset, data.byteLength)
+    return new ImageData(pixels, width, height ?? pixels.length / 4 / width, { ...settings, copy: false })
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+Object.defineProperty(Image, 'fromStream', {
+  value: function fromStream(readable, options = {}) {
+    return new Promise((resolve, reject) => {
+      const decoder = new ImageDecoder()
+      let loaded = 0
+      readable.on('data', (chunk) => {
+        loaded += chunk.length
+        try {
+          const progress = decoder.push(chunk)
+          if (typeof options.onProgress === 'function') {
+            options.onProgress({ ...progress, loaded })
+          }
+        } catch (e) {
+          readable.destroy(e)
+        }
+      })
+      readable.on('end', () => {
+        try {
+          const image = decoder.finish()
+          if (typeof options.alt === 'string') image.alt = options.alt
+          resolve(image)
+        } catch (e) {
+          reject(e)
+        }
+      })
+      readable.on('error', reject)
+    })
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+function encoderStreamToReadable(encoder) {
+  return new Readable({
+    read() {
+      try {
+        this.push(encoder.next())
+      } catch (e) {
+        this.destroy(e)
+      }
+    },
+  })
+}
+
+Object.defineProperty(CanvasElement.prototype, 'createPNGStream', {
+  value: function createPNGStream(config) {
+    return encoderStreamToReadable(this.pngEncoderStream(config))
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+Object.defineProperty(CanvasElement.prototype, 'createJPEGStream', {
+  value: function createJPEGStream(config) {
+    return encoderStreamToReadable(this.jpegEncoderStream(config))
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+// give up on the readbacks the GPU never completes, e.g. the driver hangs
+const READBACK_TIMEOUT = 10000
+
+// the GPU completes the readbacks asynchronously, poll them without blocking the event loop
+function waitForReadback(readback) {
+  return new Promise((resolve, reject) => {
+    const deadline = Date.now() + READBACK_TIMEOUT
+    const poll = () => {
+      try {
+        if (readback.poll()) {
+          resolve(readback)
+        } else if (Date.now() > deadline) {
+          reject(new Error('Read pixels from canvas timed out'))
+        } else {
+          setImmediate(poll)
+        }
+      } catch (e) {
+        reject(e)
+      }
+    }
+    poll()
+  })
+}
+
+Object.defineProperty(CanvasRenderingContext2D.prototype, 'getImageDataAsync', {
+  value: function getImageDataAsync(x, y, width, height, settings) {
+    let readback
+    try {
+      readback = this.readPixelsAsync(x, y, width, height, settings && settings.colorSpace)
+    } catch (e) {
+      return Promise.reject(e)
+    }
+    return waitForReadback(readback).then(() => readback.takeImageData())
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+Object.defineProperty(CanvasRenderingContext2D.prototype, 'getTransform', {
+  value: function getTransform() {
+    const { a, b, c, d, e, f } = this.getTransformValues()
+    return new DOMMatrix([a, b, c, d, e, f])
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+// the GPU canvases don't stall the thread on reading the pixels back, the raster canvases copy them like `encodeFrame`
+function encodeSnapshot(canvas, format, config) {
+  if (!canvas.gpu) {
+    return canvas.encodeFrame(format, config)
+  }
+  const readback = canvas.readPixelsAsync()
+  return waitForReadback(readback).then(() => canvas.encodeReadback(readback, format, config))
+}
+
+Object.defineProperty(CanvasElement.prototype, 'encodeAsync', {
+  value: function encodeAsync(format, config) {
+    try {
+      return encodeSnapshot(this, format, config)
+    } catch (e) {
+      return Promise.reject(e)
+    }
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+// encoded frames buffered before `commitFrame` waits for the consumer
+const FRAME_STREAM_HIGH_WATER_MARK = 2
+
+class FrameStream extends Readable {
+  constructor(canvas, format, config) {
+    super({ objectMode: true, highWaterMark: FRAME_STREAM_HIGH_WATER_MARK })
+    this._canvas = canvas
+    this._format = format
+    this._config = config
+    // frames are encoded concurrently, but pushed in the order they are committed
+    this._queue = Promise.resolve()
+    this._resumeRead = null
+    this._ended = false
+  }
+
+  commitFrame() {
+    if (this._ended) {
+      return Promise.reject(new Error('Can not commit frames after the stream is ended'))
+    }
+    const encoding = this._canvas.encodeAsync(this._format, this._config)
+    this._queue = this._queue
+      .then(() => encoding)
+      .then(
+        (frame) => this._pushFrame(frame),
+        (e) => {
+          this.destroy(e)
+          throw e
+        },
+      )
+    return this._queue
+  }
+
+  end() {
+    this._ended = true
+    this._queue = this._queue.then(() => {
+      this.push(null)
+    })
+    return this._queue
+  }
+
+  _pushFrame(frame) {
+    // the consumer is gone, e.g. it broke out of `for await`
+    if (this.destroyed) {
+      return
+    }
+    if (this.push(frame)) {
+      return
+    }
+    return new Promise((resolve) => {
+      this._resumeRead = resolve
+    })
+  }
+
+  _read() {
+    if (this._resumeRead) {
+      const resume = this._resumeRead
+      this._resumeRead = null
+      resume()
+    }
+  }
+
+  _destroy(err, callback) {
+    this._ended = true
+    // the pending `commitFrame` resolves, the later frames are dropped by `_pushFrame`
+    this._read()
+    callback(err)
+  }
+}
+
+Object.defineProperty(CanvasElement.prototype, 'createFrameStream', {
+  value: function createFrameStream(format, config) {
+    return new FrameStream(this, format, config)
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+const BLOB_TYPES = ['image/png', 'image/jpeg', 'image/webp', 'image/avif']
+
+// `Blob` is only available since Node.js 15.7.0
+function createBlob(buffer, type) {
+  if (typeof Blob === 'function') {
+    return new Blob([buffer], { type })
+  }
+  return {
+    size: buffer.length,
+    type,
+    arrayBuffer() {
+      return Promise.resolve(buffer.buffer.slice(buffer.byteOffset, buffer.byteOffset + buffer.length))
+    },
+    text() {
+      return Promise.resolve(buffer.toString('utf8'))
+    },
+  }
+}
+
+Object.defineProperty(CanvasElement.prototype, 'convertToBlob', {
+  value: function convertToBlob(options = {}) {
+    // https://html.spec.whatwg.org/multipage/canvas.html#dom-offscreencanvas-converttoblob
+    // unsupported types fall back to PNG, and out of range qualities to the default of the format
+    const type = typeof options.type === 'string' ? options.type.toLowerCase() : ''
+    const mime = BLOB_TYPES.includes(type) ? type : 'image/png'
+    const { quality } = options
+    const config =
+      typeof quality === 'number' && quality >= 0 && quality <= 1
+        ? mime === 'image/avif'
+          ? { quality: Math.round(quality * 100) }
+          : Math.round(quality * 100)
+        : undefined
+    return this.encode(mime, config).then((buffer) => createBlob(buffer, mime))
+  },
+  configurable: false,
+  enumerable: false,
+  writable: false,
+})
+
+// the Writable outputs of the SVG and PDF canvases, the file outputs are written by the native side
+const documentOutputs = new WeakMap()
+
+function writeChunk(writable, chunk) {
+  return new Promise((resolve, reject) => {
+    if (!chunk.length) {
+      resolve()
+      return
+    }
+    const onError = (e) => reject(e)
+    writable.once('error', onError)
+    const drained = writable.write(chunk, () => {
+      writable.removeListener('error', onError)
+      if (drained) resolve()
+    })
+    if (!drained) {
+      writable.once('drain', resolve)
+    }
+  })
+}
+
+// `finishDocument` closes the document, and returns the rest of it if it's not written to a file
+function defineDocumentOutput(Class, finishDocument) {
+  Object.defineProperty(Class.prototype, 'flush', {
+    value: function flush() {
+      const writable = documentOutputs.get(this)
+      if (!writable) {
+        return Promise.resolve()
+      }
+      return writeChunk(writable, this.takeContent())
+    },
+    configurable: false,
+    enumerable: false,
+    writable: false,
+  })
+
+  Object.defineProperty(Class.prototype, 'end', {
+    value: function end() {
+      const writable = documentOutputs.get(this)
+      let rest
+      try {
+        rest = finishDocument(this, !!writable)
+      } catch (e) {
+        return Promise.reject(e)
+      }
+      if (!writable) {
+        return Promise.resolve()
+      }
+      return new Promise((resolve, reject) => {
+        writable.once('error', reject)
+        writable.end(rest, () => {
+          writable.removeListener('error', reject)
+          resolve()
+        })
+      })
+    },
+    configurable: false,
+    enumerable: false,
+    writable: false,
+  })
+}
+
+// `getContent` returns the rest of the SVG document with the closing tag
+defineDocumentOutput(SVGCanvas, (canvas, toWritable) => (toWritable ? canvas.getContent() : canvas.finishFile()))
+
+defineDocumentOutput(PDFCanvas, (canvas, toWritable) => {
+  canvas.finish()
+  return toWritable ? canvas.takeContent() : undefined
+})
+
+function isWritable(output) {
+  return !!output && typeof output.write === 'function'
+}
+
+function createDocumentCanvas(Class, args, output, ...rest) {
+  const canvas = new Class(...args, typeof output === 'string' ? output : undefined, ...rest)
+  if (isWritable(output)) {
+    documentOutputs.set(canvas, output)
+  }
+  return canvas
+}
+
+function toTimestamp(date) {
+  return date instanceof Date ? date.getTime() : date
+}
+
+function createCanvas(width, height, flag, output, config) {
+  if (typeof flag === 'undefined') {
+    return new CanvasElement(width, height)
+  }
+  // the options of the raster canvas, like `{ gpu: true }`
+  if (typeof flag === 'object' && flag !== null) {
+    return new CanvasElement(width, height, flag)
+  }
+  // the config can take the place of the output
+  if (typeof config === 'undefined' && typeof output === 'object' && output !== null && !isWritable(output)) {
+    config = output
+    output = undefined
+  }
+  if (flag === 'picture') {
+    return new PictureCanvas(width, height)
+  }
+  if (flag === 'pdf') {
+    const pdfConfig = config && {
+      ...config,
+      creationDate: toTimestamp(config.creationDate),
+      modifiedDate: toTimestamp(config.modifiedDate),
+    }
+    return createDocumentCanvas(PDFCanvas, [width, height], output, pdfConfig)
+  }
+  // `'svg'` keeps the text as `<text>` elements, so the output stays editable
+  const svgExportFlag = flag === 'svg' ? undefined : flag
+  return createDocumentCanvas(SVGCanvas, [width, height, svgExportFlag], output, config)
 }
 
 class Canvas {
-  constructor(width, height, flag) {
-    return createCanvas(width, height, flag)
+  constructor(width, height, flag, output, config) {
+    return createCanvas(width, height, flag, output, config)
+  }
+
+  static from(data, width, height, options) {
+    // view the same memory as Uint8ClampedArray like ImageData.from, the pixels are copied to the canvas
+    const pixels = ArrayBuffer.isView(data)
+      ? new Uint8ClampedArray(data.buffer, data.byteOffset, data.byteLength)
+      : new Uint8ClampedArray(data)
+    return CanvasElement.from(pixels, width, height, options)
   }
 }
 
@@ -82,6 +473,10 @@ module.exports = {
   Path2D,
   ImageData,
   Image,
+  Picture,
+  GpuContext,
+  ColorFilter,
+  ImageFilter,
   PathOp,
   FillType,
   StrokeCap,
@@ -93,4 +488,5 @@ module.exports = {
   DOMMatrix,
   DOMRect,
   loadImage,
+  CanvasAnimation,
 }
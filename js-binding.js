@@ -225,6 +225,7 @@ const {
   CanvasGradient,
   ImageData,
   Image,
+  ImageDecoder,
   PathOp,
   FillType,
   StrokeCap,
@@ -236,6 +237,7 @@ const {
   SVGCanvas,
   clearAllCache,
   GlobalFonts,
+  CanvasAnimation,
 } = nativeBinding
 
 module.exports.ChromaSubsampling = ChromaSubsampling
@@ -244,6 +246,7 @@ module.exports.CanvasRenderingContext2D = CanvasRenderingContext2D
 module.exports.CanvasGradient = CanvasGradient
 module.exports.ImageData = ImageData
 module.exports.Image = Image
+module.exports.ImageDecoder = ImageDecoder
 module.exports.PathOp = PathOp
 module.exports.FillType = FillType
 module.exports.StrokeCap = StrokeCap
@@ -255,3 +258,4 @@ module.exports.CanvasElement = CanvasElement
 module.exports.SVGCanvas = SVGCanvas
 module.exports.clearAllCache = clearAllCache
 module.exports.GlobalFonts = GlobalFonts
+module.exports.CanvasAnimation = CanvasAnimation
@@ -34,6 +34,8 @@ let ExtraSkiaBuildFlag = ''
 let ExtraCflags
 let ExtraLdFlags
 let ExtraAsmFlags
+// build the GPU backends, Metal on macOS, OpenGL and Vulkan on Linux, the GPU canvas falls back to the CPU without them
+const GPU = !!process.env.SKIA_GPU
 
 const GN_ARGS = [
   `is_official_build=false`,
@@ -44,7 +46,7 @@ const GN_ARGS = [
   `paragraph_tests_enabled=false`,
   `skia_enable_android_utils=false`,
   `skia_enable_discrete_gpu=false`,
-  `skia_enable_gpu=false`,
+  `skia_enable_gpu=${GPU}`,
   `skia_enable_particles=true`,
   `skia_enable_pdf=true`,
   `skia_enable_skottie=false`,
@@ -55,7 +57,7 @@ const GN_ARGS = [
   `skia_enable_sktext=true`,
   `skia_pdf_subset_harfbuzz=true`,
   `skia_use_expat=true`,
-  `skia_use_gl=false`,
+  `skia_use_gl=${GPU && PLATFORM_NAME !== 'darwin'}`,
   `skia_use_harfbuzz=true`,
   `skia_use_icu=true`,
   `skia_use_sfntly=false`,
@@ -78,9 +80,16 @@ const GN_ARGS = [
   `skia_use_system_icu=false`,
   `skia_use_system_harfbuzz=false`,
   `skia_use_lua=false`,
+  `skia_use_metal=${GPU && PLATFORM_NAME === 'darwin'}`,
   `skia_use_piex=false`,
+  `skia_use_vulkan=${GPU && PLATFORM_NAME === 'linux'}`,
 ]
 
+if (GPU && PLATFORM_NAME === 'linux') {
+  // the headless context without X11 or Wayland
+  GN_ARGS.push(`skia_use_egl=true`)
+}
+
 switch (PLATFORM_NAME) {
   case 'win32':
     ExtraCflagsCC =
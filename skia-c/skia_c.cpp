@@ -11,12 +11,672 @@
 #define MATRIX_CAST reinterpret_cast<SkMatrix *>(c_matrix)
 #define MASK_FILTER_CAST reinterpret_cast<SkMaskFilter *>(c_mask_filter)
 #define IMAGE_FILTER_CAST reinterpret_cast<SkImageFilter *>(c_image_filter)
+#define COLOR_FILTER_CAST reinterpret_cast<SkColorFilter *>(c_color_filter)
 #define TYPEFACE_CAST reinterpret_cast<SkTypeface *>(c_typeface)
+#define GPU_CONTEXT_CAST reinterpret_cast<GrDirectContext *>(c_gpu_context)
 #define COLOR_SPACE_CAST cs == 0 ? SkColorSpace::MakeSRGB() : SkColorSpace::MakeRGB(SkNamedTransferFn::kSRGB, SkNamedGamut::kDisplayP3)
 
 #define MAX_LAYOUT_WIDTH 100000
 #define HANGING_AS_PERCENT_OF_ASCENT 80
 
+size_t SkiacBufferedStream::read(void *dst, size_t size)
+{
+  auto read_size = this->peek(dst, size);
+  position += read_size;
+  return read_size;
+}
+
+size_t SkiacBufferedStream::peek(void *dst, size_t size) const
+{
+  auto available = buffer->bytes.size() - (position - buffer->offset);
+  auto read_size = std::min(size, available);
+  if (dst && read_size > 0)
+  {
+    memcpy(dst, buffer->bytes.data() + (position - buffer->offset), read_size);
+  }
+  return read_size;
+}
+
+bool SkiacBufferedStream::isAtEnd() const
+{
+  return buffer->finished && position >= buffer->offset + buffer->bytes.size();
+}
+
+bool SkiacBufferedStream::rewind()
+{
+  if (buffer->offset > 0)
+  {
+    return false;
+  }
+  position = 0;
+  return true;
+}
+
+void SkiacBufferedStream::dropConsumed()
+{
+  auto consumed = position - buffer->offset;
+  buffer->bytes.erase(buffer->bytes.begin(), buffer->bytes.begin() + consumed);
+  buffer->offset = position;
+}
+
+bool SkiacChunkWStream::write(const void *buffer, size_t size)
+{
+  auto data = static_cast<const uint8_t *>(buffer);
+  bytes.insert(bytes.end(), data, data + size);
+  written += size;
+  return true;
+}
+
+bool SkiacFileWStream::write(const void *buffer, size_t size)
+{
+  if (!file)
+  {
+    return false;
+  }
+  written += size;
+  return file->write(buffer, size);
+}
+
+void SkiacFileWStream::flush()
+{
+  if (file)
+  {
+    file->flush();
+  }
+}
+
+void SkiacFileWStream::close()
+{
+  if (file)
+  {
+    file->flush();
+    delete file;
+    file = nullptr;
+  }
+}
+
+void SkiacTextToPathCanvas::onDrawTextBlob(const SkTextBlob *blob, SkScalar x, SkScalar y, const SkPaint &paint)
+{
+  SkPath path;
+  SkTextBlob::Iter::ExperimentalRun run;
+  SkTextBlob::Iter iter(*blob);
+  while (iter.experimentalNext(&run))
+  {
+    for (int i = 0; i < run.count; ++i)
+    {
+      SkPath glyph_path;
+      if (run.font.getPath(run.glyphs[i], &glyph_path))
+      {
+        path.addPath(glyph_path, x + run.positions[i].fX, y + run.positions[i].fY);
+      }
+    }
+  }
+  this->drawPath(path, paint);
+}
+
+void SkiacFontRecordingCanvas::onDrawTextBlob(const SkTextBlob *blob, SkScalar x, SkScalar y, const SkPaint &paint)
+{
+  SkTextBlob::Iter::ExperimentalRun run;
+  SkTextBlob::Iter iter(*blob);
+  while (iter.experimentalNext(&run))
+  {
+    auto typeface = run.font.refTypefaceOrDefault();
+    auto recorded = std::any_of(typefaces.begin(), typefaces.end(), [&](const sk_sp<SkTypeface> &t)
+                                { return t->uniqueID() == typeface->uniqueID(); });
+    if (!recorded)
+    {
+      typefaces.push_back(typeface);
+    }
+  }
+  SkNWayCanvas::onDrawTextBlob(blob, x, y, paint);
+}
+
+static const char *skiac_font_mime_type(const SkData *data)
+{
+  if (data->size() >= 4)
+  {
+    auto bytes = data->bytes();
+    if (memcmp(bytes, "wOF2", 4) == 0)
+    {
+      return "font/woff2";
+    }
+    if (memcmp(bytes, "wOFF", 4) == 0)
+    {
+      return "font/woff";
+    }
+    if (memcmp(bytes, "OTTO", 4) == 0)
+    {
+      return "font/otf";
+    }
+  }
+  return "font/ttf";
+}
+
+void SkiacFontRecordingCanvas::writeStyle(SkWStream *stream) const
+{
+  if (typefaces.empty())
+  {
+    return;
+  }
+  stream->writeText("<style><![CDATA[");
+  for (auto &typeface : typefaces)
+  {
+    int ttc_index = 0;
+    auto font_stream = typeface->openStream(&ttc_index);
+    // the faces in a collection can't be referenced by the data url
+    if (!font_stream || ttc_index != 0)
+    {
+      continue;
+    }
+    auto data = SkData::MakeFromStream(font_stream.get(), font_stream->getLength());
+    if (!data)
+    {
+      continue;
+    }
+    SkString family;
+    typeface->getFamilyName(&family);
+    auto font_style = typeface->fontStyle();
+    const char *slant = font_style.slant() == SkFontStyle::kItalic_Slant    ? "italic"
+                        : font_style.slant() == SkFontStyle::kOblique_Slant ? "oblique"
+                                                                            : "normal";
+    std::vector<char> encoded(SkBase64::EncodedSize(data->size()));
+    auto encoded_size = SkBase64::Encode(data->data(), data->size(), encoded.data());
+    stream->writeText("@font-face{font-family:\"");
+    stream->writeText(family.c_str());
+    stream->writeText("\";font-weight:");
+    stream->writeDecAsText(font_style.weight());
+    stream->writeText(";font-style:");
+    stream->writeText(slant);
+    stream->writeText(";src:url(data:");
+    stream->writeText(skiac_font_mime_type(data.get()));
+    stream->writeText(";base64,");
+    stream->write(encoded.data(), encoded_size);
+    stream->writeText(")}");
+  }
+  stream->writeText("]]></style>");
+}
+
+SkCanvas *skiac_pdf_document::beginPage(float width, float height)
+{
+  releasePage();
+  auto page = document->beginPage(width, height);
+  if (!text_to_paths)
+  {
+    return page;
+  }
+  text_to_path_canvas = std::make_unique<SkiacTextToPathCanvas>(page);
+  return text_to_path_canvas.get();
+}
+
+void skiac_pdf_document::releasePage()
+{
+  if (text_to_path_canvas)
+  {
+    text_to_path_canvas->removeAll();
+    text_to_path_canvas.reset();
+  }
+}
+
+static SkPDF::DateTime skiac_pdf_date_time_to_sk(const skiac_pdf_date_time &c_date_time)
+{
+  SkPDF::DateTime date_time;
+  date_time.fTimeZoneMinutes = c_date_time.time_zone_minutes;
+  date_time.fYear = c_date_time.year;
+  date_time.fMonth = c_date_time.month;
+  date_time.fDayOfWeek = c_date_time.day_of_week;
+  date_time.fDay = c_date_time.day;
+  date_time.fHour = c_date_time.hour;
+  date_time.fMinute = c_date_time.minute;
+  date_time.fSecond = c_date_time.second;
+  return date_time;
+}
+
+#ifdef SKIAC_GL
+SkiacGLContext::~SkiacGLContext()
+{
+  if (direct_context)
+  {
+    direct_context->releaseResourcesAndAbandonContext();
+    direct_context.reset();
+  }
+  if (display != EGL_NO_DISPLAY)
+  {
+    eglMakeCurrent(display, EGL_NO_SURFACE, EGL_NO_SURFACE, EGL_NO_CONTEXT);
+    if (context != EGL_NO_CONTEXT)
+    {
+      eglDestroyContext(display, context);
+    }
+    if (surface != EGL_NO_SURFACE)
+    {
+      eglDestroySurface(display, surface);
+    }
+    eglTerminate(display);
+  }
+}
+
+bool SkiacGLContext::init()
+{
+  display = eglGetDisplay(EGL_DEFAULT_DISPLAY);
+  if (display == EGL_NO_DISPLAY || !eglInitialize(display, nullptr, nullptr))
+  {
+    display = EGL_NO_DISPLAY;
+    return false;
+  }
+  const EGLint config_attribs[] = {
+      EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+      EGL_RENDERABLE_TYPE, EGL_OPENGL_ES2_BIT,
+      EGL_RED_SIZE, 8,
+      EGL_GREEN_SIZE, 8,
+      EGL_BLUE_SIZE, 8,
+      EGL_ALPHA_SIZE, 8,
+      EGL_STENCIL_SIZE, 8,
+      EGL_NONE};
+  EGLConfig config;
+  EGLint config_count = 0;
+  if (!eglChooseConfig(display, config_attribs, &config, 1, &config_count) || config_count < 1)
+  {
+    return false;
+  }
+  // nothing is presented, the 1x1 pbuffer only makes the context current
+  const EGLint pbuffer_attribs[] = {EGL_WIDTH, 1, EGL_HEIGHT, 1, EGL_NONE};
+  surface = eglCreatePbufferSurface(display, config, pbuffer_attribs);
+  if (surface == EGL_NO_SURFACE || !eglBindAPI(EGL_OPENGL_ES_API))
+  {
+    return false;
+  }
+  const EGLint context_attribs[] = {EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE};
+  context = eglCreateContext(display, config, EGL_NO_CONTEXT, context_attribs);
+  if (context == EGL_NO_CONTEXT || !eglMakeCurrent(display, surface, surface, context))
+  {
+    return false;
+  }
+  auto gl_interface = GrGLMakeNativeInterface();
+  if (!gl_interface)
+  {
+    return false;
+  }
+  direct_context = GrDirectContext::MakeGL(gl_interface);
+  return direct_context != nullptr;
+}
+
+GrDirectContext *SkiacGLContext::current()
+{
+  thread_local std::unique_ptr<SkiacGLContext> gpu_context;
+  thread_local bool initialized = false;
+  if (!initialized)
+  {
+    initialized = true;
+    auto candidate = std::make_unique<SkiacGLContext>();
+    if (candidate->init())
+    {
+      gpu_context = std::move(candidate);
+    }
+  }
+  if (!gpu_context)
+  {
+    return nullptr;
+  }
+  // other libraries in the process could switch the current context
+  if (eglGetCurrentContext() != gpu_context->context)
+  {
+    eglMakeCurrent(gpu_context->display, gpu_context->surface, gpu_context->surface, gpu_context->context);
+  }
+  return gpu_context->direct_context.get();
+}
+
+#ifndef DRM_FORMAT_MOD_INVALID
+#define DRM_FORMAT_MOD_INVALID 0x00ffffffffffffffULL
+#endif
+
+// the EGL image and the GL texture of the imported dmabuf, deleted with the surface
+struct SkiacGLExternalTexture
+{
+  EGLDisplay display;
+  EGLImageKHR image;
+  GLuint texture;
+};
+
+static void skiac_gl_release_external_texture(void *context)
+{
+  auto external = static_cast<SkiacGLExternalTexture *>(context);
+  auto destroy_image = reinterpret_cast<PFNEGLDESTROYIMAGEKHRPROC>(eglGetProcAddress("eglDestroyImageKHR"));
+  glDeleteTextures(1, &external->texture);
+  destroy_image(external->display, external->image);
+  delete external;
+}
+
+// EGL_EXT_image_dma_buf_import, the fd is not taken by EGL
+static sk_sp<SkSurface> skiac_gl_import_dmabuf(int width, int height, sk_sp<SkColorSpace> color_space, const skiac_external_texture &texture, int sample_count, const SkSurfaceProps &props)
+{
+  auto context = SkiacGLContext::current();
+  if (!context)
+  {
+    return nullptr;
+  }
+  auto create_image = reinterpret_cast<PFNEGLCREATEIMAGEKHRPROC>(eglGetProcAddress("eglCreateImageKHR"));
+  auto image_target_texture = reinterpret_cast<PFNGLEGLIMAGETARGETTEXTURE2DOESPROC>(eglGetProcAddress("glEGLImageTargetTexture2DOES"));
+  if (!create_image || !image_target_texture)
+  {
+    return nullptr;
+  }
+  std::vector<EGLint> attribs = {
+      EGL_WIDTH, width,
+      EGL_HEIGHT, height,
+      EGL_LINUX_DRM_FOURCC_EXT, static_cast<EGLint>(texture.fourcc),
+      EGL_DMA_BUF_PLANE0_FD_EXT, texture.fd,
+      EGL_DMA_BUF_PLANE0_OFFSET_EXT, static_cast<EGLint>(texture.offset),
+      EGL_DMA_BUF_PLANE0_PITCH_EXT, static_cast<EGLint>(texture.stride)};
+  if (texture.modifier != DRM_FORMAT_MOD_INVALID)
+  {
+    // EGL_EXT_image_dma_buf_import_modifiers
+    attribs.insert(attribs.end(), {EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT, static_cast<EGLint>(texture.modifier & 0xffffffff),
+                                   EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT, static_cast<EGLint>(texture.modifier >> 32)});
+  }
+  attribs.push_back(EGL_NONE);
+  auto display = eglGetCurrentDisplay();
+  auto image = create_image(display, EGL_NO_CONTEXT, EGL_LINUX_DMA_BUF_EXT, nullptr, attribs.data());
+  if (image == EGL_NO_IMAGE_KHR)
+  {
+    return nullptr;
+  }
+  auto external = new SkiacGLExternalTexture{display, image, 0};
+  glGenTextures(1, &external->texture);
+  glBindTexture(GL_TEXTURE_2D, external->texture);
+  image_target_texture(GL_TEXTURE_2D, image);
+  // the texture binding is changed behind Skia
+  context->resetContext(kTextureBinding_GrGLBackendState);
+  GrGLTextureInfo info = {GL_TEXTURE_2D, external->texture, GL_RGBA8_OES};
+  GrBackendTexture backend_texture(width, height, GrMipmapped::kNo, info);
+  // the release proc is called if the creation fails as well
+  return SkSurface::MakeFromBackendTexture(
+      context, backend_texture, kTopLeft_GrSurfaceOrigin, sample_count, kRGBA_8888_SkColorType,
+      color_space, &props, skiac_gl_release_external_texture, external);
+}
+
+// EGL_MESA_image_dma_buf_export, the surface must be flushed before
+static bool skiac_gl_export_dmabuf(SkSurface *surface, skiac_external_texture *texture)
+{
+  auto backend_texture = surface->getBackendTexture(SkSurface::kFlushRead_BackendHandleAccess);
+  GrGLTextureInfo info;
+  if (!backend_texture.isValid() || !backend_texture.getGLTextureInfo(&info) || info.fTarget != GL_TEXTURE_2D)
+  {
+    return false;
+  }
+  auto create_image = reinterpret_cast<PFNEGLCREATEIMAGEKHRPROC>(eglGetProcAddress("eglCreateImageKHR"));
+  auto destroy_image = reinterpret_cast<PFNEGLDESTROYIMAGEKHRPROC>(eglGetProcAddress("eglDestroyImageKHR"));
+  auto export_query = reinterpret_cast<PFNEGLEXPORTDMABUFIMAGEQUERYMESAPROC>(eglGetProcAddress("eglExportDMABUFImageQueryMESA"));
+  auto export_image = reinterpret_cast<PFNEGLEXPORTDMABUFIMAGEMESAPROC>(eglGetProcAddress("eglExportDMABUFImageMESA"));
+  if (!create_image || !destroy_image || !export_query || !export_image)
+  {
+    return false;
+  }
+  auto display = eglGetCurrentDisplay();
+  auto buffer = reinterpret_cast<EGLClientBuffer>(static_cast<uintptr_t>(info.fID));
+  auto image = create_image(display, eglGetCurrentContext(), EGL_GL_TEXTURE_2D_KHR, buffer, nullptr);
+  if (image == EGL_NO_IMAGE_KHR)
+  {
+    return false;
+  }
+  int fourcc = 0;
+  int planes = 0;
+  EGLuint64KHR modifier = DRM_FORMAT_MOD_INVALID;
+  int fd = -1;
+  EGLint stride = 0;
+  EGLint offset = 0;
+  // the multi-planar layouts are not supported by the import either
+  auto exported = export_query(display, image, &fourcc, &planes, &modifier) && planes == 1 &&
+                  export_image(display, image, &fd, &stride, &offset);
+  // the dmabuf keeps the memory alive
+  destroy_image(display, image);
+  if (!exported)
+  {
+    return false;
+  }
+  texture->kind = 0;
+  texture->fd = fd;
+  texture->fourcc = static_cast<uint32_t>(fourcc);
+  texture->stride = static_cast<uint32_t>(stride);
+  texture->offset = static_cast<uint32_t>(offset);
+  texture->modifier = modifier;
+  return true;
+}
+#endif
+
+#ifdef SKIAC_VULKAN
+#define SKIAC_VK_PROC(name) reinterpret_cast<PFN_##name>(get_instance_proc(instance, #name))
+
+SkiacVulkanContext::~SkiacVulkanContext()
+{
+  if (instance != VK_NULL_HANDLE)
+  {
+    auto destroy_device = SKIAC_VK_PROC(vkDestroyDevice);
+    for (auto &device : devices)
+    {
+      if (device.direct_context)
+      {
+        device.direct_context->releaseResourcesAndAbandonContext();
+        device.direct_context.reset();
+      }
+      if (device.device != VK_NULL_HANDLE)
+      {
+        destroy_device(device.device, nullptr);
+      }
+    }
+    SKIAC_VK_PROC(vkDestroyInstance)(instance, nullptr);
+  }
+  if (library)
+  {
+    dlclose(library);
+  }
+}
+
+bool SkiacVulkanContext::init()
+{
+  library = dlopen("libvulkan.so.1", RTLD_NOW | RTLD_LOCAL);
+  if (!library)
+  {
+    return false;
+  }
+  get_instance_proc = reinterpret_cast<PFN_vkGetInstanceProcAddr>(dlsym(library, "vkGetInstanceProcAddr"));
+  if (!get_instance_proc)
+  {
+    return false;
+  }
+  VkApplicationInfo app_info = {};
+  app_info.sType = VK_STRUCTURE_TYPE_APPLICATION_INFO;
+  app_info.pApplicationName = "skia-canvas";
+  // the device UUID is queried by `vkGetPhysicalDeviceProperties2`
+  app_info.apiVersion = VK_API_VERSION_1_1;
+  VkInstanceCreateInfo instance_info = {};
+  instance_info.sType = VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO;
+  instance_info.pApplicationInfo = &app_info;
+  auto create_instance = SKIAC_VK_PROC(vkCreateInstance);
+  if (!create_instance || create_instance(&instance_info, nullptr, &instance) != VK_SUCCESS)
+  {
+    instance = VK_NULL_HANDLE;
+    return false;
+  }
+  auto enumerate_physical_devices = SKIAC_VK_PROC(vkEnumeratePhysicalDevices);
+  uint32_t count = 0;
+  if (enumerate_physical_devices(instance, &count, nullptr) != VK_SUCCESS || count == 0)
+  {
+    return false;
+  }
+  std::vector<VkPhysicalDevice> physical_devices(count);
+  if (enumerate_physical_devices(instance, &count, physical_devices.data()) != VK_SUCCESS)
+  {
+    return false;
+  }
+  devices.resize(count);
+  for (uint32_t i = 0; i < count; i++)
+  {
+    devices[i].physical_device = physical_devices[i];
+  }
+  return true;
+}
+
+GrDirectContext *SkiacVulkanContext::device_context(Device &device)
+{
+  if (device.initialized)
+  {
+    return device.direct_context.get();
+  }
+  device.initialized = true;
+  // nothing is presented, any queue family with the graphics queues is fine
+  auto get_queue_families = SKIAC_VK_PROC(vkGetPhysicalDeviceQueueFamilyProperties);
+  uint32_t family_count = 0;
+  get_queue_families(device.physical_device, &family_count, nullptr);
+  std::vector<VkQueueFamilyProperties> families(family_count);
+  get_queue_families(device.physical_device, &family_count, families.data());
+  auto family = std::find_if(families.begin(), families.end(), [](const VkQueueFamilyProperties &properties)
+                             { return properties.queueFlags & VK_QUEUE_GRAPHICS_BIT; });
+  if (family == families.end())
+  {
+    return nullptr;
+  }
+  uint32_t queue_index = static_cast<uint32_t>(family - families.begin());
+  float priority = 1.0f;
+  VkDeviceQueueCreateInfo queue_info = {};
+  queue_info.sType = VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO;
+  queue_info.queueFamilyIndex = queue_index;
+  queue_info.queueCount = 1;
+  queue_info.pQueuePriorities = &priority;
+  VkPhysicalDeviceFeatures features = {};
+  VkDeviceCreateInfo device_info = {};
+  device_info.sType = VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO;
+  device_info.queueCreateInfoCount = 1;
+  device_info.pQueueCreateInfos = &queue_info;
+  device_info.pEnabledFeatures = &features;
+  if (SKIAC_VK_PROC(vkCreateDevice)(device.physical_device, &device_info, nullptr, &device.device) != VK_SUCCESS)
+  {
+    device.device = VK_NULL_HANDLE;
+    return nullptr;
+  }
+  VkQueue queue = VK_NULL_HANDLE;
+  SKIAC_VK_PROC(vkGetDeviceQueue)(device.device, queue_index, 0, &queue);
+  auto instance_proc = get_instance_proc;
+  auto device_proc = SKIAC_VK_PROC(vkGetDeviceProcAddr);
+  GrVkGetProc get_proc = [instance_proc, device_proc](const char *name, VkInstance vk_instance, VkDevice vk_device)
+  {
+    if (vk_device != VK_NULL_HANDLE)
+    {
+      return device_proc(vk_device, name);
+    }
+    return instance_proc(vk_instance, name);
+  };
+  GrVkExtensions extensions;
+  extensions.init(get_proc, instance, device.physical_device, 0, nullptr, 0, nullptr);
+  GrVkBackendContext backend_context;
+  backend_context.fInstance = instance;
+  backend_context.fPhysicalDevice = device.physical_device;
+  backend_context.fDevice = device.device;
+  backend_context.fQueue = queue;
+  backend_context.fGraphicsQueueIndex = queue_index;
+  backend_context.fMaxAPIVersion = VK_API_VERSION_1_1;
+  backend_context.fVkExtensions = &extensions;
+  backend_context.fDeviceFeatures = &features;
+  backend_context.fGetProc = get_proc;
+  device.direct_context = GrDirectContext::MakeVulkan(backend_context);
+  return device.direct_context.get();
+}
+
+GrDirectContext *SkiacVulkanContext::current(int device_index, const uint8_t *device_uuid)
+{
+  thread_local std::unique_ptr<SkiacVulkanContext> vulkan_context;
+  thread_local bool initialized = false;
+  if (!initialized)
+  {
+    initialized = true;
+    auto candidate = std::make_unique<SkiacVulkanContext>();
+    if (candidate->init())
+    {
+      vulkan_context = std::move(candidate);
+    }
+  }
+  if (!vulkan_context)
+  {
+    return nullptr;
+  }
+  auto &devices = vulkan_context->devices;
+  if (device_uuid)
+  {
+    auto instance = vulkan_context->instance;
+    auto get_instance_proc = vulkan_context->get_instance_proc;
+    auto get_properties = SKIAC_VK_PROC(vkGetPhysicalDeviceProperties2);
+    for (auto &device : devices)
+    {
+      VkPhysicalDeviceIDProperties id_properties = {};
+      id_properties.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_ID_PROPERTIES;
+      VkPhysicalDeviceProperties2 properties = {};
+      properties.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2;
+      properties.pNext = &id_properties;
+      get_properties(device.physical_device, &properties);
+      if (memcmp(id_properties.deviceUUID, device_uuid, VK_UUID_SIZE) == 0)
+      {
+        return vulkan_context->device_context(device);
+      }
+    }
+    return nullptr;
+  }
+  if (device_index >= 0)
+  {
+    if (static_cast<size_t>(device_index) >= devices.size())
+    {
+      return nullptr;
+    }
+    return vulkan_context->device_context(devices[device_index]);
+  }
+  for (auto &device : devices)
+  {
+    if (auto context = vulkan_context->device_context(device))
+    {
+      return context;
+    }
+  }
+  return nullptr;
+}
+
+#undef SKIAC_VK_PROC
+#endif
+
+#ifdef SKIAC_GPU
+// the values of `GpuBackend` in sk.rs
+enum class SkiacGpuBackend : uint8_t
+{
+  Auto,
+  GL,
+  Metal,
+  Vulkan,
+};
+
+// `Auto` prefers the native backend of the platform, the device is only selected by the Vulkan backend
+static GrDirectContext *skiac_gpu_context(uint8_t backend, int device_index, const uint8_t *device_uuid)
+{
+  auto kind = static_cast<SkiacGpuBackend>(backend);
+  GrDirectContext *context = nullptr;
+#ifdef SKIAC_METAL
+  if (kind == SkiacGpuBackend::Auto || kind == SkiacGpuBackend::Metal)
+  {
+    context = skiac_metal_context();
+  }
+#endif
+#ifdef SKIAC_GL
+  if (!context && (kind == SkiacGpuBackend::Auto || kind == SkiacGpuBackend::GL))
+  {
+    context = SkiacGLContext::current();
+  }
+#endif
+#ifdef SKIAC_VULKAN
+  // the servers without the GL drivers
+  if (!context && (kind == SkiacGpuBackend::Auto || kind == SkiacGpuBackend::Vulkan))
+  {
+    context = SkiacVulkanContext::current(device_index, device_uuid);
+  }
+#endif
+  return context;
+}
+#endif
+
 extern "C"
 {
 
@@ -37,6 +697,15 @@ extern "C"
     return SkSamplingOptions(SkFilterMode::kNearest, SkMipmapMode::kNone);
   }
 
+  static SkSamplingOptions conv_from_sampling_options(const skiac_sampling_options &c_sampling)
+  {
+    if (c_sampling.use_cubic)
+    {
+      return SkSamplingOptions(SkCubicResampler{c_sampling.cubic_b, c_sampling.cubic_c});
+    }
+    return SkSamplingOptions((SkFilterMode)c_sampling.filter_mode, (SkMipmapMode)c_sampling.mipmap_mode);
+  }
+
   static SkMatrix conv_from_transform(const skiac_transform &c_ts)
   {
     return SkMatrix::MakeAll(c_ts.a, c_ts.c, c_ts.e,
@@ -63,13 +732,13 @@ extern "C"
 
   // Surface
 
-  static SkSurface *skiac_surface_create(int width, int height, SkAlphaType alphaType, uint8_t cs)
+  static SkSurface *skiac_surface_create(int width, int height, SkAlphaType alphaType, uint8_t cs, const SkSurfaceProps *props = nullptr, SkColorType color_type = kRGBA_8888_SkColorType)
   {
     // Init() is idempotent, so can be called more than once with no adverse effect.
     SkGraphics::Init();
     auto color_space = COLOR_SPACE_CAST;
-    auto info = SkImageInfo::Make(width, height, kRGBA_8888_SkColorType, alphaType, color_space);
-    auto surface = SkSurface::MakeRaster(info);
+    auto info = SkImageInfo::Make(width, height, color_type, alphaType, color_space);
+    auto surface = SkSurface::MakeRaster(info, props);
 
     if (surface)
     {
@@ -82,11 +751,29 @@ extern "C"
     }
   }
 
-  void skiac_surface_create_svg(skiac_svg_surface *c_surface, int w, int h, int alphaType, uint32_t flag, uint8_t cs)
+  // The texture backed snapshot of the GPU surface is read back to the memory,
+  // unless it's drawn to a canvas of the same GPU context
+  static sk_sp<SkImage> skiac_surface_snapshot(SkSurface *surface, SkCanvas *canvas = nullptr)
   {
-    auto w_stream = new SkDynamicMemoryWStream();
-
-    auto canvas = SkSVGCanvas::Make(SkRect::MakeWH(w, h), w_stream, flag);
+    auto image = surface->makeImageSnapshot();
+    if (!image || !image->isTextureBacked() || (canvas && canvas->recordingContext() == surface->recordingContext()))
+    {
+      return image;
+    }
+    SkBitmap bitmap;
+    if (!bitmap.tryAllocPixels(surface->imageInfo()) || !surface->readPixels(bitmap, 0, 0))
+    {
+      return nullptr;
+    }
+    bitmap.setImmutable();
+    return bitmap.asImage();
+  }
+
+  void skiac_surface_create_svg(skiac_svg_surface *c_surface, int w, int h, int alphaType, uint32_t flag, uint8_t cs)
+  {
+    auto w_stream = new SkDynamicMemoryWStream();
+
+    auto canvas = SkSVGCanvas::Make(SkRect::MakeWH(w, h), w_stream, flag);
     if (!canvas.get())
     {
       return;
@@ -101,6 +788,105 @@ extern "C"
     c_surface->canvas = reinterpret_cast<skiac_canvas *>(canvas.release());
   }
 
+  void skiac_surface_create_pdf(skiac_pdf_surface *c_surface, int w, int h, int alphaType, uint8_t cs, const char *path, const skiac_pdf_metadata *c_metadata, bool text_to_paths)
+  {
+    auto document = new skiac_pdf_document(w, h);
+    document->text_to_paths = text_to_paths;
+    SkWStream *w_stream;
+    if (path)
+    {
+      document->file = std::make_unique<SkiacFileWStream>(path);
+      if (!document->file->isValid())
+      {
+        delete document;
+        return;
+      }
+      w_stream = document->file.get();
+    }
+    else
+    {
+      document->memory = std::make_unique<SkDynamicMemoryWStream>();
+      w_stream = document->memory.get();
+    }
+    SkPDF::Metadata metadata;
+    metadata.fCreator = c_metadata->creator ? c_metadata->creator : "@napi-rs/canvas";
+    if (c_metadata->producer)
+    {
+      metadata.fProducer = c_metadata->producer;
+    }
+    if (c_metadata->title)
+    {
+      metadata.fTitle = c_metadata->title;
+    }
+    if (c_metadata->author)
+    {
+      metadata.fAuthor = c_metadata->author;
+    }
+    if (c_metadata->subject)
+    {
+      metadata.fSubject = c_metadata->subject;
+    }
+    if (c_metadata->keywords)
+    {
+      metadata.fKeywords = c_metadata->keywords;
+    }
+    if (c_metadata->has_creation)
+    {
+      metadata.fCreation = skiac_pdf_date_time_to_sk(c_metadata->creation);
+    }
+    if (c_metadata->has_modified)
+    {
+      metadata.fModified = skiac_pdf_date_time_to_sk(c_metadata->modified);
+    }
+    metadata.fPDFA = c_metadata->pdf_a;
+    document->document = SkPDF::MakeDocument(w_stream, metadata);
+    auto surface = document->document ? skiac_surface_create(w, h, (SkAlphaType)alphaType, cs) : nullptr;
+    if (!surface)
+    {
+      delete document;
+      return;
+    }
+    c_surface->document = document;
+    c_surface->surface = reinterpret_cast<skiac_surface *>(surface);
+    c_surface->canvas = reinterpret_cast<skiac_canvas *>(document->beginPage(w, h));
+  }
+
+  void skiac_surface_create_picture(skiac_picture_surface *c_surface, int w, int h, int alphaType, uint8_t cs)
+  {
+    auto surface = skiac_surface_create(w, h, (SkAlphaType)alphaType, cs);
+    if (!surface)
+    {
+      return;
+    }
+    auto recorder = new skiac_picture_recorder(w, h);
+    auto canvas = recorder->recorder.beginRecording(SkRect::MakeWH(w, h));
+    c_surface->recorder = recorder;
+    c_surface->surface = reinterpret_cast<skiac_surface *>(surface);
+    c_surface->canvas = reinterpret_cast<skiac_canvas *>(canvas);
+  }
+
+  skiac_w_file_stream *skiac_surface_create_svg_file(skiac_svg_surface *c_surface, int w, int h, int alphaType, uint32_t flag, uint8_t cs, const char *path)
+  {
+    auto w_stream = new SkiacFileWStream(path);
+    if (!w_stream->isValid())
+    {
+      delete w_stream;
+      return nullptr;
+    }
+    auto canvas = SkSVGCanvas::Make(SkRect::MakeWH(w, h), w_stream, flag);
+    auto surface = canvas ? skiac_surface_create(w, h, (SkAlphaType)alphaType, cs) : nullptr;
+    if (!surface)
+    {
+      // the canvas writes to the stream when it's destroyed
+      canvas.reset();
+      delete w_stream;
+      return nullptr;
+    }
+    c_surface->surface = reinterpret_cast<skiac_surface *>(surface);
+    c_surface->canvas = reinterpret_cast<skiac_canvas *>(canvas.release());
+    return reinterpret_cast<skiac_w_file_stream *>(w_stream);
+  }
+
   skiac_surface *skiac_surface_create_rgba_premultiplied(int width, int height, uint8_t cs)
   {
     return reinterpret_cast<skiac_surface *>(
@@ -113,10 +899,100 @@ extern "C"
         skiac_surface_create(width, height, kUnpremul_SkAlphaType, cs));
   }
 
+  // the premultiplied raster surface, the sample count is ignored by it
+  skiac_surface *skiac_surface_create_with_props(int width, int height, uint8_t cs, skiac_surface_props c_props)
+  {
+    SkSurfaceProps props(0, static_cast<SkPixelGeometry>(c_props.pixel_geometry));
+    return reinterpret_cast<skiac_surface *>(
+        skiac_surface_create(width, height, kPremul_SkAlphaType, cs, &props, static_cast<SkColorType>(c_props.color_type)));
+  }
+
+  // nullptr if the GPU is unavailable, or the library is built without the GPU backend
+  skiac_surface *skiac_surface_create_gpu(int width, int height, uint8_t cs, uint8_t backend, int device_index, const uint8_t *device_uuid, skiac_surface_props c_props)
+  {
+#ifdef SKIAC_GPU
+    auto context = skiac_gpu_context(backend, device_index, device_uuid);
+    if (!context)
+    {
+      return nullptr;
+    }
+    auto color_space = COLOR_SPACE_CAST;
+    auto color_type = static_cast<SkColorType>(c_props.color_type);
+    auto info = SkImageInfo::Make(width, height, color_type, kPremul_SkAlphaType, color_space);
+    // the unsupported sample counts are clamped to the maximum of the GPU
+    auto sample_count = std::max(1, std::min(c_props.sample_count, context->maxSurfaceSampleCountForColorType(color_type)));
+    SkSurfaceProps props(0, static_cast<SkPixelGeometry>(c_props.pixel_geometry));
+    auto surface = SkSurface::MakeRenderTarget(context, SkBudgeted::kNo, info, sample_count, &props);
+    if (!surface)
+    {
+      return nullptr;
+    }
+    surface->getCanvas()->clear(SK_ColorTRANSPARENT);
+    return reinterpret_cast<skiac_surface *>(surface.release());
+#else
+    return nullptr;
+#endif
+  }
+
+  // the canvas draws into the texture without copying it, nullptr if the texture can't be imported
+  skiac_surface *skiac_surface_create_external(int width, int height, uint8_t cs, skiac_external_texture c_texture, skiac_surface_props c_props)
+  {
+    auto color_space = COLOR_SPACE_CAST;
+    auto sample_count = std::max(1, c_props.sample_count);
+    SkSurfaceProps props(0, static_cast<SkPixelGeometry>(c_props.pixel_geometry));
+    sk_sp<SkSurface> surface = nullptr;
+#ifdef SKIAC_GL
+    if (c_texture.kind == 0)
+    {
+      surface = skiac_gl_import_dmabuf(width, height, color_space, c_texture, sample_count, props);
+    }
+#endif
+#ifdef SKIAC_METAL
+    if (c_texture.kind == 1)
+    {
+      surface = skiac_metal_import_io_surface(width, height, color_space, c_texture.io_surface, sample_count, props);
+    }
+#endif
+    return reinterpret_cast<skiac_surface *>(surface.release());
+  }
+
+  bool skiac_surface_is_gpu(skiac_surface *c_surface)
+  {
+    return SURFACE_CAST->recordingContext() != nullptr;
+  }
+
+  // the draws are finished on the GPU before it returns, the exported dmabuf fd is owned by the caller
+  bool skiac_surface_export_texture(skiac_surface *c_surface, skiac_external_texture *c_texture)
+  {
+#ifdef SKIAC_GPU
+    auto context = GrAsDirectContext(SURFACE_CAST->recordingContext());
+    if (!context)
+    {
+      return false;
+    }
+    SURFACE_CAST->flushAndSubmit(true);
+#ifdef SKIAC_GL
+    if (context->backend() == GrBackendApi::kOpenGL)
+    {
+      return skiac_gl_export_dmabuf(SURFACE_CAST, c_texture);
+    }
+#endif
+#ifdef SKIAC_METAL
+    if (context->backend() == GrBackendApi::kMetal)
+    {
+      c_texture->kind = 1;
+      c_texture->io_surface = skiac_metal_io_surface_id(SURFACE_CAST);
+      return c_texture->io_surface != 0;
+    }
+#endif
+#endif
+    return false;
+  }
+
   bool skiac_surface_save(skiac_surface *c_surface, const char *path)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
-    auto data = image->encodeToData(SkEncodedImageFormat::kPNG, 0);
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    auto data = image ? image->encodeToData(SkEncodedImageFormat::kPNG, 0) : nullptr;
     if (data)
     {
       SkFILEWStream stream(path);
@@ -140,98 +1016,469 @@ extern "C"
       skiac_surface *c_surface,
       uint32_t x, uint32_t y, uint32_t width, uint32_t height, uint8_t cs)
   {
-    // x, y, width, height are source rectangle coordinates.
-    auto copy = skiac_surface_create((int)width, (int)height, kUnpremul_SkAlphaType, cs);
-    if (!copy)
+    // x, y, width, height are source rectangle coordinates.
+    // The half float pixels are kept, so the copy is still encoded as the 16-bit PNG
+    auto color_type = SURFACE_CAST->imageInfo().colorType() == kRGBA_F16_SkColorType ? kRGBA_F16_SkColorType : kRGBA_8888_SkColorType;
+    auto copy = skiac_surface_create((int)width, (int)height, kUnpremul_SkAlphaType, cs, nullptr, color_type);
+    if (!copy)
+    {
+      return nullptr;
+    }
+
+    SkPaint paint;
+    paint.setAlpha(SK_AlphaOPAQUE);
+
+    const auto sampling = SkSamplingOptions(SkCubicResampler::Mitchell());
+    // The original surface draws itself to the copy's canvas.
+    copy->getCanvas()->drawImage(skiac_surface_snapshot(SURFACE_CAST), -(SkScalar)x, -(SkScalar)y, sampling, &paint);
+
+    return reinterpret_cast<skiac_surface *>(copy);
+  }
+
+  skiac_surface *skiac_surface_make_raster_copy(skiac_surface *c_surface)
+  {
+    auto copy = SkSurface::MakeRaster(SURFACE_CAST->imageInfo(), &SURFACE_CAST->props());
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    if (!copy || !image)
+    {
+      return nullptr;
+    }
+    SkPaint paint;
+    paint.setBlendMode(SkBlendMode::kSrc);
+    copy->getCanvas()->drawImage(image, 0, 0, SkSamplingOptions(), &paint);
+    return reinterpret_cast<skiac_surface *>(copy.release());
+  }
+
+  // The copy is on the same backend, the pixels are composited onto the white background
+  skiac_surface *skiac_surface_make_opaque_copy(skiac_surface *c_surface)
+  {
+    auto copy = SURFACE_CAST->makeSurface(SURFACE_CAST->imageInfo().makeAlphaType(kOpaque_SkAlphaType));
+    auto image = SURFACE_CAST->makeImageSnapshot();
+    if (!copy || !image)
+    {
+      return nullptr;
+    }
+    auto canvas = copy->getCanvas();
+    canvas->clear(SK_ColorWHITE);
+    canvas->drawImage(image, 0, 0);
+    return reinterpret_cast<skiac_surface *>(copy.release());
+  }
+
+  // The pixels in the rect on a new surface on the same backend with the same pixel format,
+  // unlike skiac_surface_copy_rgba the copy can be drawn on, the parts outside of the surface are cleared
+  skiac_surface *skiac_surface_make_crop(skiac_surface *c_surface, int x, int y, int width, int height)
+  {
+    const auto &info = SURFACE_CAST->imageInfo();
+    auto surface = SURFACE_CAST->makeSurface(info.makeWH(width, height));
+    auto image = SURFACE_CAST->makeImageSnapshot();
+    if (!surface || !image)
+    {
+      return nullptr;
+    }
+    auto canvas = surface->getCanvas();
+    canvas->clear(info.isOpaque() ? SK_ColorWHITE : SK_ColorTRANSPARENT);
+    SkPaint paint;
+    paint.setBlendMode(SkBlendMode::kSrc);
+    canvas->drawImage(image, -x, -y, SkSamplingOptions(), &paint);
+    return reinterpret_cast<skiac_surface *>(surface.release());
+  }
+
+  // The new surface is on the same backend with the same pixel format.
+  // `contents` 0 clears it, 1 copies the pixels to the top left corner, 2 scales them to the new size
+  skiac_surface *skiac_surface_make_resized(skiac_surface *c_surface, int width, int height, int contents, skiac_sampling_options c_sampling)
+  {
+    const auto &info = SURFACE_CAST->imageInfo();
+    auto surface = SURFACE_CAST->makeSurface(info.makeWH(width, height));
+    if (!surface)
+    {
+      return nullptr;
+    }
+    auto canvas = surface->getCanvas();
+    canvas->clear(info.isOpaque() ? SK_ColorWHITE : SK_ColorTRANSPARENT);
+    if (contents != 0)
+    {
+      auto image = SURFACE_CAST->makeImageSnapshot();
+      if (!image)
+      {
+        return nullptr;
+      }
+      SkPaint paint;
+      paint.setBlendMode(SkBlendMode::kSrc);
+      if (contents == 2)
+      {
+        canvas->drawImageRect(image, SkRect::MakeIWH(width, height), conv_from_sampling_options(c_sampling), &paint);
+      }
+      else
+      {
+        canvas->drawImage(image, 0, 0, SkSamplingOptions(), &paint);
+      }
+    }
+    return reinterpret_cast<skiac_surface *>(surface.release());
+  }
+
+  int skiac_surface_get_width(skiac_surface *c_surface)
+  {
+    return SURFACE_CAST->width();
+  }
+
+  int skiac_surface_get_height(skiac_surface *c_surface)
+  {
+    return SURFACE_CAST->height();
+  }
+
+  skiac_canvas *skiac_surface_get_canvas(skiac_surface *c_surface)
+  {
+    return reinterpret_cast<skiac_canvas *>(SURFACE_CAST->getCanvas());
+  }
+
+  void skiac_surface_read_pixels(skiac_surface *c_surface, skiac_surface_data *data)
+  {
+    data->ptr = nullptr;
+    data->size = 0;
+
+    SkPixmap pixmap;
+    if (SURFACE_CAST->peekPixels(&pixmap))
+    {
+      data->ptr = static_cast<uint8_t *>(pixmap.writable_addr());
+      data->size = pixmap.computeByteSize();
+    }
+  }
+
+  // RGBA_8888 or BGRA_8888, `data` is `w * h * 4` bytes
+  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs, int color_type, int alpha_type)
+  {
+    auto color_space = COLOR_SPACE_CAST;
+    auto image_info = SkImageInfo::Make(w, h, (SkColorType)color_type, (SkAlphaType)alpha_type, color_space);
+    auto result = SURFACE_CAST->readPixels(image_info, data, w * 4, x, y);
+    return result;
+  }
+
+  // Replace the pixels at the top left corner, RGBA_8888 or BGRA_8888, `data` is `w * h * 4` bytes
+  bool skiac_surface_write_pixels(skiac_surface *c_surface, const uint8_t *data, int w, int h, uint8_t cs, int color_type, int alpha_type)
+  {
+    auto color_space = COLOR_SPACE_CAST;
+    auto image_info = SkImageInfo::Make(w, h, (SkColorType)color_type, (SkAlphaType)alpha_type, color_space);
+    return SURFACE_CAST->getCanvas()->writePixels(image_info, data, w * 4, 0, 0);
+  }
+
+  bool skiac_surface_read_pixels_as(skiac_surface *c_surface, uint8_t *data, int color_type, int alpha_type)
+  {
+    auto image_info = SURFACE_CAST->imageInfo().makeColorType((SkColorType)color_type).makeAlphaType((SkAlphaType)alpha_type);
+    return SURFACE_CAST->readPixels(image_info, data, image_info.minRowBytes(), 0, 0);
+  }
+
+  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    auto png_data = image ? image->encodeToData().release() : nullptr;
+    if (png_data)
+    {
+      data->ptr = png_data->bytes();
+      data->size = png_data->size();
+      data->data = reinterpret_cast<skiac_data *>(png_data);
+    }
+  }
+
+  void skiac_surface_encode_data(skiac_surface *c_surface, skiac_sk_data *data, int format, int quality)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    auto encoded_data = image ? image->encodeToData((SkEncodedImageFormat)format, quality).release() : nullptr;
+    if (encoded_data)
+    {
+      data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
+      data->size = encoded_data->size();
+      data->data = reinterpret_cast<skiac_data *>(encoded_data);
+    }
+  }
+
+  // The PNG encoder writes 16 bits per channel for the half float pixels, 8 bits for the others
+  static sk_sp<SkImage> png_source_image(skiac_surface *c_surface, skiac_png_options c_options)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    if (!image || c_options.bit_depth == 0)
+    {
+      return image;
+    }
+    auto color_type = c_options.bit_depth == 16 ? kRGBA_F16_SkColorType : kRGBA_8888_SkColorType;
+    if (image->colorType() == color_type)
+    {
+      return image;
+    }
+    SkBitmap bitmap;
+    if (!bitmap.tryAllocPixels(image->imageInfo().makeColorType(color_type)) || !image->readPixels(nullptr, bitmap.pixmap(), 0, 0))
+    {
+      return nullptr;
+    }
+    bitmap.setImmutable();
+    return bitmap.asImage();
+  }
+
+  void skiac_surface_encode_png(skiac_surface *c_surface, skiac_sk_data *data, skiac_png_options c_options)
+  {
+    auto image = png_source_image(c_surface, c_options);
+    SkPixmap pixmap;
+    if (!image || !image->peekPixels(&pixmap))
+    {
+      return;
+    }
+    SkPngEncoder::Options options;
+    options.fZLibLevel = c_options.zlib_level;
+    options.fFilterFlags = (SkPngEncoder::FilterFlag)c_options.filter_flags;
+    SkDynamicMemoryWStream stream;
+    if (SkPngEncoder::Encode(&stream, pixmap, options))
+    {
+      auto encoded_data = stream.detachAsData().release();
+      data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
+      data->size = encoded_data->size();
+      data->data = reinterpret_cast<skiac_data *>(encoded_data);
+    }
+  }
+
+  // JPEG has no alpha, the snapshot is composited over the background color if there is one
+  static sk_sp<SkImage> jpeg_source_image(skiac_surface *c_surface, skiac_jpeg_options c_options)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    if (!image || !c_options.has_background)
+    {
+      return image;
+    }
+    auto matte = SkSurface::MakeRaster(image->imageInfo());
+    if (!matte)
+    {
+      return image;
+    }
+    auto canvas = matte->getCanvas();
+    canvas->clear(c_options.background);
+    canvas->drawImage(image, 0, 0);
+    return matte->makeImageSnapshot();
+  }
+
+  static SkJpegEncoder::Options conv_from_jpeg_options(skiac_jpeg_options c_options)
+  {
+    SkJpegEncoder::Options options;
+    options.fQuality = c_options.quality;
+    options.fDownsample = (SkJpegEncoder::Downsample)c_options.downsample;
+    return options;
+  }
+
+  void skiac_surface_encode_jpeg(skiac_surface *c_surface, skiac_sk_data *data, skiac_jpeg_options c_options)
+  {
+    auto image = jpeg_source_image(c_surface, c_options);
+    SkPixmap pixmap;
+    if (!image || !image->peekPixels(&pixmap))
+    {
+      return;
+    }
+    SkDynamicMemoryWStream stream;
+    if (SkJpegEncoder::Encode(&stream, pixmap, conv_from_jpeg_options(c_options)))
+    {
+      auto encoded_data = stream.detachAsData().release();
+      data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
+      data->size = encoded_data->size();
+      data->data = reinterpret_cast<skiac_data *>(encoded_data);
+    }
+  }
+
+  void skiac_surface_encode_webp(skiac_surface *c_surface, skiac_sk_data *data, float quality, bool lossless)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    SkPixmap pixmap;
+    if (!image || !image->peekPixels(&pixmap))
+    {
+      return;
+    }
+    SkWebpEncoder::Options options;
+    // For the lossless compression, quality controls the compression effort
+    options.fCompression = lossless ? SkWebpEncoder::Compression::kLossless : SkWebpEncoder::Compression::kLossy;
+    options.fQuality = quality;
+    SkDynamicMemoryWStream stream;
+    if (SkWebpEncoder::Encode(&stream, pixmap, options))
+    {
+      auto encoded_data = stream.detachAsData().release();
+      data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
+      data->size = encoded_data->size();
+      data->data = reinterpret_cast<skiac_data *>(encoded_data);
+    }
+  }
+
+  int skiac_surface_get_alpha_type(skiac_surface *c_surface)
+  {
+    return SURFACE_CAST->imageInfo().alphaType();
+  }
+
+  // the PDF canvas has no raster surface, it's counted as RGBA
+  int skiac_surface_get_bytes_per_pixel(skiac_surface *c_surface)
+  {
+    return c_surface ? SURFACE_CAST->imageInfo().bytesPerPixel() : 4;
+  }
+
+  void skiac_surface_get_bitmap(skiac_surface *c_surface, skiac_bitmap_info *info)
+  {
+    auto image = skiac_surface_snapshot(SURFACE_CAST);
+    if (!image)
     {
-      return nullptr;
+      return;
     }
+    auto bitmap = new SkBitmap();
+    auto image_info = image->imageInfo();
+    bitmap->allocPixels(image_info);
+    image->readPixels(image_info, bitmap->getPixels(), bitmap->rowBytes(), 0, 0);
+    info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
+    info->width = (size_t)image_info.width();
+    info->height = (size_t)image_info.height();
+  }
 
-    SkPaint paint;
-    paint.setAlpha(SK_AlphaOPAQUE);
-
-    const auto sampling = SkSamplingOptions(SkCubicResampler::Mitchell());
-    // The original surface draws itself to the copy's canvas.
-    SURFACE_CAST->draw(copy->getCanvas(), -(SkScalar)x, -(SkScalar)y, sampling, &paint);
+  // GpuContext
 
-    return reinterpret_cast<skiac_surface *>(copy);
+  // the context is shared by all the GPU surfaces of the backend and device on this thread,
+  // and lives until the thread exits, nullptr if it's unavailable
+  skiac_gpu_context *skiac_gpu_context_get(uint8_t backend, int device_index, const uint8_t *device_uuid)
+  {
+#ifdef SKIAC_GPU
+    return reinterpret_cast<skiac_gpu_context *>(skiac_gpu_context(backend, device_index, device_uuid));
+#else
+    return nullptr;
+#endif
   }
 
-  int skiac_surface_get_width(skiac_surface *c_surface)
+  size_t skiac_gpu_context_get_resource_cache_limit(skiac_gpu_context *c_gpu_context)
   {
-    return SURFACE_CAST->width();
+#ifdef SKIAC_GPU
+    return GPU_CONTEXT_CAST->getResourceCacheLimit();
+#else
+    return 0;
+#endif
   }
 
-  int skiac_surface_get_height(skiac_surface *c_surface)
+  void skiac_gpu_context_set_resource_cache_limit(skiac_gpu_context *c_gpu_context, size_t bytes)
   {
-    return SURFACE_CAST->height();
+#ifdef SKIAC_GPU
+    GPU_CONTEXT_CAST->setResourceCacheLimit(bytes);
+#endif
   }
 
-  skiac_canvas *skiac_surface_get_canvas(skiac_surface *c_surface)
+  size_t skiac_gpu_context_get_resource_cache_usage(skiac_gpu_context *c_gpu_context)
   {
-    return reinterpret_cast<skiac_canvas *>(SURFACE_CAST->getCanvas());
+#ifdef SKIAC_GPU
+    size_t bytes = 0;
+    GPU_CONTEXT_CAST->getResourceCacheUsage(nullptr, &bytes);
+    return bytes;
+#else
+    return 0;
+#endif
   }
 
-  void skiac_surface_read_pixels(skiac_surface *c_surface, skiac_surface_data *data)
+  // the resources still used by the surfaces are recreated on demand
+  void skiac_gpu_context_free_resources(skiac_gpu_context *c_gpu_context)
   {
-    data->ptr = nullptr;
-    data->size = 0;
-
-    SkPixmap pixmap;
-    if (SURFACE_CAST->peekPixels(&pixmap))
-    {
-      data->ptr = static_cast<uint8_t *>(pixmap.writable_addr());
-      data->size = pixmap.computeByteSize();
-    }
+#ifdef SKIAC_GPU
+    GPU_CONTEXT_CAST->freeGpuResources();
+#endif
   }
 
-  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs)
+  // PixelsReadback
+
+  // the unpremultiplied RGBA pixels in the rect, which must be inside of the surface
+  skiac_pixels_readback *skiac_surface_read_pixels_async(skiac_surface *c_surface, int x, int y, int w, int h, uint8_t cs)
   {
+    auto readback = new skiac_pixels_readback();
+    readback->surface = sk_ref_sp(SURFACE_CAST);
+    readback->width = w;
+    readback->height = h;
     auto color_space = COLOR_SPACE_CAST;
-    auto image_info = SkImageInfo::Make(w, h, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType, color_space);
-    auto result = SURFACE_CAST->readPixels(image_info, data, w * 4, x, y);
-    return result;
+    auto info = SkImageInfo::Make(w, h, kRGBA_8888_SkColorType, kUnpremul_SkAlphaType, color_space);
+    // the raster surfaces call it before returning
+    SURFACE_CAST->asyncRescaleAndReadPixels(
+        info,
+        SkIRect::MakeXYWH(x, y, w, h),
+        SkSurface::RescaleGamma::kSrc,
+        SkSurface::RescaleMode::kNearest,
+        [](void *context, std::unique_ptr<const SkSurface::AsyncReadResult> result)
+        {
+          auto readback = static_cast<skiac_pixels_readback *>(context);
+          readback->result = std::move(result);
+          readback->done = true;
+        },
+        readback);
+#ifdef SKIAC_GPU
+    if (auto context = GrAsDirectContext(SURFACE_CAST->recordingContext()))
+    {
+      // send the draws and the read to the GPU without waiting for them
+      context->submit(false);
+    }
+#endif
+    return readback;
   }
 
-  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data)
+  bool skiac_pixels_readback_poll(skiac_pixels_readback *c_readback)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
-    auto png_data = image->encodeToData().release();
-    if (png_data)
+#ifdef SKIAC_GPU
+    if (!c_readback->done)
     {
-      data->ptr = png_data->bytes();
-      data->size = png_data->size();
-      data->data = reinterpret_cast<skiac_data *>(png_data);
+      if (auto context = GrAsDirectContext(c_readback->surface->recordingContext()))
+      {
+        context->checkAsyncWorkCompletion();
+        // the lost context never completes the read, it failed without the result.
+        // The pending callbacks are called while the context is abandoned, so it's safe to delete the readback
+        if (context->abandoned())
+        {
+          c_readback->done = true;
+        }
+      }
     }
+#endif
+    return c_readback->done;
   }
 
-  void skiac_surface_encode_data(skiac_surface *c_surface, skiac_sk_data *data, int format, int quality)
+  // `data` is `width * height * 4` bytes
+  bool skiac_pixels_readback_copy(skiac_pixels_readback *c_readback, uint8_t *data)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
-    auto encoded_data = image->encodeToData((SkEncodedImageFormat)format, quality).release();
-    if (encoded_data)
+    if (!c_readback->result)
     {
-      data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
-      data->size = encoded_data->size();
-      data->data = reinterpret_cast<skiac_data *>(encoded_data);
+      return false;
+    }
+    auto pixels = static_cast<const uint8_t *>(c_readback->result->data(0));
+    auto row_bytes = c_readback->result->rowBytes(0);
+    auto tight_row_bytes = static_cast<size_t>(c_readback->width) * 4;
+    for (int y = 0; y < c_readback->height; y++)
+    {
+      memcpy(data + y * tight_row_bytes, pixels + y * row_bytes, tight_row_bytes);
     }
+    return true;
   }
 
-  int skiac_surface_get_alpha_type(skiac_surface *c_surface)
+  // the raster copy of the read pixels, encoded like the snapshot of the canvas
+  skiac_surface *skiac_pixels_readback_make_surface(skiac_pixels_readback *c_readback, uint8_t cs)
   {
-    return SURFACE_CAST->imageInfo().alphaType();
+    if (!c_readback->result)
+    {
+      return nullptr;
+    }
+    auto surface = skiac_surface_create(c_readback->width, c_readback->height, kUnpremul_SkAlphaType, cs);
+    if (!surface)
+    {
+      return nullptr;
+    }
+    auto color_space = COLOR_SPACE_CAST;
+    auto info = SkImageInfo::Make(c_readback->width, c_readback->height, kRGBA_8888_SkColorType, kUnpremul_SkAlphaType, color_space);
+    surface->writePixels(SkPixmap(info, c_readback->result->data(0), c_readback->result->rowBytes(0)), 0, 0);
+    return reinterpret_cast<skiac_surface *>(surface);
   }
 
-  void skiac_surface_get_bitmap(skiac_surface *c_surface, skiac_bitmap_info *info)
+  void skiac_pixels_readback_destroy(skiac_pixels_readback *c_readback)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
-    auto bitmap = new SkBitmap();
-    auto image_info = image->imageInfo();
-    bitmap->allocPixels(image_info);
-    image->readPixels(image_info, bitmap->getPixels(), bitmap->rowBytes(), 0, 0);
-    info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
-    info->width = (size_t)image_info.width();
-    info->height = (size_t)image_info.height();
+#ifdef SKIAC_GPU
+    // the pending callback must not be called after it's deleted
+    if (!c_readback->done)
+    {
+      if (auto context = GrAsDirectContext(c_readback->surface->recordingContext()))
+      {
+        context->submit(true);
+        context->checkAsyncWorkCompletion();
+      }
+    }
+#endif
+    delete c_readback;
   }
 
   // Canvas
@@ -293,19 +1540,67 @@ extern "C"
       float dy,
       float d_width,
       float d_height,
-      bool enable_smoothing,
-      int filter_quality,
+      skiac_sampling_options c_sampling,
       skiac_paint *c_paint)
   {
     const auto src_rect = SkRect::MakeXYWH(sx, sy, s_width, s_height);
     const auto dst_rect = SkRect::MakeXYWH(dx, dy, d_width, d_height);
     auto sk_image = SkImage::MakeFromBitmap(*BITMAP_CAST);
-    auto fq = enable_smoothing ? filter_quality : 0;
-    const auto sampling = SamplingOptionsFromFQ(fq);
+    const auto sampling = conv_from_sampling_options(c_sampling);
+    if (sampling.mipmap != SkMipmapMode::kNone)
+    {
+      sk_image = sk_image->withDefaultMipmaps();
+    }
     auto paint = reinterpret_cast<const SkPaint *>(c_paint);
     CANVAS_CAST->drawImageRect(sk_image, src_rect, dst_rect, sampling, paint, SkCanvas::kFast_SrcRectConstraint);
   }
 
+  void skiac_canvas_draw_image_nine(
+      skiac_canvas *c_canvas,
+      skiac_bitmap *c_bitmap,
+      int center_x,
+      int center_y,
+      int center_width,
+      int center_height,
+      float dx,
+      float dy,
+      float d_width,
+      float d_height,
+      int filter_mode,
+      skiac_paint *c_paint)
+  {
+    const auto center = SkIRect::MakeXYWH(center_x, center_y, center_width, center_height);
+    const auto dst_rect = SkRect::MakeXYWH(dx, dy, d_width, d_height);
+    auto sk_image = SkImage::MakeFromBitmap(*BITMAP_CAST);
+    auto paint = reinterpret_cast<const SkPaint *>(c_paint);
+    CANVAS_CAST->drawImageNine(sk_image.get(), center, dst_rect, (SkFilterMode)filter_mode, paint);
+  }
+
+  void skiac_canvas_draw_atlas(
+      skiac_canvas *c_canvas,
+      skiac_bitmap *c_bitmap,
+      const float *xforms,
+      const float *src_rects,
+      const uint32_t *colors,
+      int count,
+      int blend_mode,
+      skiac_sampling_options c_sampling,
+      skiac_paint *c_paint)
+  {
+    auto sk_image = SkImage::MakeFromBitmap(*BITMAP_CAST);
+    // [x, y, width, height] from JavaScript side
+    std::vector<SkRect> tex(count);
+    for (auto i = 0; i < count; i++)
+    {
+      tex[i] = SkRect::MakeXYWH(src_rects[i * 4], src_rects[i * 4 + 1], src_rects[i * 4 + 2], src_rects[i * 4 + 3]);
+    }
+    // [scos, ssin, tx, ty] has the same memory layout with SkRSXform
+    auto rs_xforms = reinterpret_cast<const SkRSXform *>(xforms);
+    const auto sampling = conv_from_sampling_options(c_sampling);
+    auto paint = reinterpret_cast<const SkPaint *>(c_paint);
+    CANVAS_CAST->drawAtlas(sk_image.get(), rs_xforms, tex.data(), colors, count, (SkBlendMode)blend_mode, sampling, nullptr, paint);
+  }
+
   void skiac_canvas_draw_path(skiac_canvas *c_canvas, skiac_path *c_path, skiac_paint *c_paint)
   {
     CANVAS_CAST->drawPath(*PATH_CAST, *PAINT_CAST);
@@ -326,13 +1621,21 @@ extern "C"
       float top,
       uint8_t alpha,
       int blend_mode,
-      int filter_quality)
+      skiac_sampling_options c_sampling)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
+    auto image = skiac_surface_snapshot(SURFACE_CAST, CANVAS_CAST);
+    if (!image)
+    {
+      return;
+    }
     SkPaint paint;
     paint.setAlpha(alpha);
     paint.setBlendMode((SkBlendMode)blend_mode);
-    const auto sampling = SamplingOptionsFromFQ(filter_quality);
+    const auto sampling = conv_from_sampling_options(c_sampling);
+    if (sampling.mipmap != SkMipmapMode::kNone)
+    {
+      image = image->withDefaultMipmaps();
+    }
     CANVAS_CAST->drawImage(image, left, top, sampling, &paint);
   }
 
@@ -347,12 +1650,20 @@ extern "C"
       float dy,
       float dw,
       float dh,
-      int filter_quality)
+      skiac_sampling_options c_sampling)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
+    auto image = skiac_surface_snapshot(SURFACE_CAST, CANVAS_CAST);
+    if (!image)
+    {
+      return;
+    }
     auto src = SkRect::MakeXYWH(sx, sy, sw, sh);
     auto dst = SkRect::MakeXYWH(dx, dy, dw, dh);
-    const auto sampling = SamplingOptionsFromFQ(filter_quality);
+    const auto sampling = conv_from_sampling_options(c_sampling);
+    if (sampling.mipmap != SkMipmapMode::kNone)
+    {
+      image = image->withDefaultMipmaps();
+    }
     CANVAS_CAST->drawImageRect(image, src, dst, sampling, nullptr, SkCanvas::kFast_SrcRectConstraint);
   }
 
@@ -548,10 +1859,78 @@ extern "C"
     CANVAS_CAST->clipRect(rect, true);
   }
 
-  void skiac_canvas_clip_path(skiac_canvas *c_canvas, skiac_path *c_path)
+  // The annotations are only written by the PDF canvas
+  void skiac_canvas_annotate_link_to_url(skiac_canvas *c_canvas, float x, float y, float w, float h, const char *url)
+  {
+    // the data includes the null terminator as SkPDF expects
+    auto data = SkData::MakeWithCString(url);
+    SkAnnotateRectWithURL(CANVAS_CAST, SkRect::MakeXYWH(x, y, w, h), data.get());
+  }
+
+  void skiac_canvas_annotate_link_to_destination(skiac_canvas *c_canvas, float x, float y, float w, float h, const char *name)
+  {
+    auto data = SkData::MakeWithCString(name);
+    SkAnnotateLinkToDestination(CANVAS_CAST, SkRect::MakeXYWH(x, y, w, h), data.get());
+  }
+
+  void skiac_canvas_annotate_named_destination(skiac_canvas *c_canvas, float x, float y, const char *name)
+  {
+    auto data = SkData::MakeWithCString(name);
+    SkAnnotateNamedDestination(CANVAS_CAST, SkPoint::Make(x, y), data.get());
+  }
+
+  void skiac_canvas_clip_path(skiac_canvas *c_canvas, skiac_path *c_path, bool aa)
   {
     auto path = reinterpret_cast<SkPath *>(c_path);
-    CANVAS_CAST->clipPath(*path, true);
+    CANVAS_CAST->clipPath(*path, aa);
+  }
+
+  // The paint applies the alpha, the blend mode and the filter to the whole picture
+  void skiac_canvas_draw_picture(skiac_canvas *c_canvas, skiac_picture *c_picture, skiac_matrix *c_matrix, skiac_paint *c_paint)
+  {
+    CANVAS_CAST->drawPicture(reinterpret_cast<SkPicture *>(c_picture), MATRIX_CAST, PAINT_CAST);
+  }
+
+  // Render the SVG document as vectors, so it's sharp at any scale and stays vector in the SVG and PDF canvases.
+  // The document is composited with the alpha, the blend mode and the image filter of the paint like a picture.
+  bool skiac_canvas_draw_svg(skiac_canvas *c_canvas, const uint8_t *data, size_t length, float dx, float dy, float dw, float dh, skiac_paint *c_paint, skiac_font_collection *c_collection)
+  {
+    SkMemoryStream svg_stream(data, length, false);
+    auto svg_dom = SkSVGDOM::Builder().setFontManager(c_collection->assets).make(svg_stream);
+    if (!svg_dom)
+    {
+      return false;
+    }
+    auto svg_root = svg_dom->getRoot();
+    auto svg_container_size = svg_root->intrinsicSize(SkSVGLengthContext(SkSize::Make(0, 0)));
+    if (svg_container_size.isZero())
+    {
+      auto view_box = svg_root->getViewBox();
+      if (!view_box.isValid())
+      {
+        return false;
+      }
+      svg_container_size = SkSize::Make(view_box->width(), view_box->height());
+      if (svg_container_size.isEmpty())
+      {
+        return false;
+      }
+    }
+    svg_dom->setContainerSize(svg_container_size);
+    auto canvas = CANVAS_CAST;
+    SkAutoCanvasRestore restore(canvas, true);
+    canvas->translate(dx, dy);
+    if (dw > 0 && dh > 0)
+    {
+      canvas->scale(dw / svg_container_size.width(), dh / svg_container_size.height());
+    }
+    auto paint = PAINT_CAST;
+    if (paint->getAlpha() < 255 || paint->getImageFilter() || paint->getBlendMode_or(SkBlendMode::kSrcOver) != SkBlendMode::kSrcOver)
+    {
+      canvas->saveLayer(nullptr, paint);
+    }
+    svg_dom->render(canvas);
+    return true;
   }
 
   void skiac_canvas_save(skiac_canvas *c_canvas)
@@ -559,19 +1938,39 @@ extern "C"
     CANVAS_CAST->save();
   }
 
+  // The layer is unbounded without the bounds, the image filter of the paint applies to everything drawn in it
+  void skiac_canvas_save_layer(skiac_canvas *c_canvas, skiac_paint *c_paint, skiac_rect *c_bounds)
+  {
+    if (c_bounds)
+    {
+      auto bounds = SkRect::MakeLTRB(c_bounds->left, c_bounds->top, c_bounds->right, c_bounds->bottom);
+      CANVAS_CAST->saveLayer(&bounds, PAINT_CAST);
+    }
+    else
+    {
+      CANVAS_CAST->saveLayer(nullptr, PAINT_CAST);
+    }
+  }
+
   void skiac_canvas_restore(skiac_canvas *c_canvas)
   {
     CANVAS_CAST->restore();
   }
 
+  // Restore all the saves, then drop the transform and the clips of the base layer.
+  // The replace op only remains as the deprecated SkClipOp value 5
   void skiac_canvas_reset(skiac_canvas *c_canvas)
   {
-    CANVAS_CAST->restoreToCount(1);
+    auto canvas = CANVAS_CAST;
+    canvas->restoreToCount(1);
+    canvas->resetMatrix();
+    canvas->clipRect(SkRect::Make(canvas->getBaseLayerSize()), static_cast<SkClipOp>(5), false);
   }
 
-  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y)
+  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y, uint8_t cs)
   {
-    auto info = SkImageInfo::Make(width, height, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType);
+    auto color_space = COLOR_SPACE_CAST;
+    auto info = SkImageInfo::Make(width, height, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType, color_space);
     CANVAS_CAST->writePixels(info, pixels, row_bytes, x, y);
   }
 
@@ -628,6 +2027,11 @@ extern "C"
     PAINT_CAST->setAntiAlias(aa);
   }
 
+  bool skiac_paint_get_anti_alias(skiac_paint *c_paint)
+  {
+    return PAINT_CAST->isAntiAlias();
+  }
+
   void skiac_paint_set_blend_mode(skiac_paint *c_paint, int blend_mode)
   {
     PAINT_CAST->setBlendMode((SkBlendMode)blend_mode);
@@ -675,6 +2079,11 @@ extern "C"
     PAINT_CAST->setImageFilter(imageFilter);
   }
 
+  void skiac_paint_set_color_filter(skiac_paint *c_paint, skiac_color_filter *c_color_filter)
+  {
+    PAINT_CAST->setColorFilter(sk_ref_sp(COLOR_FILTER_CAST));
+  }
+
   void skiac_paint_set_style(skiac_paint *c_paint, int style)
   {
     PAINT_CAST->setStyle((SkPaint::Style)style);
@@ -727,13 +2136,6 @@ extern "C"
     return reinterpret_cast<skiac_path *>(new SkPath());
   }
 
-  skiac_path *skiac_path_from_svg(char *svg_path)
-  {
-    auto path = new SkPath();
-    SkParsePath::FromSVGString(svg_path, path);
-    return reinterpret_cast<skiac_path *>(path);
-  }
-
   skiac_path *skiac_path_clone(skiac_path *c_path)
   {
     auto new_path = new SkPath(*PATH_CAST);
@@ -777,7 +2179,7 @@ extern "C"
     return AsWinding(*PATH_CAST, PATH_CAST);
   }
 
-  bool skiac_path_stroke(skiac_path *c_path, int cap, uint8_t join, float width, float miter_limit)
+  bool skiac_path_stroke(skiac_path *c_path, int cap, uint8_t join, float width, float miter_limit, skiac_path_effect *c_path_effect)
   {
     auto path = PATH_CAST;
     SkPaint p;
@@ -786,6 +2188,10 @@ extern "C"
     p.setStrokeJoin((SkPaint::Join)join);
     p.setStrokeWidth(width);
     p.setStrokeMiter(miter_limit);
+    if (c_path_effect)
+    {
+      p.setPathEffect(sk_ref_sp(reinterpret_cast<SkPathEffect *>(c_path_effect)));
+    }
 
     return p.getFillPath(*path, path);
   }
@@ -808,6 +2214,48 @@ extern "C"
     c_rect->bottom = rect.fBottom;
   }
 
+  float skiac_path_length(skiac_path *c_path)
+  {
+    SkContourMeasureIter iter(*PATH_CAST, false);
+    float length = 0;
+    while (auto contour = iter.next())
+    {
+      length += contour->length();
+    }
+    return length;
+  }
+
+  bool skiac_path_get_pos_tan(skiac_path *c_path, float distance, skiac_point *c_position, skiac_point *c_tangent)
+  {
+    SkContourMeasureIter iter(*PATH_CAST, false);
+    sk_sp<SkContourMeasure> measure;
+    while (auto contour = iter.next())
+    {
+      measure = contour;
+      if (distance <= contour->length())
+      {
+        break;
+      }
+      distance -= contour->length();
+    }
+    if (!measure)
+    {
+      return false;
+    }
+    SkPoint position;
+    SkVector tangent;
+    // the distance is pinned to the ends of the contour
+    if (!measure->getPosTan(distance, &position, &tangent))
+    {
+      return false;
+    }
+    c_position->x = position.fX;
+    c_position->y = position.fY;
+    c_tangent->x = tangent.fX;
+    c_tangent->y = tangent.fY;
+    return true;
+  }
+
   bool skiac_path_trim(skiac_path *c_path, float start_t, float stop_t, bool is_complement)
   {
     auto mode = is_complement ? SkTrimPathEffect::Mode::kInverted : SkTrimPathEffect::Mode::kNormal;
@@ -824,10 +2272,9 @@ extern "C"
     return false;
   }
 
-  bool skiac_path_dash(skiac_path *c_path, float on, float off, float phase)
+  bool skiac_path_dash(skiac_path *c_path, const float *intervals, int count, float phase)
   {
-    float intervals[] = {on, off};
-    auto pe = SkDashPathEffect::Make(intervals, 2, phase);
+    auto pe = SkDashPathEffect::Make(intervals, count, phase);
     if (!pe)
     {
       return false;
@@ -872,6 +2319,13 @@ extern "C"
     PATH_CAST->arcTo(rect, startAngle, sweepAngle, forceMoveTo);
   }
 
+  void skiac_path_svg_arc_to(skiac_path *c_path, float rx, float ry, float x_axis_rotate, bool large_arc, bool sweep, float x, float y)
+  {
+    auto arc_size = large_arc ? SkPath::kLarge_ArcSize : SkPath::kSmall_ArcSize;
+    auto direction = sweep ? SkPathDirection::kCW : SkPathDirection::kCCW;
+    PATH_CAST->arcTo(rx, ry, x_axis_rotate, arc_size, direction, x, y);
+  }
+
   void skiac_path_move_to(skiac_path *c_path, float x, float y)
   {
     PATH_CAST->moveTo(x, y);
@@ -905,6 +2359,19 @@ extern "C"
     PATH_CAST->addRect(rect);
   }
 
+  void skiac_path_add_round_rect(skiac_path *c_path, float x, float y, float width, float height, const skiac_point *radii)
+  {
+    SkVector corners[4];
+    for (int i = 0; i < 4; i++)
+    {
+      corners[i] = SkVector::Make(radii[i].x, radii[i].y);
+    }
+    SkRRect rrect;
+    // the radii are scaled down if they overlap, like `roundRect` of the canvas
+    rrect.setRectRadii(SkRect::MakeXYWH(x, y, width, height), corners);
+    PATH_CAST->addRRect(rrect);
+  }
+
   void skiac_path_add_circle(skiac_path *c_path, float x, float y, float r)
   {
     PATH_CAST->addCircle(x, y, r);
@@ -1100,7 +2567,11 @@ extern "C"
     auto skia_tile_mode = SkTileMode::kRepeat;
     const auto ts = conv_from_transform(c_ts);
     const SkSamplingOptions sampling_options = SamplingOptionsFromFQ(filter_quality);
-    sk_sp<SkImage> image = SURFACE_CAST->makeImageSnapshot();
+    sk_sp<SkImage> image = skiac_surface_snapshot(SURFACE_CAST);
+    if (!image)
+    {
+      return nullptr;
+    }
     auto shader = image->makeShader(
                            skia_tile_mode,
                            skia_tile_mode,
@@ -1122,6 +2593,91 @@ extern "C"
     shader->unref();
   }
 
+  // RuntimeEffect
+
+  // nullptr if the SkSL is invalid, the compile errors are written to `c_error`
+  skiac_runtime_effect *skiac_runtime_effect_make(const char *sksl, skiac_string *c_error)
+  {
+    auto [effect, error] = SkRuntimeEffect::Make(SkString(sksl));
+    if (!effect)
+    {
+      auto string = new SkString(error);
+      c_error->length = string->size();
+      c_error->ptr = string->c_str();
+      c_error->sk_string = string;
+      return nullptr;
+    }
+    // the children are filled with shaders, so the color filters and the blenders can't be sampled
+    for (const auto &child : effect->children())
+    {
+      if (child.type != SkRuntimeEffect::ChildType::kShader)
+      {
+        auto string = new SkString();
+        string->printf("only the shader children are supported, %s is a %s", child.name.c_str(), child.type == SkRuntimeEffect::ChildType::kColorFilter ? "colorFilter" : "blender");
+        c_error->length = string->size();
+        c_error->ptr = string->c_str();
+        c_error->sk_string = string;
+        return nullptr;
+      }
+    }
+    auto c_effect = new skiac_runtime_effect();
+    for (const auto &uniform : effect->uniforms())
+    {
+      c_effect->uniforms.push_back(&uniform);
+    }
+    c_effect->child_count = effect->children().size();
+    c_effect->effect = std::move(effect);
+    return c_effect;
+  }
+
+  size_t skiac_runtime_effect_get_uniform_count(skiac_runtime_effect *c_effect)
+  {
+    return c_effect->uniforms.size();
+  }
+
+  void skiac_runtime_effect_get_uniform(skiac_runtime_effect *c_effect, size_t index, skiac_runtime_effect_uniform *c_uniform)
+  {
+    auto uniform = c_effect->uniforms[index];
+    c_uniform->name = uniform->fName.c_str();
+    c_uniform->offset = uniform->fOffset / sizeof(float);
+    c_uniform->count = uniform->sizeInBytes() / sizeof(float);
+    switch (uniform->fType)
+    {
+    case SkRuntimeEffect::Uniform::Type::kInt:
+    case SkRuntimeEffect::Uniform::Type::kInt2:
+    case SkRuntimeEffect::Uniform::Type::kInt3:
+    case SkRuntimeEffect::Uniform::Type::kInt4:
+      c_uniform->is_int = true;
+      break;
+    default:
+      c_uniform->is_int = false;
+    }
+  }
+
+  // in 4 bytes values
+  size_t skiac_runtime_effect_get_uniform_size(skiac_runtime_effect *c_effect)
+  {
+    return c_effect->effect->uniformSize() / sizeof(float);
+  }
+
+  // `c_input` is the first child shader, the others are transparent
+  skiac_shader *skiac_runtime_effect_make_shader(skiac_runtime_effect *c_effect, const float *uniforms, skiac_shader *c_input)
+  {
+    auto data = SkData::MakeWithCopy(uniforms, c_effect->effect->uniformSize());
+    std::vector<sk_sp<SkShader>> children(c_effect->child_count, SkShaders::Color(SK_ColorTRANSPARENT));
+    if (c_input && !children.empty())
+    {
+      children[0] = sk_ref_sp(reinterpret_cast<SkShader *>(c_input));
+    }
+    auto shader = c_effect->effect->makeShader(data, children.data(), children.size(), nullptr, false);
+    return reinterpret_cast<skiac_shader *>(shader.release());
+  }
+
+  void skiac_runtime_effect_destroy(skiac_runtime_effect *c_effect)
+  {
+    delete c_effect;
+  }
+
   skiac_matrix *skiac_matrix_create()
   {
     return reinterpret_cast<skiac_matrix *>(new SkMatrix());
@@ -1351,8 +2907,45 @@ extern "C"
     }
     else
     {
-      return nullptr;
+      return nullptr;
+    }
+  }
+
+  skiac_image_filter *skiac_image_filter_make_offset(float dx, float dy, skiac_image_filter *c_image_filter)
+  {
+    auto input = sk_ref_sp(IMAGE_FILTER_CAST);
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::Offset(dx, dy, input).release());
+  }
+
+  skiac_image_filter *skiac_image_filter_make_color_filter(skiac_color_filter *c_color_filter, skiac_image_filter *c_image_filter)
+  {
+    auto input = sk_ref_sp(IMAGE_FILTER_CAST);
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::ColorFilter(sk_ref_sp(COLOR_FILTER_CAST), input).release());
+  }
+
+  // The nullptr in the filters is the source
+  skiac_image_filter *skiac_image_filter_make_merge(skiac_image_filter **c_image_filters, size_t count)
+  {
+    std::vector<sk_sp<SkImageFilter>> filters;
+    for (size_t i = 0; i < count; i++)
+    {
+      filters.push_back(sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_image_filters[i])));
     }
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::Merge(filters.data(), count).release());
+  }
+
+  skiac_image_filter *skiac_image_filter_make_compose(skiac_image_filter *c_outer, skiac_image_filter *c_inner)
+  {
+    auto outer = sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_outer));
+    auto inner = sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_inner));
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::Compose(outer, inner).release());
+  }
+
+  skiac_image_filter *skiac_image_filter_make_displacement_map(int x_channel, int y_channel, float scale, skiac_image_filter *c_displacement, skiac_image_filter *c_image_filter)
+  {
+    auto displacement = sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_displacement));
+    auto input = sk_ref_sp(IMAGE_FILTER_CAST);
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::DisplacementMap((SkColorChannel)x_channel, (SkColorChannel)y_channel, scale, displacement, input).release());
   }
 
   void skiac_image_filter_ref(skiac_image_filter *c_image_filter)
@@ -1367,6 +2960,55 @@ extern "C"
     image_filter->unref();
   }
 
+  // ColorFilter
+
+  // Skia makes nullptr for the filters which don't change the colors, they are the identity matrix to be assignable
+  static skiac_color_filter *skiac_color_filter_or_identity(sk_sp<SkColorFilter> filter)
+  {
+    if (!filter)
+    {
+      filter = SkColorFilters::Matrix(SkColorMatrix());
+    }
+    return reinterpret_cast<skiac_color_filter *>(filter.release());
+  }
+
+  skiac_color_filter *skiac_color_filter_make_matrix(const float row_major[20])
+  {
+    return skiac_color_filter_or_identity(SkColorFilters::Matrix(row_major));
+  }
+
+  skiac_color_filter *skiac_color_filter_make_blend(uint32_t color, int blend_mode)
+  {
+    return skiac_color_filter_or_identity(SkColorFilters::Blend(color, (SkBlendMode)blend_mode));
+  }
+
+  skiac_color_filter *skiac_color_filter_make_lighting(uint32_t mul, uint32_t add)
+  {
+    return skiac_color_filter_or_identity(SkColorMatrixFilter::MakeLightingFilter(mul, add));
+  }
+
+  skiac_color_filter *skiac_color_filter_make_luma()
+  {
+    return skiac_color_filter_or_identity(SkLumaColorFilter::Make());
+  }
+
+  skiac_color_filter *skiac_color_filter_compose(skiac_color_filter *c_outer, skiac_color_filter *c_inner)
+  {
+    auto outer = sk_ref_sp(reinterpret_cast<SkColorFilter *>(c_outer));
+    auto inner = sk_ref_sp(reinterpret_cast<SkColorFilter *>(c_inner));
+    return skiac_color_filter_or_identity(SkColorFilters::Compose(outer, inner));
+  }
+
+  void skiac_color_filter_ref(skiac_color_filter *c_color_filter)
+  {
+    COLOR_FILTER_CAST->ref();
+  }
+
+  void skiac_color_filter_destroy(skiac_color_filter *c_color_filter)
+  {
+    COLOR_FILTER_CAST->unref();
+  }
+
   // SkData
 
   void skiac_sk_data_destroy(skiac_data *c_data)
@@ -1377,11 +3019,32 @@ extern "C"
 
   // Bitmap
 
-  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, skiac_bitmap_info *bitmap_info)
+  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, int width, int height, uint8_t cs, bool color_space_conversion, skiac_bitmap_info *bitmap_info)
   {
     auto data = SkData::MakeWithoutCopy(reinterpret_cast<const void *>(ptr), size);
     auto codec = SkCodec::MakeFromData(data);
+    if (!codec)
+    {
+      return;
+    }
     auto info = codec->getInfo();
+    // ICO files may embed several sizes, SkCodec picks the largest one by default.
+    // Select the embedded image closest to the requested size instead.
+    if (codec->getEncodedFormat() == SkEncodedImageFormat::kICO && width > 0 && height > 0)
+    {
+      auto scale = std::max((float)width / info.width(), (float)height / info.height());
+      info = info.makeDimensions(codec->getScaledDimensions(scale));
+    }
+    // Convert from the embedded ICC profile (Display P3, Adobe RGB, CMYK...) into the target color space,
+    // otherwise the raw values are interpreted as sRGB.
+    if (color_space_conversion)
+    {
+      info = info.makeColorSpace(COLOR_SPACE_CAST);
+    }
+    else
+    {
+      info = info.makeColorSpace(nullptr);
+    }
     auto row_bytes = info.width() * info.bytesPerPixel();
     auto bitmap = new SkBitmap();
     bitmap->allocPixels(info);
@@ -1391,6 +3054,21 @@ extern "C"
     bitmap_info->height = info.height();
   }
 
+  void skiac_bitmap_make_from_pixels(const uint8_t *ptr, int width, int height, skiac_bitmap_info *bitmap_info)
+  {
+    auto info = SkImageInfo::Make(width, height, kRGBA_8888_SkColorType, kUnpremul_SkAlphaType);
+    auto bitmap = new SkBitmap();
+    if (!bitmap->tryAllocPixels(info))
+    {
+      delete bitmap;
+      return;
+    }
+    bitmap->writePixels(SkPixmap(info, ptr, info.minRowBytes()), 0, 0);
+    bitmap_info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
+    bitmap_info->width = width;
+    bitmap_info->height = height;
+  }
+
   void skiac_bitmap_make_from_svg(const uint8_t *data, size_t length, float width, float height, skiac_bitmap_info *bitmap_info, uint8_t cs)
   {
     auto color_space = COLOR_SPACE_CAST;
@@ -1438,6 +3116,13 @@ extern "C"
     return reinterpret_cast<skiac_bitmap *>(bitmap);
   }
 
+  bool skiac_bitmap_read_pixels(skiac_bitmap *c_bitmap, uint8_t *data, size_t row_bytes)
+  {
+    auto bitmap = BITMAP_CAST;
+    auto info = SkImageInfo::Make(bitmap->width(), bitmap->height(), kRGBA_8888_SkColorType, kUnpremul_SkAlphaType);
+    return bitmap->readPixels(info, data, row_bytes, 0, 0);
+  }
+
   size_t skiac_bitmap_get_width(skiac_bitmap *c_bitmap)
   {
     auto bitmap = reinterpret_cast<SkBitmap *>(c_bitmap);
@@ -1565,11 +3250,428 @@ extern "C"
     stream->write(string->c_str(), string->size());
   }
 
+  // The bytes written since the last call, without closing the document
+  void skiac_sk_w_stream_take(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data)
+  {
+    auto stream = reinterpret_cast<SkDynamicMemoryWStream *>(c_w_memory_stream);
+    auto data = stream->detachAsData().release();
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
+  // The whole document closed by `</svg>`, the stream is not modified
+  void skiac_sk_w_stream_snapshot(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data)
+  {
+    auto stream = reinterpret_cast<SkDynamicMemoryWStream *>(c_w_memory_stream);
+    auto size = stream->bytesWritten();
+    auto data = SkData::MakeUninitialized(size + 6).release();
+    auto bytes = reinterpret_cast<char *>(data->writable_data());
+    stream->copyTo(bytes);
+    memcpy(bytes + size, "</svg>", 6);
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
   void skiac_sk_w_stream_destroy(skiac_w_memory_stream *c_w_memory_stream)
   {
     delete reinterpret_cast<SkDynamicMemoryWStream *>(c_w_memory_stream);
   }
 
+  // SkPDF
+
+  // Ends the current page, returns nullptr if the document is closed
+  skiac_canvas *skiac_pdf_document_add_page(skiac_pdf_document *c_document, float width, float height)
+  {
+    if (c_document->closed)
+    {
+      return nullptr;
+    }
+    return reinterpret_cast<skiac_canvas *>(c_document->beginPage(width, height));
+  }
+
+  // Returns the canvas ignoring the later draws
+  skiac_canvas *skiac_pdf_document_close(skiac_pdf_document *c_document)
+  {
+    if (!c_document->closed)
+    {
+      c_document->releasePage();
+      c_document->document->close();
+      if (c_document->file)
+      {
+        c_document->file->close();
+      }
+      c_document->closed = true;
+    }
+    return reinterpret_cast<skiac_canvas *>(&c_document->closed_canvas);
+  }
+
+  // The bytes written since the last call, the data is empty if the document is written to a file
+  void skiac_pdf_document_take(skiac_pdf_document *c_document, skiac_sk_data *sk_data)
+  {
+    auto data = c_document->memory ? c_document->memory->detachAsData().release() : SkData::MakeEmpty().release();
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
+  void skiac_pdf_document_snapshot(skiac_pdf_document *c_document, skiac_sk_data *sk_data)
+  {
+    auto data = c_document->memory ? c_document->memory->snapshotAsData().release() : SkData::MakeEmpty().release();
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
+  void skiac_pdf_document_destroy(skiac_pdf_document *c_document)
+  {
+    if (!c_document->closed)
+    {
+      c_document->releasePage();
+      c_document->document->abort();
+    }
+    delete c_document;
+  }
+
+  // SkPicture
+
+  // Returns the canvas ignoring the later draws
+  skiac_canvas *skiac_picture_recorder_finish(skiac_picture_recorder *c_recorder)
+  {
+    if (!c_recorder->picture)
+    {
+      c_recorder->picture = c_recorder->recorder.finishRecordingAsPicture();
+    }
+    return reinterpret_cast<skiac_canvas *>(&c_recorder->closed_canvas);
+  }
+
+  // nullptr if the recording isn't finished
+  skiac_picture *skiac_picture_recorder_get_picture(skiac_picture_recorder *c_recorder)
+  {
+    return reinterpret_cast<skiac_picture *>(SkSafeRef(c_recorder->picture.get()));
+  }
+
+  void skiac_picture_recorder_destroy(skiac_picture_recorder *c_recorder)
+  {
+    delete c_recorder;
+  }
+
+  // nullptr if the data isn't a valid `.skp`
+  skiac_picture *skiac_picture_make_from_data(const uint8_t *data, size_t length)
+  {
+    auto picture = SkPicture::MakeFromData(data, length);
+    return reinterpret_cast<skiac_picture *>(picture.release());
+  }
+
+  void skiac_picture_get_cull_rect(skiac_picture *c_picture, skiac_rect *c_rect)
+  {
+    auto rect = reinterpret_cast<SkPicture *>(c_picture)->cullRect();
+    c_rect->left = rect.fLeft;
+    c_rect->top = rect.fTop;
+    c_rect->right = rect.fRight;
+    c_rect->bottom = rect.fBottom;
+  }
+
+  // The `.skp` bytes, the fonts are embedded so the picture can be replayed without them installed
+  void skiac_picture_serialize(skiac_picture *c_picture, skiac_sk_data *sk_data)
+  {
+    SkSerialProcs procs;
+    procs.fTypefaceProc = [](SkTypeface *typeface, void *) -> sk_sp<SkData>
+    {
+      SkDynamicMemoryWStream stream;
+      typeface->serialize(&stream, SkTypeface::SerializeBehavior::kDoIncludeData);
+      return stream.detachAsData();
+    };
+    auto data = reinterpret_cast<SkPicture *>(c_picture)->serialize(&procs).release();
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
+  void skiac_picture_destroy(skiac_picture *c_picture)
+  {
+    // SkPicture is ref counted.
+    reinterpret_cast<SkPicture *>(c_picture)->unref();
+  }
+
+  // SkiacFontRecordingCanvas
+
+  // Draw to the canvas of the recorder instead of `c_canvas`
+  skiac_svg_font_recorder *skiac_svg_font_recorder_create(skiac_canvas *c_canvas)
+  {
+    return reinterpret_cast<skiac_svg_font_recorder *>(new SkiacFontRecordingCanvas(CANVAS_CAST));
+  }
+
+  skiac_canvas *skiac_svg_font_recorder_get_canvas(skiac_svg_font_recorder *c_recorder)
+  {
+    return reinterpret_cast<skiac_canvas *>(reinterpret_cast<SkiacFontRecordingCanvas *>(c_recorder));
+  }
+
+  void skiac_svg_font_recorder_get_style(skiac_svg_font_recorder *c_recorder, skiac_sk_data *sk_data)
+  {
+    SkDynamicMemoryWStream stream;
+    reinterpret_cast<SkiacFontRecordingCanvas *>(c_recorder)->writeStyle(&stream);
+    auto data = stream.detachAsData().release();
+    sk_data->data = reinterpret_cast<skiac_data *>(data);
+    sk_data->ptr = data->bytes();
+    sk_data->size = data->size();
+  }
+
+  void skiac_svg_font_recorder_destroy(skiac_svg_font_recorder *c_recorder)
+  {
+    auto recorder = reinterpret_cast<SkiacFontRecordingCanvas *>(c_recorder);
+    // the SVG canvas outlives the recorder
+    recorder->removeAll();
+    delete recorder;
+  }
+
+  // SkiacFileWStream
+
+  bool skiac_w_file_stream_finish_svg(skiac_w_file_stream *c_w_file_stream, const uint8_t *style, size_t style_size)
+  {
+    auto stream = reinterpret_cast<SkiacFileWStream *>(c_w_file_stream);
+    auto success = stream->write(style, style_size) && stream->write("</svg>", 6);
+    stream->close();
+    return success;
+  }
+
+  void skiac_w_file_stream_destroy(skiac_w_file_stream *c_w_file_stream)
+  {
+    delete reinterpret_cast<SkiacFileWStream *>(c_w_file_stream);
+  }
+
+  // ImageDecoder
+
+  skiac_image_decoder *skiac_image_decoder_create()
+  {
+    return new skiac_image_decoder();
+  }
+
+  // Returns 0 if more data is needed, 1 once the image is decoded and -1 on failure.
+  int skiac_image_decoder_push(skiac_image_decoder *c_decoder, const uint8_t *ptr, size_t size, bool finished, skiac_decode_progress *progress)
+  {
+    auto buffer = c_decoder->buffer;
+    buffer->bytes.insert(buffer->bytes.end(), ptr, ptr + size);
+    buffer->finished = finished;
+    if (!c_decoder->codec)
+    {
+      SkCodec::Result result;
+      auto stream = std::make_unique<SkiacBufferedStream>(buffer);
+      c_decoder->stream = stream.get();
+      c_decoder->codec = SkCodec::MakeFromStream(std::move(stream), &result);
+      if (!c_decoder->codec)
+      {
+        // Not enough bytes to read the header yet.
+        return result == SkCodec::kIncompleteInput && !finished ? 0 : -1;
+      }
+      auto info = c_decoder->codec->getInfo().makeColorSpace(SkColorSpace::MakeSRGB());
+      auto bitmap = new SkBitmap();
+      if (!bitmap->tryAllocPixels(info))
+      {
+        delete bitmap;
+        return -1;
+      }
+      bitmap->eraseColor(SK_ColorTRANSPARENT);
+      c_decoder->bitmap = bitmap;
+      c_decoder->incremental = c_decoder->codec->startIncrementalDecode(info, bitmap->getPixels(), bitmap->rowBytes()) == SkCodec::kSuccess;
+    }
+    auto info = c_decoder->bitmap ? c_decoder->bitmap->info() : c_decoder->codec->getInfo();
+    progress->width = info.width();
+    progress->height = info.height();
+    progress->decoded_rows = 0;
+    if (c_decoder->complete)
+    {
+      progress->decoded_rows = info.height();
+      return 1;
+    }
+    if (!c_decoder->bitmap)
+    {
+      return -1;
+    }
+    if (c_decoder->incremental)
+    {
+      int rows_decoded = 0;
+      auto result = c_decoder->codec->incrementalDecode(&rows_decoded);
+      if (result == SkCodec::kSuccess)
+      {
+        progress->decoded_rows = info.height();
+        c_decoder->complete = true;
+        return 1;
+      }
+      if (result != SkCodec::kIncompleteInput)
+      {
+        return -1;
+      }
+      progress->decoded_rows = rows_decoded;
+      // The PNG codec only reads forward once the incremental decode started, the other codecs may
+      // rewind to read the frames again so they keep the whole input.
+      if (c_decoder->codec->getEncodedFormat() == SkEncodedImageFormat::kPNG)
+      {
+        c_decoder->stream->dropConsumed();
+      }
+      // Truncated input, keep the rows decoded so far like browsers do.
+      c_decoder->complete = finished;
+      return finished ? 1 : 0;
+    }
+    // Codecs without incremental support (JPEG, WebP...) have to wait for the whole input.
+    if (!finished)
+    {
+      return 0;
+    }
+    auto result = c_decoder->codec->getPixels(info, c_decoder->bitmap->getPixels(), c_decoder->bitmap->rowBytes());
+    if (result != SkCodec::kSuccess && result != SkCodec::kIncompleteInput)
+    {
+      return -1;
+    }
+    progress->decoded_rows = info.height();
+    c_decoder->complete = true;
+    return 1;
+  }
+
+  void skiac_image_decoder_get_bitmap(skiac_image_decoder *c_decoder, skiac_bitmap_info *bitmap_info)
+  {
+    auto bitmap = c_decoder->bitmap;
+    if (!c_decoder->complete || !bitmap)
+    {
+      return;
+    }
+    // The ownership of the bitmap is moved to the caller.
+    c_decoder->bitmap = nullptr;
+    bitmap_info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
+    bitmap_info->width = bitmap->width();
+    bitmap_info->height = bitmap->height();
+  }
+
+  void skiac_image_decoder_destroy(skiac_image_decoder *c_decoder)
+  {
+    if (c_decoder->bitmap)
+    {
+      delete c_decoder->bitmap;
+    }
+    delete c_decoder;
+  }
+
+  // StreamEncoder
+
+  // The rows are encoded from the pixmap of the image across calls, so it must stay in the memory.
+  // The texture of the GPU canvases can't be peeked, it's copied into a raster image.
+  static sk_sp<SkImage> stream_source_image(sk_sp<SkImage> image)
+  {
+    return image ? image->makeRasterImage() : nullptr;
+  }
+
+  skiac_stream_encoder *skiac_stream_encoder_create_png(skiac_surface *c_surface, skiac_png_options c_options)
+  {
+    auto c_encoder = new skiac_stream_encoder();
+    c_encoder->image = stream_source_image(png_source_image(c_surface, c_options));
+    if (!c_encoder->image || !c_encoder->image->peekPixels(&c_encoder->pixmap))
+    {
+      delete c_encoder;
+      return nullptr;
+    }
+    SkPngEncoder::Options options;
+    options.fZLibLevel = c_options.zlib_level;
+    options.fFilterFlags = (SkPngEncoder::FilterFlag)c_options.filter_flags;
+    c_encoder->encoder = SkPngEncoder::Make(&c_encoder->stream, c_encoder->pixmap, options);
+    if (!c_encoder->encoder)
+    {
+      delete c_encoder;
+      return nullptr;
+    }
+    return c_encoder;
+  }
+
+  skiac_stream_encoder *skiac_stream_encoder_create_jpeg(skiac_surface *c_surface, skiac_jpeg_options c_options)
+  {
+    auto c_encoder = new skiac_stream_encoder();
+    c_encoder->image = stream_source_image(jpeg_source_image(c_surface, c_options));
+    if (!c_encoder->image || !c_encoder->image->peekPixels(&c_encoder->pixmap))
+    {
+      delete c_encoder;
+      return nullptr;
+    }
+    c_encoder->encoder = SkJpegEncoder::Make(&c_encoder->stream, c_encoder->pixmap, conv_from_jpeg_options(c_options));
+    if (!c_encoder->encoder)
+    {
+      delete c_encoder;
+      return nullptr;
+    }
+    return c_encoder;
+  }
+
+  // Returns the count of the rows left, or -1 if the encoding failed.
+  // `data` points to the bytes written by this call and the header, it's valid until the next call.
+  int skiac_stream_encoder_encode_rows(skiac_stream_encoder *c_encoder, int rows, const uint8_t **data, size_t *size)
+  {
+    if (c_encoder->taken)
+    {
+      c_encoder->stream.bytes.clear();
+    }
+    auto height = c_encoder->pixmap.height();
+    rows = std::min(rows, height - c_encoder->encoded_rows);
+    if (rows > 0)
+    {
+      if (!c_encoder->encoder->encodeRows(rows))
+      {
+        return -1;
+      }
+      c_encoder->encoded_rows += rows;
+    }
+    c_encoder->taken = true;
+    *data = c_encoder->stream.bytes.data();
+    *size = c_encoder->stream.bytes.size();
+    return height - c_encoder->encoded_rows;
+  }
+
+  void skiac_stream_encoder_destroy(skiac_stream_encoder *c_encoder)
+  {
+    delete c_encoder;
+  }
+
+  // SkColorSpace
+
+  void skiac_color_space_icc(uint8_t cs, skiac_sk_data *data)
+  {
+    auto color_space = COLOR_SPACE_CAST;
+    skcms_TransferFunction transfer_fn;
+    skcms_Matrix3x3 to_xyz;
+    color_space->transferFn(&transfer_fn);
+    if (!color_space->toXYZD50(&to_xyz))
+    {
+      return;
+    }
+    auto icc = SkWriteICCProfile(transfer_fn, to_xyz);
+    if (!icc)
+    {
+      return;
+    }
+    auto icc_data = icc.release();
+    data->ptr = const_cast<uint8_t *>(icc_data->bytes());
+    data->size = icc_data->size();
+    data->data = reinterpret_cast<skiac_data *>(icc_data);
+  }
+
+  // SkDeflateWStream
+
+  // zlib stream, used by the indexed color PNG output
+  void skiac_deflate(const uint8_t *data, size_t size, int level, skiac_sk_data *output_data)
+  {
+    SkDynamicMemoryWStream stream;
+    {
+      SkDeflateWStream deflate(&stream, level);
+      if (!deflate.write(data, size))
+      {
+        return;
+      }
+      deflate.finalize();
+    }
+    auto encoded_data = stream.detachAsData().release();
+    output_data->ptr = const_cast<uint8_t *>(encoded_data->bytes());
+    output_data->size = encoded_data->size();
+    output_data->data = reinterpret_cast<skiac_data *>(encoded_data);
+  }
+
   // SkSVG
   void skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, skiac_sk_data *output_data)
   {
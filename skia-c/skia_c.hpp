@@ -7,27 +7,43 @@
 #include <include/core/SkString.h>
 #include <include/effects/SkImageFilters.h>
 #include <include/pathops/SkPathOps.h>
+#include <include/utils/SkBase64.h>
+#include <include/utils/SkNWayCanvas.h>
+#include <include/utils/SkNoDrawCanvas.h>
 #include <include/utils/SkParsePath.h>
 #include <include/core/SkBitmap.h>
+#include <include/core/SkAnnotation.h>
 #include <include/core/SkCanvas.h>
+#include <include/core/SkContourMeasure.h>
 #include <include/core/SkColorFilter.h>
 #include <include/core/SkData.h>
 #include <include/core/SkDrawable.h>
 #include <include/core/SkGraphics.h>
+#include <include/core/SkICC.h>
+#include <include/core/SkImage.h>
 #include <include/core/SkFontMgr.h>
 #include <include/core/SkPaint.h>
 #include <include/core/SkPathEffect.h>
 #include <include/core/SkSurface.h>
+#include <include/core/SkTextBlob.h>
 #include <include/core/SkMaskFilter.h>
 #include <include/core/SkStream.h>
 #include <include/core/SkPictureRecorder.h>
+#include <include/core/SkRSXform.h>
 #include <include/core/SkStrokeRec.h>
+#include <include/docs/SkPDFDocument.h>
 #include <include/effects/SkColorMatrix.h>
+#include <include/effects/SkColorMatrixFilter.h>
 #include <include/effects/SkDashPathEffect.h>
+#include <include/effects/SkLumaColorFilter.h>
 #include <include/effects/SkImageFilters.h>
 #include <include/effects/SkTableColorFilter.h>
 #include <include/effects/SkTrimPathEffect.h>
 #include <include/effects/SkGradientShader.h>
+#include <include/effects/SkRuntimeEffect.h>
+#include <include/encode/SkJpegEncoder.h>
+#include <include/encode/SkPngEncoder.h>
+#include <include/encode/SkWebpEncoder.h>
 #include <include/svg/SkSVGCanvas.h>
 #include <modules/skparagraph/include/FontCollection.h>
 #include <modules/skparagraph/include/Paragraph.h>
@@ -42,8 +58,31 @@
 #include <src/ports/SkFontMgr_custom.h>
 #include <src/core/SkFontDescriptor.h>
 #include <src/xml/SkXMLWriter.h>
+#include <src/pdf/SkDeflate.h>
 
+#ifdef SKIAC_GPU
+#include <include/gpu/GrBackendSurface.h>
+#include <include/gpu/GrDirectContext.h>
+#endif
+
+#ifdef SKIAC_GL
+#include <EGL/egl.h>
+#include <EGL/eglext.h>
+#include <GLES2/gl2.h>
+#include <GLES2/gl2ext.h>
+#include <include/gpu/gl/GrGLInterface.h>
+#endif
+
+#ifdef SKIAC_VULKAN
+#include <dlfcn.h>
+#include <include/gpu/vk/GrVkBackendContext.h>
+#include <include/gpu/vk/GrVkExtensions.h>
+#endif
+
+#include <algorithm>
+#include <memory>
 #include <stdint.h>
+#include <vector>
 
 using namespace skia::textlayout;
 
@@ -56,6 +95,7 @@ typedef struct skiac_path_effect skiac_path_effect;
 typedef struct skiac_matrix skiac_matrix;
 typedef struct skiac_mask_filter skiac_mask_filter;
 typedef struct skiac_image_filter skiac_image_filter;
+typedef struct skiac_color_filter skiac_color_filter;
 typedef struct skiac_data skiac_data;
 typedef struct skiac_image skiac_image;
 typedef struct skiac_bitmap skiac_bitmap;
@@ -65,6 +105,11 @@ typedef struct skiac_typeface skiac_typeface;
 typedef struct skiac_font_mgr skiac_font_mgr;
 typedef struct skiac_typeface_font_provider skiac_typeface_font_provider;
 typedef struct skiac_w_memory_stream skiac_w_memory_stream;
+typedef struct skiac_w_file_stream skiac_w_file_stream;
+typedef struct skiac_pdf_document skiac_pdf_document;
+typedef struct skiac_svg_font_recorder skiac_svg_font_recorder;
+typedef struct skiac_picture skiac_picture;
+typedef struct skiac_gpu_context skiac_gpu_context;
 
 #if defined(WIN32) || defined(_WIN32) || defined(__WIN32__) || defined(__NT__)
 #define SK_FONT_FILE_PREFIX "C:/Windows/Fonts"
@@ -137,6 +182,303 @@ struct skiac_font_collection
   }
 };
 
+struct skiac_stream_buffer
+{
+  std::vector<uint8_t> bytes;
+  // Position in the input of bytes[0], the bytes before it were consumed and dropped.
+  size_t offset = 0;
+  bool finished = false;
+};
+
+// SkStream over the bytes received so far, reads return short counts until more data arrives.
+// It can't rewind once the consumed bytes were dropped.
+class SkiacBufferedStream : public SkStream
+{
+public:
+  explicit SkiacBufferedStream(std::shared_ptr<skiac_stream_buffer> buffer) : buffer(std::move(buffer))
+  {
+  }
+
+  size_t read(void *buffer, size_t size) override;
+  size_t peek(void *buffer, size_t size) const override;
+  bool isAtEnd() const override;
+  bool rewind() override;
+
+  // Drops the bytes before the read position.
+  void dropConsumed();
+
+private:
+  std::shared_ptr<skiac_stream_buffer> buffer;
+  size_t position = 0;
+};
+
+struct skiac_image_decoder
+{
+  std::shared_ptr<skiac_stream_buffer> buffer = std::make_shared<skiac_stream_buffer>();
+  std::unique_ptr<SkCodec> codec;
+  // Owned by the codec.
+  SkiacBufferedStream *stream = nullptr;
+  SkBitmap *bitmap = nullptr;
+  bool incremental = false;
+  bool complete = false;
+};
+
+// SkWStream collecting the bytes written since the last time they were taken.
+class SkiacChunkWStream : public SkWStream
+{
+public:
+  bool write(const void *buffer, size_t size) override;
+  void flush() override {}
+  size_t bytesWritten() const override { return written; }
+
+  std::vector<uint8_t> bytes;
+
+private:
+  size_t written = 0;
+};
+
+// SkWStream writing to a file until it's closed, the writes after that are dropped.
+// The SVG canvas keeps writing to its stream after the document is finished.
+class SkiacFileWStream : public SkWStream
+{
+public:
+  explicit SkiacFileWStream(const char *path) : file(new SkFILEWStream(path))
+  {
+  }
+  ~SkiacFileWStream() override { close(); }
+
+  bool isValid() const { return file && file->isValid(); }
+  bool write(const void *buffer, size_t size) override;
+  void flush() override;
+  size_t bytesWritten() const override { return written; }
+  void close();
+
+private:
+  SkFILEWStream *file;
+  size_t written = 0;
+};
+
+// Forwards the draws to the wrapped canvas, with the text outlined to paths
+class SkiacTextToPathCanvas : public SkNWayCanvas
+{
+public:
+  explicit SkiacTextToPathCanvas(SkCanvas *canvas) : SkNWayCanvas(canvas->getBaseLayerSize().width(), canvas->getBaseLayerSize().height())
+  {
+    addCanvas(canvas);
+  }
+
+protected:
+  void onDrawTextBlob(const SkTextBlob *blob, SkScalar x, SkScalar y, const SkPaint &paint) override;
+};
+
+// Forwards the draws to the wrapped SVG canvas, and records the typefaces of the text to embed them
+class SkiacFontRecordingCanvas : public SkNWayCanvas
+{
+public:
+  explicit SkiacFontRecordingCanvas(SkCanvas *canvas) : SkNWayCanvas(canvas->getBaseLayerSize().width(), canvas->getBaseLayerSize().height())
+  {
+    addCanvas(canvas);
+  }
+
+  // `<style>` with the @font-face rules of the recorded typefaces, nothing is written without text
+  void writeStyle(SkWStream *stream) const;
+
+protected:
+  void onDrawTextBlob(const SkTextBlob *blob, SkScalar x, SkScalar y, const SkPaint &paint) override;
+
+private:
+  std::vector<sk_sp<SkTypeface>> typefaces;
+};
+
+#ifdef SKIAC_GL
+// Headless EGL context of the GPU canvases, one for each thread.
+// The GPU surfaces must be drawn and destroyed on the thread created them
+class SkiacGLContext
+{
+public:
+  ~SkiacGLContext();
+  // nullptr if there is no usable GPU, the failed initialization is not retried
+  static GrDirectContext *current();
+
+private:
+  bool init();
+  EGLDisplay display = EGL_NO_DISPLAY;
+  EGLSurface surface = EGL_NO_SURFACE;
+  EGLContext context = EGL_NO_CONTEXT;
+  sk_sp<GrDirectContext> direct_context;
+};
+#endif
+
+#ifdef SKIAC_VULKAN
+// Headless Vulkan contexts of the physical devices, one for each thread like `SkiacGLContext`.
+// The Vulkan loader is opened at runtime, so the library still loads on the machines without it
+class SkiacVulkanContext
+{
+public:
+  ~SkiacVulkanContext();
+  // `device_uuid` is 16 bytes and takes precedence over `device_index`,
+  // the first usable device is selected if both are unset, nullptr if there is no such device
+  static GrDirectContext *current(int device_index, const uint8_t *device_uuid);
+
+private:
+  struct Device
+  {
+    VkPhysicalDevice physical_device = VK_NULL_HANDLE;
+    VkDevice device = VK_NULL_HANDLE;
+    sk_sp<GrDirectContext> direct_context;
+    // the context creation is not retried
+    bool initialized = false;
+  };
+  bool init();
+  GrDirectContext *device_context(Device &device);
+  void *library = nullptr;
+  PFN_vkGetInstanceProcAddr get_instance_proc = nullptr;
+  VkInstance instance = VK_NULL_HANDLE;
+  std::vector<Device> devices;
+};
+#endif
+
+struct skiac_external_texture
+{
+  // 0 is the dmabuf, 1 is the IOSurface
+  uint8_t kind;
+  // the single plane dmabuf
+  int fd;
+  uint32_t fourcc;
+  uint32_t stride;
+  uint32_t offset;
+  // DRM_FORMAT_MOD_INVALID if the modifier is implicit
+  uint64_t modifier;
+  uint32_t io_surface;
+};
+
+#ifdef SKIAC_METAL
+// Metal context of the system default device, one for each thread like `SkiacGLContext`.
+// nullptr if there is no Metal device, implemented in skia_c_metal.mm
+GrDirectContext *skiac_metal_context();
+// the BGRA IOSurface of the same size as the surface, looked up by the ID of it
+sk_sp<SkSurface> skiac_metal_import_io_surface(int width, int height, sk_sp<SkColorSpace> color_space, uint32_t io_surface_id, int sample_count, const SkSurfaceProps &props);
+// 0 if the surface is not backed by an IOSurface
+uint32_t skiac_metal_io_surface_id(SkSurface *surface);
+#endif
+
+struct skiac_pdf_document
+{
+  // only one of the outputs is set
+  std::unique_ptr<SkDynamicMemoryWStream> memory;
+  std::unique_ptr<SkiacFileWStream> file;
+  sk_sp<SkDocument> document;
+  // wraps the page canvas if the text is drawn as paths
+  std::unique_ptr<SkiacTextToPathCanvas> text_to_path_canvas;
+  // drawn to after the document is closed, the page canvas is owned by the document
+  SkNoDrawCanvas closed_canvas;
+  bool text_to_paths = false;
+  bool closed = false;
+  skiac_pdf_document(int w, int h) : closed_canvas(w, h) {}
+  // ends the current page
+  SkCanvas *beginPage(float width, float height);
+  // must be called before the page canvas is destroyed by the document
+  void releasePage();
+};
+
+struct skiac_pdf_date_time
+{
+  int16_t time_zone_minutes;
+  uint16_t year;
+  uint8_t month;
+  uint8_t day_of_week;
+  uint8_t day;
+  uint8_t hour;
+  uint8_t minute;
+  uint8_t second;
+};
+
+// the strings are nullable
+struct skiac_pdf_metadata
+{
+  const char *title;
+  const char *author;
+  const char *subject;
+  const char *keywords;
+  const char *creator;
+  const char *producer;
+  bool has_creation;
+  skiac_pdf_date_time creation;
+  bool has_modified;
+  skiac_pdf_date_time modified;
+  bool pdf_a;
+};
+
+struct skiac_pdf_surface
+{
+  skiac_pdf_document *document;
+  skiac_surface *surface;
+  skiac_canvas *canvas;
+};
+
+// The pixels read by `asyncRescaleAndReadPixels`, the GPU surfaces complete it when the GPU finishes the draws
+struct skiac_pixels_readback
+{
+  // keeps the GPU context of the surface alive while it's pending
+  sk_sp<SkSurface> surface;
+  int width = 0;
+  int height = 0;
+  bool done = false;
+  // nullptr if the read failed
+  std::unique_ptr<const SkSurface::AsyncReadResult> result;
+};
+
+// The compiled SkSL of `ShaderEffect`
+struct skiac_runtime_effect
+{
+  sk_sp<SkRuntimeEffect> effect;
+  // the uniforms and the child shaders in the declaration order
+  std::vector<const SkRuntimeEffect::Uniform *> uniforms;
+  size_t child_count = 0;
+};
+
+struct skiac_runtime_effect_uniform
+{
+  const char *name;
+  // in 4 bytes values, the floats and the ints are 4 bytes
+  size_t offset;
+  size_t count;
+  bool is_int;
+};
+
+struct skiac_picture_recorder
+{
+  SkPictureRecorder recorder;
+  sk_sp<SkPicture> picture;
+  // drawn to after the recording is finished
+  SkNoDrawCanvas closed_canvas;
+  skiac_picture_recorder(int w, int h) : closed_canvas(w, h) {}
+};
+
+struct skiac_picture_surface
+{
+  skiac_picture_recorder *recorder;
+  skiac_surface *surface;
+  skiac_canvas *canvas;
+};
+
+struct skiac_stream_encoder
+{
+  sk_sp<SkImage> image;
+  SkPixmap pixmap;
+  SkiacChunkWStream stream;
+  std::unique_ptr<SkEncoder> encoder;
+  int encoded_rows = 0;
+  bool taken = false;
+};
+
+struct skiac_decode_progress
+{
+  int width;
+  int height;
+  int decoded_rows;
+};
+
 struct skiac_line_metrics
 {
   float ascent;
@@ -148,6 +490,16 @@ struct skiac_line_metrics
   float font_descent;
 };
 
+struct skiac_surface_props
+{
+  // MSAA samples of the GPU surfaces, 1 is off
+  int sample_count;
+  // SkPixelGeometry of the LCD text
+  uint8_t pixel_geometry;
+  // SkColorType of the pixels, RGBA_8888, RGBA_F16 or Alpha_8
+  int color_type;
+};
+
 struct skiac_rect
 {
   float left;
@@ -172,6 +524,32 @@ struct skiac_point
   float y;
 };
 
+struct skiac_png_options
+{
+  int zlib_level;
+  int filter_flags;
+  // 8 or 16 bits per channel, 0 is the bit depth of the surface
+  int bit_depth;
+};
+
+struct skiac_jpeg_options
+{
+  int quality;
+  // SkJpegEncoder::Downsample
+  int downsample;
+  bool has_background;
+  uint32_t background;
+};
+
+struct skiac_sampling_options
+{
+  int filter_mode;
+  int mipmap_mode;
+  bool use_cubic;
+  float cubic_b;
+  float cubic_c;
+};
+
 struct skiac_surface_data
 {
   uint8_t *ptr;
@@ -215,7 +593,15 @@ extern "C"
   // Surface
   skiac_surface *skiac_surface_create_rgba_premultiplied(int width, int height, uint8_t cs);
   void skiac_surface_create_svg(skiac_svg_surface *c_surface, int width, int height, int alphaType, uint32_t flag, uint8_t cs);
+  void skiac_surface_create_pdf(skiac_pdf_surface *c_surface, int width, int height, int alphaType, uint8_t cs, const char *path, const skiac_pdf_metadata *c_metadata, bool text_to_paths);
+  void skiac_surface_create_picture(skiac_picture_surface *c_surface, int width, int height, int alphaType, uint8_t cs);
+  skiac_w_file_stream *skiac_surface_create_svg_file(skiac_svg_surface *c_surface, int width, int height, int alphaType, uint32_t flag, uint8_t cs, const char *path);
   skiac_surface *skiac_surface_create_rgba(int width, int height, uint8_t cs);
+  skiac_surface *skiac_surface_create_with_props(int width, int height, uint8_t cs, skiac_surface_props c_props);
+  skiac_surface *skiac_surface_create_gpu(int width, int height, uint8_t cs, uint8_t backend, int device_index, const uint8_t *device_uuid, skiac_surface_props c_props);
+  skiac_surface *skiac_surface_create_external(int width, int height, uint8_t cs, skiac_external_texture c_texture, skiac_surface_props c_props);
+  bool skiac_surface_is_gpu(skiac_surface *c_surface);
+  bool skiac_surface_export_texture(skiac_surface *c_surface, skiac_external_texture *c_texture);
   void skiac_surface_destroy(skiac_surface *c_surface);
   skiac_surface *skiac_surface_copy_rgba(
       skiac_surface *c_surface,
@@ -224,17 +610,41 @@ extern "C"
       uint32_t width,
       uint32_t height,
       uint8_t cs);
+  skiac_surface *skiac_surface_make_raster_copy(skiac_surface *c_surface);
+  skiac_surface *skiac_surface_make_opaque_copy(skiac_surface *c_surface);
+  skiac_surface *skiac_surface_make_crop(skiac_surface *c_surface, int x, int y, int width, int height);
+  skiac_surface *skiac_surface_make_resized(skiac_surface *c_surface, int width, int height, int contents, skiac_sampling_options c_sampling);
   skiac_canvas *skiac_surface_get_canvas(skiac_surface *c_surface);
   int skiac_surface_get_width(skiac_surface *c_surface);
   int skiac_surface_get_height(skiac_surface *c_surface);
   void skiac_surface_read_pixels(skiac_surface *c_surface, skiac_surface_data *data);
-  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs);
+  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs, int color_type, int alpha_type);
+  bool skiac_surface_write_pixels(skiac_surface *c_surface, const uint8_t *data, int w, int h, uint8_t cs, int color_type, int alpha_type);
+  bool skiac_surface_read_pixels_as(skiac_surface *c_surface, uint8_t *data, int color_type, int alpha_type);
   void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data);
   void skiac_surface_encode_data(skiac_surface *c_surface, skiac_sk_data *data, int format, int quality);
+  void skiac_surface_encode_png(skiac_surface *c_surface, skiac_sk_data *data, skiac_png_options c_options);
+  void skiac_surface_encode_jpeg(skiac_surface *c_surface, skiac_sk_data *data, skiac_jpeg_options c_options);
+  void skiac_surface_encode_webp(skiac_surface *c_surface, skiac_sk_data *data, float quality, bool lossless);
   int skiac_surface_get_alpha_type(skiac_surface *c_surface);
+  int skiac_surface_get_bytes_per_pixel(skiac_surface *c_surface);
   bool skiac_surface_save(skiac_surface *c_surface, const char *path);
   void skiac_surface_get_bitmap(skiac_surface *c_surface, skiac_bitmap_info *info);
 
+  // GpuContext
+  skiac_gpu_context *skiac_gpu_context_get(uint8_t backend, int device_index, const uint8_t *device_uuid);
+  size_t skiac_gpu_context_get_resource_cache_limit(skiac_gpu_context *c_gpu_context);
+  void skiac_gpu_context_set_resource_cache_limit(skiac_gpu_context *c_gpu_context, size_t bytes);
+  size_t skiac_gpu_context_get_resource_cache_usage(skiac_gpu_context *c_gpu_context);
+  void skiac_gpu_context_free_resources(skiac_gpu_context *c_gpu_context);
+
+  // PixelsReadback
+  skiac_pixels_readback *skiac_surface_read_pixels_async(skiac_surface *c_surface, int x, int y, int w, int h, uint8_t cs);
+  bool skiac_pixels_readback_poll(skiac_pixels_readback *c_readback);
+  bool skiac_pixels_readback_copy(skiac_pixels_readback *c_readback, uint8_t *data);
+  skiac_surface *skiac_pixels_readback_make_surface(skiac_pixels_readback *c_readback, uint8_t cs);
+  void skiac_pixels_readback_destroy(skiac_pixels_readback *c_readback);
+
   // Canvas
   void skiac_canvas_clear(skiac_canvas *c_canvas, uint32_t color);
   void skiac_canvas_set_transform(skiac_canvas *c_canvas, skiac_matrix *c_matrix);
@@ -256,8 +666,30 @@ extern "C"
       float dy,
       float d_width,
       float d_height,
-      bool enable_smoothing,
-      int filter_quality,
+      skiac_sampling_options c_sampling,
+      skiac_paint *c_paint);
+  void skiac_canvas_draw_image_nine(
+      skiac_canvas *c_canvas,
+      skiac_bitmap *c_bitmap,
+      int center_x,
+      int center_y,
+      int center_width,
+      int center_height,
+      float dx,
+      float dy,
+      float d_width,
+      float d_height,
+      int filter_mode,
+      skiac_paint *c_paint);
+  void skiac_canvas_draw_atlas(
+      skiac_canvas *c_canvas,
+      skiac_bitmap *c_bitmap,
+      const float *xforms,
+      const float *src_rects,
+      const uint32_t *colors,
+      int count,
+      int blend_mode,
+      skiac_sampling_options c_sampling,
       skiac_paint *c_paint);
   void skiac_canvas_draw_path(skiac_canvas *c_canvas, skiac_path *c_path, skiac_paint *c_paint);
   void skiac_canvas_draw_rect(
@@ -271,7 +703,7 @@ extern "C"
       float top,
       uint8_t alpha,
       int blend_mode,
-      int filter_quality);
+      skiac_sampling_options c_sampling);
   void skiac_canvas_draw_surface_rect(
       skiac_canvas *c_canvas,
       skiac_surface *c_surface,
@@ -283,7 +715,7 @@ extern "C"
       float dy,
       float dw,
       float dh,
-      int filter_quality);
+      skiac_sampling_options c_sampling);
   void skiac_canvas_get_line_metrics_or_draw_text(
       const char *text,
       size_t text_len,
@@ -305,11 +737,17 @@ extern "C"
       skiac_line_metrics *c_line_metrics);
   void skiac_canvas_reset_transform(skiac_canvas *c_canvas);
   void skiac_canvas_clip_rect(skiac_canvas *c_canvas, float x, float y, float w, float h);
-  void skiac_canvas_clip_path(skiac_canvas *c_canvas, skiac_path *c_path);
+  void skiac_canvas_annotate_link_to_url(skiac_canvas *c_canvas, float x, float y, float w, float h, const char *url);
+  void skiac_canvas_annotate_link_to_destination(skiac_canvas *c_canvas, float x, float y, float w, float h, const char *name);
+  void skiac_canvas_annotate_named_destination(skiac_canvas *c_canvas, float x, float y, const char *name);
+  void skiac_canvas_clip_path(skiac_canvas *c_canvas, skiac_path *c_path, bool aa);
+  void skiac_canvas_draw_picture(skiac_canvas *c_canvas, skiac_picture *c_picture, skiac_matrix *c_matrix, skiac_paint *c_paint);
+  bool skiac_canvas_draw_svg(skiac_canvas *c_canvas, const uint8_t *data, size_t length, float dx, float dy, float dw, float dh, skiac_paint *c_paint, skiac_font_collection *c_collection);
   void skiac_canvas_save(skiac_canvas *c_canvas);
+  void skiac_canvas_save_layer(skiac_canvas *c_canvas, skiac_paint *c_paint, skiac_rect *c_bounds);
   void skiac_canvas_restore(skiac_canvas *c_canvas);
   void skiac_canvas_reset(skiac_canvas *c_canvas);
-  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y);
+  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y, uint8_t cs);
   void skiac_canvas_write_pixels_dirty(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, size_t length, float x, float y, float dirty_x, float dirty_y, float dirty_width, float dirty_height, uint8_t cs);
 
   // Paint
@@ -321,6 +759,7 @@ extern "C"
   void skiac_paint_set_alpha(skiac_paint *c_paint, uint8_t a);
   uint8_t skiac_paint_get_alpha(skiac_paint *c_paint);
   void skiac_paint_set_anti_alias(skiac_paint *c_paint, bool aa);
+  bool skiac_paint_get_anti_alias(skiac_paint *c_paint);
   void skiac_paint_set_blend_mode(skiac_paint *c_paint, int blend_mode);
   int skiac_paint_get_blend_mode(skiac_paint *c_paint);
   void skiac_paint_set_shader(skiac_paint *c_paint, skiac_shader *c_shader);
@@ -335,21 +774,23 @@ extern "C"
   void skiac_paint_set_path_effect(skiac_paint *c_paint, skiac_path_effect *c_path_effect);
   void skiac_paint_set_mask_filter(skiac_paint *c_paint, skiac_mask_filter *c_mask_filter);
   void skiac_paint_set_image_filter(skiac_paint *c_paint, skiac_image_filter *c_image_filter);
+  void skiac_paint_set_color_filter(skiac_paint *c_paint, skiac_color_filter *c_color_filter);
 
   // Path
   skiac_path *skiac_path_create();
-  skiac_path *skiac_path_from_svg(char *svg_path);
   skiac_path *skiac_path_clone(skiac_path *c_path);
   void skiac_path_swap(skiac_path *c_path, skiac_path *other_path);
   void skiac_add_path(skiac_path *c_path, skiac_path *other_path, skiac_matrix *c_matrix);
   bool skiac_path_op(skiac_path *c_path_one, skiac_path *c_path_two, int op);
   void skiac_path_to_svg_string(skiac_path *c_path, skiac_string *c_string);
   bool skiac_path_simplify(skiac_path *c_path);
-  bool skiac_path_stroke(skiac_path *c_path, int cap, uint8_t join, float width, float miter_limit);
+  bool skiac_path_stroke(skiac_path *c_path, int cap, uint8_t join, float width, float miter_limit, skiac_path_effect *c_path_effect);
   void skiac_path_get_bounds(skiac_path *c_path, skiac_rect *c_rect);
   void skiac_path_compute_tight_bounds(skiac_path *c_path, skiac_rect *c_rect);
+  float skiac_path_length(skiac_path *c_path);
+  bool skiac_path_get_pos_tan(skiac_path *c_path, float distance, skiac_point *c_position, skiac_point *c_tangent);
   bool skiac_path_trim(skiac_path *c_path, float start_t, float stop_t, bool is_complement);
-  bool skiac_path_dash(skiac_path *c_path, float on, float off, float phase);
+  bool skiac_path_dash(skiac_path *c_path, const float *intervals, int count, float phase);
   bool skiac_path_equals(skiac_path *c_path, skiac_path *other_path);
   void skiac_path_destroy(skiac_path *c_path);
   void skiac_path_set_fill_type(skiac_path *c_path, int type);
@@ -357,6 +798,7 @@ extern "C"
   bool skiac_path_as_winding(skiac_path *c_path);
   void skiac_path_arc_to(skiac_path *c_path, float left, float top, float right, float bottom, float startAngle, float sweepAngle, bool forceMoveTo);
   void skiac_path_arc_to_tangent(skiac_path *c_path, float x1, float y1, float x2, float y2, float radius);
+  void skiac_path_svg_arc_to(skiac_path *c_path, float rx, float ry, float x_axis_rotate, bool large_arc, bool sweep, float x, float y);
   void skiac_path_move_to(skiac_path *c_path, float x, float y);
   void skiac_path_line_to(skiac_path *c_path, float x, float y);
   void skiac_path_cubic_to(
@@ -364,6 +806,7 @@ extern "C"
       float x1, float y1, float x2, float y2, float x3, float y3);
   void skiac_path_quad_to(skiac_path *c_path, float cpx, float cpy, float x, float y);
   void skiac_path_close(skiac_path *c_path);
+  void skiac_path_add_round_rect(skiac_path *c_path, float x, float y, float width, float height, const skiac_point *radii);
   void skiac_path_add_rect(skiac_path *c_path, float l, float t, float r, float b);
   void skiac_path_add_circle(skiac_path *c_path, float x, float y, float r);
   skiac_path *skiac_path_transform(skiac_path *c_path, skiac_matrix *c_matrix);
@@ -413,6 +856,14 @@ extern "C"
 
   void skiac_shader_destroy(skiac_shader *c_shader);
 
+  // RuntimeEffect
+  skiac_runtime_effect *skiac_runtime_effect_make(const char *sksl, skiac_string *c_error);
+  size_t skiac_runtime_effect_get_uniform_count(skiac_runtime_effect *c_effect);
+  void skiac_runtime_effect_get_uniform(skiac_runtime_effect *c_effect, size_t index, skiac_runtime_effect_uniform *c_uniform);
+  size_t skiac_runtime_effect_get_uniform_size(skiac_runtime_effect *c_effect);
+  skiac_shader *skiac_runtime_effect_make_shader(skiac_runtime_effect *c_effect, const float *uniforms, skiac_shader *c_input);
+  void skiac_runtime_effect_destroy(skiac_runtime_effect *c_effect);
+
   // Matrix
   skiac_matrix *skiac_matrix_create();
   skiac_matrix *skiac_matrix_new(float a, float b, float c, float d, float e, float f);
@@ -443,15 +894,31 @@ extern "C"
   skiac_image_filter *skiac_image_filter_make_blur(float sigma_x, float sigma_y, int tile_mode, skiac_image_filter *c_image_filter);
   skiac_image_filter *skiac_image_filter_color_filter(float m00, float m01, float m02, float m10, float m11, float m12, float m20, float m21, float m22, float opacity, skiac_image_filter *c_image_filter);
   skiac_image_filter *skiac_image_filter_from_argb(const uint8_t table_a[256], const uint8_t table_r[256], const uint8_t table_g[256], const uint8_t table_b[256], skiac_image_filter *c_image_filter);
+  skiac_image_filter *skiac_image_filter_make_offset(float dx, float dy, skiac_image_filter *c_image_filter);
+  skiac_image_filter *skiac_image_filter_make_color_filter(skiac_color_filter *c_color_filter, skiac_image_filter *c_image_filter);
+  skiac_image_filter *skiac_image_filter_make_merge(skiac_image_filter **c_image_filters, size_t count);
+  skiac_image_filter *skiac_image_filter_make_compose(skiac_image_filter *c_outer, skiac_image_filter *c_inner);
+  skiac_image_filter *skiac_image_filter_make_displacement_map(int x_channel, int y_channel, float scale, skiac_image_filter *c_displacement, skiac_image_filter *c_image_filter);
   void skiac_image_filter_destroy(skiac_image_filter *c_image_filter);
 
+  // ColorFilter
+  skiac_color_filter *skiac_color_filter_make_matrix(const float row_major[20]);
+  skiac_color_filter *skiac_color_filter_make_blend(uint32_t color, int blend_mode);
+  skiac_color_filter *skiac_color_filter_make_lighting(uint32_t mul, uint32_t add);
+  skiac_color_filter *skiac_color_filter_make_luma();
+  skiac_color_filter *skiac_color_filter_compose(skiac_color_filter *c_outer, skiac_color_filter *c_inner);
+  void skiac_color_filter_ref(skiac_color_filter *c_color_filter);
+  void skiac_color_filter_destroy(skiac_color_filter *c_color_filter);
+
   // Data
   void skiac_sk_data_destroy(skiac_data *c_data);
 
   // Bitmap
-  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, skiac_bitmap_info *bitmap_info);
+  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, int width, int height, uint8_t cs, bool color_space_conversion, skiac_bitmap_info *bitmap_info);
+  void skiac_bitmap_make_from_pixels(const uint8_t *ptr, int width, int height, skiac_bitmap_info *bitmap_info);
   void skiac_bitmap_make_from_svg(const uint8_t *data, size_t length, float width, float height, skiac_bitmap_info *bitmap_info, uint8_t cs);
   skiac_bitmap *skiac_bitmap_make_from_image_data(uint8_t *ptr, size_t width, size_t height, size_t row_bytes, size_t size, int ct, int at);
+  bool skiac_bitmap_read_pixels(skiac_bitmap *c_bitmap, uint8_t *data, size_t row_bytes);
   size_t skiac_bitmap_get_width(skiac_bitmap *c_bitmap);
   size_t skiac_bitmap_get_height(skiac_bitmap *c_bitmap);
   skiac_shader *skiac_bitmap_get_shader(
@@ -477,8 +944,54 @@ extern "C"
 
   // SkDynamicMemoryWStream
   void skiac_sk_w_stream_get(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data, int width, int height);
+  void skiac_sk_w_stream_take(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data);
+  void skiac_sk_w_stream_snapshot(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data);
   void skiac_sk_w_stream_destroy(skiac_w_memory_stream *c_w_memory_stream);
 
+  // SkPDF
+  skiac_canvas *skiac_pdf_document_add_page(skiac_pdf_document *c_document, float width, float height);
+  skiac_canvas *skiac_pdf_document_close(skiac_pdf_document *c_document);
+  void skiac_pdf_document_take(skiac_pdf_document *c_document, skiac_sk_data *sk_data);
+  void skiac_pdf_document_snapshot(skiac_pdf_document *c_document, skiac_sk_data *sk_data);
+  void skiac_pdf_document_destroy(skiac_pdf_document *c_document);
+
+  // SkPicture
+  skiac_canvas *skiac_picture_recorder_finish(skiac_picture_recorder *c_recorder);
+  skiac_picture *skiac_picture_recorder_get_picture(skiac_picture_recorder *c_recorder);
+  void skiac_picture_recorder_destroy(skiac_picture_recorder *c_recorder);
+  skiac_picture *skiac_picture_make_from_data(const uint8_t *data, size_t length);
+  void skiac_picture_get_cull_rect(skiac_picture *c_picture, skiac_rect *c_rect);
+  void skiac_picture_serialize(skiac_picture *c_picture, skiac_sk_data *sk_data);
+  void skiac_picture_destroy(skiac_picture *c_picture);
+
+  // SkiacFontRecordingCanvas
+  skiac_svg_font_recorder *skiac_svg_font_recorder_create(skiac_canvas *c_canvas);
+  skiac_canvas *skiac_svg_font_recorder_get_canvas(skiac_svg_font_recorder *c_recorder);
+  void skiac_svg_font_recorder_get_style(skiac_svg_font_recorder *c_recorder, skiac_sk_data *sk_data);
+  void skiac_svg_font_recorder_destroy(skiac_svg_font_recorder *c_recorder);
+
+  // SkiacFileWStream
+  bool skiac_w_file_stream_finish_svg(skiac_w_file_stream *c_w_file_stream, const uint8_t *style, size_t style_size);
+  void skiac_w_file_stream_destroy(skiac_w_file_stream *c_w_file_stream);
+
+  // ImageDecoder
+  skiac_image_decoder *skiac_image_decoder_create();
+  int skiac_image_decoder_push(skiac_image_decoder *c_decoder, const uint8_t *ptr, size_t size, bool finished, skiac_decode_progress *progress);
+  void skiac_image_decoder_get_bitmap(skiac_image_decoder *c_decoder, skiac_bitmap_info *bitmap_info);
+  void skiac_image_decoder_destroy(skiac_image_decoder *c_decoder);
+
+  // StreamEncoder
+  skiac_stream_encoder *skiac_stream_encoder_create_png(skiac_surface *c_surface, skiac_png_options c_options);
+  skiac_stream_encoder *skiac_stream_encoder_create_jpeg(skiac_surface *c_surface, skiac_jpeg_options c_options);
+  int skiac_stream_encoder_encode_rows(skiac_stream_encoder *c_encoder, int rows, const uint8_t **data, size_t *size);
+  void skiac_stream_encoder_destroy(skiac_stream_encoder *c_encoder);
+
+  // SkColorSpace
+  void skiac_color_space_icc(uint8_t cs, skiac_sk_data *data);
+
+  // SkDeflateWStream
+  void skiac_deflate(const uint8_t *data, size_t size, int level, skiac_sk_data *output_data);
+
   // SkSVG
   void skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, skiac_sk_data *output_data);
 }
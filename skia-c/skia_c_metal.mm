@@ -0,0 +1,88 @@
+// Compiled without ARC, the Metal objects are released manually
+#include "skia_c.hpp"
+
+#import <Metal/Metal.h>
+#include <IOSurface/IOSurface.h>
+#include <include/gpu/mtl/GrMtlBackendContext.h>
+#include <include/gpu/mtl/GrMtlTypes.h>
+
+// the device of `skiac_metal_context`, the IOSurface textures are created by it
+thread_local id<MTLDevice> metal_device = nil;
+
+GrDirectContext *skiac_metal_context()
+{
+  thread_local sk_sp<GrDirectContext> direct_context;
+  thread_local bool initialized = false;
+  if (initialized)
+  {
+    return direct_context.get();
+  }
+  initialized = true;
+  id<MTLDevice> device = MTLCreateSystemDefaultDevice();
+  if (!device)
+  {
+    return nullptr;
+  }
+  GrMtlBackendContext backend_context = {};
+  // the backend context takes the ownership of the returned +1 references
+  backend_context.fDevice.reset((GrMTLHandle)device);
+  backend_context.fQueue.reset((GrMTLHandle)[device newCommandQueue]);
+  direct_context = GrDirectContext::MakeMetal(backend_context);
+  if (direct_context)
+  {
+    metal_device = device;
+  }
+  return direct_context.get();
+}
+
+sk_sp<SkSurface> skiac_metal_import_io_surface(int width, int height, sk_sp<SkColorSpace> color_space, uint32_t io_surface_id, int sample_count, const SkSurfaceProps &props)
+{
+  auto context = skiac_metal_context();
+  if (!context)
+  {
+    return nullptr;
+  }
+  IOSurfaceRef io_surface = IOSurfaceLookup(io_surface_id);
+  if (!io_surface)
+  {
+    return nullptr;
+  }
+  if (IOSurfaceGetPixelFormat(io_surface) != 'BGRA' || IOSurfaceGetWidth(io_surface) != (size_t)width ||
+      IOSurfaceGetHeight(io_surface) != (size_t)height)
+  {
+    CFRelease(io_surface);
+    return nullptr;
+  }
+  MTLTextureDescriptor *descriptor =
+      [MTLTextureDescriptor texture2DDescriptorWithPixelFormat:MTLPixelFormatBGRA8Unorm
+                                                         width:width
+                                                        height:height
+                                                     mipmapped:NO];
+  descriptor.usage = MTLTextureUsageShaderRead | MTLTextureUsageRenderTarget;
+  // the texture retains the IOSurface
+  id<MTLTexture> texture = [metal_device newTextureWithDescriptor:descriptor iosurface:io_surface plane:0];
+  CFRelease(io_surface);
+  if (!texture)
+  {
+    return nullptr;
+  }
+  GrMtlTextureInfo info;
+  // the wrapped texture of the surface retains it as well
+  info.fTexture.reset((GrMTLHandle)texture);
+  GrBackendTexture backend_texture(width, height, GrMipmapped::kNo, info);
+  return SkSurface::MakeFromBackendTexture(
+      context, backend_texture, kTopLeft_GrSurfaceOrigin, sample_count, kBGRA_8888_SkColorType, color_space, &props);
+}
+
+uint32_t skiac_metal_io_surface_id(SkSurface *surface)
+{
+  auto backend_texture = surface->getBackendTexture(SkSurface::kFlushRead_BackendHandleAccess);
+  GrMtlTextureInfo info;
+  if (!backend_texture.isValid() || !backend_texture.getMtlTextureInfo(&info))
+  {
+    return 0;
+  }
+  id<MTLTexture> texture = (id<MTLTexture>)info.fTexture.get();
+  IOSurfaceRef io_surface = texture.iosurface;
+  return io_surface ? IOSurfaceGetID(io_surface) : 0;
+}
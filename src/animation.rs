@@ -0,0 +1,235 @@
+use std::mem;
+
+use napi::bindgen_prelude::*;
+use napi::JsBuffer;
+
+use crate::ctx::{CanvasRenderingContext2D, Context};
+use crate::png;
+use crate::sk::{AlphaType, ColorSpace, ColorType, PngEncodeOptions, Surface};
+use crate::CanvasElement;
+
+const FORMAT_APNG: &str = "apng";
+const FORMAT_RAW: &str = "raw";
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct AnimationConfig {
+  /// How many times the animation plays, 0 means looping forever. Default is 0
+  pub repeat: Option<u32>,
+}
+
+/// Collects the frames drawn on a canvas and encodes them into an animation
+#[napi]
+pub struct CanvasAnimation {
+  pub width: u32,
+  pub height: u32,
+  pub fps: f64,
+  /// Unpremultiplied RGBA pixels of every frame
+  frames: Vec<Vec<u8>>,
+}
+
+#[napi]
+impl CanvasAnimation {
+  #[napi(constructor)]
+  pub fn new(width: u32, height: u32, fps: Option<f64>) -> Result<Self> {
+    if width == 0 || height == 0 {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The width and height must be greater than 0".to_owned(),
+      ));
+    }
+    let fps = fps.unwrap_or(30.0);
+    if !fps.is_finite() || fps <= 0.0 {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("Invalid fps: {}", fps),
+      ));
+    }
+    Ok(Self {
+      width,
+      height,
+      fps,
+      frames: Vec::new(),
+    })
+  }
+
+  #[napi(getter)]
+  pub fn frame_count(&self) -> u32 {
+    self.frames.len() as u32
+  }
+
+  /// Copy the current pixels of the canvas as the next frame
+  #[napi]
+  pub fn add_frame(
+    &mut self,
+    source: Either<&mut CanvasElement, &mut CanvasRenderingContext2D>,
+  ) -> Result<()> {
+    let context: &Context = match source {
+      Either::A(canvas) => &canvas.ctx.context,
+      Either::B(ctx) => &ctx.context,
+    };
+    if context.width != self.width || context.height != self.height {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!(
+          "The frame size {}x{} is different from the animation size {}x{}",
+          context.width, context.height, self.width, self.height
+        ),
+      ));
+    }
+    let pixels = context
+      .surface
+      .reference()
+      .read_pixels_as(
+        self.width,
+        self.height,
+        ColorType::RGBA8888,
+        AlphaType::Unpremultiplied,
+      )
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Read pixels from surface failed".to_owned(),
+        )
+      })?;
+    self.frames.push(pixels);
+    Ok(())
+  }
+
+  /// Encode the frames on the thread pool, the animation is empty after it's called.
+  /// `raw` concatenates the RGBA pixels of the frames, e.g. for `ffmpeg -f rawvideo -pix_fmt rgba`
+  #[napi]
+  pub fn finalize(
+    &mut self,
+    format: String,
+    config: Option<AnimationConfig>,
+  ) -> Result<AsyncTask<FinalizeAnimation>> {
+    if self.frames.is_empty() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The animation has no frames".to_owned(),
+      ));
+    }
+    let config = config.unwrap_or_default();
+    let sink: Box<dyn FrameSink> = match format.as_str() {
+      FORMAT_APNG => {
+        // fall back to milliseconds if 1/fps isn't a fraction of integers
+        let (delay_num, delay_den) = if self.fps.fract() == 0.0 && self.fps <= u16::MAX as f64 {
+          (1, self.fps as u16)
+        } else {
+          (((1000.0 / self.fps).round() as u16).max(1), 1000)
+        };
+        Box::new(ApngSink {
+          width: self.width,
+          height: self.height,
+          delay_num,
+          delay_den,
+          repeat: config.repeat.unwrap_or(0),
+          frames: Vec::with_capacity(self.frames.len()),
+        })
+      }
+      FORMAT_RAW => Box::new(RawSink(Vec::with_capacity(
+        self.frames.len() * (self.width * self.height * 4) as usize,
+      ))),
+      _ => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("{} is not valid animation format", format),
+        ))
+      }
+    };
+    Ok(AsyncTask::new(FinalizeAnimation {
+      frames: mem::take(&mut self.frames),
+      sink,
+    }))
+  }
+}
+
+/// Receives the frames in order when the animation is finalized
+pub trait FrameSink: Send {
+  fn add_frame(&mut self, pixels: &[u8]) -> Result<()>;
+
+  fn finish(&mut self) -> Result<Vec<u8>>;
+}
+
+struct RawSink(Vec<u8>);
+
+impl FrameSink for RawSink {
+  fn add_frame(&mut self, pixels: &[u8]) -> Result<()> {
+    self.0.extend_from_slice(pixels);
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<Vec<u8>> {
+    Ok(mem::take(&mut self.0))
+  }
+}
+
+struct ApngSink {
+  width: u32,
+  height: u32,
+  /// Every frame is shown for `delay_num / delay_den` seconds
+  delay_num: u16,
+  delay_den: u16,
+  repeat: u32,
+  /// PNG encoded frames
+  frames: Vec<Vec<u8>>,
+}
+
+impl FrameSink for ApngSink {
+  fn add_frame(&mut self, pixels: &[u8]) -> Result<()> {
+    let mut surface = Surface::new_rgba(self.width, self.height, ColorSpace::default())
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Create frame surface failed".to_owned(),
+        )
+      })?;
+    surface
+      .data_mut()
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Write frame pixels failed".to_owned(),
+        )
+      })?
+      .copy_from_slice(pixels);
+    let data_ref = surface
+      .reference()
+      .encode_png(PngEncodeOptions::default())
+      .ok_or_else(|| Error::new(Status::GenericFailure, "Encode frame failed".to_owned()))?;
+    self.frames.push(data_ref.slice().to_vec());
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<Vec<u8>> {
+    Ok(png::assemble_apng(
+      &self.frames,
+      self.delay_num,
+      self.delay_den,
+      self.repeat,
+    )?)
+  }
+}
+
+pub struct FinalizeAnimation {
+  frames: Vec<Vec<u8>>,
+  sink: Box<dyn FrameSink>,
+}
+
+#[napi]
+impl Task for FinalizeAnimation {
+  type Output = Vec<u8>;
+  type JsValue = JsBuffer;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    for frame in self.frames.drain(..) {
+      self.sink.add_frame(&frame)?;
+    }
+    self.sink.finish()
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    env.create_buffer_with_data(output).map(|b| b.into_raw())
+  }
+}
@@ -98,16 +98,6 @@ impl From<AvifConfig> for Config {
   }
 }
 
-impl From<&Either3<u32, AvifConfig, Unknown>> for AvifConfig {
-  fn from(value: &Either3<u32, AvifConfig, Unknown>) -> Self {
-    if let Either3::B(a) = value {
-      a.clone()
-    } else {
-      Default::default()
-    }
-  }
-}
-
 pub(crate) fn encode(
   avif_image: &[u8],
   width: u32,
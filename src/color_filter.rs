@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use napi::bindgen_prelude::*;
+
+use crate::error::SkError;
+use crate::pattern::parse_color;
+use crate::sk::{BlendMode, Color, ColorFilter as SkColorFilter};
+
+/// The Skia color filter, it's assigned to `colorFilter` or the layer of `saveLayer`
+#[napi]
+pub struct ColorFilter {
+  pub(crate) inner: SkColorFilter,
+}
+
+impl ColorFilter {
+  fn new(inner: Option<SkColorFilter>) -> Result<Self> {
+    let inner = inner.ok_or_else(|| SkError::Generic("Make color filter failed".to_owned()))?;
+    Ok(Self { inner })
+  }
+}
+
+#[napi]
+impl ColorFilter {
+  /// The 4x5 row major matrix, the unpremultiplied colors and the offsets in the fifth column are 0 ~ 1
+  #[napi]
+  pub fn matrix(matrix: Vec<f64>) -> Result<ColorFilter> {
+    let row_major = color_matrix(&matrix)?;
+    Self::new(SkColorFilter::make_matrix(&row_major))
+  }
+
+  /// Blend the CSS color onto the colors, the mode is the `globalCompositeOperation` names or `modulate`
+  #[napi]
+  pub fn blend(color: String, blend_mode: String) -> Result<ColorFilter> {
+    let blend_mode = BlendMode::from_str(&blend_mode)?;
+    Self::new(SkColorFilter::make_blend(css_color(&color)?, blend_mode))
+  }
+
+  /// Multiply the RGB by the CSS color `multiply`, then add `add`, the alpha is not changed
+  #[napi]
+  pub fn lighting(multiply: String, add: String) -> Result<ColorFilter> {
+    Self::new(SkColorFilter::make_lighting(
+      css_color(&multiply)?,
+      css_color(&add)?,
+    ))
+  }
+
+  /// The luminance of the colors becomes the alpha, the RGB are black
+  #[napi]
+  pub fn luma() -> Result<ColorFilter> {
+    Self::new(SkColorFilter::make_luma())
+  }
+
+  /// The filter applying `inner` first, then this one
+  #[napi]
+  pub fn compose(&self, inner: &ColorFilter) -> Result<ColorFilter> {
+    Self::new(self.inner.compose(&inner.inner))
+  }
+}
+
+fn css_color(color: &str) -> std::result::Result<u32, SkError> {
+  let rgba = parse_color(color)?;
+  Ok(Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha).0)
+}
+
+fn color_matrix(matrix: &[f64]) -> std::result::Result<[f32; 20], SkError> {
+  if matrix.len() != 20 {
+    return Err(SkError::TypeError(format!(
+      "The color matrix has 20 numbers, but {} are given",
+      matrix.len()
+    )));
+  }
+  if !matrix.iter().all(|value| value.is_finite()) {
+    return Err(SkError::TypeError(
+      "The color matrix has the infinite or NaN numbers".to_owned(),
+    ));
+  }
+  let mut row_major = [0f32; 20];
+  for (target, value) in row_major.iter_mut().zip(matrix) {
+    *target = *value as f32;
+  }
+  Ok(row_major)
+}
+
+#[test]
+fn test_color_matrix() {
+  let mut identity = vec![0.0; 20];
+  for i in 0..4 {
+    identity[i * 6] = 1.0;
+  }
+  let row_major = color_matrix(&identity).unwrap();
+  assert_eq!(&row_major[..6], &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+  assert_eq!(row_major[18], 1.0);
+  assert!(color_matrix(&identity[..16]).is_err());
+  identity[4] = f64::NAN;
+  assert!(color_matrix(&identity).is_err());
+}
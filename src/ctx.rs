@@ -1,27 +1,44 @@
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::iter;
 use std::mem;
 use std::result;
 use std::slice;
 use std::str::FromStr;
 
-use cssparser::{Color as CSSColor, Parser, ParserInput, RGBA};
+use cssparser::RGBA;
 use libavif::AvifData;
 use napi::{bindgen_prelude::*, JsBuffer, JsString, NapiRaw, NapiValue};
 
 use crate::{
   avif::Config,
+  color_filter::ColorFilter,
   error::SkError,
   filter::css_filter,
   filter::css_filters_to_image_filter,
   font::Font,
+  gpu::GpuDevice,
   gradient::{CanvasGradient, Gradient},
+  icc,
   image::*,
-  path::Path,
-  pattern::{CanvasPattern, Pattern},
+  image_filter::ImageFilter,
+  jpeg::{self, JpegMetadata},
+  path::{all_finite, radius_args_valid, round_rect_radii, Path, RoundRectRadii},
+  pattern::{parse_color, parse_color_with, serialize_color, CanvasPattern, Pattern},
+  pdf::PdfMetadata,
+  picture::Picture,
+  png::{self, PngMetadata},
+  quantize::{self, PaletteOptions},
+  readback::PixelsReadback,
+  resize::{resize_rgba, ResizeFilter, ResizeFit},
+  shader::{Effect, ShaderEffect},
   sk::{
-    AlphaType, Bitmap, BlendMode, ColorSpace, FillType, ImageFilter, LineMetrics, MaskFilter,
-    Matrix, Paint, PaintStyle, Path as SkPath, PathEffect, SkEncodedImageFormat, SkWMemoryStream,
-    SkiaDataRef, Surface, SurfaceRef, Transform,
+    AlphaType, Bitmap, BlendMode, Canvas, Color, ColorSpace, ColorType, ExternalTexture, FillType,
+    FilterQuality, GpuBackend, ImageFilter as SkImageFilter, JpegEncodeOptions, LineMetrics,
+    Matrix, Paint, PaintStyle, Path as SkPath, PathEffect, PdfDocument, Picture as SkPicture,
+    PictureRecorder, PngEncodeOptions, ResizeContents, SamplingOptions, Shader,
+    SkEncodedImageFormat, SkWFileStream, SkWMemoryStream, SkiaDataRef, Surface, SurfaceProps,
+    SurfaceRef, SvgFontRecorder, SvgTextPolicy, Transform,
   },
   state::Context2dRenderingState,
   CanvasElement, SVGCanvas,
@@ -41,48 +58,251 @@ pub struct Context {
   pub(crate) surface: Surface,
   path: SkPath,
   pub alpha: bool,
+  /// The frames are encoded without copying the pixels, the draws before the encoding finishes could be encoded
+  pub desynchronized: bool,
+  /// The GPU canvas is moved to the CPU, so the pixels are read without waiting for the GPU
+  pub will_read_frequently: bool,
+  /// Draws into the imported texture, replacing the surface would detach the canvas from it
+  pub(crate) external_texture: bool,
   pub(crate) states: Vec<Context2dRenderingState>,
   state: Context2dRenderingState,
   pub width: u32,
   pub height: u32,
   pub color_space: ColorSpace,
   pub stream: Option<SkWMemoryStream>,
+  /// The SVG document is written to a file instead of `stream`
+  pub file_stream: Option<SkWFileStream>,
+  pub pdf: Option<PdfDocument>,
+  /// Set if the fonts of the SVG text are embedded
+  pub svg_fonts: Option<SvgFontRecorder>,
+  pub picture: Option<PictureRecorder>,
+  /// The color `currentcolor` is resolved to, it's rejected if it's not set
+  pub current_color: Option<RGBA>,
 }
 
 impl Context {
   pub fn new_svg(
     width: u32,
     height: u32,
-    svg_export_flag: crate::sk::SvgExportFlag,
+    svg_export_flag: Option<crate::sk::SvgExportFlag>,
+    text_policy: SvgTextPolicy,
+    color_space: ColorSpace,
+    path: Option<&str>,
+  ) -> Result<Self> {
+    let mut flags = svg_export_flag.map(|flag| flag as u32).unwrap_or(0);
+    if text_policy == SvgTextPolicy::Paths {
+      flags |= crate::sk::SvgExportFlag::ConvertTextToPaths as u32;
+    }
+    let (mut surface, stream, file_stream) = match path {
+      Some(path) => {
+        let (surface, file_stream) = Surface::new_svg_file(
+          width,
+          height,
+          AlphaType::Premultiplied,
+          flags,
+          color_space,
+          path,
+        )
+        .ok_or_else(|| Error::from_reason(format!("Create skia svg surface to {} failed", path)))?;
+        (surface, None, Some(file_stream))
+      }
+      None => {
+        let (surface, stream) =
+          Surface::new_svg(width, height, AlphaType::Premultiplied, flags, color_space)
+            .ok_or_else(|| Error::from_reason("Create skia svg surface failed".to_owned()))?;
+        (surface, Some(stream), None)
+      }
+    };
+    let svg_fonts =
+      (text_policy == SvgTextPolicy::Embed).then(|| SvgFontRecorder::new(&mut surface));
+    Ok(Context {
+      surface,
+      alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space,
+      stream,
+      file_stream,
+      pdf: None,
+      svg_fonts,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  pub fn new(width: u32, height: u32, color_space: ColorSpace) -> Result<Self> {
+    let surface = Surface::new_rgba_premultiplied(width, height, color_space)
+      .ok_or_else(|| Error::from_reason("Create skia surface failed".to_owned()))?;
+    Ok(Context {
+      surface,
+      alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space,
+      stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  pub fn new_with_props(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    props: SurfaceProps,
+  ) -> Result<Self> {
+    let surface = Surface::new_with_props(width, height, color_space, props)
+      .ok_or_else(|| Error::from_reason("Create skia surface failed".to_owned()))?;
+    Ok(Context {
+      surface,
+      alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space,
+      stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  /// Falls back to the raster surface if there is no GPU
+  pub fn new_gpu(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    backend: GpuBackend,
+    device: Option<GpuDevice>,
+    props: SurfaceProps,
+  ) -> Result<Self> {
+    let surface = match Surface::new_gpu(width, height, color_space, backend, device, props) {
+      Some(surface) => surface,
+      None => return Self::new_with_props(width, height, color_space, props),
+    };
+    Ok(Context {
+      surface,
+      alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space,
+      stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  /// Unlike `new_gpu`, there is no fallback, the canvas must draw into the texture
+  pub fn new_external(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    texture: ExternalTexture,
+    props: SurfaceProps,
+  ) -> Result<Self> {
+    let surface = Surface::new_external(width, height, color_space, texture, props)
+      .ok_or_else(|| Error::from_reason("Import the external texture failed".to_owned()))?;
+    Ok(Context {
+      surface,
+      alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: true,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space,
+      stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  pub fn new_pdf(
+    width: u32,
+    height: u32,
     color_space: ColorSpace,
+    path: Option<&str>,
+    metadata: &PdfMetadata,
+    text_to_paths: bool,
   ) -> Result<Self> {
-    let (surface, stream) = Surface::new_svg(
+    let (surface, pdf) = Surface::new_pdf(
       width,
       height,
       AlphaType::Premultiplied,
-      svg_export_flag,
       color_space,
+      path,
+      metadata,
+      text_to_paths,
     )
-    .ok_or_else(|| Error::from_reason("Create skia svg surface failed".to_owned()))?;
+    .ok_or_else(|| Error::from_reason("Create skia pdf surface failed".to_owned()))?;
     Ok(Context {
       surface,
       alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
       path: SkPath::new(),
       states: vec![],
       state: Context2dRenderingState::default(),
       width,
       height,
       color_space,
-      stream: Some(stream),
+      stream: None,
+      file_stream: None,
+      pdf: Some(pdf),
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
     })
   }
 
-  pub fn new(width: u32, height: u32, color_space: ColorSpace) -> Result<Self> {
-    let surface = Surface::new_rgba_premultiplied(width, height, color_space)
-      .ok_or_else(|| Error::from_reason("Create skia surface failed".to_owned()))?;
+  pub fn new_picture(width: u32, height: u32, color_space: ColorSpace) -> Result<Self> {
+    let (surface, picture) =
+      Surface::new_picture(width, height, AlphaType::Premultiplied, color_space)
+        .ok_or_else(|| Error::from_reason("Create skia picture surface failed".to_owned()))?;
     Ok(Context {
       surface,
       alpha: true,
+      desynchronized: false,
+      will_read_frequently: false,
+      external_texture: false,
       path: SkPath::new(),
       states: vec![],
       state: Context2dRenderingState::default(),
@@ -90,9 +310,52 @@ impl Context {
       height,
       color_space,
       stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: Some(picture),
+      current_color: None,
     })
   }
 
+  /// `#name` links to the destination added by `add_destination`, the other urls are external
+  pub fn add_link(
+    &mut self,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    url: &str,
+  ) -> result::Result<(), SkError> {
+    let canvas = &mut self.surface.canvas;
+    match url.strip_prefix('#') {
+      Some(name) => canvas.annotate_link_to_destination(x, y, width, height, name)?,
+      None => canvas.annotate_link_to_url(x, y, width, height, url)?,
+    }
+    Ok(())
+  }
+
+  pub fn add_destination(&mut self, name: &str, x: f32, y: f32) -> result::Result<(), SkError> {
+    self.surface.canvas.annotate_named_destination(x, y, name)?;
+    Ok(())
+  }
+
+  /// The clips don't carry over to the new page, the transform and the save depth do
+  pub fn add_page(&mut self, width: f32, height: f32) -> result::Result<(), SkError> {
+    let pdf = self
+      .pdf
+      .as_mut()
+      .ok_or_else(|| SkError::Generic("addPage is only supported by the PDF canvas".to_owned()))?;
+    if !pdf.add_page(&mut self.surface, width, height) {
+      return Err(SkError::Generic("The PDF document is closed".to_owned()));
+    }
+    for _ in &self.states {
+      self.surface.canvas.save();
+    }
+    self.surface.canvas.set_transform(&self.state.transform);
+    Ok(())
+  }
+
   pub fn arc(
     &mut self,
     center_x: f32,
@@ -147,13 +410,14 @@ impl Context {
     self.path.quad_to(cpx, cpy, x, y);
   }
 
-  pub fn clip(&mut self, path: Option<&mut SkPath>, fill_rule: FillType) {
-    let clip = match path {
-      Some(path) => path,
-      None => &mut self.path,
-    };
+  /// The clips intersect with the previous ones, the fill rule of the clip path is not changed
+  pub fn clip(&mut self, path: Option<&SkPath>, fill_rule: FillType) {
+    let mut clip = path.unwrap_or(&self.path).clone();
     clip.set_fill_type(fill_rule);
-    self.surface.canvas.set_clip_path(clip);
+    let anti_alias = self.state.paint.get_anti_alias();
+    self.surface.canvas.set_clip_path(&clip, anti_alias);
+    clip.transform_self(&self.state.transform);
+    self.state.clips.push((clip, anti_alias));
   }
 
   pub fn clear_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
@@ -173,86 +437,309 @@ impl Context {
     self.path.add_rect(x, y, width, height);
   }
 
+  pub fn round_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radii: [(f32, f32); 4]) {
+    self.path.add_round_rect(x, y, width, height, radii);
+  }
+
   pub fn save(&mut self) {
     self.surface.canvas.save();
     self.states.push(self.state.clone());
   }
 
+  /// Clear the pixels, drop the path, the saved states and the clips,
+  /// and reset the state to the defaults except the antialias of the canvas
+  pub fn reset(&mut self) {
+    self.surface.canvas.reset();
+    if self.alpha {
+      self.surface.canvas.clear();
+    } else {
+      self.surface.canvas.fill(255, 255, 255, 255);
+    }
+    let anti_alias = self.state.paint.get_anti_alias();
+    self.states.clear();
+    self.state = Context2dRenderingState::default();
+    self.state.paint.set_anti_alias(anti_alias);
+    self.path = SkPath::new();
+  }
+
+  /// Like `save`, the draws until the `restore` go to the layer,
+  /// which is composited onto the canvas with the paint on `restore`
+  pub fn save_layer(&mut self, paint: &Paint, bounds: Option<(f32, f32, f32, f32)>) {
+    self.surface.canvas.save_layer(paint, bounds);
+    let mut state = self.state.clone();
+    state.layer = Some((paint.clone(), bounds));
+    self.states.push(state);
+  }
+
   pub fn restore(&mut self) {
     if let Some(s) = self.states.pop() {
-      self.path.transform_self(&self.state.transform);
+      self.keep_path_for_transform(&s.transform);
       self.surface.canvas.restore();
-      self.path.transform_self(&s.transform.invert().unwrap());
       self.state = s;
+      // the layer belongs to the save, not to the restored state
+      self.state.layer = None;
+    }
+  }
+
+  /// The current path is kept in the coordinates of the current transform,
+  /// so it's mapped to the coordinates of the new transform to stay in place.
+  /// It's kept in the device coordinates while the transform is singular,
+  /// so the points added before are still there after an invertible transform is set, like the browsers
+  fn keep_path_for_transform(&mut self, transform: &Matrix) {
+    if self.state.transform.invert().is_some() {
+      self.path.transform_self(&self.state.transform);
+    }
+    if let Some(inverse) = transform.invert() {
+      self.path.transform_self(&inverse);
     }
   }
 
   pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32) -> result::Result<(), SkError> {
     let stroke_paint = self.stroke_paint()?;
-    if let Some(shadow_paint) = self.shadow_blur_paint(&stroke_paint) {
-      let surface = &mut self.surface;
-      let last_state = &self.state;
-      surface.save();
-      Self::apply_shadow_offset_matrix(
-        surface,
-        last_state.shadow_offset_x,
-        last_state.shadow_offset_y,
-      )?;
-      surface.draw_rect(x, y, w, h, &shadow_paint);
-      surface.restore();
-    };
-
-    self.surface.draw_rect(x, y, w, h, &stroke_paint);
-
+    Self::draw_primitive(
+      &mut self.surface,
+      &self.state,
+      &stroke_paint,
+      |canvas, paint| {
+        canvas.draw_rect(x, y, w, h, paint);
+        Ok(())
+      },
+    )?;
     Ok(())
   }
 
   pub fn translate(&mut self, x: f32, y: f32) {
-    let s = &mut self.state;
-    let inverse = Matrix::translated(-x, -y);
-    self.path.transform_self(&inverse);
-    s.transform.pre_translate(x, y);
-    self.surface.canvas.set_transform(&s.transform);
+    let mut ts = self.state.transform.clone();
+    ts.pre_translate(x, y);
+    self.set_transform(ts);
   }
 
   pub fn transform(&mut self, ts: Matrix) -> result::Result<(), SkError> {
-    let s = &mut self.state;
-    self.path.transform_self(
-      &ts
-        .invert()
-        .ok_or_else(|| SkError::InvalidTransform(ts.clone()))?,
-    );
-    s.transform = ts.multiply(&s.transform);
-    self.surface.set_transform(&s.transform);
+    if ts.invert().is_none() {
+      return Err(SkError::InvalidTransform(ts));
+    }
+    let ts = ts.multiply(&self.state.transform);
+    self.set_transform(ts);
     Ok(())
   }
 
   pub fn rotate(&mut self, angle: f32) {
-    let s = &mut self.state;
     let degrees = angle as f32 / PI * 180f32;
-    let inverse = Matrix::rotated(-angle, 0.0, 0.0);
-    self.path.transform_self(&inverse);
-    s.transform.pre_rotate(degrees);
-    self.surface.canvas.set_transform(&s.transform);
+    let mut ts = self.state.transform.clone();
+    ts.pre_rotate(degrees);
+    self.set_transform(ts);
   }
 
   pub fn scale(&mut self, x: f32, y: f32) {
-    let s = &mut self.state;
-    let mut inverse = Matrix::identity();
-    inverse.pre_scale(1f32 / x, 1f32 / y);
-    self.path.transform_self(&inverse);
-    s.transform.pre_scale(x, y);
-    self.surface.canvas.set_transform(&s.transform);
+    let mut ts = self.state.transform.clone();
+    ts.pre_scale(x, y);
+    self.set_transform(ts);
   }
 
   pub fn set_transform(&mut self, ts: Matrix) {
+    self.keep_path_for_transform(&ts);
     self.surface.canvas.set_transform(&ts);
     self.state.transform = ts;
   }
 
   pub fn reset_transform(&mut self) {
+    self.set_transform(Matrix::identity());
+  }
+
+  /// Continue drawing on the CPU, the pixels, the transform and the clips are kept
+  pub fn use_raster_surface(&mut self) -> result::Result<(), SkError> {
+    self.check_surface_replaceable()?;
+    self.surface = self
+      .surface
+      .to_raster()
+      .ok_or_else(|| SkError::Generic("Copy the canvas pixels failed".to_owned()))?;
+    self.replay_states();
+    Ok(())
+  }
+
+  /// Continue drawing on an opaque surface, the pixels are composited onto the white background
+  pub fn use_opaque_surface(&mut self) -> result::Result<(), SkError> {
+    self.check_surface_replaceable()?;
+    self.surface = self
+      .surface
+      .to_opaque()
+      .ok_or_else(|| SkError::Generic("Copy the canvas pixels failed".to_owned()))?;
+    self.alpha = false;
+    self.replay_states();
+    Ok(())
+  }
+
+  /// The context of a new surface of the pixels in the rect, with the same attributes and the default state
+  pub fn extract(
+    &self,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+  ) -> result::Result<Context, SkError> {
+    let surface = self
+      .surface
+      .crop(x, y, width, height)
+      .ok_or_else(|| SkError::Generic("Copy the canvas pixels failed".to_owned()))?;
+    let mut state = Context2dRenderingState::default();
+    state
+      .paint
+      .set_anti_alias(self.state.paint.get_anti_alias());
+    Ok(Context {
+      surface,
+      alpha: self.alpha,
+      desynchronized: self.desynchronized,
+      will_read_frequently: self.will_read_frequently,
+      external_texture: false,
+      path: SkPath::new(),
+      states: vec![],
+      state,
+      width,
+      height,
+      color_space: self.color_space,
+      stream: None,
+      file_stream: None,
+      pdf: None,
+      svg_fonts: None,
+      picture: None,
+      current_color: None,
+    })
+  }
+
+  /// The bytes of the pixels reported to V8, computed in `i64` so the large half float surfaces don't overflow
+  pub fn external_memory_size(&self) -> i64 {
+    self.width as i64 * self.height as i64 * self.surface.bytes_per_pixel() as i64
+  }
+
+  /// Like setting the size of the canvas element, the new surface is cleared and the context is `reset`
+  pub fn resize(&mut self, width: u32, height: u32) -> result::Result<(), SkError> {
+    // `canvas.width = canvas.width` only clears the canvas drawing into the external texture
+    if !(self.external_texture && width == self.width && height == self.height) {
+      self.use_resized_surface(width, height, ResizeContents::Clear)?;
+    }
+    self.reset();
+    Ok(())
+  }
+
+  /// Unlike `resize`, the states are kept, and the pixels are copied to the top left corner,
+  /// or scaled to the new size with the image smoothing of the state
+  pub fn resize_preserving(
+    &mut self,
+    width: u32,
+    height: u32,
+    scale: bool,
+  ) -> result::Result<(), SkError> {
+    let contents = if scale {
+      let ratio = (width as f32 / self.width as f32).min(height as f32 / self.height as f32);
+      ResizeContents::Scale(SamplingOptions::from_quality(
+        self.state.image_smoothing_quality,
+        self.state.image_smoothing_enabled,
+        ratio,
+      ))
+    } else {
+      ResizeContents::Copy
+    };
+    self.use_resized_surface(width, height, contents)
+  }
+
+  /// Continue drawing on a surface of the new size, the states are kept
+  fn use_resized_surface(
+    &mut self,
+    width: u32,
+    height: u32,
+    contents: ResizeContents,
+  ) -> result::Result<(), SkError> {
+    self.check_surface_replaceable()?;
+    self.surface = self
+      .surface
+      .resized(width, height, contents)
+      .ok_or_else(|| {
+        SkError::Generic(format!("Resize the canvas to {}x{} failed", width, height))
+      })?;
+    self.width = width;
+    self.height = height;
+    self.replay_states();
+    Ok(())
+  }
+
+  /// The size and the pixel format of the imported texture are owned by the caller
+  fn check_surface_replaceable(&self) -> result::Result<(), SkError> {
+    if self.external_texture {
+      return Err(SkError::Generic(
+        "The canvas drawing into the external texture can't be resized or moved to the CPU"
+          .to_owned(),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Apply the saved states to the new surface, so `restore` still reverts the clips and the transform,
+  /// and composites the layers. The pixels drawn into the layers before are lost
+  fn replay_states(&mut self) {
+    let canvas = &mut self.surface.canvas;
+    // the clips of a state start with the clips of the state saved before it
+    let mut clipped = 0;
+    let mut saved: Option<&Context2dRenderingState> = None;
+    for state in self.states.iter().chain(iter::once(&self.state)) {
+      // the layer is saved with the transform and the clips of the state pushed by it
+      match saved.map(|s| &s.layer) {
+        Some(Some((paint, bounds))) => canvas.save_layer(paint, *bounds),
+        Some(None) => canvas.save(),
+        None => {}
+      }
+      saved = Some(state);
+      canvas.reset_transform();
+      for (clip, anti_alias) in &state.clips[clipped..] {
+        canvas.set_clip_path(clip, *anti_alias);
+      }
+      clipped = state.clips.len();
+      canvas.set_transform(&state.transform);
+    }
+  }
+
+  /// Replace the pixels with the output of the effect, the clip is applied but the transform is not
+  pub fn apply_shader_effect(&mut self, effect: &Effect) -> result::Result<(), SkError> {
+    let shader = effect
+      .get_shader(Some(&self.surface))
+      .ok_or_else(|| SkError::Generic("Make shader of the shader effect failed".to_owned()))?;
+    let mut paint = Paint::new();
+    paint.set_shader(&shader);
+    paint.set_blend_mode(BlendMode::Source);
+    self.surface.canvas.save();
     self.surface.canvas.reset_transform();
-    self.state.transform = Matrix::identity();
+    self
+      .surface
+      .canvas
+      .draw_rect(0f32, 0f32, self.width as f32, self.height as f32, &paint);
+    self.surface.canvas.restore();
+    Ok(())
+  }
+
+  /// Replace the pixels in the rect with the filtered ones, the `paint` has the image filter or the color filter.
+  /// The rect is in the pixels of the canvas like `putImageData`, the clip is applied,
+  /// and the filter samples the pixels around the rect like the CSS `backdrop-filter`
+  pub fn apply_filter(
+    &mut self,
+    mut paint: Paint,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+  ) -> result::Result<(), SkError> {
+    let shader =
+      Shader::new_from_surface_image(&self.surface, Transform::default(), FilterQuality::None)
+        .ok_or_else(|| SkError::Generic("Make shader of the canvas failed".to_owned()))?;
+    paint.set_shader(&shader);
+    paint.set_blend_mode(BlendMode::Source);
+    self.surface.canvas.save();
+    self.surface.canvas.reset_transform();
+    self.surface.canvas.set_clip_rect(x, y, width, height);
+    self
+      .surface
+      .canvas
+      .draw_rect(0f32, 0f32, self.width as f32, self.height as f32, &paint);
+    self.surface.canvas.restore();
+    Ok(())
   }
 
   pub fn stroke_text(
@@ -275,21 +762,15 @@ impl Context {
 
   pub fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32) -> result::Result<(), SkError> {
     let fill_paint = self.fill_paint()?;
-    if let Some(shadow_paint) = self.shadow_blur_paint(&fill_paint) {
-      let surface = &mut self.surface;
-      let last_state = &self.state;
-      surface.save();
-      Self::apply_shadow_offset_matrix(
-        surface,
-        last_state.shadow_offset_x,
-        last_state.shadow_offset_y,
-      )?;
-      surface.draw_rect(x, y, w, h, &shadow_paint);
-      surface.restore();
-    };
-
-    self.surface.draw_rect(x, y, w, h, &fill_paint);
-
+    Self::draw_primitive(
+      &mut self.surface,
+      &self.state,
+      &fill_paint,
+      |canvas, paint| {
+        canvas.draw_rect(x, y, w, h, paint);
+        Ok(())
+      },
+    )?;
     Ok(())
   }
 
@@ -312,25 +793,20 @@ impl Context {
   }
 
   pub fn stroke(&mut self, path: Option<&mut SkPath>) -> Result<()> {
-    let last_state = &self.state;
     let p = match path {
       Some(path) => path,
       None => &self.path,
     };
     let stroke_paint = self.stroke_paint()?;
-    if let Some(shadow_paint) = self.shadow_blur_paint(&stroke_paint) {
-      let surface = &mut self.surface;
-      surface.save();
-      Self::apply_shadow_offset_matrix(
-        surface,
-        last_state.shadow_offset_x,
-        last_state.shadow_offset_y,
-      )?;
-      self.surface.canvas.draw_path(p, &shadow_paint);
-      self.surface.restore();
-      mem::drop(shadow_paint);
-    }
-    self.surface.canvas.draw_path(p, &stroke_paint);
+    Self::draw_primitive(
+      &mut self.surface,
+      &self.state,
+      &stroke_paint,
+      |canvas, paint| {
+        canvas.draw_path(p, paint);
+        Ok(())
+      },
+    )?;
     Ok(())
   }
 
@@ -339,7 +815,6 @@ impl Context {
     path: Option<&mut SkPath>,
     fill_rule: FillType,
   ) -> result::Result<(), SkError> {
-    let last_state = &self.state;
     let p = if let Some(p) = path {
       p.set_fill_type(fill_rule);
       p
@@ -348,19 +823,15 @@ impl Context {
       &self.path
     };
     let fill_paint = self.fill_paint()?;
-    if let Some(shadow_paint) = self.shadow_blur_paint(&fill_paint) {
-      let surface = &mut self.surface;
-      surface.save();
-      Self::apply_shadow_offset_matrix(
-        surface,
-        last_state.shadow_offset_x,
-        last_state.shadow_offset_y,
-      )?;
-      surface.canvas.draw_path(p, &shadow_paint);
-      surface.restore();
-      mem::drop(shadow_paint);
-    }
-    self.surface.draw_path(p, &fill_paint);
+    Self::draw_primitive(
+      &mut self.surface,
+      &self.state,
+      &fill_paint,
+      |canvas, paint| {
+        canvas.draw_path(p, paint);
+        Ok(())
+      },
+    )?;
     Ok(())
   }
 
@@ -371,7 +842,7 @@ impl Context {
     paint.set_style(PaintStyle::Fill);
     let alpha = current_paint.get_alpha();
     match &last_state.fill_style {
-      Pattern::Color(c, _) => {
+      Pattern::Color(c) => {
         let color = Self::multiply_by_alpha(c, alpha);
         paint.set_color(color.red, color.green, color.blue, color.alpha);
       }
@@ -387,6 +858,12 @@ impl Context {
           paint.set_shader(&shader);
         }
       }
+      Pattern::Shader(e) => {
+        if let Some(shader) = e.get_shader(None) {
+          paint.set_color(0, 0, 0, alpha);
+          paint.set_shader(&shader);
+        }
+      }
     };
     if !last_state.line_dash_list.is_empty() {
       let path_effect = PathEffect::new_dash_path(
@@ -399,20 +876,26 @@ impl Context {
     if let Some(f) = &self.state.filter {
       paint.set_image_filter(f);
     }
+    if let Some(f) = &self.state.color_filter {
+      paint.set_color_filter(f);
+    }
     Ok(paint)
   }
 
-  pub fn set_filter(&mut self, filter_str: &str) -> result::Result<(), SkError> {
+  /// The invalid value is ignored like the other properties, the current filter is kept
+  pub fn set_filter(&mut self, filter_str: &str) {
     if filter_str.trim() == "none" {
-      self.state.filters_string = "none".to_owned();
+      self.state.filters_string = Some("none".to_owned());
       self.state.filter = None;
-    } else {
-      let (_, filters) =
-        css_filter(filter_str).map_err(|e| SkError::StringToFillRuleError(format!("{}", e)))?;
-      self.state.filter = css_filters_to_image_filter(filters);
-      self.state.filters_string = filter_str.to_owned();
+    } else if let Ok(filter) = parse_filter(filter_str) {
+      self.state.filter = filter;
+      self.state.filters_string = Some(filter_str.to_owned());
     }
-    Ok(())
+  }
+
+  pub fn set_image_filter(&mut self, filter: SkImageFilter) {
+    self.state.filter = Some(filter);
+    self.state.filters_string = None;
   }
 
   pub fn get_font(&self) -> &str {
@@ -441,27 +924,6 @@ impl Context {
     self.state.paint.get_alpha() as f64 / 255.0
   }
 
-  pub fn set_shadow_color(&mut self, shadow_color: String) -> result::Result<(), SkError> {
-    let mut parser_input = ParserInput::new(&shadow_color);
-    let mut parser = Parser::new(&mut parser_input);
-    let color = CSSColor::parse(&mut parser)
-      .map_err(|e| SkError::Generic(format!("Parse color [{}] error: {:?}", &shadow_color, e)))?;
-
-    match color {
-      CSSColor::CurrentColor => {
-        return Err(SkError::Generic(
-          "Color should not be `currentcolor` keyword".to_owned(),
-        ))
-      }
-      CSSColor::RGBA(rgba) => {
-        drop(parser_input);
-        self.state.shadow_color_string = shadow_color;
-        self.state.shadow_color = rgba;
-      }
-    }
-    Ok(())
-  }
-
   pub fn set_text_align(&mut self, text_align: String) -> result::Result<(), SkError> {
     self.state.text_align = text_align.parse()?;
     Ok(())
@@ -478,11 +940,19 @@ impl Context {
     y: f32,
     w: f32,
     h: f32,
-    color_type: ColorSpace,
+    color_space: ColorSpace,
+    color_type: ColorType,
+    alpha_type: AlphaType,
   ) -> Option<Vec<u8>> {
-    self
-      .surface
-      .read_pixels(x as u32, y as u32, w as u32, h as u32, color_type)
+    self.surface.read_pixels_rect(
+      x as u32,
+      y as u32,
+      w as u32,
+      h as u32,
+      color_space,
+      color_type,
+      alpha_type,
+    )
   }
 
   pub fn set_line_dash(&mut self, line_dash_list: Vec<f32>) {
@@ -496,7 +966,7 @@ impl Context {
     paint.set_style(PaintStyle::Stroke);
     let global_alpha = current_paint.get_alpha();
     match &last_state.stroke_style {
-      Pattern::Color(c, _) => {
+      Pattern::Color(c) => {
         let color = Self::multiply_by_alpha(c, global_alpha);
         paint.set_color(color.red, color.green, color.blue, color.alpha);
       }
@@ -512,6 +982,12 @@ impl Context {
           paint.set_shader(&shader);
         }
       }
+      Pattern::Shader(e) => {
+        if let Some(shader) = e.get_shader(None) {
+          paint.set_color(0, 0, 0, global_alpha);
+          paint.set_shader(&shader);
+        }
+      }
     };
     if !last_state.line_dash_list.is_empty() {
       let path_effect = PathEffect::new_dash_path(
@@ -524,80 +1000,95 @@ impl Context {
     if let Some(f) = &self.state.filter {
       paint.set_image_filter(f);
     }
+    if let Some(f) = &self.state.color_filter {
+      paint.set_color_filter(f);
+    }
     Ok(paint)
   }
 
-  fn drop_shadow_paint(&self, paint: &Paint) -> Option<Paint> {
-    let alpha = paint.get_alpha();
-    let last_state = &self.state;
-    let shadow_color = &last_state.shadow_color;
-    let mut shadow_alpha = shadow_color.alpha;
-    shadow_alpha = ((shadow_alpha as f32) * (alpha as f32 / 255.0)) as u8;
-    if shadow_alpha == 0 {
-      return None;
-    }
-    if last_state.shadow_blur == 0f32
-      && last_state.shadow_offset_x == 0f32
-      && last_state.shadow_offset_y == 0f32
-    {
-      return None;
+  /// Draw the shadow of the primitive, then the primitive itself.
+  /// The shadow and the primitive are composited onto the canvas separately as the spec says
+  fn draw_primitive(
+    canvas: &mut Canvas,
+    state: &Context2dRenderingState,
+    paint: &Paint,
+    draw: impl Fn(&mut Canvas, &Paint) -> result::Result<(), SkError>,
+  ) -> result::Result<(), SkError> {
+    Self::draw_shadow(canvas, state, paint, &draw)?;
+    match paint.get_blend_mode() {
+      // the transparent pixels around the primitive clear the canvas for these operations,
+      // so the primitive is drawn in a layer which is composited onto the whole canvas
+      BlendMode::Source
+      | BlendMode::SourceIn
+      | BlendMode::SourceOut
+      | BlendMode::DestinationIn
+      | BlendMode::DestinationATop => {
+        let mut layer_paint = Paint::new();
+        layer_paint.set_blend_mode(paint.get_blend_mode());
+        let mut primitive_paint = paint.clone();
+        primitive_paint.set_blend_mode(BlendMode::SourceOver);
+        canvas.save_layer(&layer_paint, None);
+        let result = draw(canvas, &primitive_paint);
+        canvas.restore();
+        result
+      }
+      _ => draw(canvas, paint),
     }
-    let mut drop_shadow_paint = paint.clone();
-    let a = shadow_color.alpha;
-    let r = shadow_color.red;
-    let g = shadow_color.green;
-    let b = shadow_color.blue;
-    let transform = last_state.transform.get_transform();
-    let sigma_x = last_state.shadow_blur / (2f32 * transform.scale_x());
-    let sigma_y = last_state.shadow_blur / (2f32 * transform.scale_y());
-    let shadow_effect = ImageFilter::make_drop_shadow_only(
-      last_state.shadow_offset_x,
-      last_state.shadow_offset_y,
-      sigma_x,
-      sigma_y,
-      (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32,
-      None,
-    )?;
-    drop_shadow_paint.set_alpha(shadow_alpha);
-    drop_shadow_paint.set_image_filter(&shadow_effect);
-    Some(drop_shadow_paint)
   }
 
-  fn shadow_blur_paint(&self, paint: &Paint) -> Option<Paint> {
-    let alpha = paint.get_alpha();
-    let last_state = &self.state;
-    let shadow_color = Self::multiply_by_alpha(&last_state.shadow_color, alpha);
-    let shadow_alpha = shadow_color.alpha;
-    if shadow_alpha == 0 {
-      return None;
-    }
-    if last_state.shadow_blur == 0f32
-      && last_state.shadow_offset_x == 0f32
-      && last_state.shadow_offset_y == 0f32
+  /// The primitive is drawn in a layer with the drop shadow filter, the layer is saved without
+  /// the transform, so the offset and the blur of the shadow are not transformed as the spec says
+  fn draw_shadow(
+    canvas: &mut Canvas,
+    state: &Context2dRenderingState,
+    paint: &Paint,
+    draw: impl FnOnce(&mut Canvas, &Paint) -> result::Result<(), SkError>,
+  ) -> result::Result<(), SkError> {
+    let layer_paint = match Self::shadow_layer_paint(state, paint) {
+      Some(layer_paint) => layer_paint,
+      None => return Ok(()),
+    };
+    // the layer is composited with the composite operation, the primitive is drawn in it as is
+    let mut shadow_paint = paint.clone();
+    shadow_paint.set_blend_mode(BlendMode::SourceOver);
+    canvas.save();
+    canvas.reset_transform();
+    canvas.save_layer(&layer_paint, None);
+    canvas.set_transform(&state.transform);
+    let result = draw(canvas, &shadow_paint);
+    canvas.restore();
+    canvas.restore();
+    result
+  }
+
+  fn shadow_layer_paint(state: &Context2dRenderingState, paint: &Paint) -> Option<Paint> {
+    let shadow_color = &state.shadow_color;
+    if shadow_color.alpha == 0
+      || paint.get_alpha() == 0
+      || (state.shadow_blur == 0f32
+        && state.shadow_offset_x == 0f32
+        && state.shadow_offset_y == 0f32)
     {
       return None;
     }
-    let mut drop_shadow_paint = paint.clone();
-    let a = shadow_color.alpha;
-    let r = shadow_color.red;
-    let g = shadow_color.green;
-    let b = shadow_color.blue;
-    let transform = last_state.transform.get_transform();
-    let sigma_x = last_state.shadow_blur / (2f32 * transform.scale_x());
-    let sigma_y = last_state.shadow_blur / (2f32 * transform.scale_y());
-    let shadow_effect = ImageFilter::make_drop_shadow_only(
-      0.0,
-      0.0,
-      sigma_x,
-      sigma_y,
-      (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32,
+    // the alpha of the primitive, which has the global alpha, multiplies the alpha of the shadow
+    let color = (shadow_color.alpha as u32) << 24
+      | (shadow_color.red as u32) << 16
+      | (shadow_color.green as u32) << 8
+      | shadow_color.blue as u32;
+    let sigma = state.shadow_blur / 2f32;
+    let shadow_effect = SkImageFilter::make_drop_shadow_only(
+      state.shadow_offset_x,
+      state.shadow_offset_y,
+      sigma,
+      sigma,
+      color,
       None,
     )?;
-    drop_shadow_paint.set_alpha(shadow_alpha);
-    drop_shadow_paint.set_image_filter(&shadow_effect);
-    let blur_effect = MaskFilter::make_blur(last_state.shadow_blur / 2f32)?;
-    drop_shadow_paint.set_mask_filter(&blur_effect);
-    Some(drop_shadow_paint)
+    let mut layer_paint = Paint::new();
+    layer_paint.set_blend_mode(paint.get_blend_mode());
+    layer_paint.set_image_filter(&shadow_effect);
+    Some(layer_paint)
   }
 
   pub(crate) fn draw_image(
@@ -615,38 +1106,126 @@ impl Context {
     let bitmap = bitmap.0.bitmap;
     let mut paint = self.fill_paint()?;
     paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
-    if let Some(drop_shadow_paint) = self.drop_shadow_paint(&paint) {
-      let surface = &mut self.surface;
-      surface.canvas.draw_image(
-        bitmap,
-        sx,
-        sy,
-        s_width,
-        s_height,
+    let ts = self.state.transform.get_transform();
+    let scale = ((d_width / s_width).abs() * ts.a.hypot(ts.b))
+      .min((d_height / s_height).abs() * ts.c.hypot(ts.d));
+    let sampling = SamplingOptions::from_quality(
+      self.state.image_smoothing_quality,
+      self.state.image_smoothing_enabled,
+      scale,
+    );
+    Self::draw_primitive(&mut self.surface, &self.state, &paint, |canvas, paint| {
+      canvas.draw_image(
+        bitmap, sx, sy, s_width, s_height, dx, dy, d_width, d_height, sampling, paint,
+      );
+      Ok(())
+    })?;
+    Ok(())
+  }
+
+  pub(crate) fn draw_picture(
+    &mut self,
+    picture: &SkPicture,
+    matrix: Option<&Matrix>,
+  ) -> result::Result<(), SkError> {
+    let mut paint = self.fill_paint()?;
+    paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
+    Self::draw_primitive(&mut self.surface, &self.state, &paint, |canvas, paint| {
+      canvas.draw_picture(picture, matrix, paint);
+      Ok(())
+    })?;
+    Ok(())
+  }
+
+  pub(crate) fn draw_svg(
+    &mut self,
+    svg: &[u8],
+    dx: f32,
+    dy: f32,
+    d_width: f32,
+    d_height: f32,
+  ) -> result::Result<(), SkError> {
+    // the SVG has its own fill and stroke, only the compositing of the state applies to it
+    let mut paint = Paint::new();
+    paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
+    paint.set_blend_mode(self.state.paint.get_blend_mode());
+    if let Some(f) = &self.state.filter {
+      paint.set_image_filter(f);
+    }
+    Self::draw_primitive(&mut self.surface, &self.state, &paint, |canvas, paint| {
+      if !canvas.draw_svg(
+        svg,
         dx,
         dy,
         d_width,
         d_height,
-        self.state.image_smoothing_enabled,
-        self.state.image_smoothing_quality,
-        &drop_shadow_paint,
-      );
-    }
-    self.surface.canvas.draw_image(
-      bitmap,
-      sx,
-      sy,
-      s_width,
-      s_height,
-      dx,
-      dy,
-      d_width,
-      d_height,
-      self.state.image_smoothing_enabled,
+        paint,
+        &*crate::global_fonts::GLOBAL_FONT_COLLECTION,
+      ) {
+        return Err(SkError::Generic(
+          "Invalid SVG, or the SVG has no width, height or viewBox".to_owned(),
+        ));
+      }
+      Ok(())
+    })
+  }
+
+  pub(crate) fn draw_atlas(
+    &mut self,
+    bitmap: &Bitmap,
+    xforms: &[f32],
+    src_rects: &[f32],
+    colors: Option<&[u32]>,
+    blend_mode: BlendMode,
+  ) -> Result<()> {
+    let mut paint = self.fill_paint()?;
+    paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
+    let sampling = SamplingOptions::from_quality(
       self.state.image_smoothing_quality,
-      &paint,
+      self.state.image_smoothing_enabled,
+      1.0,
     );
+    Self::draw_primitive(&mut self.surface, &self.state, &paint, |canvas, paint| {
+      canvas.draw_atlas(
+        bitmap.0.bitmap,
+        xforms,
+        src_rects,
+        colors,
+        blend_mode,
+        sampling,
+        paint,
+      );
+      Ok(())
+    })?;
+    Ok(())
+  }
 
+  pub(crate) fn draw_image_nine(
+    &mut self,
+    bitmap: &Bitmap,
+    center: (i32, i32, i32, i32),
+    dx: f32,
+    dy: f32,
+    d_width: f32,
+    d_height: f32,
+  ) -> Result<()> {
+    let bitmap = bitmap.0.bitmap;
+    let mut paint = self.fill_paint()?;
+    paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
+    let enable_smoothing = self.state.image_smoothing_enabled;
+    Self::draw_primitive(&mut self.surface, &self.state, &paint, |canvas, paint| {
+      canvas.draw_image_nine(
+        bitmap,
+        center,
+        dx,
+        dy,
+        d_width,
+        d_height,
+        enable_smoothing,
+        paint,
+      );
+      Ok(())
+    })?;
     Ok(())
   }
 
@@ -662,16 +1241,14 @@ impl Context {
     let weight = state.font_style.weight;
     let stretch = state.font_style.stretch;
     let slant = state.font_style.style;
-    if let Some(shadow_paint) = self.shadow_blur_paint(paint) {
-      let surface = &mut self.surface;
-      surface.save();
-      Self::apply_shadow_offset_matrix(surface, state.shadow_offset_x, state.shadow_offset_y)?;
-      surface.canvas.draw_text(
+    let width = self.width as f32;
+    Self::draw_primitive(&mut self.surface, state, paint, |canvas, paint| {
+      canvas.draw_text(
         text,
         x,
         y,
         max_width,
-        self.width as f32,
+        width,
         weight,
         stretch as i32,
         slant,
@@ -681,28 +1258,10 @@ impl Context {
         state.text_baseline,
         state.text_align,
         state.text_direction,
-        &shadow_paint,
+        paint,
       )?;
-      surface.restore();
-    }
-
-    self.surface.canvas.draw_text(
-      text,
-      x,
-      y,
-      max_width,
-      self.width as f32,
-      weight,
-      stretch as i32,
-      slant,
-      &*crate::global_fonts::GLOBAL_FONT_COLLECTION,
-      state.font_style.size,
-      &state.font_style.family,
-      state.text_baseline,
-      state.text_align,
-      state.text_direction,
-      paint,
-    )?;
+      Ok(())
+    })?;
     Ok(())
   }
 
@@ -728,23 +1287,6 @@ impl Context {
     Ok(line_metrics)
   }
 
-  fn apply_shadow_offset_matrix(
-    surface: &mut Surface,
-    shadow_offset_x: f32,
-    shadow_offset_y: f32,
-  ) -> result::Result<(), SkError> {
-    let current_transform = surface.canvas.get_transform_matrix();
-    let invert = current_transform
-      .invert()
-      .ok_or_else(|| SkError::Generic("Invert matrix failed".to_owned()))?;
-    surface.canvas.concat(&invert);
-    let mut shadow_offset = current_transform.clone();
-    shadow_offset.pre_translate(shadow_offset_x, shadow_offset_y);
-    surface.canvas.concat(&shadow_offset);
-    surface.canvas.concat(&current_transform);
-    Ok(())
-  }
-
   // ./skia/modules/canvaskit/color.js
   fn multiply_by_alpha(color: &RGBA, global_alpha: u8) -> RGBA {
     let mut result = *color;
@@ -759,6 +1301,7 @@ impl Context {
 pub struct ContextAttributes {
   pub alpha: bool,
   pub desynchronized: bool,
+  pub will_read_frequently: bool,
 }
 
 #[napi]
@@ -785,7 +1328,7 @@ pub struct CanvasRenderingContext2D {
 
 impl ObjectFinalize for CanvasRenderingContext2D {
   fn finalize(self, mut env: Env) -> Result<()> {
-    env.adjust_external_memory(-((self.context.width * self.context.height * 4) as i64))?;
+    env.adjust_external_memory(-self.context.external_memory_size())?;
     Ok(())
   }
 }
@@ -801,7 +1344,14 @@ impl CanvasRenderingContext2D {
   ) -> Result<Self> {
     let color_space = ColorSpace::from_str(&color_space)?;
     let context = if let Some(flag) = flag {
-      Context::new_svg(width, height, flag.into(), color_space)?
+      Context::new_svg(
+        width,
+        height,
+        Some(flag.into()),
+        SvgTextPolicy::default(),
+        color_space,
+        None,
+      )?
     } else {
       Context::new(width, height, color_space)?
     };
@@ -858,6 +1408,25 @@ impl CanvasRenderingContext2D {
     };
   }
 
+  /// Non-standard, the color `currentcolor` is resolved to in the colors, `null` if it's rejected
+  #[napi(getter)]
+  pub fn get_current_color(&self) -> Option<String> {
+    self.context.current_color.as_ref().map(serialize_color)
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_current_color(&mut self, color: Option<String>) {
+    match color {
+      Some(color) => {
+        // the invalid colors are ignored like `fillStyle`
+        if let Ok(color) = parse_color(&color) {
+          self.context.current_color = Some(color);
+        }
+      }
+      None => self.context.current_color = None,
+    }
+  }
+
   #[napi(getter)]
   pub fn get_image_smoothing_enabled(&self) -> bool {
     self.context.state.image_smoothing_enabled
@@ -941,9 +1510,13 @@ impl CanvasRenderingContext2D {
     self.context.state.paint.set_stroke_width(width as f32);
   }
 
+  /// The colors are serialized, the other styles are the objects assigned
   #[napi(getter)]
-  pub fn get_fill_style(&self, this: This) -> Result<Unknown> {
-    this.get_named_property_unchecked(FILL_STYLE_HIDDEN_NAME)
+  pub fn get_fill_style(&self, env: Env, this: This) -> Result<Unknown> {
+    match &self.context.state.fill_style {
+      Pattern::Color(color) => Ok(env.create_string(&serialize_color(color))?.into_unknown()),
+      _ => this.get_named_property_unchecked(FILL_STYLE_HIDDEN_NAME),
+    }
   }
 
   #[napi(setter, return_if_invalid)]
@@ -951,29 +1524,63 @@ impl CanvasRenderingContext2D {
     &mut self,
     env: Env,
     mut this: This,
-    fill_style: Either3<JsString, ClassInstance<CanvasGradient>, ClassInstance<CanvasPattern>>,
+    fill_style: Either4<
+      JsString,
+      ClassInstance<CanvasGradient>,
+      ClassInstance<CanvasPattern>,
+      ClassInstance<ShaderEffect>,
+    >,
   ) -> Result<()> {
     if let Some(pattern) = match &fill_style {
-      Either3::A(color) => Pattern::from_color(color.into_utf8()?.as_str()?).ok(),
-      Either3::B(gradient) => Some(Pattern::Gradient(gradient.0.clone())),
-      Either3::C(pattern) => Some(pattern.inner.clone()),
+      Either4::A(color) => {
+        Pattern::from_color(color.into_utf8()?.as_str()?, self.context.current_color).ok()
+      }
+      Either4::B(gradient) => Some(Pattern::Gradient(gradient.interpolated_gradient())),
+      Either4::C(pattern) => Some(pattern.inner.clone()),
+      Either4::D(effect) => Some(Pattern::Shader(effect.inner.clone())),
     } {
-      let raw_fill_style = fill_style.as_unknown(env);
+      if !matches!(pattern, Pattern::Color(_)) {
+        this.set(FILL_STYLE_HIDDEN_NAME, &fill_style.as_unknown(env))?;
+      }
       self.context.state.fill_style = pattern;
-      this.set(FILL_STYLE_HIDDEN_NAME, &raw_fill_style)?;
     }
     Ok(())
   }
 
+  /// The CSS filter, or an `ImageFilter` of the same filter if it's assigned
   #[napi(getter)]
-  pub fn get_filter(&self) -> String {
-    self.context.state.filters_string.clone()
+  pub fn get_filter(&self) -> Either<String, ImageFilter> {
+    let state = &self.context.state;
+    match (&state.filters_string, &state.filter) {
+      (None, Some(filter)) => Either::B(ImageFilter {
+        inner: filter.clone(),
+      }),
+      (filters_string, _) => Either::A(filters_string.clone().unwrap_or_else(|| "none".to_owned())),
+    }
   }
 
   #[napi(setter, return_if_invalid)]
-  pub fn set_filter(&mut self, filter: String) -> Result<()> {
-    self.context.set_filter(&filter)?;
-    Ok(())
+  pub fn set_filter(&mut self, filter: Either<String, ClassInstance<ImageFilter>>) {
+    match filter {
+      Either::A(filter) => self.context.set_filter(&filter),
+      Either::B(filter) => self.context.set_image_filter(filter.inner.clone()),
+    }
+  }
+
+  /// Non-standard, a `ColorFilter` of the same filter is returned
+  #[napi(getter)]
+  pub fn get_color_filter(&self) -> Option<ColorFilter> {
+    self
+      .context
+      .state
+      .color_filter
+      .clone()
+      .map(|inner| ColorFilter { inner })
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_color_filter(&mut self, color_filter: Option<ClassInstance<ColorFilter>>) {
+    self.context.state.color_filter = color_filter.map(|filter| filter.inner.clone());
   }
 
   #[napi(getter)]
@@ -1000,8 +1607,11 @@ impl CanvasRenderingContext2D {
   }
 
   #[napi(getter)]
-  pub fn get_stroke_style(&self, this: This) -> Option<Unknown> {
-    this.get(STROKE_STYLE_HIDDEN_NAME).ok().flatten()
+  pub fn get_stroke_style(&self, env: Env, this: This) -> Result<Unknown> {
+    match &self.context.state.stroke_style {
+      Pattern::Color(color) => Ok(env.create_string(&serialize_color(color))?.into_unknown()),
+      _ => this.get_named_property_unchecked(STROKE_STYLE_HIDDEN_NAME),
+    }
   }
 
   #[napi(setter, return_if_invalid)]
@@ -1009,15 +1619,24 @@ impl CanvasRenderingContext2D {
     &mut self,
     env: Env,
     mut this: This,
-    fill_style: Either3<JsString, ClassInstance<CanvasGradient>, ClassInstance<CanvasPattern>>,
+    fill_style: Either4<
+      JsString,
+      ClassInstance<CanvasGradient>,
+      ClassInstance<CanvasPattern>,
+      ClassInstance<ShaderEffect>,
+    >,
   ) -> Result<()> {
     if let Some(pattern) = match &fill_style {
-      Either3::A(color) => Pattern::from_color(color.into_utf8()?.as_str()?).ok(),
-      Either3::B(gradient) => Some(Pattern::Gradient(gradient.0.clone())),
-      Either3::C(pattern) => Some(pattern.inner.clone()),
+      Either4::A(color) => {
+        Pattern::from_color(color.into_utf8()?.as_str()?, self.context.current_color).ok()
+      }
+      Either4::B(gradient) => Some(Pattern::Gradient(gradient.interpolated_gradient())),
+      Either4::C(pattern) => Some(pattern.inner.clone()),
+      Either4::D(effect) => Some(Pattern::Shader(effect.inner.clone())),
     } {
-      let raw_fill_style = fill_style.as_unknown(env);
-      this.set(STROKE_STYLE_HIDDEN_NAME, &raw_fill_style)?;
+      if !matches!(pattern, Pattern::Color(_)) {
+        this.set(STROKE_STYLE_HIDDEN_NAME, &fill_style.as_unknown(env))?;
+      }
       self.context.state.stroke_style = pattern;
     }
     Ok(())
@@ -1035,13 +1654,15 @@ impl CanvasRenderingContext2D {
 
   #[napi(getter)]
   pub fn get_shadow_color(&self) -> String {
-    self.context.state.shadow_color_string.clone()
+    serialize_color(&self.context.state.shadow_color)
   }
 
   #[napi(setter, return_if_invalid)]
-  pub fn set_shadow_color(&mut self, shadow_color: String) -> Result<()> {
-    self.context.set_shadow_color(shadow_color)?;
-    Ok(())
+  pub fn set_shadow_color(&mut self, shadow_color: String) {
+    // the invalid colors are ignored like `fillStyle`
+    if let Ok(color) = parse_color_with(&shadow_color, self.context.current_color) {
+      self.context.state.shadow_color = color;
+    }
   }
 
   #[napi(getter)]
@@ -1095,7 +1716,10 @@ impl CanvasRenderingContext2D {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !radius_args_valid(&[x, y, start_angle, end_angle], &[radius])? {
+      return Ok(());
+    }
     self.context.arc(
       x as f32,
       y as f32,
@@ -1104,13 +1728,17 @@ impl CanvasRenderingContext2D {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
-  pub fn arc_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) {
-    self
-      .context
-      .arc_to(x1 as f32, y1 as f32, x2 as f32, y2 as f32, radius as f32);
+  pub fn arc_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) -> Result<()> {
+    if radius_args_valid(&[x1, y1, x2, y2], &[radius])? {
+      self
+        .context
+        .arc_to(x1 as f32, y1 as f32, x2 as f32, y2 as f32, radius as f32);
+    }
+    Ok(())
   }
 
   #[napi]
@@ -1155,7 +1783,7 @@ impl CanvasRenderingContext2D {
       Either::A(_) => None,
       Either::B(p) => Some(p),
     });
-    self.context.clip(path.map(|p| &mut p.inner), rule);
+    self.context.clip(path.map(|p| &p.inner), rule);
   }
 
   #[napi]
@@ -1174,20 +1802,21 @@ impl CanvasRenderingContext2D {
   pub fn create_image_data(
     &mut self,
     env: Env,
-    width_or_data: Either<u32, Uint8ClampedArray>,
-    width_or_height: u32,
+    width_or_data: Either3<u32, Uint8ClampedArray, &ImageData>,
+    width_or_height: Option<u32>,
     height_or_settings: Option<Either<u32, Settings>>,
     maybe_settings: Option<Settings>,
   ) -> Result<ClassInstance<ImageData>> {
+    let missing_size =
+      || SkError::TypeError("createImageData requires the width and the height".to_owned());
     match width_or_data {
-      Either::A(width) => {
-        let height = width_or_height;
-        let color_space = match height_or_settings {
-          Some(Either::B(settings)) => {
-            ColorSpace::from_str(&settings.color_space).unwrap_or_default()
-          }
-          _ => ColorSpace::default(),
-        };
+      Either3::A(width) => {
+        let height = width_or_height.ok_or_else(missing_size)?;
+        let color_space = match &height_or_settings {
+          Some(Either::B(settings)) => settings.color_space()?,
+          _ => None,
+        }
+        .unwrap_or(self.context.color_space);
         let arraybuffer_length = (width * height * 4) as usize;
         let mut data_buffer = vec![0; arraybuffer_length];
         let data_ptr = data_buffer.as_mut_ptr();
@@ -1208,17 +1837,23 @@ impl CanvasRenderingContext2D {
         image_instance.set("data", &data_object)?;
         Ok(instance)
       }
-      Either::B(mut data_object) => {
+      Either3::B(mut data_object) => {
         let input_data_length = data_object.len();
-        let width = width_or_height;
+        let width = width_or_height.ok_or_else(missing_size)?;
         let height = match &height_or_settings {
           Some(Either::A(height)) => *height,
           _ => (input_data_length as u32) / 4 / width,
         };
         let data = data_object.as_mut_ptr();
-        let color_space = maybe_settings
-          .and_then(|settings| ColorSpace::from_str(&settings.color_space).ok())
-          .unwrap_or_default();
+        let settings = match &height_or_settings {
+          Some(Either::B(settings)) => Some(settings),
+          _ => maybe_settings.as_ref(),
+        };
+        let color_space = settings
+          .map(Settings::color_space)
+          .transpose()?
+          .flatten()
+          .unwrap_or(self.context.color_space);
         let instance = ImageData {
           width: width as usize,
           height: height as usize,
@@ -1230,6 +1865,14 @@ impl CanvasRenderingContext2D {
         image_instance.set("data", data_object)?;
         Ok(instance)
       }
+      // the transparent pixels of the same size and color space
+      Either3::C(image_data) => ImageData::from_pixels(
+        env,
+        vec![0; image_data.width * image_data.height * 4],
+        image_data.width,
+        image_data.height,
+        image_data.color_space,
+      ),
     }
   }
 
@@ -1242,9 +1885,10 @@ impl CanvasRenderingContext2D {
     x1: f64,
     y1: f64,
   ) -> Result<ClassInstance<CanvasGradient>> {
+    gradient_args_valid(&[x0, y0, x1, y1], &[])?;
     let linear_gradient =
       Gradient::create_linear_gradient(x0 as f32, y0 as f32, x1 as f32, y1 as f32);
-    CanvasGradient(linear_gradient).into_instance(env)
+    CanvasGradient::new(linear_gradient, self.context.current_color).into_instance(env)
   }
 
   #[napi]
@@ -1258,10 +1902,11 @@ impl CanvasRenderingContext2D {
     y1: f64,
     r1: f64,
   ) -> Result<ClassInstance<CanvasGradient>> {
+    gradient_args_valid(&[x0, y0, x1, y1], &[r0, r1])?;
     let radial_gradient = Gradient::create_radial_gradient(
       x0 as f32, y0 as f32, r0 as f32, x1 as f32, y1 as f32, r1 as f32,
     );
-    CanvasGradient(radial_gradient).into_instance(env)
+    CanvasGradient::new(radial_gradient, self.context.current_color).into_instance(env)
   }
 
   #[napi]
@@ -1272,8 +1917,9 @@ impl CanvasRenderingContext2D {
     x: f64,
     y: f64,
   ) -> Result<ClassInstance<CanvasGradient>> {
+    gradient_args_valid(&[r, x, y], &[])?;
     let conic_gradient = Gradient::create_conic_gradient(x as f32, y as f32, r as f32);
-    CanvasGradient(conic_gradient).into_instance(env)
+    CanvasGradient::new(conic_gradient, self.context.current_color).into_instance(env)
   }
 
   #[napi]
@@ -1286,6 +1932,61 @@ impl CanvasRenderingContext2D {
     CanvasPattern::new(input, repetition)?.into_instance(env)
   }
 
+  /// Compile the SkSL shader, the uniforms are the numbers or the arrays of numbers by the names
+  #[napi]
+  pub fn create_shader_effect(
+    &self,
+    env: Env,
+    sksl: String,
+    uniforms: Option<HashMap<String, Either<f64, Vec<f64>>>>,
+  ) -> Result<ClassInstance<ShaderEffect>> {
+    ShaderEffect::new(&sksl, uniforms)?.into_instance(env)
+  }
+
+  /// Filter the canvas by the effect, the first child shader of it samples the current pixels
+  #[napi]
+  pub fn apply_shader_effect(&mut self, effect: &ShaderEffect) -> Result<()> {
+    self.context.apply_shader_effect(&effect.inner)?;
+    Ok(())
+  }
+
+  /// Filter the pixels in the rect of the canvas by the CSS filter or the `ColorFilter`,
+  /// the transform is not applied to the rect like `putImageData`
+  #[napi]
+  pub fn apply_filter(
+    &mut self,
+    filter: Either<String, ClassInstance<ColorFilter>>,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+  ) -> Result<()> {
+    if !all_finite(&[x, y, width, height]) {
+      return Ok(());
+    }
+    let mut paint = Paint::new();
+    match &filter {
+      Either::A(filter) => match filter.trim() {
+        "none" => return Ok(()),
+        filter => {
+          if let Some(image_filter) = parse_filter(filter)? {
+            paint.set_image_filter(&image_filter);
+          }
+        }
+      },
+      Either::B(color_filter) => paint.set_color_filter(&color_filter.inner),
+    }
+    let (x, y, width, height) = (x as f32, y as f32, width as f32, height as f32);
+    self.context.apply_filter(
+      paint,
+      x.min(x + width),
+      y.min(y + height),
+      width.abs(),
+      height.abs(),
+    )?;
+    Ok(())
+  }
+
   #[napi]
   pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
     self
@@ -1293,6 +1994,23 @@ impl CanvasRenderingContext2D {
       .rect(x as f32, y as f32, width as f32, height as f32);
   }
 
+  #[napi]
+  pub fn round_rect(
+    &mut self,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    radii: Option<RoundRectRadii>,
+  ) -> Result<()> {
+    if let Some(radii) = round_rect_radii(&[x, y, width, height], radii)? {
+      self
+        .context
+        .round_rect(x as f32, y as f32, width as f32, height as f32, radii);
+    }
+    Ok(())
+  }
+
   #[napi]
   pub fn fill(
     &mut self,
@@ -1320,14 +2038,96 @@ impl CanvasRenderingContext2D {
     self.context.save();
   }
 
+  /// Save the state and draw into a layer until `restore`,
+  /// then the layer is composited onto the canvas with the alpha, the blend mode and the filter
+  #[napi]
+  pub fn save_layer(&mut self, options: Option<SaveLayerOptions>) -> Result<()> {
+    let mut paint = Paint::new();
+    let mut bounds = None;
+    if let Some(options) = options {
+      if let Some(alpha) = options.alpha.filter(|a| a.is_finite()) {
+        paint.set_alpha((alpha.clamp(0.0, 1.0) * 255.0).round() as u8);
+      }
+      if let Some(blend_mode) = options.blend_mode {
+        paint.set_blend_mode(BlendMode::from_str(&blend_mode)?);
+      }
+      match &options.filter {
+        Some(Either::A(filter)) if filter.trim() != "none" => {
+          if let Some(image_filter) = parse_filter(filter)? {
+            paint.set_image_filter(&image_filter);
+          }
+        }
+        Some(Either::B(filter)) => paint.set_image_filter(&filter.inner),
+        _ => {}
+      }
+      if let Some(color_filter) = &options.color_filter {
+        paint.set_color_filter(&color_filter.inner);
+      }
+      bounds = options.bounds.map(|b| {
+        let (x, y, width, height) = (b.x as f32, b.y as f32, b.width as f32, b.height as f32);
+        (
+          x.min(x + width),
+          y.min(y + height),
+          x.max(x + width),
+          y.max(y + height),
+        )
+      });
+    }
+    self.context.save_layer(&paint, bounds);
+    Ok(())
+  }
+
+  /// Make the rect a clickable link in the PDF, it's ignored by the other canvas types
+  #[napi]
+  pub fn add_link(&mut self, rect: RectInit, url: String) -> Result<()> {
+    self.context.add_link(
+      rect.x as f32,
+      rect.y as f32,
+      rect.width as f32,
+      rect.height as f32,
+      &url,
+    )?;
+    Ok(())
+  }
+
+  /// Add a destination in the PDF for the `#name` links
+  #[napi]
+  pub fn add_destination(&mut self, name: String, x: f64, y: f64) -> Result<()> {
+    self.context.add_destination(&name, x as f32, y as f32)?;
+    Ok(())
+  }
+
+  /// Start a new page of the PDF canvas, the size defaults to the size of the canvas
+  #[napi]
+  pub fn add_page(&mut self, width: Option<f64>, height: Option<f64>) -> Result<()> {
+    let width = width.unwrap_or(self.context.width as f64);
+    let height = height.unwrap_or(self.context.height as f64);
+    if !(width > 0.0 && width.is_finite() && height > 0.0 && height.is_finite()) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("Invalid page size {}x{}", width, height),
+      ));
+    }
+    self.context.add_page(width as f32, height as f32)?;
+    Ok(())
+  }
+
   #[napi(return_if_invalid)]
   pub fn restore(&mut self) {
     self.context.restore();
   }
 
+  #[napi]
+  pub fn reset(&mut self) {
+    self.context.reset();
+  }
+
   #[napi(return_if_invalid)]
   pub fn rotate(&mut self, angle: f64) {
-    self.context.rotate(angle as f32);
+    // the infinite or NaN angle would break the transform
+    if angle.is_finite() {
+      self.context.rotate(angle as f32);
+    }
   }
 
   #[napi(return_if_invalid)]
@@ -1348,20 +2148,9 @@ impl CanvasRenderingContext2D {
     d_width: Option<f64>,
     d_height: Option<f64>,
   ) -> Result<()> {
-    let bitmap = match image {
-      Either3::A(canvas) => BitmapRef::Owned(canvas.ctx.as_ref().context.surface.get_bitmap()),
-      Either3::B(svg) => BitmapRef::Owned(svg.ctx.as_ref().context.surface.get_bitmap()),
-      Either3::C(image) => {
-        if !image.complete {
-          return Ok(());
-        }
-        image.regenerate_bitmap_if_need();
-        if let Some(bitmap) = &mut image.bitmap {
-          BitmapRef::Borrowed(bitmap)
-        } else {
-          return Ok(());
-        }
-      }
+    let bitmap = match BitmapRef::from_image_source(image) {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
     };
     let bitmap_ref = bitmap.as_ref();
     let (sx, sy, s_width, s_height, dx, dy, d_width, d_height) =
@@ -1413,11 +2202,157 @@ impl CanvasRenderingContext2D {
     Ok(())
   }
 
+  /// Replay the picture recorded by the picture canvas, or loaded from the `.skp` bytes by `new Picture`.
+  /// `transform` is applied on top of the current transform
+  #[napi]
+  pub fn draw_picture(
+    &mut self,
+    picture: &Picture,
+    transform: Option<TransformObject>,
+  ) -> Result<()> {
+    let matrix = transform.map(|ts| Matrix::from_transform(&ts.into()));
+    self.context.draw_picture(&picture.inner, matrix.as_ref())?;
+    Ok(())
+  }
+
+  /// Render the SVG document as vectors at `(dx, dy)`, the size defaults to the intrinsic size of the SVG
+  #[napi]
+  pub fn draw_svg(
+    &mut self,
+    svg: Either<String, Buffer>,
+    dx: f64,
+    dy: f64,
+    d_width: Option<f64>,
+    d_height: Option<f64>,
+  ) -> Result<()> {
+    let (d_width, d_height) = match (d_width, d_height) {
+      // nothing to draw, like `drawImage` with the empty destination
+      (Some(w), Some(h)) if !(w > 0.0 && h > 0.0) => return Ok(()),
+      (Some(w), Some(h)) => (w as f32, h as f32),
+      (None, None) => (0.0, 0.0),
+      _ => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "The width and height of drawSvg must be set together".to_owned(),
+        ))
+      }
+    };
+    let svg = match &svg {
+      Either::A(s) => s.as_bytes(),
+      Either::B(b) => b.as_ref(),
+    };
+    self
+      .context
+      .draw_svg(svg, dx as f32, dy as f32, d_width, d_height)?;
+    Ok(())
+  }
+
+  /// Draw many sprites from the same image in one call.
+  /// Every sprite is described by `[scos, ssin, tx, ty]` in `xforms` and `[x, y, width, height]` in `src_rects`
+  #[napi]
+  pub fn draw_atlas(
+    &mut self,
+    image: Either3<&mut CanvasElement, &mut SVGCanvas, &mut Image>,
+    xforms: Float32Array,
+    src_rects: Float32Array,
+    colors: Option<Either<Uint32Array, Vec<String>>>,
+    blend_mode: Option<String>,
+  ) -> Result<()> {
+    if xforms.len() % 4 != 0 || xforms.len() != src_rects.len() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "xforms and srcRects must have 4 values for every sprite".to_owned(),
+      ));
+    }
+    let count = xforms.len() / 4;
+    let colors = match colors {
+      Some(Either::A(colors)) => Some(colors.to_vec()),
+      Some(Either::B(colors)) => Some(
+        colors
+          .iter()
+          .map(|color| {
+            let rgba = parse_color_with(color, self.context.current_color)?;
+            Ok(Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha).0)
+          })
+          .collect::<result::Result<Vec<u32>, SkError>>()?,
+      ),
+      None => None,
+    };
+    if let Some(colors) = &colors {
+      if colors.len() != count {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "colors must have one color for every sprite".to_owned(),
+        ));
+      }
+    }
+    let blend_mode = match blend_mode {
+      Some(blend_mode) => BlendMode::from_str(&blend_mode)?,
+      None => BlendMode::Modulate,
+    };
+    let bitmap = match BitmapRef::from_image_source(image) {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
+    };
+    self.context.draw_atlas(
+      bitmap.as_ref(),
+      xforms.as_ref(),
+      src_rects.as_ref(),
+      colors.as_deref(),
+      blend_mode,
+    )
+  }
+
+  /// Draw the image stretched to the destination rect, only the `center` part of the image is scaled in both directions
+  #[napi]
+  pub fn draw_image_nine(
+    &mut self,
+    image: Either3<&mut CanvasElement, &mut SVGCanvas, &mut Image>,
+    center: RectInit,
+    dx: f64,
+    dy: f64,
+    d_width: f64,
+    d_height: f64,
+  ) -> Result<()> {
+    let bitmap = match BitmapRef::from_image_source(image) {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
+    };
+    let bitmap_ref = bitmap.as_ref();
+    let (width, height) = (bitmap_ref.0.width as f64, bitmap_ref.0.height as f64);
+    if center.x < 0.0
+      || center.y < 0.0
+      || center.width <= 0.0
+      || center.height <= 0.0
+      || center.x + center.width > width
+      || center.y + center.height > height
+    {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The center rect must be inside of the image".to_owned(),
+      ));
+    }
+    self.context.draw_image_nine(
+      bitmap_ref,
+      (
+        center.x as i32,
+        center.y as i32,
+        center.width as i32,
+        center.height as i32,
+      ),
+      dx as f32,
+      dy as f32,
+      d_width as f32,
+      d_height as f32,
+    )
+  }
+
   #[napi]
   pub fn get_context_attributes(&self) -> ContextAttributes {
     ContextAttributes {
       alpha: self.context.alpha,
-      desynchronized: false,
+      desynchronized: self.context.desynchronized,
+      will_read_frequently: self.context.will_read_frequently,
     }
   }
 
@@ -1502,7 +2437,13 @@ impl CanvasRenderingContext2D {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !radius_args_valid(
+      &[x, y, rotation, start_angle, end_angle],
+      &[radius_x, radius_y],
+    )? {
+      return Ok(());
+    }
     self.context.ellipse(
       x as f32,
       y as f32,
@@ -1513,6 +2454,7 @@ impl CanvasRenderingContext2D {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi(return_if_invalid)]
@@ -1642,7 +2584,7 @@ impl CanvasRenderingContext2D {
     y: f64,
     width: f64,
     height: f64,
-    color_space: Option<String>,
+    settings: Option<GetImageDataSettings>,
   ) -> Result<ClassInstance<ImageData>> {
     if !x.is_nan()
       && !x.is_infinite()
@@ -1653,35 +2595,42 @@ impl CanvasRenderingContext2D {
       && !height.is_nan()
       && !height.is_infinite()
     {
-      let color_space = color_space
-        .and_then(|cs| cs.parse().ok())
-        .unwrap_or(ColorSpace::Srgb);
-      let mut image_data = self
+      // the pixels are in the color space of the canvas by default
+      let color_space = settings
+        .as_ref()
+        .map(|settings| settings.color_space())
+        .transpose()?
+        .flatten()
+        .unwrap_or(self.context.color_space);
+      let (color_type, alpha_type) = settings
+        .as_ref()
+        .map(|settings| settings.pixel_format())
+        .transpose()?
+        .unwrap_or((ColorType::RGBA8888, AlphaType::Unpremultiplied));
+      let image_data = self
         .context
-        .get_image_data(x as f32, y as f32, width as f32, height as f32, color_space)
+        .get_image_data(
+          x as f32,
+          y as f32,
+          width as f32,
+          height as f32,
+          color_space,
+          color_type,
+          alpha_type,
+        )
         .ok_or_else(|| {
           Error::new(
             Status::GenericFailure,
             "Read pixels from canvas failed".to_string(),
           )
         })?;
-      let data = image_data.as_mut_ptr();
-      let data_object = unsafe {
-        Object::from_raw_unchecked(
-          env.raw(),
-          Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(image_data))?,
-        )
-      };
-      let instance = ImageData {
-        width: width as usize,
-        height: height as usize,
+      ImageData::from_pixels(
+        env,
+        image_data,
+        width as usize,
+        height as usize,
         color_space,
-        data,
-      }
-      .into_instance(env)?;
-      let mut image_instance = unsafe { Object::from_raw_unchecked(env.raw(), instance.raw()) };
-      image_instance.set("data", data_object)?;
-      Ok(instance)
+      )
     } else {
       Err(Error::new(
         Status::InvalidArg,
@@ -1690,6 +2639,38 @@ impl CanvasRenderingContext2D {
     }
   }
 
+  /// Start reading the pixels of `getImageDataAsync`
+  #[napi]
+  pub fn read_pixels_async(
+    &self,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    color_space: Option<String>,
+  ) -> Result<PixelsReadback> {
+    if ![x, y, width, height].iter().all(|v| v.is_finite()) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The x, y, width, and height arguments must be finite numbers".to_owned(),
+      ));
+    }
+    let color_space = color_space
+      .as_deref()
+      .map(parse_color_space)
+      .transpose()?
+      .unwrap_or(self.context.color_space);
+    // cast like `getImageData`
+    PixelsReadback::new(
+      &self.context.surface,
+      x as f32 as u32,
+      y as f32 as u32,
+      width as f32 as u32,
+      height as f32 as u32,
+      color_space,
+    )
+  }
+
   #[napi]
   pub fn get_line_dash(&self) -> Vec<f64> {
     self
@@ -1758,12 +2739,23 @@ impl CanvasRenderingContext2D {
       );
       self.context.surface.canvas.restore();
     } else {
-      self.context.surface.canvas.write_pixels(image_data, dx, dy);
+      self
+        .context
+        .surface
+        .canvas
+        .write_pixels(image_data, dx, dy, image_data.color_space);
     }
   }
 
   #[napi]
   pub fn set_line_dash(&mut self, dash_list: Vec<f64>) {
+    // the list with any negative, infinite or NaN value is ignored
+    if dash_list
+      .iter()
+      .any(|dash| !dash.is_finite() || *dash < 0.0)
+    {
+      return;
+    }
     let len = dash_list.len();
     let is_odd = len & 1 != 0;
     let mut line_dash_list = if is_odd {
@@ -1790,46 +2782,99 @@ impl CanvasRenderingContext2D {
     self.context.translate(x as f32, y as f32);
   }
 
+  /// `transform(a, b, c, d, e, f)`, or `transform(matrix)` with the `DOMMatrix` like `setTransform`
   #[napi(return_if_invalid)]
-  pub fn transform(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Result<()> {
-    let ts = Matrix::new(a as f32, c as f32, e as f32, b as f32, d as f32, f as f32);
-    self.context.transform(ts)?;
+  pub fn transform(
+    &mut self,
+    a_or_matrix: Either<f64, DOMMatrixInit>,
+    b: Option<f64>,
+    c: Option<f64>,
+    d: Option<f64>,
+    e: Option<f64>,
+    f: Option<f64>,
+  ) -> Result<()> {
+    if let Some(ts) = matrix_from_args(a_or_matrix, [b, c, d, e, f])? {
+      self.context.transform(ts)?;
+    }
     Ok(())
   }
 
+  /// The values of the `DOMMatrix` returned by `getTransform`
   #[napi]
-  pub fn get_transform(&self) -> TransformObject {
+  pub fn get_transform_values(&self) -> TransformObject {
     self.context.state.transform.get_transform().into()
   }
 
+  /// `setTransform()` resets the transform like `resetTransform`
   #[napi]
   pub fn set_transform(
     &mut self,
-    a_or_transform: Either<f64, TransformObject>,
+    a_or_matrix: Option<Either<f64, DOMMatrixInit>>,
     b: Option<f64>,
     c: Option<f64>,
     d: Option<f64>,
     e: Option<f64>,
     f: Option<f64>,
-  ) -> Option<()> {
-    let ts = match a_or_transform {
-      Either::A(a) => Transform::new(
-        a as f32, c? as f32, e? as f32, b? as f32, d? as f32, f? as f32,
-      ),
-      Either::B(transform) => transform.into(),
-    };
-    self
-      .context
-      .set_transform(Matrix::new(ts.a, ts.b, ts.c, ts.d, ts.e, ts.f));
-    None
+  ) -> Result<()> {
+    let a_or_matrix = a_or_matrix.unwrap_or_else(|| Either::B(DOMMatrixInit::default()));
+    if let Some(ts) = matrix_from_args(a_or_matrix, [b, c, d, e, f])? {
+      self.context.set_transform(ts);
+    }
+    Ok(())
   }
 }
 
+/// `None` if any of the values is infinite or NaN, the call is ignored like the browsers
+fn matrix_from_args(
+  a_or_matrix: Either<f64, DOMMatrixInit>,
+  rest: [Option<f64>; 5],
+) -> result::Result<Option<Matrix>, SkError> {
+  let [a, b, c, d, e, f] = match a_or_matrix {
+    Either::A(a) => {
+      let mut values = [a, 0.0, 0.0, 0.0, 0.0, 0.0];
+      for (value, arg) in values[1..].iter_mut().zip(rest) {
+        *value = arg.ok_or_else(|| SkError::TypeError("6 arguments required".to_owned()))?;
+      }
+      values
+    }
+    Either::B(init) => init.to_2d()?,
+  };
+  if !all_finite(&[a, b, c, d, e, f]) {
+    return Ok(None);
+  }
+  Ok(Some(Matrix::new(
+    a as f32, c as f32, e as f32, b as f32, d as f32, f as f32,
+  )))
+}
+
 enum BitmapRef<'a> {
   Borrowed(&'a mut Bitmap),
   Owned(Bitmap),
 }
 
+impl<'a> BitmapRef<'a> {
+  /// Returns `None` if the `Image` is not loaded yet
+  fn from_image_source(
+    image: Either3<&'a mut CanvasElement, &'a mut SVGCanvas, &'a mut Image>,
+  ) -> Option<Self> {
+    match image {
+      Either3::A(canvas) => Some(BitmapRef::Owned(
+        canvas.ctx.as_ref().context.surface.get_bitmap(),
+      )),
+      Either3::B(svg) => Some(BitmapRef::Owned(
+        svg.ctx.as_ref().context.surface.get_bitmap(),
+      )),
+      Either3::C(image) => {
+        if !image.complete {
+          return None;
+        }
+        image.regenerate_bitmap_if_need();
+        image.bitmap.as_mut().map(BitmapRef::Borrowed)
+      }
+    }
+  }
+}
+
 impl AsRef<Bitmap> for BitmapRef<'_> {
   fn as_ref(&self) -> &Bitmap {
     match self {
@@ -1839,6 +2884,28 @@ impl AsRef<Bitmap> for BitmapRef<'_> {
   }
 }
 
+#[napi(object)]
+pub struct SaveLayerOptions {
+  /// 0.0 ~ 1.0, the layer is opaque by default
+  pub alpha: Option<f64>,
+  /// The `globalCompositeOperation` names
+  pub blend_mode: Option<String>,
+  /// The layer is unbounded by default
+  pub bounds: Option<RectInit>,
+  /// The CSS filter or the `ImageFilter` like `filter`
+  pub filter: Option<Either<String, ClassInstance<ImageFilter>>>,
+  /// Applied after the `filter`
+  pub color_filter: Option<ClassInstance<ColorFilter>>,
+}
+
+#[napi(object)]
+pub struct RectInit {
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+}
+
 #[napi(object)]
 pub struct TextMetrics {
   pub actual_bounding_box_ascent: f64,
@@ -1860,6 +2927,77 @@ pub struct TransformObject {
   pub f: f64,
 }
 
+/// The `DOMMatrixInit` dictionary, the `DOMMatrix` instances are read by their getters
+#[napi(object)]
+#[derive(Default)]
+pub struct DOMMatrixInit {
+  pub a: Option<f64>,
+  pub b: Option<f64>,
+  pub c: Option<f64>,
+  pub d: Option<f64>,
+  pub e: Option<f64>,
+  pub f: Option<f64>,
+  pub m11: Option<f64>,
+  pub m12: Option<f64>,
+  pub m13: Option<f64>,
+  pub m14: Option<f64>,
+  pub m21: Option<f64>,
+  pub m22: Option<f64>,
+  pub m23: Option<f64>,
+  pub m24: Option<f64>,
+  pub m31: Option<f64>,
+  pub m32: Option<f64>,
+  pub m33: Option<f64>,
+  pub m34: Option<f64>,
+  pub m41: Option<f64>,
+  pub m42: Option<f64>,
+  pub m43: Option<f64>,
+  pub m44: Option<f64>,
+}
+
+impl DOMMatrixInit {
+  /// `[a, b, c, d, e, f]` after the validate and fixup of
+  /// https://drafts.fxtf.org/geometry/#matrix-validate-and-fixup,
+  /// the canvas can't draw with the 3D matrix so it's a `TypeError` whatever the `is2D` is
+  fn to_2d(&self) -> result::Result<[f64; 6], SkError> {
+    let pairs = [
+      ("a", self.a, "m11", self.m11, 1.0),
+      ("b", self.b, "m12", self.m12, 0.0),
+      ("c", self.c, "m21", self.m21, 0.0),
+      ("d", self.d, "m22", self.m22, 1.0),
+      ("e", self.e, "m41", self.m41, 0.0),
+      ("f", self.f, "m42", self.m42, 0.0),
+    ];
+    let mut values = [0f64; 6];
+    for (value, (name, short, long_name, long, default)) in values.iter_mut().zip(pairs) {
+      *value = match (short, long) {
+        // SameValueZero, NaN is the same as NaN
+        (Some(short), Some(long)) if short != long && !(short.is_nan() && long.is_nan()) => {
+          return Err(SkError::TypeError(format!(
+            "The {} and {} of the matrix are different",
+            name, long_name
+          )));
+        }
+        (_, Some(long)) => long,
+        (Some(short), None) => short,
+        (None, None) => default,
+      };
+    }
+    let is_2d = [
+      self.m13, self.m14, self.m23, self.m24, self.m31, self.m32, self.m34, self.m43,
+    ]
+    .iter()
+    .all(|value| value.map_or(true, |value| value == 0.0))
+      && [self.m33, self.m44]
+        .iter()
+        .all(|value| value.map_or(true, |value| value == 1.0));
+    if !is_2d {
+      return Err(SkError::TypeError("The matrix is not 2D".to_owned()));
+    }
+    Ok(values)
+  }
+}
+
 impl From<TransformObject> for Transform {
   fn from(value: TransformObject) -> Self {
     Self::new(
@@ -1887,15 +3025,206 @@ impl From<Transform> for TransformObject {
 }
 
 pub enum ContextData {
-  Png(SurfaceRef),
-  Jpeg(SurfaceRef, u8),
+  Png(
+    SurfaceRef,
+    PngEncodeOptions,
+    PngMetadata,
+    Option<PaletteOptions>,
+  ),
+  Jpeg(SurfaceRef, JpegEncodeOptions, JpegMetadata),
   Webp(SurfaceRef, u8),
+  WebpLossless(SurfaceRef, u8),
   Avif(SurfaceRef, Config, u32, u32),
+  Raw(SurfaceRef, u32, u32, ColorType, AlphaType),
+  /// Resize the pixels of the source canvas before encoding, the source width, height and color space are kept
+  Resized(Box<ContextData>, ExportSize, u32, u32, ColorSpace),
+  /// Embed the ICC profile into the encoded PNG, JPEG or WebP data
+  Icc(Box<ContextData>, Vec<u8>),
+  /// Encode a copy of the pixels taken when the task was created, the canvas could be drawn while encoding
+  Snapshot(Box<ContextData>, Surface),
+}
+
+impl ContextData {
+  fn surface(&self) -> &SurfaceRef {
+    match self {
+      ContextData::Png(surface, ..)
+      | ContextData::Jpeg(surface, ..)
+      | ContextData::Webp(surface, ..)
+      | ContextData::WebpLossless(surface, ..)
+      | ContextData::Avif(surface, ..)
+      | ContextData::Raw(surface, ..) => surface,
+      ContextData::Resized(data, ..)
+      | ContextData::Icc(data, ..)
+      | ContextData::Snapshot(data, ..) => data.surface(),
+    }
+  }
+
+  pub(crate) fn set_surface(
+    &mut self,
+    surface_ref: SurfaceRef,
+    surface_width: u32,
+    surface_height: u32,
+  ) {
+    match self {
+      ContextData::Png(surface, ..)
+      | ContextData::Jpeg(surface, ..)
+      | ContextData::Webp(surface, ..)
+      | ContextData::WebpLossless(surface, ..) => *surface = surface_ref,
+      ContextData::Avif(surface, _, width, height)
+      | ContextData::Raw(surface, width, height, ..) => {
+        *surface = surface_ref;
+        *width = surface_width;
+        *height = surface_height;
+      }
+      ContextData::Resized(data, ..)
+      | ContextData::Icc(data, ..)
+      | ContextData::Snapshot(data, ..) => {
+        data.set_surface(surface_ref, surface_width, surface_height)
+      }
+    }
+  }
+}
+
+/// The `width`, `height` and `fit` options of `toBuffer` and `encode`
+#[derive(Debug, Clone, Copy)]
+pub struct ExportSize {
+  pub width: u32,
+  pub height: u32,
+  pub fit: ResizeFit,
+}
+
+impl ExportSize {
+  /// Returns a new surface with the pixels of `surface` resized to this size
+  pub fn resize(
+    &self,
+    surface: &SurfaceRef,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+  ) -> Result<Surface> {
+    let pixels = surface
+      .read_pixels_as(
+        width,
+        height,
+        ColorType::RGBA8888,
+        AlphaType::Unpremultiplied,
+      )
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Read pixels from surface failed".to_owned(),
+        )
+      })?;
+    let resized = resize_rgba(
+      &pixels,
+      width,
+      height,
+      self.width,
+      self.height,
+      self.fit,
+      ResizeFilter::default(),
+    );
+    let mut output = Surface::new_rgba(self.width, self.height, color_space).ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Create resized surface failed".to_owned(),
+      )
+    })?;
+    output
+      .data_mut()
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Write resized pixels failed".to_owned(),
+        )
+      })?
+      .copy_from_slice(&resized);
+    Ok(output)
+  }
+}
+
+/// The gradients throw the `TypeError` for the infinite or NaN arguments,
+/// unlike the path methods which ignore them, and the `IndexSizeError` for the negative radii
+fn gradient_args_valid(args: &[f64], radii: &[f64]) -> result::Result<(), SkError> {
+  if !all_finite(args) || !all_finite(radii) {
+    return Err(SkError::TypeError(
+      "The provided double value is non-finite.".to_owned(),
+    ));
+  }
+  if let Some(radius) = radii.iter().find(|radius| **radius < 0.0) {
+    return Err(SkError::IndexSizeError(format!(
+      "The radius provided ({}) is negative.",
+      radius
+    )));
+  }
+  Ok(())
+}
+
+/// The image filter of the CSS `filter` value, except `none`.
+/// It's an error if any part of the value is not a filter function
+fn parse_filter(filter_str: &str) -> result::Result<Option<SkImageFilter>, SkError> {
+  match css_filter(filter_str) {
+    Ok(("", filters)) if !filters.is_empty() => Ok(css_filters_to_image_filter(filters)),
+    _ => Err(SkError::TypeError(format!(
+      "The filter {:?} is invalid",
+      filter_str
+    ))),
+  }
+}
+
+/// Embed `profile` into the encoded PNG, JPEG or WebP `data`
+pub(crate) fn embed_icc(data: &[u8], profile: &[u8]) -> Result<Vec<u8>> {
+  Ok(icc::embed_icc(data, profile, |profile| {
+    crate::sk::deflate(profile, 9).map(|data| data.slice().to_vec())
+  })?)
+}
+
+/// Quantize the pixels of `surface` and encode them as an indexed color PNG
+pub(crate) fn encode_palette_png(
+  surface: &SurfaceRef,
+  options: &PngEncodeOptions,
+  palette: &PaletteOptions,
+) -> Result<Vec<u8>> {
+  let (width, height) = (surface.width(), surface.height());
+  let pixels = surface
+    .read_pixels_as(
+      width,
+      height,
+      ColorType::RGBA8888,
+      AlphaType::Unpremultiplied,
+    )
+    .ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Read pixels from surface failed".to_owned(),
+      )
+    })?;
+  let quantized = quantize::quantize(&pixels, width, height, palette);
+  Ok(png::encode_indexed(
+    width,
+    height,
+    &quantized.palette,
+    &quantized.indices,
+    |scanlines| {
+      crate::sk::deflate(scanlines, options.zlib_level()).map(|data| data.slice().to_vec())
+    },
+  )?)
 }
 
 pub enum ContextOutputData {
   Skia(SkiaDataRef),
   Avif(AvifData<'static>),
+  Buffer(Vec<u8>),
+}
+
+impl ContextOutputData {
+  pub fn as_slice(&self) -> &[u8] {
+    match self {
+      ContextOutputData::Skia(data_ref) => data_ref.slice(),
+      ContextOutputData::Avif(output) => output.as_slice(),
+      ContextOutputData::Buffer(output) => output.as_slice(),
+    }
+  }
 }
 
 unsafe impl Send for ContextOutputData {}
@@ -1907,26 +3236,43 @@ impl Task for ContextData {
 
   fn compute(&mut self) -> Result<Self::Output> {
     match self {
-      ContextData::Png(surface) => {
-        surface
-          .png_data()
-          .map(ContextOutputData::Skia)
-          .ok_or_else(|| {
+      ContextData::Png(surface, options, metadata, palette) => {
+        let output = match palette {
+          Some(palette) => {
+            ContextOutputData::Buffer(encode_palette_png(surface, options, palette)?)
+          }
+          None => ContextOutputData::Skia(surface.encode_png(*options).ok_or_else(|| {
             Error::new(
               Status::GenericFailure,
               "Get png data from surface failed".to_string(),
             )
-          })
+          })?),
+        };
+        if metadata.is_empty() {
+          Ok(output)
+        } else {
+          Ok(ContextOutputData::Buffer(png::insert_metadata(
+            output.as_slice(),
+            metadata,
+          )?))
+        }
       }
-      ContextData::Jpeg(surface, quality) => surface
-        .encode_data(SkEncodedImageFormat::Jpeg, *quality)
-        .map(ContextOutputData::Skia)
-        .ok_or_else(|| {
+      ContextData::Jpeg(surface, options, metadata) => {
+        let data_ref = surface.encode_jpeg(*options).ok_or_else(|| {
           Error::new(
             Status::GenericFailure,
             "Get jpeg data from surface failed".to_string(),
           )
-        }),
+        })?;
+        if metadata.is_empty() {
+          Ok(ContextOutputData::Skia(data_ref))
+        } else {
+          Ok(ContextOutputData::Buffer(jpeg::insert_metadata(
+            data_ref.slice(),
+            metadata,
+          )?))
+        }
+      }
       ContextData::Webp(surface, quality) => surface
         .encode_data(SkEncodedImageFormat::Webp, *quality)
         .map(ContextOutputData::Skia)
@@ -1936,6 +3282,15 @@ impl Task for ContextData {
             "Get webp data from surface failed".to_string(),
           )
         }),
+      ContextData::WebpLossless(surface, effort) => surface
+        .encode_webp(*effort, true)
+        .map(ContextOutputData::Skia)
+        .ok_or_else(|| {
+          Error::new(
+            Status::GenericFailure,
+            "Get webp data from surface failed".to_string(),
+          )
+        }),
       ContextData::Avif(surface, config, width, height) => surface
         .data()
         .ok_or_else(|| {
@@ -1954,6 +3309,29 @@ impl Task for ContextData {
           .map(ContextOutputData::Avif)
           .map_err(|e| Error::new(Status::GenericFailure, format!("{}", e)))
         }),
+      ContextData::Raw(surface, width, height, color_type, alpha_type) => surface
+        .read_pixels_as(*width, *height, *color_type, *alpha_type)
+        .map(ContextOutputData::Buffer)
+        .ok_or_else(|| {
+          Error::new(
+            Status::GenericFailure,
+            "Read pixels from surface failed".to_string(),
+          )
+        }),
+      ContextData::Resized(data, size, width, height, color_space) => {
+        let resized = size.resize(data.surface(), *width, *height, *color_space)?;
+        data.set_surface(resized.reference(), size.width, size.height);
+        // the encoded output doesn't reference the pixels of the resized surface
+        data.compute()
+      }
+      ContextData::Icc(data, profile) => {
+        let output = data.compute()?;
+        Ok(ContextOutputData::Buffer(embed_icc(
+          output.as_slice(),
+          profile,
+        )?))
+      }
+      ContextData::Snapshot(data, _) => data.compute(),
     }
   }
 
@@ -1973,6 +3351,9 @@ impl Task for ContextData {
           })
           .map(|b| b.into_raw())
       },
+      ContextOutputData::Buffer(output) => {
+        env.create_buffer_with_data(output).map(|b| b.into_raw())
+      }
     }
   }
 }
@@ -9,6 +9,10 @@ use crate::sk::Matrix;
 pub enum SkError {
   #[error("[`{0}`] is not valid ColorSpace value")]
   StringToColorSpaceError(String),
+  #[error("[`{0}`] is not valid pixel geometry")]
+  StringToPixelGeometryError(String),
+  #[error("[`{0}`] is not valid GPU backend")]
+  StringToGpuBackendError(String),
   #[error("[`{0}`] is not valid Blend value")]
   StringToBlendError(String),
   #[error("[`{0}`] is not valid FillRule value")]
@@ -25,6 +29,8 @@ pub enum SkError {
   StringToStrokeCapError(String),
   #[error("[`{0}`] is not valid LineJoin value")]
   StringToStrokeJoinError(String),
+  #[error("[`{0}`] is not valid PathOp value")]
+  StringToPathOpError(String),
   #[error("[`{0}`] is not valid SvgExportFlag value")]
   U32ToStrokeJoinError(u32),
   #[error("[`{0}`] is not valid transform")]
@@ -39,6 +45,12 @@ pub enum SkError {
   PixelsToRgb(Error),
   #[error("[`{0}`]")]
   EncodeAvifError(Error),
+  #[error("IndexSizeError: {0}")]
+  IndexSizeError(String),
+  #[error("RangeError: {0}")]
+  RangeError(String),
+  #[error("TypeError: {0}")]
+  TypeError(String),
   #[error("[`{0}`]")]
   Generic(String),
 }
@@ -6,7 +6,7 @@ use nom::{
   bytes::complete::{tag, take_till, take_until},
   character::{complete::char, is_alphabetic},
   combinator::map_res,
-  error::Error,
+  error::{Error, ErrorKind},
   number::complete::float,
   Err, IResult,
 };
@@ -191,52 +191,94 @@ fn blur_parser(input: &str) -> IResult<&str, CssFilter> {
   Ok((finished_input.trim(), CssFilter::Blur(pixel)))
 }
 
-#[allow(clippy::unnecessary_lazy_evaluations)]
+/// `drop-shadow(<color>? <length>{2,3} <color>?)`, the color defaults to black
 fn drop_shadow_parser(input: &str) -> IResult<&str, CssFilter> {
-  let (drop_shadow_input, _) = tag("drop-shadow(")(input)?;
-  let drop_shadow_input = drop_shadow_input.trim();
-  let (offset_x_output, offset_x) = map_res(take_until(" "), pixel)(drop_shadow_input)?;
-  let offset_x_output = offset_x_output.trim();
-  let (offset_y_output, offset_y) =
-    map_res(take_till(|ch| ch == ' ' || ch == ')'), pixel)(offset_x_output)?;
-  let offset_y_output = offset_y_output.trim();
-  let (blur_radius_output, blur_radius) =
-    map_res(take_till(|ch| ch == ' ' || ch == ')'), pixel)(offset_y_output)
-      .unwrap_or_else(|_: Err<Error<&str>>| (offset_y_output, 0.0f32));
-  let blur_radius_output = blur_radius_output.trim();
-  let is_rgb_fn = blur_radius_output.starts_with("rgb(") || blur_radius_output.starts_with("rgba(");
-  let (shadow_color_output, shadow_color_str) =
-    take_until(if is_rgb_fn { "))" } else { ")" })(blur_radius_output)?;
-  let shadow_color_str = shadow_color_str.trim();
-  static BLACK: RGBA = RGBA {
-    red: 0,
-    green: 0,
-    blue: 0,
-    alpha: 255,
-  };
-  let shadow_color = if !shadow_color_str.is_empty() {
-    let mut parser_input = ParserInput::new(shadow_color_str);
-    let mut parser = Parser::new(&mut parser_input);
-    let color = Color::parse(&mut parser).unwrap_or_else(|_| Color::RGBA(BLACK));
-    if let Color::RGBA(rgba) = color {
-      rgba
+  let (input, _) = tag("drop-shadow(")(input)?;
+  let (output, args) = take_args(input)?;
+  let (output, _) = char(')')(output)?;
+  let invalid = || Err::Error(Error::new(input, ErrorKind::Verify));
+  let mut lengths = Vec::with_capacity(3);
+  let mut color = None;
+  for arg in split_args(args) {
+    if let Ok(length) = pixel(arg) {
+      // the color is either before or after all the lengths
+      if color.is_some() && !lengths.is_empty() {
+        return Err(invalid());
+      }
+      lengths.push(length);
+    } else if color.is_none() {
+      color = Some(shadow_color(arg).ok_or_else(invalid)?);
     } else {
-      BLACK
+      return Err(invalid());
     }
-  } else {
-    BLACK
-  };
-  let (mut drop_shadow_output, _) = char(')')(shadow_color_output.trim())?;
-  if is_rgb_fn {
-    let (trimmed_drop_shadow_output, _) = char(')')(drop_shadow_output)?;
-    drop_shadow_output = trimmed_drop_shadow_output;
   }
+  let (offset_x, offset_y, blur_radius) = match lengths[..] {
+    [offset_x, offset_y] => (offset_x, offset_y, 0.0),
+    [offset_x, offset_y, blur_radius] if blur_radius >= 0.0 => (offset_x, offset_y, blur_radius),
+    _ => return Err(invalid()),
+  };
   Ok((
-    drop_shadow_output.trim(),
-    CssFilter::DropShadow(offset_x, offset_y, blur_radius, shadow_color),
+    output.trim(),
+    CssFilter::DropShadow(
+      offset_x,
+      offset_y,
+      blur_radius,
+      color.unwrap_or_else(|| RGBA::new(0, 0, 0, 255)),
+    ),
   ))
 }
 
+/// The arguments until the closing parenthesis of the function, the nested functions like `rgb()` included
+fn take_args(input: &str) -> IResult<&str, &str> {
+  let mut depth = 0;
+  for (i, ch) in input.char_indices() {
+    match ch {
+      '(' => depth += 1,
+      ')' if depth == 0 => return Ok((&input[i..], &input[..i])),
+      ')' => depth -= 1,
+      _ => {}
+    }
+  }
+  Err(Err::Error(Error::new(input, ErrorKind::Char)))
+}
+
+/// The arguments separated by the whitespaces outside of the nested functions
+fn split_args(args: &str) -> Vec<&str> {
+  let mut split = Vec::new();
+  let mut depth = 0;
+  let mut start = None;
+  for (i, ch) in args.char_indices() {
+    match ch {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      ch if ch.is_whitespace() && depth == 0 => {
+        if let Some(start) = start.take() {
+          split.push(&args[start..i]);
+        }
+        continue;
+      }
+      _ => {}
+    }
+    start.get_or_insert(i);
+  }
+  if let Some(start) = start {
+    split.push(&args[start..]);
+  }
+  split
+}
+
+/// `currentcolor` is black, the `currentColor` of the context doesn't apply to the filters
+fn shadow_color(input: &str) -> Option<RGBA> {
+  let mut parser_input = ParserInput::new(input);
+  let mut parser = Parser::new(&mut parser_input);
+  let color = Color::parse(&mut parser).ok()?;
+  parser.expect_exhausted().ok()?;
+  Some(match color {
+    Color::RGBA(rgba) => rgba,
+    Color::CurrentColor => RGBA::new(0, 0, 0, 255),
+  })
+}
+
 pub fn css_filter(input: &str) -> IResult<&str, Vec<CssFilter>> {
   let mut filters = Vec::with_capacity(10);
   let mut input = input.trim();
@@ -295,12 +337,6 @@ pub(crate) fn css_filters_to_image_filter(filters: Vec<CssFilter>) -> Option<Ima
       }
       CssFilter::DropShadow(offset_x, offset_y, blur_radius, shadow_color) => {
         let sigma = blur_radius / 2.0;
-        if shadow_color.alpha == 0 {
-          return None;
-        }
-        if blur_radius == 0f32 && offset_x == 0f32 && offset_y == 0f32 {
-          return None;
-        }
         ImageFilter::make_drop_shadow(
           offset_x,
           offset_y,
@@ -471,6 +507,37 @@ fn drop_shadow_parse() {
       CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255))
     ))
   );
+
+  assert_eq!(
+    drop_shadow_parser("drop-shadow(hsl(0, 100%, 50%) -2px 1em) blur(1px)"),
+    Ok((
+      "blur(1px)",
+      CssFilter::DropShadow(-2.0f32, 16.0f32, 0.0f32, RGBA::new(255, 0, 0, 255))
+    ))
+  );
+
+  assert_eq!(
+    drop_shadow_parser("drop-shadow( red 0 0 )"),
+    Ok((
+      "",
+      CssFilter::DropShadow(0.0f32, 0.0f32, 0.0f32, RGBA::new(255, 0, 0, 255))
+    ))
+  );
+}
+
+#[test]
+fn drop_shadow_parse_error() {
+  for input in [
+    "drop-shadow(2px)",
+    "drop-shadow(2px 2px 2px 2px)",
+    "drop-shadow(2px 2px -2px)",
+    "drop-shadow(2px red 2px)",
+    "drop-shadow(red 2px 2px blue)",
+    "drop-shadow(2px 2px notacolor)",
+    "drop-shadow(2px 2px rgb(0, 0, 0)",
+  ] {
+    assert!(drop_shadow_parser(input).is_err(), "{}", input);
+  }
 }
 
 #[test]
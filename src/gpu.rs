@@ -0,0 +1,284 @@
+// Options of the GPU canvases, the GPU context shared by them, and the textures shared with the other APIs
+
+use std::str::FromStr;
+
+use napi::bindgen_prelude::*;
+
+use crate::error::SkError;
+use crate::sk::{
+  DirectContext, ExternalTexture as SkExternalTexture, GpuBackend, DRM_FORMAT_MOD_INVALID,
+};
+use crate::CanvasOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuDevice {
+  /// Index in the physical devices enumerated by Vulkan
+  Index(u32),
+  /// `VkPhysicalDeviceIDProperties::deviceUUID`, as printed by `vulkaninfo`
+  Uuid([u8; 16]),
+}
+
+impl FromStr for GpuDevice {
+  type Err = SkError;
+
+  /// The hyphens of the UUID are optional, `0` and the other decimal strings are the indexes
+  fn from_str(value: &str) -> Result<Self, SkError> {
+    if let Ok(index) = value.parse::<u32>() {
+      return Ok(Self::Index(index));
+    }
+    let digits = value.chars().filter(|c| *c != '-').collect::<Vec<_>>();
+    let mut uuid = [0u8; 16];
+    if digits.len() != uuid.len() * 2 {
+      return Err(SkError::Generic(format!("Invalid GPU device: {}", value)));
+    }
+    for (byte, pair) in uuid.iter_mut().zip(digits.chunks(2)) {
+      match (pair[0].to_digit(16), pair[1].to_digit(16)) {
+        (Some(high), Some(low)) => *byte = (high << 4 | low) as u8,
+        _ => return Err(SkError::Generic(format!("Invalid GPU device: {}", value))),
+      }
+    }
+    Ok(Self::Uuid(uuid))
+  }
+}
+
+/// `None` if the canvas is rendered on the CPU
+pub(crate) fn gpu_options(
+  options: &CanvasOptions,
+) -> Result<Option<(GpuBackend, Option<GpuDevice>)>> {
+  let backend = match &options.gpu {
+    Some(Either::A(true)) => Some(GpuBackend::Auto),
+    Some(Either::B(backend)) => Some(GpuBackend::from_str(backend)?),
+    _ => None,
+  };
+  let device = match &options.device {
+    Some(_) if backend != Some(GpuBackend::Vulkan) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The GPU device can only be selected by the vulkan backend".to_owned(),
+      ))
+    }
+    Some(Either::A(index)) => Some(GpuDevice::Index(*index)),
+    Some(Either::B(uuid)) => Some(GpuDevice::from_str(uuid)?),
+    None => None,
+  };
+  Ok(backend.map(|backend| (backend, device)))
+}
+
+/// The dmabuf or the IOSurface shared with the video decoders and the compositors without copying it
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct ExternalTexture {
+  /// `dmabuf` on Linux, or `iosurface` on macOS
+  #[napi(js_name = "type")]
+  pub kind: String,
+  /// The file descriptor of the dmabuf, the imported one is not closed by the canvas
+  pub fd: Option<i32>,
+  /// DRM fourcc code of the dmabuf, default is `AB24`, the RGBA bytes
+  pub fourcc: Option<String>,
+  /// Bytes of a row of the dmabuf
+  pub stride: Option<u32>,
+  pub offset: Option<u32>,
+  /// DRM format modifier of the dmabuf, decimal or `0x` prefixed hex, the layout is implied by the driver if unset
+  pub modifier: Option<String>,
+  /// `IOSurfaceGetID` of the IOSurface
+  pub io_surface: Option<u32>,
+}
+
+impl ExternalTexture {
+  fn parse(&self) -> Result<SkExternalTexture> {
+    match self.kind.as_str() {
+      "dmabuf" => Ok(SkExternalTexture::DmaBuf {
+        fd: self.fd.ok_or_else(|| missing_field("fd"))?,
+        fourcc: parse_fourcc(self.fourcc.as_deref().unwrap_or("AB24"))?,
+        stride: self.stride.ok_or_else(|| missing_field("stride"))?,
+        offset: self.offset.unwrap_or(0),
+        modifier: self
+          .modifier
+          .as_deref()
+          .map(parse_modifier)
+          .transpose()?
+          .unwrap_or(DRM_FORMAT_MOD_INVALID),
+      }),
+      "iosurface" => Ok(SkExternalTexture::IoSurface(
+        self.io_surface.ok_or_else(|| missing_field("ioSurface"))?,
+      )),
+      kind => Err(Error::new(
+        Status::InvalidArg,
+        format!("{} is not a valid external texture type", kind),
+      )),
+    }
+  }
+}
+
+impl From<SkExternalTexture> for ExternalTexture {
+  fn from(texture: SkExternalTexture) -> Self {
+    match texture {
+      SkExternalTexture::DmaBuf {
+        fd,
+        fourcc,
+        stride,
+        offset,
+        modifier,
+      } => Self {
+        kind: "dmabuf".to_owned(),
+        fd: Some(fd),
+        fourcc: Some(fourcc.to_le_bytes().iter().map(|b| *b as char).collect()),
+        stride: Some(stride),
+        offset: Some(offset),
+        modifier: (modifier != DRM_FORMAT_MOD_INVALID).then(|| format!("{:#x}", modifier)),
+        io_surface: None,
+      },
+      SkExternalTexture::IoSurface(id) => Self {
+        kind: "iosurface".to_owned(),
+        io_surface: Some(id),
+        ..Default::default()
+      },
+    }
+  }
+}
+
+fn missing_field(field: &str) -> Error {
+  Error::new(
+    Status::InvalidArg,
+    format!("The {} of the external texture is required", field),
+  )
+}
+
+/// The 4 characters like `AB24`, the first one is the lowest byte
+fn parse_fourcc(value: &str) -> Result<u32, SkError> {
+  let bytes = value.as_bytes();
+  if bytes.len() != 4 || !value.is_ascii() {
+    return Err(SkError::Generic(format!("Invalid fourcc: {}", value)));
+  }
+  Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn parse_modifier(value: &str) -> Result<u64, SkError> {
+  match value
+    .strip_prefix("0x")
+    .or_else(|| value.strip_prefix("0X"))
+  {
+    Some(hex) => u64::from_str_radix(hex, 16),
+    None => value.parse::<u64>(),
+  }
+  .map_err(|_| SkError::Generic(format!("Invalid format modifier: {}", value)))
+}
+
+/// The texture the canvas draws into, `None` if the canvas allocates the surface itself
+pub(crate) fn external_texture(options: &CanvasOptions) -> Result<Option<SkExternalTexture>> {
+  let texture = match &options.texture {
+    Some(texture) => texture.parse()?,
+    None => return Ok(None),
+  };
+  match gpu_options(options)? {
+    Some((backend, _)) if backend != GpuBackend::Auto && backend != texture.backend() => {
+      let message = match texture {
+        SkExternalTexture::DmaBuf { .. } => "The dmabuf is only imported by the gl backend",
+        SkExternalTexture::IoSurface(_) => "The IOSurface is only imported by the metal backend",
+      };
+      Err(Error::new(Status::InvalidArg, message.to_owned()))
+    }
+    _ => Ok(Some(texture)),
+  }
+}
+
+/// The GPU context and the resource cache shared by the canvases created with the same `gpu` and `device` options
+/// on the current thread, so the tiles of a renderer don't compile the shaders and upload the textures again
+#[napi]
+pub struct GpuContext {
+  inner: DirectContext,
+}
+
+#[napi]
+impl GpuContext {
+  #[napi(constructor)]
+  pub fn new(options: Option<CanvasOptions>) -> Result<Self> {
+    let (backend, device) =
+      gpu_options(&options.unwrap_or_default())?.unwrap_or((GpuBackend::Auto, None));
+    let inner = DirectContext::get(backend, device).ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "The GPU backend is unavailable".to_owned(),
+      )
+    })?;
+    Ok(Self { inner })
+  }
+
+  /// Budget of the cached GPU resources in bytes, the least recently used ones are purged above it
+  #[napi(getter)]
+  pub fn get_resource_cache_limit(&self) -> f64 {
+    self.inner.resource_cache_limit() as f64
+  }
+
+  #[napi(setter)]
+  pub fn set_resource_cache_limit(&mut self, bytes: f64) {
+    if bytes.is_finite() && bytes >= 0.0 {
+      self.inner.set_resource_cache_limit(bytes as usize);
+    }
+  }
+
+  /// Bytes of the GPU resources in the cache, including the ones used by the canvases
+  #[napi(getter)]
+  pub fn get_resource_cache_usage(&self) -> f64 {
+    self.inner.resource_cache_usage() as f64
+  }
+
+  /// Free the cached GPU resources not used by the canvases
+  #[napi]
+  pub fn free_resources(&self) {
+    self.inner.free_resources();
+  }
+}
+
+#[test]
+fn test_device_index() {
+  assert_eq!(GpuDevice::from_str("0").unwrap(), GpuDevice::Index(0));
+  assert_eq!(GpuDevice::from_str("12").unwrap(), GpuDevice::Index(12));
+}
+
+#[test]
+fn test_device_uuid() {
+  let uuid = [
+    0x8d, 0x0c, 0x2c, 0x0a, 0x7e, 0x1f, 0x4b, 0x2a, 0x9b, 0x51, 0x11, 0x6d, 0x2f, 0xe8, 0x0a, 0xc3,
+  ];
+  assert_eq!(
+    GpuDevice::from_str("8d0c2c0a-7e1f-4b2a-9b51-116d2fe80ac3").unwrap(),
+    GpuDevice::Uuid(uuid)
+  );
+  assert_eq!(
+    GpuDevice::from_str("8D0C2C0A7E1F4B2A9B51116D2FE80AC3").unwrap(),
+    GpuDevice::Uuid(uuid)
+  );
+}
+
+#[test]
+fn test_fourcc() {
+  // DRM_FORMAT_ABGR8888 and DRM_FORMAT_ARGB8888
+  assert_eq!(parse_fourcc("AB24").unwrap(), 0x3432_4241);
+  assert_eq!(parse_fourcc("AR24").unwrap(), 0x3432_5241);
+  assert!(parse_fourcc("AB2").is_err());
+  assert!(parse_fourcc("AB244").is_err());
+}
+
+#[test]
+fn test_modifier() {
+  // I915_FORMAT_MOD_X_TILED
+  assert_eq!(
+    parse_modifier("0x0100000000000001").unwrap(),
+    0x0100_0000_0000_0001
+  );
+  assert_eq!(
+    parse_modifier("72057594037927937").unwrap(),
+    0x0100_0000_0000_0001
+  );
+  assert_eq!(parse_modifier("0").unwrap(), 0);
+  assert!(parse_modifier("0xg").is_err());
+  assert!(parse_modifier("-1").is_err());
+}
+
+#[test]
+fn test_invalid_device() {
+  assert!(GpuDevice::from_str("-1").is_err());
+  assert!(GpuDevice::from_str("8d0c2c0a-7e1f-4b2a-9b51").is_err());
+  assert!(GpuDevice::from_str("8d0c2c0a-7e1f-4b2a-9b51-116d2fe80acg").is_err());
+}
@@ -1,6 +1,7 @@
 use std::result;
+use std::str::FromStr;
 
-use cssparser::{Color as CSSColor, Parser, ParserInput};
+use cssparser::{Color as CSSColor, Parser, ParserInput, RGBA};
 use napi::bindgen_prelude::*;
 
 use crate::{
@@ -11,6 +12,200 @@ use crate::{
   },
 };
 
+/// The number of the stops inserted between 2 stops, Skia interpolates the colors in sRGB between them
+const INTERPOLATION_STEPS: usize = 16;
+
+/// The hue interpolation method of the polar color spaces, it's `shorter` by default like CSS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HueInterpolation {
+  Shorter,
+  Longer,
+  Increasing,
+  Decreasing,
+}
+
+impl FromStr for HueInterpolation {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+    match value {
+      "shorter" => Ok(Self::Shorter),
+      "longer" => Ok(Self::Longer),
+      "increasing" => Ok(Self::Increasing),
+      "decreasing" => Ok(Self::Decreasing),
+      _ => Err(SkError::TypeError(format!(
+        "{} is not a valid hue interpolation method",
+        value
+      ))),
+    }
+  }
+}
+
+/// The color space the stops are interpolated in, like the `in <color-space>` of the CSS gradients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorInterpolation {
+  Srgb,
+  SrgbLinear,
+  Oklab,
+  Oklch(HueInterpolation),
+}
+
+impl ColorInterpolation {
+  pub fn new(color_space: &str, hue: Option<&str>) -> result::Result<Self, SkError> {
+    let hue = hue.map(HueInterpolation::from_str).transpose()?;
+    match (color_space, hue) {
+      ("oklch", hue) => Ok(Self::Oklch(hue.unwrap_or(HueInterpolation::Shorter))),
+      (_, Some(_)) => Err(SkError::TypeError(format!(
+        "The hue interpolation method is only valid for the oklch color space, not {}",
+        color_space
+      ))),
+      ("srgb", None) => Ok(Self::Srgb),
+      ("srgb-linear" | "linear-rgb", None) => Ok(Self::SrgbLinear),
+      ("oklab", None) => Ok(Self::Oklab),
+      _ => Err(SkError::TypeError(format!(
+        "{} is not a supported interpolation color space",
+        color_space
+      ))),
+    }
+  }
+
+  /// The premultiplied components of the color in the color space, the hue of oklch is in degrees and not premultiplied
+  fn components(&self, color: Color) -> [f32; 4] {
+    let alpha = ((color.0 >> 24) & 0xFF) as f32 / 255.0;
+    let rgb = [
+      srgb_to_linear(((color.0 >> 16) & 0xFF) as f32 / 255.0),
+      srgb_to_linear(((color.0 >> 8) & 0xFF) as f32 / 255.0),
+      srgb_to_linear((color.0 & 0xFF) as f32 / 255.0),
+    ];
+    let [c0, c1, c2] = match self {
+      Self::Srgb | Self::SrgbLinear => rgb,
+      Self::Oklab => linear_srgb_to_oklab(rgb),
+      Self::Oklch(_) => {
+        let [l, a, b] = linear_srgb_to_oklab(rgb);
+        [l, a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0)]
+      }
+    };
+    if matches!(self, Self::Oklch(_)) {
+      [c0 * alpha, c1 * alpha, c2, alpha]
+    } else {
+      [c0 * alpha, c1 * alpha, c2 * alpha, alpha]
+    }
+  }
+
+  fn color(&self, components: [f32; 4]) -> Color {
+    let [c0, c1, c2, alpha] = components;
+    if alpha <= 0.0 {
+      return Color::from_rgba(0, 0, 0, 0);
+    }
+    let rgb = match self {
+      Self::Srgb | Self::SrgbLinear => [c0 / alpha, c1 / alpha, c2 / alpha],
+      Self::Oklab => oklab_to_linear_srgb([c0 / alpha, c1 / alpha, c2 / alpha]),
+      Self::Oklch(_) => {
+        let (sin, cos) = c2.to_radians().sin_cos();
+        let chroma = c1 / alpha;
+        oklab_to_linear_srgb([c0 / alpha, chroma * cos, chroma * sin])
+      }
+    };
+    let [r, g, b] = rgb.map(|c| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8);
+    Color::from_rgba(r, g, b, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+  }
+
+  /// Make the hues of the 2 stops interpolate in the hue interpolation method
+  fn fix_hues(&self, start: &mut [f32; 4], end: &mut [f32; 4]) {
+    let hue = match self {
+      Self::Oklch(hue) => hue,
+      _ => return,
+    };
+    // the hue of the achromatic color is powerless, it takes the hue of the other stop
+    let powerless = |components: &[f32; 4]| components[1] <= components[3] * 1e-4;
+    if powerless(start) {
+      start[2] = end[2];
+    } else if powerless(end) {
+      end[2] = start[2];
+    }
+    let diff = end[2] - start[2];
+    match hue {
+      HueInterpolation::Shorter if diff > 180.0 => start[2] += 360.0,
+      HueInterpolation::Shorter if diff < -180.0 => end[2] += 360.0,
+      HueInterpolation::Longer if diff > 0.0 && diff < 180.0 => start[2] += 360.0,
+      HueInterpolation::Longer if diff > -180.0 && diff <= 0.0 => end[2] += 360.0,
+      HueInterpolation::Increasing if diff < 0.0 => end[2] += 360.0,
+      HueInterpolation::Decreasing if diff > 0.0 => start[2] += 360.0,
+      _ => {}
+    }
+  }
+
+  /// Insert the stops interpolated in the color space between the stops
+  fn expand_stops(&self, base: &mut SkGradient) {
+    if *self == Self::Srgb || base.colors.len() < 2 {
+      return;
+    }
+    let capacity = base.colors.len() * INTERPOLATION_STEPS;
+    let mut positions = Vec::with_capacity(capacity);
+    let mut colors = Vec::with_capacity(capacity);
+    for index in 0..base.colors.len() {
+      if index > 0 && base.positions[index] > base.positions[index - 1] {
+        let (from, to) = (base.positions[index - 1], base.positions[index]);
+        let mut start = self.components(base.colors[index - 1]);
+        let mut end = self.components(base.colors[index]);
+        self.fix_hues(&mut start, &mut end);
+        for step in 1..INTERPOLATION_STEPS {
+          let t = step as f32 / INTERPOLATION_STEPS as f32;
+          let mut components = [0f32; 4];
+          for (i, component) in components.iter_mut().enumerate() {
+            *component = start[i] + (end[i] - start[i]) * t;
+          }
+          positions.push(from + (to - from) * t);
+          colors.push(self.color(components));
+        }
+      }
+      positions.push(base.positions[index]);
+      colors.push(base.colors[index]);
+    }
+    base.positions = positions;
+    base.colors = colors;
+  }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+  if c <= 0.0031308 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+/// [A perceptual color space for image processing](https://bottosson.github.io/posts/oklab/)
+fn linear_srgb_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+  let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+  let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+  let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+  [
+    0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+    1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+    0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+  ]
+}
+
+fn oklab_to_linear_srgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+  let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+  let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+  let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+  [
+    4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+    -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+    -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+  ]
+}
+
 #[derive(Debug, Clone)]
 pub enum Gradient {
   Linear(LinearGradient),
@@ -95,6 +290,18 @@ impl Gradient {
     }
   }
 
+  pub fn set_tile_mode(&mut self, tile_mode: TileMode) {
+    self.base_mut().tile_mode = tile_mode;
+  }
+
+  fn base_mut(&mut self) -> &mut SkGradient {
+    match self {
+      Self::Linear(linear_gradient) => &mut linear_gradient.base,
+      Self::Radial(radial_gradient) => &mut radial_gradient.base,
+      Self::Conic(conic_gradient) => &mut conic_gradient.base,
+    }
+  }
+
   /// Transform is [3 x 3] matrix, but stored in 2d array:
   /// | A B C |
   /// | D E F |
@@ -155,7 +362,29 @@ impl Gradient {
 }
 
 #[napi]
-pub struct CanvasGradient(pub(crate) Gradient);
+pub struct CanvasGradient {
+  inner: Gradient,
+  interpolation: ColorInterpolation,
+  /// The `currentColor` of the context when the gradient is created
+  current_color: Option<RGBA>,
+}
+
+impl CanvasGradient {
+  pub(crate) fn new(inner: Gradient, current_color: Option<RGBA>) -> Self {
+    Self {
+      inner,
+      interpolation: ColorInterpolation::Srgb,
+      current_color,
+    }
+  }
+
+  /// The gradient with the stops interpolated in the interpolation color space
+  pub(crate) fn interpolated_gradient(&self) -> Gradient {
+    let mut gradient = self.inner.clone();
+    self.interpolation.expand_stops(gradient.base_mut());
+    gradient
+  }
+}
 
 #[napi]
 impl CanvasGradient {
@@ -173,16 +402,45 @@ impl CanvasGradient {
         format!("Parse color [{}] error: {:?}", color_str, e),
       )
     })?;
-    let skia_color = match color {
-      CSSColor::CurrentColor => {
-        return Err(Error::new(
+    let rgba = match color {
+      CSSColor::CurrentColor => self.current_color.ok_or_else(|| {
+        Error::new(
           Status::InvalidArg,
           "Gradient stop color should not be `currentcolor` keyword".to_owned(),
-        ))
+        )
+      })?,
+      CSSColor::RGBA(rgba) => rgba,
+    };
+    let skia_color = Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha);
+    self.inner.add_color_stop(index as f32, skia_color);
+    Ok(())
+  }
+
+  /// Non-standard, how the colors are drawn outside of the gradient, like the `spreadMethod` of the SVG gradients
+  #[napi]
+  pub fn set_spread_method(&mut self, spread: String) -> Result<()> {
+    let tile_mode = match spread.as_str() {
+      "pad" => TileMode::Clamp,
+      "repeat" => TileMode::Repeat,
+      "reflect" => TileMode::Mirror,
+      _ => {
+        return Err(
+          SkError::TypeError(format!(
+            "The spread method should be 'pad', 'repeat' or 'reflect', but {} is given",
+            spread
+          ))
+          .into(),
+        )
       }
-      CSSColor::RGBA(rgba) => Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha),
     };
-    self.0.add_color_stop(index as f32, skia_color);
+    self.inner.set_tile_mode(tile_mode);
+    Ok(())
+  }
+
+  /// Non-standard, the color space the colors are interpolated in, like `linear-gradient(in oklch longer hue, ...)`
+  #[napi]
+  pub fn set_interpolation(&mut self, color_space: String, hue: Option<String>) -> Result<()> {
+    self.interpolation = ColorInterpolation::new(&color_space, hue.as_deref())?;
     Ok(())
   }
 }
@@ -209,3 +467,59 @@ fn test_add_color_stop() {
     unreachable!();
   }
 }
+
+#[test]
+fn test_color_interpolation() {
+  assert_eq!(
+    ColorInterpolation::new("oklch", None).unwrap(),
+    ColorInterpolation::Oklch(HueInterpolation::Shorter)
+  );
+  assert_eq!(
+    ColorInterpolation::new("linear-rgb", None).unwrap(),
+    ColorInterpolation::SrgbLinear
+  );
+  assert!(ColorInterpolation::new("oklab", Some("longer")).is_err());
+  assert!(ColorInterpolation::new("hsl", None).is_err());
+  // the colors are converted back without the loss
+  for interpolation in [
+    ColorInterpolation::Oklab,
+    ColorInterpolation::new("oklch", None).unwrap(),
+  ] {
+    for color in [
+      Color::from_rgba(255, 0, 0, 255),
+      Color::from_rgba(12, 200, 99, 128),
+      Color::from_rgba(255, 255, 255, 255),
+    ] {
+      assert_eq!(interpolation.color(interpolation.components(color)), color);
+    }
+  }
+}
+
+#[test]
+fn test_expand_stops() {
+  let mut gradient = Gradient::create_linear_gradient(0.0, 0.0, 100.0, 0.0);
+  gradient.add_color_stop(0.0, Color::from_rgba(0, 0, 0, 255));
+  gradient.add_color_stop(0.5, Color::from_rgba(255, 255, 255, 255));
+  gradient.add_color_stop(0.5, Color::from_rgba(255, 0, 0, 255));
+  let mut base = gradient.base_mut().clone();
+  ColorInterpolation::SrgbLinear.expand_stops(&mut base);
+  // no stops are inserted between the stops at the same offset
+  assert_eq!(base.positions.len(), INTERPOLATION_STEPS + 2);
+  assert_eq!(base.positions[INTERPOLATION_STEPS / 2], 0.25);
+  // 0.5 in the linear sRGB
+  assert_eq!(
+    base.colors[INTERPOLATION_STEPS / 2],
+    Color::from_rgba(188, 188, 188, 255)
+  );
+}
+
+#[test]
+fn test_set_tile_mode() {
+  let mut radial_gradient = Gradient::create_radial_gradient(0.0, 0.0, 0.0, 0.0, 0.0, 10.0);
+  radial_gradient.set_tile_mode(TileMode::Mirror);
+  if let Gradient::Radial(radial_gradient) = radial_gradient {
+    assert_eq!(radial_gradient.base.tile_mode, TileMode::Mirror);
+  } else {
+    unreachable!();
+  }
+}
@@ -0,0 +1,245 @@
+// Embed ICC profiles into the encoded PNG, JPEG and WebP data, the color space information written by the Skia encoders is replaced.
+// https://www.w3.org/TR/png/#11iCCP
+// https://www.color.org/specification/ICC1v43_2010-12.pdf B.4 Embedding ICC profiles in JFIF files
+// https://developers.google.com/speed/webp/docs/riff_container#extended_file_format
+
+use crate::error::SkError;
+use crate::jpeg;
+use crate::png;
+
+const JPEG_ICC_MARKER: &[u8] = b"ICC_PROFILE\0";
+// 65535 bytes minus the length field and the ICC_PROFILE header
+const JPEG_ICC_CHUNK_SIZE: usize = 65519;
+const WEBP_ICC_FLAG: u8 = 0x20;
+const WEBP_ALPHA_FLAG: u8 = 0x10;
+
+/// `deflate` compresses the profile into a zlib stream for PNG
+pub fn embed_icc(
+  data: &[u8],
+  profile: &[u8],
+  deflate: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Result<Vec<u8>, SkError> {
+  if data.starts_with(png::SIGNATURE) {
+    embed_png(data, profile, deflate)
+  } else if data.starts_with(&[0xff, 0xd8]) {
+    embed_jpeg(data, profile)
+  } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+    embed_webp(data, profile)
+  } else {
+    Err(SkError::Generic(
+      "ICC profiles could only be embedded in PNG, JPEG and WebP".to_owned(),
+    ))
+  }
+}
+
+fn embed_png(
+  data: &[u8],
+  profile: &[u8],
+  deflate: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Result<Vec<u8>, SkError> {
+  let compressed =
+    deflate(profile).ok_or_else(|| SkError::Generic("Compress ICC profile failed".to_owned()))?;
+  // profile name, null separator and the compression method
+  let mut iccp = b"ICC Profile\0\0".to_vec();
+  iccp.extend_from_slice(&compressed);
+  let mut output = png::SIGNATURE.to_vec();
+  for (chunk_type, chunk_data) in png::read_chunks(data)? {
+    match &chunk_type {
+      // superseded by iCCP
+      b"iCCP" | b"sRGB" | b"gAMA" | b"cHRM" => {}
+      b"IHDR" => {
+        png::write_chunk(&mut output, &chunk_type, chunk_data);
+        png::write_chunk(&mut output, b"iCCP", &iccp);
+      }
+      _ => png::write_chunk(&mut output, &chunk_type, chunk_data),
+    }
+  }
+  Ok(output)
+}
+
+fn embed_jpeg(data: &[u8], profile: &[u8]) -> Result<Vec<u8>, SkError> {
+  let chunks = profile.chunks(JPEG_ICC_CHUNK_SIZE).collect::<Vec<&[u8]>>();
+  if chunks.is_empty() || chunks.len() > 255 {
+    return Err(SkError::Generic("Invalid ICC profile size".to_owned()));
+  }
+  let (segments, scan) = jpeg::read_segments(data)?;
+  let mut output = data[..2].to_vec();
+  let mut inserted = false;
+  for (marker, segment) in segments {
+    // after JFIF APP0 and EXIF APP1, which must come first
+    if !inserted && marker != 0xe0 && marker != 0xe1 {
+      write_jpeg_icc(&mut output, &chunks);
+      inserted = true;
+    }
+    if !(marker == 0xe2 && segment[4..].starts_with(JPEG_ICC_MARKER)) {
+      output.extend_from_slice(segment);
+    }
+  }
+  if !inserted {
+    write_jpeg_icc(&mut output, &chunks);
+  }
+  // the entropy coded data follows SOS, copy the rest as is
+  output.extend_from_slice(scan);
+  Ok(output)
+}
+
+fn write_jpeg_icc(output: &mut Vec<u8>, chunks: &[&[u8]]) {
+  for (i, chunk) in chunks.iter().enumerate() {
+    output.extend_from_slice(&[0xff, 0xe2]);
+    output.extend_from_slice(&((2 + JPEG_ICC_MARKER.len() + 2 + chunk.len()) as u16).to_be_bytes());
+    output.extend_from_slice(JPEG_ICC_MARKER);
+    output.extend_from_slice(&[i as u8 + 1, chunks.len() as u8]);
+    output.extend_from_slice(chunk);
+  }
+}
+
+fn embed_webp(data: &[u8], profile: &[u8]) -> Result<Vec<u8>, SkError> {
+  let invalid = || SkError::Generic("Invalid webp data".to_owned());
+  let mut chunks = Vec::new();
+  let mut offset = 12;
+  while offset + 8 <= data.len() {
+    let size = u32::from_le_bytes([
+      data[offset + 4],
+      data[offset + 5],
+      data[offset + 6],
+      data[offset + 7],
+    ]) as usize;
+    let end = offset + 8 + size;
+    if end > data.len() {
+      return Err(invalid());
+    }
+    let mut fourcc = [0u8; 4];
+    fourcc.copy_from_slice(&data[offset..offset + 4]);
+    chunks.push((fourcc, &data[offset + 8..end]));
+    // chunks are padded to even sizes
+    offset = end + (size & 1);
+  }
+  let vp8x = match chunks.first() {
+    Some((fourcc, chunk_data)) if fourcc == b"VP8X" && chunk_data.len() >= 10 => {
+      let mut vp8x = chunk_data.to_vec();
+      vp8x[0] |= WEBP_ICC_FLAG;
+      vp8x
+    }
+    Some((fourcc, chunk_data)) if fourcc == b"VP8L" && chunk_data.len() >= 5 => {
+      // 14 bits width - 1, 14 bits height - 1 and the alpha_is_used bit after the signature byte
+      let bits = u32::from_le_bytes([chunk_data[1], chunk_data[2], chunk_data[3], chunk_data[4]]);
+      let alpha = if bits & (1 << 28) != 0 {
+        WEBP_ALPHA_FLAG
+      } else {
+        0
+      };
+      vp8x_chunk(WEBP_ICC_FLAG | alpha, bits & 0x3fff, (bits >> 14) & 0x3fff)
+    }
+    Some((fourcc, chunk_data)) if fourcc == b"VP8 " && chunk_data.len() >= 10 => {
+      // the 14 bits width and height after the frame tag and the start code
+      let width = u16::from_le_bytes([chunk_data[6], chunk_data[7]]) as u32 & 0x3fff;
+      let height = u16::from_le_bytes([chunk_data[8], chunk_data[9]]) as u32 & 0x3fff;
+      vp8x_chunk(
+        WEBP_ICC_FLAG,
+        width.saturating_sub(1),
+        height.saturating_sub(1),
+      )
+    }
+    _ => return Err(invalid()),
+  };
+  let mut output = b"RIFF\0\0\0\0WEBP".to_vec();
+  write_webp_chunk(&mut output, b"VP8X", &vp8x);
+  write_webp_chunk(&mut output, b"ICCP", profile);
+  for (fourcc, chunk_data) in chunks.iter() {
+    if fourcc != b"VP8X" && fourcc != b"ICCP" {
+      write_webp_chunk(&mut output, fourcc, chunk_data);
+    }
+  }
+  let riff_size = (output.len() - 8) as u32;
+  output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+  Ok(output)
+}
+
+fn vp8x_chunk(flags: u8, width_minus_one: u32, height_minus_one: u32) -> Vec<u8> {
+  let mut vp8x = vec![flags, 0, 0, 0];
+  vp8x.extend_from_slice(&width_minus_one.to_le_bytes()[..3]);
+  vp8x.extend_from_slice(&height_minus_one.to_le_bytes()[..3]);
+  vp8x
+}
+
+fn write_webp_chunk(output: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+  output.extend_from_slice(fourcc);
+  output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+  output.extend_from_slice(data);
+  if data.len() & 1 == 1 {
+    output.push(0);
+  }
+}
+
+#[test]
+fn test_embed_png() {
+  let mut data = png::SIGNATURE.to_vec();
+  png::write_chunk(&mut data, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+  png::write_chunk(&mut data, b"sRGB", &[0]);
+  png::write_chunk(&mut data, b"IDAT", b"pixels");
+  png::write_chunk(&mut data, b"IEND", &[]);
+  let output = embed_icc(&data, b"profile", |profile| Some(profile.to_vec())).unwrap();
+  let chunks = png::read_chunks(&output).unwrap();
+  let types = chunks
+    .iter()
+    .map(|(chunk_type, _)| chunk_type)
+    .collect::<Vec<_>>();
+  assert_eq!(types, vec![b"IHDR", b"iCCP", b"IDAT", b"IEND"]);
+  assert_eq!(chunks[1].1, b"ICC Profile\0\0profile");
+}
+
+#[test]
+fn test_embed_jpeg() {
+  let mut data = vec![0xff, 0xd8];
+  // APP0, an existing ICC profile and DQT
+  data.extend_from_slice(&[0xff, 0xe0, 0, 4, 1, 2]);
+  data.extend_from_slice(&[0xff, 0xe2, 0, 17]);
+  data.extend_from_slice(JPEG_ICC_MARKER);
+  data.extend_from_slice(&[1, 1, 9]);
+  data.extend_from_slice(&[0xff, 0xdb, 0, 3, 7]);
+  data.extend_from_slice(&[0xff, 0xda, 0, 2, 5, 5, 0xff, 0xd9]);
+  let output = embed_icc(&data, b"icc", |_| None).unwrap();
+  let mut expected = vec![0xff, 0xd8, 0xff, 0xe0, 0, 4, 1, 2, 0xff, 0xe2, 0, 19];
+  expected.extend_from_slice(JPEG_ICC_MARKER);
+  expected.extend_from_slice(&[1, 1]);
+  expected.extend_from_slice(b"icc");
+  expected.extend_from_slice(&[0xff, 0xdb, 0, 3, 7, 0xff, 0xda, 0, 2, 5, 5, 0xff, 0xd9]);
+  assert_eq!(output, expected);
+
+  // split into multiple APP2 segments
+  let large = vec![1u8; JPEG_ICC_CHUNK_SIZE + 1];
+  let output = embed_icc(&data, &large, |_| None).unwrap();
+  assert_eq!(&output[8..10], &[0xff, 0xe2]);
+  assert_eq!(&output[24..26], &[1, 2]);
+  let second = 8 + 2 + 65535;
+  assert_eq!(&output[second..second + 4], &[0xff, 0xe2, 0, 17]);
+  assert_eq!(&output[second + 16..second + 18], &[2, 2]);
+}
+
+#[test]
+fn test_embed_webp() {
+  // VP8L 3x2 with alpha
+  let bits: u32 = 2 | (1 << 14) | (1 << 28);
+  let mut vp8l = vec![0x2f];
+  vp8l.extend_from_slice(&bits.to_le_bytes());
+  let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+  write_webp_chunk(&mut data, b"VP8L", &vp8l);
+  let output = embed_icc(&data, b"icc", |_| None).unwrap();
+  assert_eq!(
+    u32::from_le_bytes([output[4], output[5], output[6], output[7]]) as usize,
+    output.len() - 8
+  );
+  assert_eq!(&output[12..16], b"VP8X");
+  assert_eq!(&output[20..30], &[0x30, 0, 0, 0, 2, 0, 0, 1, 0, 0]);
+  assert_eq!(&output[30..34], b"ICCP");
+  // padded to even size
+  assert_eq!(&output[38..42], b"icc\0");
+  assert_eq!(&output[42..46], b"VP8L");
+
+  // the existing profile is replaced
+  let replaced = embed_icc(&output, b"new profile", |_| None).unwrap();
+  assert_eq!(&replaced[20..21], &[0x30]);
+  assert_eq!(&replaced[38..49], b"new profile");
+  assert_eq!(&replaced[50..54], b"VP8L");
+  assert!(embed_icc(b"GIF89a", b"icc", |_| None).is_err());
+}
@@ -4,8 +4,13 @@ use std::str::FromStr;
 use base64::decode;
 use napi::{bindgen_prelude::*, NapiValue};
 
+use crate::error::SkError;
+use crate::resize::{resize_rgba, rgba_len, ResizeFilter, ResizeFit};
 use crate::sk::Bitmap;
 use crate::sk::ColorSpace;
+use crate::sk::{AlphaType, ColorType};
+use crate::sk::{DecodeStatus, ImageDecoder as SkImageDecoder};
+use crate::tga;
 
 #[napi]
 pub struct ImageData {
@@ -17,7 +22,69 @@ pub struct ImageData {
 
 #[napi(object)]
 pub struct Settings {
-  pub color_space: String,
+  pub color_space: Option<String>,
+  /// Wrap the passed `Uint8ClampedArray` instead of copying it, used by `ImageData.from`
+  pub copy: Option<bool>,
+}
+
+impl Settings {
+  pub(crate) fn color_space(&self) -> std::result::Result<Option<ColorSpace>, SkError> {
+    self
+      .color_space
+      .as_deref()
+      .map(parse_color_space)
+      .transpose()
+  }
+}
+
+/// The settings of `getImageData`, `colorType` and `premultiplied` are non-standard.
+/// The pixels read in the other formats are for the native consumers, `putImageData` treats them as RGBA unpremultiplied
+#[napi(object)]
+pub struct GetImageDataSettings {
+  pub color_space: Option<String>,
+  /// `rgba` or `bgra`, `rgba` by default
+  pub color_type: Option<String>,
+  /// `false` by default
+  pub premultiplied: Option<bool>,
+}
+
+impl GetImageDataSettings {
+  pub(crate) fn color_space(&self) -> std::result::Result<Option<ColorSpace>, SkError> {
+    self
+      .color_space
+      .as_deref()
+      .map(parse_color_space)
+      .transpose()
+  }
+
+  pub(crate) fn pixel_format(&self) -> Result<(ColorType, AlphaType)> {
+    let color_type = match self.color_type.as_deref() {
+      None | Some("rgba") => ColorType::RGBA8888,
+      Some("bgra") => ColorType::BGRA8888,
+      Some(color_type) => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("{} is not valid image data color type", color_type),
+        ))
+      }
+    };
+    let alpha_type = if self.premultiplied.unwrap_or(false) {
+      AlphaType::Premultiplied
+    } else {
+      AlphaType::Unpremultiplied
+    };
+    Ok((color_type, alpha_type))
+  }
+}
+
+/// `srgb` or `display-p3`, the other values are a TypeError like the `PredefinedColorSpace` enum
+pub(crate) fn parse_color_space(color_space: &str) -> std::result::Result<ColorSpace, SkError> {
+  ColorSpace::from_str(color_space).map_err(|_| {
+    SkError::TypeError(format!(
+      "The color space {:?} is not srgb or display-p3",
+      color_space
+    ))
+  })
 }
 
 #[napi]
@@ -34,10 +101,8 @@ impl ImageData {
     match width_or_data {
       Either::A(width) => {
         let height = width_or_height;
-        let color_space = match height_or_settings {
-          Some(Either::B(settings)) => {
-            ColorSpace::from_str(&settings.color_space).unwrap_or_default()
-          }
+        let color_space = match &height_or_settings {
+          Some(Either::B(settings)) => settings.color_space()?.unwrap_or_default(),
           _ => ColorSpace::default(),
         };
         let arraybuffer_length = (width * height * 4) as usize;
@@ -61,7 +126,7 @@ impl ImageData {
           data: data_ptr,
         })
       }
-      Either::B(data_object) => {
+      Either::B(mut data_object) => {
         let input_data_length = data_object.len();
         let width = width_or_height;
         let height = match &height_or_settings {
@@ -74,23 +139,37 @@ impl ImageData {
             "Index or size is negative or greater than the allowed amount".to_owned(),
           ));
         }
-        // https://developer.mozilla.org/en-US/docs/Web/API/CanvasRenderingContext2D/createImageData
-        // An existing ImageData object from which to copy the width and height.
-        let mut cloned_data = Uint8ClampedArray::new(data_object.to_vec());
-        let data = cloned_data.as_mut_ptr();
-        this.define_properties(&[Property::new("data")?
-          .with_value(&unsafe {
-            Object::from_raw_unchecked(
-              env.raw(),
-              Uint8ClampedArray::to_napi_value(env.raw(), cloned_data)?,
-            )
+        // the settings are the third argument without the height
+        let settings = match &height_or_settings {
+          Some(Either::B(settings)) => Some(settings),
+          _ => maybe_settings.as_ref(),
+        };
+        let copy = settings.and_then(|settings| settings.copy).unwrap_or(true);
+        let color_space = settings
+          .map(Settings::color_space)
+          .transpose()?
+          .flatten()
+          .unwrap_or_default();
+        let (data, data_value) = if copy {
+          // https://developer.mozilla.org/en-US/docs/Web/API/CanvasRenderingContext2D/createImageData
+          // An existing ImageData object from which to copy the width and height.
+          let mut cloned_data = Uint8ClampedArray::new(data_object.to_vec());
+          let data = cloned_data.as_mut_ptr();
+          (data, unsafe {
+            Uint8ClampedArray::to_napi_value(env.raw(), cloned_data)?
           })
+        } else {
+          // The pixels stay in the caller's ArrayBuffer, which is kept alive by the `data` property
+          let data = data_object.as_mut_ptr();
+          (data, unsafe {
+            Uint8ClampedArray::to_napi_value(env.raw(), data_object)?
+          })
+        };
+        this.define_properties(&[Property::new("data")?
+          .with_value(&unsafe { Object::from_raw_unchecked(env.raw(), data_value) })
           .with_property_attributes(
             PropertyAttributes::Enumerable | PropertyAttributes::Configurable,
           )])?;
-        let color_space = maybe_settings
-          .and_then(|settings| ColorSpace::from_str(&settings.color_space).ok())
-          .unwrap_or_default();
         Ok(ImageData {
           width: width as usize,
           height: height as usize,
@@ -110,6 +189,46 @@ impl ImageData {
   pub fn get_height(&self) -> u32 {
     self.height as u32
   }
+
+  #[napi(getter)]
+  pub fn get_color_space(&self) -> String {
+    self.color_space.as_str().to_owned()
+  }
+}
+
+impl ImageData {
+  /// Wrap the unpremultiplied RGBA pixels read from the canvas without copying them
+  pub(crate) fn from_pixels(
+    env: Env,
+    mut pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    color_space: ColorSpace,
+  ) -> Result<ClassInstance<ImageData>> {
+    let data = pixels.as_mut_ptr();
+    let data_object = unsafe {
+      Object::from_raw_unchecked(
+        env.raw(),
+        Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(pixels))?,
+      )
+    };
+    let instance = ImageData {
+      width,
+      height,
+      color_space,
+      data,
+    }
+    .into_instance(env)?;
+    let mut image_instance = unsafe { Object::from_raw_unchecked(env.raw(), instance.raw()) };
+    image_instance.set("data", data_object)?;
+    Ok(instance)
+  }
+}
+
+#[napi(object)]
+pub struct ResizeOptions {
+  pub fit: Option<String>,
+  pub filter: Option<String>,
 }
 
 #[napi]
@@ -122,6 +241,7 @@ pub struct Image {
   pub(crate) need_regenerate_bitmap: bool,
   pub(crate) is_svg: bool,
   pub(crate) color_space: ColorSpace,
+  color_space_conversion: bool,
   pub(crate) src: Option<Buffer>,
 }
 
@@ -143,6 +263,7 @@ impl Image {
       need_regenerate_bitmap: false,
       is_svg: false,
       color_space,
+      color_space_conversion: true,
       src: None,
     })
   }
@@ -191,6 +312,26 @@ impl Image {
     self.bitmap.as_ref().map(|b| b.0.height).unwrap_or(0) as f64
   }
 
+  /// Same as the `colorSpaceConversion` option of `createImageBitmap`,
+  /// `none` disables the conversion from the embedded ICC profile, must be set before `src`
+  #[napi(getter)]
+  pub fn get_color_space_conversion(&self) -> String {
+    if self.color_space_conversion {
+      "default".to_owned()
+    } else {
+      "none".to_owned()
+    }
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_color_space_conversion(&mut self, conversion: String) {
+    match conversion.as_str() {
+      "default" => self.color_space_conversion = true,
+      "none" => self.color_space_conversion = false,
+      _ => {}
+    }
+  }
+
   #[napi(getter)]
   pub fn get_complete(&self) -> bool {
     self.complete
@@ -261,15 +402,12 @@ impl Image {
         if let Some(base64_str) = data_str.split(',').last() {
           let image_binary = decode(base64_str)
             .map_err(|e| Error::new(Status::InvalidArg, format!("Decode data url failed {}", e)))?;
-          Some(Bitmap::from_buffer(
-            image_binary.as_ptr() as *mut u8,
-            image_binary.len(),
-          ))
+          self.decode_bitmap(&image_binary)
         } else {
           None
         }
       } else {
-        Some(Bitmap::from_buffer(data.as_ptr() as *mut u8, length))
+        self.decode_bitmap(data_ref)
       };
       if let Some(ref b) = bitmap {
         if (self.width - -1.0).abs() < f64::EPSILON {
@@ -290,6 +428,91 @@ impl Image {
     Ok(())
   }
 
+  #[napi]
+  pub fn resize(
+    &mut self,
+    env: Env,
+    width: u32,
+    height: u32,
+    options: Option<ResizeOptions>,
+  ) -> Result<ClassInstance<Image>> {
+    if width == 0 || height == 0 {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The width and height must be greater than 0".to_owned(),
+      ));
+    }
+    if rgba_len(width, height).is_none() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("The resized size {}x{} is too large", width, height),
+      ));
+    }
+    let (fit, filter) = match options {
+      Some(options) => (
+        options
+          .fit
+          .map(|fit| ResizeFit::from_str(&fit))
+          .transpose()?
+          .unwrap_or_default(),
+        options
+          .filter
+          .map(|filter| ResizeFilter::from_str(&filter))
+          .transpose()?
+          .unwrap_or_default(),
+      ),
+      None => (ResizeFit::default(), ResizeFilter::default()),
+    };
+    self.regenerate_bitmap_if_need();
+    let (src_width, src_height, pixels) = self
+      .bitmap
+      .as_ref()
+      .and_then(|bitmap| bitmap.read_pixels())
+      .ok_or_else(|| Error::new(Status::GenericFailure, "Image is not loaded".to_owned()))?;
+    let resized = resize_rgba(&pixels, src_width, src_height, width, height, fit, filter);
+    let bitmap = Bitmap::from_pixels(&resized, width, height)
+      .ok_or_else(|| Error::new(Status::GenericFailure, "Create bitmap failed".to_owned()))?;
+    Image {
+      complete: true,
+      alt: self.alt.clone(),
+      width: width as f64,
+      height: height as f64,
+      bitmap: Some(bitmap),
+      need_regenerate_bitmap: false,
+      is_svg: false,
+      color_space: self.color_space,
+      color_space_conversion: self.color_space_conversion,
+      src: None,
+    }
+    .into_instance(env)
+  }
+
+  fn decode_bitmap(&self, data: &[u8]) -> Option<Bitmap> {
+    // the size hints select the closest embedded image in ICO files
+    let width = if self.width >= 0.0 {
+      self.width as i32
+    } else {
+      -1
+    };
+    let height = if self.height >= 0.0 {
+      self.height as i32
+    } else {
+      -1
+    };
+    Bitmap::from_buffer(
+      data.as_ptr() as *mut u8,
+      data.len(),
+      width,
+      height,
+      self.color_space,
+      self.color_space_conversion,
+    )
+    .or_else(|| {
+      tga::decode(data)
+        .and_then(|image| Bitmap::from_pixels(&image.pixels, image.width, image.height))
+    })
+  }
+
   pub(crate) fn regenerate_bitmap_if_need(&mut self) {
     if !self.need_regenerate_bitmap || !self.is_svg || self.src.is_none() {
       return;
@@ -305,3 +528,69 @@ impl Image {
     }
   }
 }
+
+#[napi(object)]
+pub struct DecodeProgress {
+  pub width: u32,
+  pub height: u32,
+  pub decoded_rows: u32,
+  pub complete: bool,
+}
+
+/// Decode image from the chunks pushed, used by `Image.fromStream`
+#[napi]
+pub struct ImageDecoder {
+  inner: SkImageDecoder,
+}
+
+#[napi]
+impl ImageDecoder {
+  #[napi(constructor)]
+  pub fn new() -> Self {
+    ImageDecoder {
+      inner: SkImageDecoder::new(),
+    }
+  }
+
+  #[napi]
+  pub fn push(&mut self, chunk: Buffer) -> Result<DecodeProgress> {
+    self.push_inner(chunk.as_ref(), false)
+  }
+
+  #[napi]
+  pub fn finish(&mut self, env: Env) -> Result<ClassInstance<Image>> {
+    self.push_inner(&[], true)?;
+    let bitmap = self
+      .inner
+      .take_bitmap()
+      .ok_or_else(|| Error::new(Status::GenericFailure, "Decode image failed".to_owned()))?;
+    Image {
+      complete: true,
+      alt: "".to_string(),
+      width: bitmap.0.width as f64,
+      height: bitmap.0.height as f64,
+      bitmap: Some(bitmap),
+      need_regenerate_bitmap: false,
+      is_svg: false,
+      color_space: ColorSpace::default(),
+      color_space_conversion: true,
+      src: None,
+    }
+    .into_instance(env)
+  }
+
+  fn push_inner(&mut self, chunk: &[u8], finished: bool) -> Result<DecodeProgress> {
+    let (status, progress) = self.inner.push(chunk, finished).ok_or_else(|| {
+      Error::new(
+        Status::InvalidArg,
+        "Unsupported image type or corrupted image data".to_owned(),
+      )
+    })?;
+    Ok(DecodeProgress {
+      width: progress.width as u32,
+      height: progress.height as u32,
+      decoded_rows: progress.decoded_rows as u32,
+      complete: status == DecodeStatus::Complete,
+    })
+  }
+}
@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use napi::bindgen_prelude::*;
+
+use crate::color_filter::ColorFilter;
+use crate::error::SkError;
+use crate::pattern::parse_color;
+use crate::sk::{Color, ColorChannel, ImageFilter as SkImageFilter, TileMode};
+
+/// The Skia image filter built from the other filters, it's assigned to `filter` or the layer of `saveLayer`.
+/// The missing `input` is the pixels drawn with the filter
+#[napi]
+pub struct ImageFilter {
+  pub(crate) inner: SkImageFilter,
+}
+
+impl ImageFilter {
+  fn new(inner: Option<SkImageFilter>) -> Result<Self> {
+    let inner = inner.ok_or_else(|| SkError::Generic("Make image filter failed".to_owned()))?;
+    Ok(Self { inner })
+  }
+}
+
+#[napi]
+impl ImageFilter {
+  /// The Gaussian blur, the sigmas are the standard deviations
+  #[napi]
+  pub fn blur(sigma_x: f64, sigma_y: f64, input: Option<&ImageFilter>) -> Result<ImageFilter> {
+    Self::new(SkImageFilter::make_blur(
+      sigma_x as f32,
+      sigma_y as f32,
+      TileMode::Decal,
+      input.map(|f| &f.inner),
+    ))
+  }
+
+  /// The input drawn over its shadow in the CSS color
+  #[napi]
+  pub fn drop_shadow(
+    dx: f64,
+    dy: f64,
+    sigma_x: f64,
+    sigma_y: f64,
+    color: String,
+    input: Option<&ImageFilter>,
+  ) -> Result<ImageFilter> {
+    let rgba = parse_color(&color)?;
+    Self::new(SkImageFilter::make_drop_shadow(
+      dx as f32,
+      dy as f32,
+      sigma_x as f32,
+      sigma_y as f32,
+      Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha).0,
+      input.map(|f| &f.inner),
+    ))
+  }
+
+  #[napi]
+  pub fn color_filter(
+    color_filter: &ColorFilter,
+    input: Option<&ImageFilter>,
+  ) -> Result<ImageFilter> {
+    Self::new(SkImageFilter::make_color_filter(
+      &color_filter.inner,
+      input.map(|f| &f.inner),
+    ))
+  }
+
+  #[napi]
+  pub fn offset(dx: f64, dy: f64, input: Option<&ImageFilter>) -> Result<ImageFilter> {
+    Self::new(SkImageFilter::make_offset(
+      dx as f32,
+      dy as f32,
+      input.map(|f| &f.inner),
+    ))
+  }
+
+  /// Draw the outputs of the filters over each other in order, `null` is the input pixels
+  #[napi]
+  pub fn merge(filters: Vec<Option<ClassInstance<ImageFilter>>>) -> Result<ImageFilter> {
+    let filters = filters
+      .iter()
+      .map(|filter| filter.as_ref().map(|f| &f.inner))
+      .collect::<Vec<_>>();
+    Self::new(SkImageFilter::make_merge(&filters))
+  }
+
+  /// The filter applying `inner` first, then `outer`
+  #[napi]
+  pub fn compose(outer: &ImageFilter, inner: &ImageFilter) -> Result<ImageFilter> {
+    Self::new(SkImageFilter::make_compose(&outer.inner, &inner.inner))
+  }
+
+  /// Move the pixels of the input by the `r`, `g`, `b` or `a` channels of the displacement, 0.5 doesn't move them
+  #[napi]
+  pub fn displacement_map(
+    x_channel: String,
+    y_channel: String,
+    scale: f64,
+    displacement: &ImageFilter,
+    input: Option<&ImageFilter>,
+  ) -> Result<ImageFilter> {
+    Self::new(SkImageFilter::make_displacement_map(
+      ColorChannel::from_str(&x_channel)?,
+      ColorChannel::from_str(&y_channel)?,
+      scale as f32,
+      &displacement.inner,
+      input.map(|f| &f.inner),
+    ))
+  }
+}
@@ -0,0 +1,289 @@
+// Metadata segments inserted into the JPEG data encoded by Skia, the density of the JFIF APP0 segment and an EXIF APP1 segment.
+// https://www.w3.org/Graphics/JPEG/jfif3.pdf
+// https://www.cipa.jp/std/documents/e/DC-X008-Translation-2019-E.pdf
+
+use crate::error::SkError;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JFIF_HEADER: &[u8] = b"JFIF\0";
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_RATIONAL: u16 = 5;
+
+#[derive(Debug, Default, Clone)]
+pub struct JpegMetadata {
+  /// Pixels per inch
+  pub density: Option<f64>,
+  /// 1 - 8
+  pub orientation: Option<u32>,
+  pub software: Option<String>,
+  pub artist: Option<String>,
+  pub copyright: Option<String>,
+  pub description: Option<String>,
+}
+
+impl JpegMetadata {
+  pub fn is_empty(&self) -> bool {
+    self.density.is_none()
+      && self.orientation.is_none()
+      && self.software.is_none()
+      && self.artist.is_none()
+      && self.copyright.is_none()
+      && self.description.is_none()
+  }
+
+  /// TIFF structure in big endian with only IFD0
+  fn to_exif(&self) -> Result<Vec<u8>, SkError> {
+    // (tag, type, count, value)
+    let mut entries: Vec<(u16, u16, u32, Vec<u8>)> = Vec::new();
+    let mut ascii = |tag: u16, value: &Option<String>| {
+      if let Some(value) = value {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.push(0);
+        entries.push((tag, TYPE_ASCII, bytes.len() as u32, bytes));
+      }
+    };
+    ascii(0x010e, &self.description);
+    ascii(0x0131, &self.software);
+    ascii(0x013b, &self.artist);
+    ascii(0x8298, &self.copyright);
+    if let Some(orientation) = self.orientation {
+      if !(1..=8).contains(&orientation) {
+        return Err(SkError::Generic(format!(
+          "Invalid jpeg orientation: {}",
+          orientation
+        )));
+      }
+      entries.push((
+        0x0112,
+        TYPE_SHORT,
+        1,
+        (orientation as u16).to_be_bytes().to_vec(),
+      ));
+    }
+    if let Some(density) = self.density {
+      let mut rational = Vec::with_capacity(8);
+      if density.fract() == 0.0 {
+        rational.extend_from_slice(&(density as u32).to_be_bytes());
+        rational.extend_from_slice(&1u32.to_be_bytes());
+      } else {
+        rational.extend_from_slice(&((density * 100.0).round() as u32).to_be_bytes());
+        rational.extend_from_slice(&100u32.to_be_bytes());
+      }
+      entries.push((0x011a, TYPE_RATIONAL, 1, rational.clone()));
+      entries.push((0x011b, TYPE_RATIONAL, 1, rational));
+      // inch
+      entries.push((0x0128, TYPE_SHORT, 1, 2u16.to_be_bytes().to_vec()));
+    }
+    entries.sort_by_key(|(tag, ..)| *tag);
+
+    let mut exif = EXIF_HEADER.to_vec();
+    // byte order, 42 and the offset of IFD0
+    exif.extend_from_slice(b"MM\0\x2a\0\0\0\x08");
+    let ifd_size = 2 + entries.len() * 12 + 4;
+    let mut data_area = Vec::new();
+    exif.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+    for (tag, value_type, count, value) in entries.iter() {
+      exif.extend_from_slice(&tag.to_be_bytes());
+      exif.extend_from_slice(&value_type.to_be_bytes());
+      exif.extend_from_slice(&count.to_be_bytes());
+      if value.len() <= 4 {
+        let mut inline = [0u8; 4];
+        inline[..value.len()].copy_from_slice(value);
+        exif.extend_from_slice(&inline);
+      } else {
+        // the offsets are from the start of the TIFF header, the values start at word boundaries
+        let offset = 8 + ifd_size + data_area.len();
+        exif.extend_from_slice(&(offset as u32).to_be_bytes());
+        data_area.extend_from_slice(value);
+        if data_area.len() % 2 == 1 {
+          data_area.push(0);
+        }
+      }
+    }
+    // no next IFD
+    exif.extend_from_slice(&[0; 4]);
+    exif.extend_from_slice(&data_area);
+    if exif.len() + 2 > u16::MAX as usize {
+      return Err(SkError::Generic(
+        "The jpeg metadata is too large".to_owned(),
+      ));
+    }
+    Ok(exif)
+  }
+}
+
+/// The marker and the segment including the marker and length
+pub(crate) type Segment<'a> = (u8, &'a [u8]);
+
+/// Segments before the SOS marker, and the data from SOS
+pub(crate) fn read_segments(jpeg: &[u8]) -> Result<(Vec<Segment<'_>>, &[u8]), SkError> {
+  let invalid = || SkError::Generic("Invalid jpeg data".to_owned());
+  if !jpeg.starts_with(&[0xff, 0xd8]) {
+    return Err(invalid());
+  }
+  let mut segments = Vec::new();
+  let mut offset = 2;
+  loop {
+    if offset + 4 > jpeg.len() || jpeg[offset] != 0xff {
+      return Err(invalid());
+    }
+    let marker = jpeg[offset + 1];
+    // the entropy coded data follows SOS
+    if marker == 0xda {
+      return Ok((segments, &jpeg[offset..]));
+    }
+    let length = u16::from_be_bytes([jpeg[offset + 2], jpeg[offset + 3]]) as usize;
+    let end = offset + 2 + length;
+    if length < 2 || end > jpeg.len() {
+      return Err(invalid());
+    }
+    segments.push((marker, &jpeg[offset..end]));
+    offset = end;
+  }
+}
+
+pub(crate) fn write_segment(output: &mut Vec<u8>, marker: u8, data: &[u8]) {
+  output.extend_from_slice(&[0xff, marker]);
+  output.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+  output.extend_from_slice(data);
+}
+
+/// Returns `jpeg` with the density and the EXIF segment, any existing EXIF segment is replaced
+pub fn insert_metadata(jpeg: &[u8], metadata: &JpegMetadata) -> Result<Vec<u8>, SkError> {
+  let density = match metadata.density {
+    Some(density) if !density.is_finite() || density <= 0.0 || density > u16::MAX as f64 => {
+      return Err(SkError::Generic(format!(
+        "Invalid jpeg density: {}",
+        density
+      )))
+    }
+    Some(density) => Some(density.round() as u16),
+    None => None,
+  };
+  let exif = metadata.to_exif()?;
+  let (segments, scan) = read_segments(jpeg)?;
+  let mut output = jpeg[..2].to_vec();
+  let is_jfif =
+    |(marker, segment): &(u8, &[u8])| *marker == 0xe0 && segment[4..].starts_with(JFIF_HEADER);
+  match segments.iter().find(|segment| is_jfif(segment)) {
+    Some((_, segment)) => {
+      let mut app0 = segment[4..].to_vec();
+      if let Some(density) = density {
+        // units in dots per inch, X density and Y density
+        if app0.len() < 12 {
+          return Err(SkError::Generic("Invalid jpeg data".to_owned()));
+        }
+        app0[7] = 1;
+        app0[8..10].copy_from_slice(&density.to_be_bytes());
+        app0[10..12].copy_from_slice(&density.to_be_bytes());
+      }
+      write_segment(&mut output, 0xe0, &app0);
+    }
+    None => {
+      let density = density.unwrap_or(1);
+      let mut app0 = JFIF_HEADER.to_vec();
+      // version 1.01, units, X density, Y density and no thumbnail
+      app0.extend_from_slice(&[1, 1, if metadata.density.is_some() { 1 } else { 0 }]);
+      app0.extend_from_slice(&density.to_be_bytes());
+      app0.extend_from_slice(&density.to_be_bytes());
+      app0.extend_from_slice(&[0, 0]);
+      write_segment(&mut output, 0xe0, &app0);
+    }
+  }
+  write_segment(&mut output, 0xe1, &exif);
+  for segment in segments.iter() {
+    let (marker, data) = segment;
+    if is_jfif(segment) || (*marker == 0xe1 && data[4..].starts_with(EXIF_HEADER)) {
+      continue;
+    }
+    output.extend_from_slice(data);
+  }
+  output.extend_from_slice(scan);
+  Ok(output)
+}
+
+#[cfg(test)]
+fn minimal_jpeg() -> Vec<u8> {
+  let mut jpeg = vec![0xff, 0xd8];
+  write_segment(
+    &mut jpeg,
+    0xe0,
+    b"JFIF\0\x01\x01\x00\x00\x01\x00\x01\x00\x00",
+  );
+  write_segment(&mut jpeg, 0xdb, &[1, 2, 3]);
+  jpeg.extend_from_slice(&[0xff, 0xda, 0, 2, 7, 0xff, 0xd9]);
+  jpeg
+}
+
+#[test]
+fn test_insert_density() {
+  let metadata = JpegMetadata {
+    density: Some(300.0),
+    ..Default::default()
+  };
+  let output = insert_metadata(&minimal_jpeg(), &metadata).unwrap();
+  let (segments, scan) = read_segments(&output).unwrap();
+  // dpi, 300 x 300
+  assert_eq!(&segments[0].1[11..16], &[1, 1, 44, 1, 44]);
+  assert_eq!(segments[1].0, 0xe1);
+  let exif = &segments[1].1[4..];
+  assert_eq!(&exif[..14], b"Exif\0\0MM\0\x2a\0\0\0\x08");
+  // XResolution, YResolution and ResolutionUnit
+  assert_eq!(&exif[14..16], &[0, 3]);
+  assert_eq!(&exif[16..20], &[0x01, 0x1a, 0, 5]);
+  // the rational after the IFD, 8 + 2 + 3 * 12 + 4
+  assert_eq!(&exif[24..28], &[0, 0, 0, 50]);
+  assert_eq!(&exif[56..64], &[0, 0, 1, 44, 0, 0, 0, 1]);
+  assert_eq!(&exif[40..48], &[0x01, 0x28, 0, 3, 0, 0, 0, 1]);
+  assert_eq!(&exif[48..50], &[0, 2]);
+  assert_eq!(segments[2].1, &[0xff, 0xdb, 0, 5, 1, 2, 3]);
+  assert_eq!(scan, &[0xff, 0xda, 0, 2, 7, 0xff, 0xd9]);
+}
+
+#[test]
+fn test_insert_exif() {
+  let metadata = JpegMetadata {
+    orientation: Some(6),
+    software: Some("canvas".to_owned()),
+    artist: Some("me".to_owned()),
+    ..Default::default()
+  };
+  let output = insert_metadata(&minimal_jpeg(), &metadata).unwrap();
+  let (segments, _) = read_segments(&output).unwrap();
+  // density is not changed
+  assert_eq!(&segments[0].1[11..16], &[0, 0, 1, 0, 1]);
+  let exif = &segments[1].1[4..];
+  assert_eq!(&exif[14..16], &[0, 3]);
+  // Orientation is inline
+  assert_eq!(&exif[16..28], &[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0]);
+  // Software is after the IFD
+  assert_eq!(&exif[28..36], &[0x01, 0x31, 0, 2, 0, 0, 0, 7]);
+  assert_eq!(&exif[36..40], &[0, 0, 0, 50]);
+  assert_eq!(&exif[56..63], b"canvas\0");
+  // Artist is inline
+  assert_eq!(
+    &exif[40..52],
+    &[0x01, 0x3b, 0, 2, 0, 0, 0, 3, b'm', b'e', 0, 0]
+  );
+
+  // the existing EXIF segment is replaced
+  let replaced = insert_metadata(&output, &metadata).unwrap();
+  assert_eq!(replaced, output);
+}
+
+#[test]
+fn test_reject_invalid_jpeg_metadata() {
+  let orientation = JpegMetadata {
+    orientation: Some(9),
+    ..Default::default()
+  };
+  assert!(insert_metadata(&minimal_jpeg(), &orientation).is_err());
+  let density = JpegMetadata {
+    density: Some(0.0),
+    ..Default::default()
+  };
+  assert!(insert_metadata(&minimal_jpeg(), &density).is_err());
+  assert!(insert_metadata(b"not a jpeg", &JpegMetadata::default()).is_err());
+}
@@ -8,18 +8,37 @@ extern crate napi_derive;
 #[macro_use]
 extern crate serde_derive;
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::str::FromStr;
-use std::{mem, slice};
+use std::{fs, mem, slice};
 
-use napi::bindgen_prelude::{AsyncTask, ClassInstance, Either3, This, Unknown};
+use napi::bindgen_prelude::{
+  AsyncTask, Buffer, ClassInstance, Either, Either3, FromNapiValue, This, Uint8ClampedArray,
+  Unknown,
+};
 use napi::*;
 
 use ctx::{
-  CanvasRenderingContext2D, Context, ContextData, ContextOutputData, SvgExportFlag,
+  CanvasRenderingContext2D, Context, ContextData, ContextOutputData, ExportSize, SvgExportFlag,
   FILL_STYLE_HIDDEN_NAME, STROKE_STYLE_HIDDEN_NAME,
 };
 use font::{init_font_regexp, FONT_REGEXP};
-use sk::{ColorSpace, SkiaDataRef};
+use gpu::{external_texture, gpu_options, ExternalTexture};
+use jpeg::JpegMetadata;
+use pattern::parse_color;
+use pdf::{PdfDateTime, PdfMetadata};
+use picture::Picture;
+use png::PngMetadata;
+use quantize::{Dither, PaletteOptions};
+use readback::PixelsReadback;
+use resize::ResizeFit;
+use sk::{
+  AlphaType, Color, ColorSpace, ColorType, JpegEncodeOptions, PdfDocument, PixelGeometry,
+  PngEncodeOptions, SkWMemoryStream, SkiaDataRef, StreamEncoder, Surface, SurfaceProps,
+  SvgTextPolicy,
+};
+use yuv::{YuvFormat, YuvMatrix, YuvOptions};
 
 use avif::AvifConfig;
 
@@ -30,25 +49,43 @@ use avif::AvifConfig;
 #[global_allocator]
 static ALLOC: mimalloc_rust::GlobalMiMalloc = mimalloc_rust::GlobalMiMalloc;
 
+mod animation;
 mod avif;
+mod color_filter;
 mod ctx;
 mod error;
 mod filter;
 mod font;
 pub mod global_fonts;
+mod gpu;
 mod gradient;
+mod icc;
 mod image;
+mod image_filter;
+mod jpeg;
 pub mod path;
 mod pattern;
+mod pdf;
+mod picture;
+mod placeholder;
+mod png;
+mod quantize;
+mod readback;
+mod resize;
+mod shader;
 #[allow(dead_code)]
 mod sk;
 mod state;
 pub mod svg;
+mod svg_path;
+mod tga;
+mod yuv;
 
 const MIME_WEBP: &str = "image/webp";
 const MIME_PNG: &str = "image/png";
 const MIME_JPEG: &str = "image/jpeg";
 const MIME_AVIF: &str = "image/avif";
+const MIME_RAW: &str = "raw";
 
 // Consistent with the default value of JPEG quality in Blink
 // https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/platform/image-encoders/image_encoder.cc;l=85;drc=81c6f843fdfd8ef660d733289a7a32abe68e247a
@@ -68,40 +105,208 @@ fn init() {
 pub struct CanvasRenderingContext2DAttributes {
   pub alpha: Option<bool>,
   pub color_space: Option<String>,
+  /// Move the GPU canvas to the CPU, so `getImageData` doesn't wait for the GPU
+  pub will_read_frequently: Option<bool>,
+  /// Encode the frames without copying the pixels
+  pub desynchronized: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct CanvasOptions {
+  /// Render on the GPU, falls back to the CPU if there is no GPU, default is false.
+  /// `true` is the native backend of the platform, or one of `auto`, `gl`, `metal` and `vulkan`
+  pub gpu: Option<Either<bool, String>>,
+  /// The physical device of the `vulkan` backend, the index or the UUID of it
+  pub device: Option<Either<u32, String>>,
+  /// MSAA samples of the GPU canvas like 4 or 8, clamped to the maximum of the GPU, default is 1 which is off
+  pub msaa: Option<u32>,
+  /// Antialias the shapes and the images, default is true
+  pub antialias: Option<bool>,
+  /// The subpixel layout of the LCD text, one of `unknown`, `rgb`, `bgr`, `vrgb` and `vbgr`, default is `unknown`
+  pub pixel_geometry: Option<String>,
+  /// Draw into the dmabuf or the IOSurface without copying it, the canvas is not created if it can't be imported
+  pub texture: Option<ExternalTexture>,
+  /// The color space of the pixels, `srgb` or `display-p3`, default is `srgb`
+  pub color_space: Option<String>,
+  /// `unorm8`, `float16` for the HDR pixels out of 0 ~ 1, or `alpha8` for the masks, default is `unorm8`
+  pub color_type: Option<String>,
+}
+
+impl CanvasOptions {
+  fn surface_props(&self) -> Result<SurfaceProps> {
+    Ok(SurfaceProps {
+      sample_count: self.msaa.unwrap_or(1).max(1),
+      pixel_geometry: self
+        .pixel_geometry
+        .as_deref()
+        .map(PixelGeometry::from_str)
+        .transpose()?
+        .unwrap_or_default(),
+      color_type: match self.color_type.as_deref() {
+        None | Some("unorm8") => ColorType::RGBA8888,
+        Some("float16") => ColorType::RGBAF16,
+        Some("alpha8") => ColorType::Alpha8,
+        Some(color_type) => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            format!("{} is not valid canvas color type", color_type),
+          ))
+        }
+      },
+    })
+  }
 }
 
 #[napi]
 pub struct CanvasElement {
-  pub width: u32,
-  pub height: u32,
+  pub(crate) width: u32,
+  pub(crate) height: u32,
   pub(crate) ctx: ClassInstance<CanvasRenderingContext2D>,
 }
 
 #[napi]
 impl CanvasElement {
   #[napi(constructor)]
-  pub fn new(mut env: Env, mut this: This, width: u32, height: u32) -> Result<Self> {
-    let ctx = CanvasRenderingContext2D::into_instance(
-      CanvasRenderingContext2D {
-        context: Context::new(width, height, ColorSpace::default())?,
-      },
-      env,
-    )?;
-    ctx.as_object(env).define_properties(&[
-      Property::new(FILL_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-      Property::new(STROKE_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-    ])?;
-    env.adjust_external_memory((width * height * 4) as i64)?;
+  pub fn new(
+    mut env: Env,
+    mut this: This,
+    width: u32,
+    height: u32,
+    options: Option<CanvasOptions>,
+  ) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let props = options.surface_props()?;
+    let color_space = options
+      .color_space
+      .as_deref()
+      .map(ColorSpace::from_str)
+      .transpose()?
+      .unwrap_or_default();
+    let mut context = match (external_texture(&options)?, gpu_options(&options)?) {
+      (Some(texture), _) => Context::new_external(width, height, color_space, texture, props)?,
+      (None, Some((backend, device))) => {
+        Context::new_gpu(width, height, color_space, backend, device, props)?
+      }
+      (None, None) => Context::new_with_props(width, height, color_space, props)?,
+    };
+    // the state is cloned by `save`, so the later states are not antialiased either
+    if !options.antialias.unwrap_or(true) {
+      context.state.paint.set_anti_alias(false);
+    }
+    let ctx = Self::context_instance(&mut env, context)?;
     this.define_properties(&[Property::new("ctx")?
       .with_value(&ctx)
       .with_property_attributes(PropertyAttributes::Default)])?;
     Ok(Self { width, height, ctx })
   }
 
+  /// A new canvas of the pixels in the rect, the parts outside of the canvas are cleared.
+  /// It's on the same backend with the same pixel format and context attributes, the context states are not copied
+  #[napi]
+  pub fn extract(
+    &self,
+    mut env: Env,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+  ) -> Result<ClassInstance<CanvasElement>> {
+    let context = self.ctx.context.extract(x, y, width, height)?;
+    Self::from_context(&mut env, context)
+  }
+
+  /// A new canvas of the copy of the pixels, used by `Canvas.from`.
+  /// The pixels are `width * height * 4` bytes of RGBA or BGRA, unpremultiplied by default
+  #[napi(js_name = "from")]
+  pub fn from_pixels(
+    mut env: Env,
+    pixels: Uint8ClampedArray,
+    width: u32,
+    height: u32,
+    options: Option<PixelsOptions>,
+  ) -> Result<ClassInstance<CanvasElement>> {
+    let options = options.unwrap_or_default();
+    let expected_length = width as usize * height as usize * 4;
+    if pixels.len() != expected_length {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!(
+          "The pixels of {}x{} are {} bytes, but {} bytes are given",
+          width,
+          height,
+          expected_length,
+          pixels.len()
+        ),
+      ));
+    }
+    let color_space = options
+      .color_space
+      .as_deref()
+      .map(ColorSpace::from_str)
+      .transpose()?
+      .unwrap_or_default();
+    let color_type = match options.color_type.as_deref() {
+      None | Some("rgba") => ColorType::RGBA8888,
+      Some("bgra") => ColorType::BGRA8888,
+      Some(color_type) => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("{} is not valid pixels color type", color_type),
+        ))
+      }
+    };
+    let alpha_type = if options.premultiplied.unwrap_or(false) {
+      AlphaType::Premultiplied
+    } else {
+      AlphaType::Unpremultiplied
+    };
+    let mut context = Context::new_with_props(width, height, color_space, SurfaceProps::default())?;
+    if !context
+      .surface
+      .write_pixels(&pixels, width, height, color_space, color_type, alpha_type)
+    {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "Write the pixels to the canvas failed".to_owned(),
+      ));
+    }
+    Self::from_context(&mut env, context)
+  }
+
+  #[napi(getter)]
+  pub fn get_width(&self) -> u32 {
+    self.width
+  }
+
+  /// Like the canvas element, setting the size clears the pixels and resets the context, even if it's not changed
+  #[napi(setter)]
+  pub fn set_width(&mut self, env: Env, width: u32) -> Result<()> {
+    self.set_size(env, width, self.height, None)
+  }
+
+  #[napi(getter)]
+  pub fn get_height(&self) -> u32 {
+    self.height
+  }
+
+  #[napi(setter)]
+  pub fn set_height(&mut self, env: Env, height: u32) -> Result<()> {
+    self.set_size(env, self.width, height, None)
+  }
+
+  /// Resize the canvas, the pixels and the context states are kept if `preserveContents` is set
+  #[napi]
+  pub fn resize(
+    &mut self,
+    env: Env,
+    width: u32,
+    height: u32,
+    options: Option<ResizeOptions>,
+  ) -> Result<()> {
+    self.set_size(env, width, height, options)
+  }
+
   #[napi]
   pub fn get_context(
     &mut self,
@@ -116,35 +321,117 @@ impl CanvasElement {
       ));
     }
     let context_2d = &mut self.ctx.context;
-    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) {
-      let mut fill_paint = context_2d.fill_paint()?;
-      fill_paint.set_color(255, 255, 255, 255);
-      context_2d.alpha = false;
-      context_2d.surface.draw_rect(
-        0f32,
-        0f32,
-        self.width as f32,
-        self.height as f32,
-        &fill_paint,
-      );
+    // the canvas stays on the CPU, the later calls don't move it back to the GPU.
+    // The canvas drawing into the external texture ignores it
+    if attrs
+      .as_ref()
+      .and_then(|a| a.will_read_frequently)
+      .unwrap_or(false)
+      && !context_2d.will_read_frequently
+    {
+      context_2d.will_read_frequently = true;
+      if context_2d.surface.is_gpu() && !context_2d.external_texture {
+        context_2d.use_raster_surface()?;
+      }
     }
-    let color_space = attrs
+    if let Some(desynchronized) = attrs.as_ref().and_then(|a| a.desynchronized) {
+      context_2d.desynchronized = desynchronized;
+    }
+    // the canvas stays opaque, the later calls don't make it transparent again
+    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) && context_2d.alpha {
+      context_2d.use_opaque_surface()?;
+    }
+    // the pixels are read and encoded in it, the color space of the canvas is kept without it
+    if let Some(color_space) = attrs
       .and_then(|a| a.color_space)
       .and_then(|cs| ColorSpace::from_str(&cs).ok())
-      .unwrap_or_default();
-    context_2d.color_space = color_space;
+    {
+      context_2d.color_space = color_space;
+    }
     this.get_named_property("ctx")
   }
 
+  /// Whether the canvas is rendered on the GPU, false if it fell back to the CPU
+  #[napi(getter)]
+  pub fn get_gpu(&self) -> bool {
+    self.ctx.context.surface.is_gpu()
+  }
+
   #[napi]
   pub fn encode(
     &self,
+    env: Env,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
   ) -> Result<AsyncTask<ContextData>> {
-    Ok(AsyncTask::new(
-      self.encode_inner(format, quality_or_config)?,
-    ))
+    Ok(AsyncTask::new(self.encode_inner(
+      &env,
+      format,
+      quality_or_config,
+    )?))
+  }
+
+  /// Share the texture of the GPU canvas, the draws are finished on the GPU before it returns.
+  /// The fd of the exported dmabuf is owned by the caller
+  #[napi]
+  pub fn export_texture(&self) -> Result<ExternalTexture> {
+    self
+      .ctx
+      .context
+      .surface
+      .export_texture()
+      .map(ExternalTexture::from)
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "The texture of the canvas can't be exported".to_owned(),
+        )
+      })
+  }
+
+  /// Start reading the pixels of the GPU canvas for `encodeReadback`, without waiting for the GPU
+  #[napi]
+  pub fn read_pixels_async(&self) -> Result<PixelsReadback> {
+    let ctx2d = &self.ctx.context;
+    PixelsReadback::new(
+      &ctx2d.surface,
+      0,
+      0,
+      ctx2d.width,
+      ctx2d.height,
+      ctx2d.color_space,
+    )
+  }
+
+  /// `encode` the pixels of the completed `readPixelsAsync`
+  #[napi]
+  pub fn encode_readback(
+    &self,
+    env: Env,
+    readback: &mut PixelsReadback,
+    format: String,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<AsyncTask<ContextData>> {
+    let surface = readback.take_surface()?;
+    let task = self.encode_task(&env, format, quality_or_config)?;
+    Ok(AsyncTask::new(self.with_surface(task, surface)))
+  }
+
+  /// Like `encode`, but the pixels are copied when it's called, so the next frame could be drawn while encoding
+  #[napi]
+  pub fn encode_frame(
+    &self,
+    env: Env,
+    format: String,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<AsyncTask<ContextData>> {
+    let task = self.encode_inner(&env, format, quality_or_config)?;
+    // the GPU canvas is snapshotted by `encode_inner` already,
+    // and the desynchronized canvas is encoded in place like `encode`
+    if self.ctx.context.surface.is_gpu() || self.ctx.context.desynchronized {
+      return Ok(AsyncTask::new(task));
+    }
+    Ok(AsyncTask::new(self.snapshot(task)?))
   }
 
   #[napi]
@@ -152,9 +439,9 @@ impl CanvasElement {
     &self,
     env: Env,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
   ) -> Result<JsBuffer> {
-    let mut task = self.encode_inner(format, quality_or_config)?;
+    let mut task = self.encode_inner(&env, format, quality_or_config)?;
     let output = task.compute()?;
     task.resolve(env, output)
   }
@@ -164,10 +451,15 @@ impl CanvasElement {
     &self,
     env: Env,
     mime: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
   ) -> Result<JsBuffer> {
     let mime = mime.as_str();
-    let context_data = get_data_ref(&self.ctx.context, mime, &quality_or_config)?;
+    if mime == MIME_RAW {
+      let mut task = self.encode_inner(&env, mime.to_owned(), quality_or_config)?;
+      let output = task.compute()?;
+      return task.resolve(env, output);
+    }
+    let context_data = get_data_ref(&env, &self.ctx.context, mime, &quality_or_config)?;
     match context_data {
       ContextOutputData::Skia(data_ref) => unsafe {
         env
@@ -186,6 +478,9 @@ impl CanvasElement {
           })
           .map(|b| b.into_raw())
       },
+      ContextOutputData::Buffer(output) => {
+        env.create_buffer_with_data(output).map(|b| b.into_raw())
+      }
     }
   }
 
@@ -194,6 +489,23 @@ impl CanvasElement {
     let ctx2d = &self.ctx.context;
 
     let surface_ref = ctx2d.surface.reference();
+    // the pixels of the GPU surface are copied, so they aren't changed by the later draws
+    if ctx2d.surface.is_gpu() {
+      let pixels = surface_ref
+        .read_pixels_as(
+          ctx2d.width,
+          ctx2d.height,
+          ColorType::RGBA8888,
+          AlphaType::Premultiplied,
+        )
+        .ok_or_else(|| {
+          Error::new(
+            Status::GenericFailure,
+            "Read pixels from the GPU surface failed".to_owned(),
+          )
+        })?;
+      return env.create_buffer_with_data(pixels).map(|b| b.into_raw());
+    }
 
     let (ptr, size) = surface_ref.data().ok_or_else(|| {
       Error::new(
@@ -208,24 +520,86 @@ impl CanvasElement {
     }
   }
 
+  /// BlurHash of the pixels with `components_x` x `components_y` components, 4x3 by default
+  #[napi]
+  pub fn blurhash(&self, components_x: Option<u32>, components_y: Option<u32>) -> Result<String> {
+    let ctx2d = &self.ctx.context;
+    let pixels = read_rgba(ctx2d)?;
+    Ok(placeholder::blurhash(
+      &pixels,
+      ctx2d.width,
+      ctx2d.height,
+      components_x.unwrap_or(4),
+      components_y.unwrap_or(3),
+    )?)
+  }
+
+  /// ThumbHash of the pixels, canvases larger than 100x100 are downscaled first
+  #[napi]
+  pub fn thumbhash(&self) -> Result<Buffer> {
+    let ctx2d = &self.ctx.context;
+    let pixels = read_rgba(ctx2d)?;
+    Ok(placeholder::thumbhash(&pixels, ctx2d.width, ctx2d.height)?.into())
+  }
+
+  /// Planar YUV 4:2:0 pixels for video encoders, the transparent pixels become black
+  #[napi(js_name = "toYUV")]
+  pub fn to_yuv(&self, config: Option<YuvConfig>) -> Result<Buffer> {
+    let config = config.unwrap_or_default();
+    let options = YuvOptions {
+      format: config
+        .format
+        .map(|format| YuvFormat::from_str(&format))
+        .transpose()?
+        .unwrap_or_default(),
+      matrix: config
+        .matrix
+        .map(|matrix| YuvMatrix::from_str(&matrix))
+        .transpose()?
+        .unwrap_or_default(),
+      full_range: config.full_range.unwrap_or(false),
+    };
+    let ctx2d = &self.ctx.context;
+    let pixels = ctx2d
+      .surface
+      .reference()
+      .read_pixels_as(
+        ctx2d.width,
+        ctx2d.height,
+        ColorType::RGBA8888,
+        AlphaType::Premultiplied,
+      )
+      .ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Read pixels from surface failed".to_owned(),
+        )
+      })?;
+    Ok(yuv::rgba_to_yuv(&pixels, ctx2d.width, ctx2d.height, &options).into())
+  }
+
   #[napi(js_name = "toDataURLAsync")]
   pub fn to_data_url_async(
     &self,
+    env: Env,
     mime: Option<String>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, JsObject, Unknown>,
   ) -> Result<AsyncTask<AsyncDataUrl>> {
-    Ok(AsyncTask::new(
-      self.to_data_url_inner(mime.as_deref(), quality_or_config)?,
-    ))
+    Ok(AsyncTask::new(self.to_data_url_inner(
+      &env,
+      mime.as_deref(),
+      quality_or_config,
+    )?))
   }
 
   #[napi(js_name = "toDataURL")]
   pub fn to_data_url(
     &self,
+    env: Env,
     mime: Option<String>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, JsObject, Unknown>,
   ) -> Result<String> {
-    let mut task = self.to_data_url_inner(mime.as_deref(), quality_or_config)?;
+    let mut task = self.to_data_url_inner(&env, mime.as_deref(), quality_or_config)?;
     task.compute()
   }
 
@@ -235,24 +609,151 @@ impl CanvasElement {
     ctx2d.surface.save_png(&path);
   }
 
+  /// Encode and write to `path` on the thread pool, the format is inferred from the extension of `path` if not specified
+  #[napi]
+  pub fn save_as(
+    &self,
+    env: Env,
+    path: String,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<AsyncTask<SaveAs>> {
+    let config: SaveAsConfig = parse_config(&env, &quality_or_config)?;
+    let format = match config.format {
+      Some(format) => format,
+      None => {
+        let extension = Path::new(&path)
+          .extension()
+          .and_then(|ext| ext.to_str())
+          .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+          Some("jpg") => "jpeg".to_owned(),
+          Some(extension) => extension.to_owned(),
+          None => {
+            return Err(Error::new(
+              Status::InvalidArg,
+              format!("Can't infer the format from {}", path),
+            ))
+          }
+        }
+      }
+    };
+    Ok(AsyncTask::new(SaveAs {
+      context_data: self.encode_inner(&env, format, quality_or_config)?,
+      path,
+    }))
+  }
+
+  /// Used by `createJPEGStream`, the pixels are snapshotted when it's called
+  #[napi]
+  pub fn jpeg_encoder_stream(
+    &self,
+    env: Env,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<EncoderStream> {
+    let (options, metadata) = jpeg_options(&env, &quality_or_config)?;
+    let ctx2d = &self.ctx.context;
+    let encoder = StreamEncoder::jpeg(&ctx2d.surface.reference(), options).ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Create jpeg encoder failed".to_owned(),
+      )
+    })?;
+    let mcu_height = options.mcu_height();
+    let rows_per_chunk = (ENCODER_STREAM_CHUNK_SIZE / (ctx2d.width * 4)).max(1);
+    Ok(EncoderStream {
+      encoder,
+      rows_per_chunk: (rows_per_chunk + mcu_height - 1) / mcu_height * mcu_height,
+      metadata: Some(metadata)
+        .filter(|m| !m.is_empty())
+        .map(StreamMetadata::Jpeg),
+      finished: false,
+    })
+  }
+
+  /// Used by `createPNGStream`, the pixels are snapshotted when it's called
+  #[napi]
+  pub fn png_encoder_stream(
+    &self,
+    env: Env,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<EncoderStream> {
+    let (options, metadata, palette) = png_options(&env, &quality_or_config)?;
+    if palette.is_some() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The indexed color png can't be streamed".to_owned(),
+      ));
+    }
+    let ctx2d = &self.ctx.context;
+    let encoder = StreamEncoder::png(&ctx2d.surface.reference(), options).ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Create png encoder failed".to_owned(),
+      )
+    })?;
+    Ok(EncoderStream {
+      encoder,
+      rows_per_chunk: (ENCODER_STREAM_CHUNK_SIZE / (ctx2d.width * 4)).max(1),
+      metadata: Some(metadata)
+        .filter(|m| !m.is_empty())
+        .map(StreamMetadata::Png),
+      finished: false,
+    })
+  }
+
   fn encode_inner(
     &self,
+    env: &Env,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
   ) -> Result<ContextData> {
-    let format_str = format.as_str();
-    let quality = quality_or_config.to_quality(format_str);
+    let task = self.encode_task(env, format, quality_or_config)?;
+    // the GPU surface must only be accessed on the main thread, encode a copy of the pixels instead
+    if self.ctx.context.surface.is_gpu() {
+      return self.snapshot(task);
+    }
+    Ok(task)
+  }
+
+  /// The task encoding the canvas itself
+  fn encode_task(
+    &self,
+    env: &Env,
+    format: String,
+    quality_or_config: Either3<u32, JsObject, Unknown>,
+  ) -> Result<ContextData> {
+    // mime types are accepted as well, to mirror `toBuffer`
+    let format_str = format.strip_prefix("image/").unwrap_or(&format);
     let ctx2d = &self.ctx.context;
     let surface_ref = ctx2d.surface.reference();
 
     let task = match format_str {
-      "webp" => ContextData::Webp(surface_ref, quality),
-      "jpeg" => ContextData::Jpeg(surface_ref, quality),
-      "png" => ContextData::Png(surface_ref),
+      "webp" => match webp_options(env, &quality_or_config)? {
+        (effort, true) => ContextData::WebpLossless(surface_ref, effort),
+        (quality, false) => ContextData::Webp(surface_ref, quality),
+      },
+      "jpeg" => {
+        let (options, metadata) = jpeg_options(env, &quality_or_config)?;
+        ContextData::Jpeg(surface_ref, options, metadata)
+      }
+      "png" => {
+        let (options, metadata, palette) = png_options(env, &quality_or_config)?;
+        ContextData::Png(surface_ref, options, metadata, palette)
+      }
       "avif" => {
-        let cfg = AvifConfig::from(&quality_or_config);
+        let cfg: AvifConfig = parse_config(env, &quality_or_config)?;
         ContextData::Avif(surface_ref, cfg.into(), ctx2d.width, ctx2d.height)
       }
+      "raw" => {
+        let (color_type, alpha_type) = raw_options(env, &quality_or_config)?;
+        ContextData::Raw(
+          surface_ref,
+          ctx2d.width,
+          ctx2d.height,
+          color_type,
+          alpha_type,
+        )
+      }
       _ => {
         return Err(Error::new(
           Status::InvalidArg,
@@ -261,20 +762,120 @@ impl CanvasElement {
       }
     };
 
+    let mime = format!("image/{}", format_str);
+    let task = match icc_profile(env, &mime, &quality_or_config, ctx2d)? {
+      Some(profile) => ContextData::Icc(Box::new(task), profile),
+      None => task,
+    };
+    let task = match export_size(env, &quality_or_config, ctx2d)? {
+      Some(size) => ContextData::Resized(
+        Box::new(task),
+        size,
+        ctx2d.width,
+        ctx2d.height,
+        ctx2d.color_space,
+      ),
+      None => task,
+    };
     Ok(task)
   }
 
+  /// Encode a copy of the pixels taken now instead of the canvas
+  fn snapshot(&self, task: ContextData) -> Result<ContextData> {
+    let ctx2d = &self.ctx.context;
+    let snapshot = ctx2d.surface.try_clone(ctx2d.color_space).ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Copy the canvas pixels failed".to_owned(),
+      )
+    })?;
+    Ok(self.with_surface(task, snapshot))
+  }
+
+  fn with_surface(&self, mut task: ContextData, surface: Surface) -> ContextData {
+    let ctx2d = &self.ctx.context;
+    task.set_surface(surface.reference(), ctx2d.width, ctx2d.height);
+    ContextData::Snapshot(Box::new(task), surface)
+  }
+
+  /// Wrap the context returned by `getContext`, its pixels are counted as the external memory
+  fn context_instance(
+    env: &mut Env,
+    context: Context,
+  ) -> Result<ClassInstance<CanvasRenderingContext2D>> {
+    let size = context.external_memory_size();
+    let ctx = CanvasRenderingContext2D::into_instance(CanvasRenderingContext2D { context }, *env)?;
+    ctx.as_object(*env).define_properties(&[
+      Property::new(FILL_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+      Property::new(STROKE_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+    ])?;
+    env.adjust_external_memory(size)?;
+    Ok(ctx)
+  }
+
+  /// The canvas element created without the constructor
+  fn from_context(env: &mut Env, context: Context) -> Result<ClassInstance<CanvasElement>> {
+    let (width, height) = (context.width, context.height);
+    let ctx = Self::context_instance(env, context)?;
+    let canvas = CanvasElement::into_instance(CanvasElement { width, height, ctx }, *env)?;
+    canvas
+      .as_object(*env)
+      .define_properties(&[Property::new("ctx")?
+        .with_value(&canvas.ctx)
+        .with_property_attributes(PropertyAttributes::Default)])?;
+    Ok(canvas)
+  }
+
+  /// The contents are cleared and the context is reset without `preserveContents`, like setting the size
+  fn set_size(
+    &mut self,
+    mut env: Env,
+    width: u32,
+    height: u32,
+    options: Option<ResizeOptions>,
+  ) -> Result<()> {
+    let ctx2d = &mut self.ctx.context;
+    let previous_size = ctx2d.external_memory_size();
+    match options {
+      Some(options) if options.preserve_contents.unwrap_or(false) => {
+        ctx2d.resize_preserving(width, height, options.scale.unwrap_or(false))?
+      }
+      _ => ctx2d.resize(width, height)?,
+    }
+    env.adjust_external_memory(ctx2d.external_memory_size() - previous_size)?;
+    self.width = width;
+    self.height = height;
+    Ok(())
+  }
+
   fn to_data_url_inner(
     &self,
+    env: &Env,
     mime: Option<&str>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, JsObject, Unknown>,
   ) -> Result<AsyncDataUrl> {
-    let mime = mime.unwrap_or(MIME_PNG);
+    // https://html.spec.whatwg.org/multipage/canvas.html#dom-canvas-todataurl
+    // unsupported types fallback to PNG, and the quality outside of 0..1 is ignored
+    let mime = match mime.map(|m| m.to_ascii_lowercase()).as_deref() {
+      Some(MIME_JPEG) => MIME_JPEG,
+      Some(MIME_WEBP) => MIME_WEBP,
+      Some(MIME_AVIF) => MIME_AVIF,
+      _ => MIME_PNG,
+    };
     let data_ref = get_data_ref(
+      env,
       &self.ctx.context,
       mime,
       &match quality_or_config {
-        Either3::A(q) => Either3::A((q * 100.0) as u32),
+        Either3::A(q) if (0.0..=1.0).contains(&q) => Either3::A((q * 100.0) as u32),
+        Either3::A(_) => Either3::A(match mime {
+          MIME_WEBP => DEFAULT_WEBP_QUALITY as u32,
+          _ => DEFAULT_JPEG_QUALITY as u32,
+        }),
         Either3::B(s) => Either3::B(s),
         Either3::C(u) => Either3::C(u),
       },
@@ -286,35 +887,544 @@ impl CanvasElement {
   }
 }
 
+// Encode about 64KB of pixels for every chunk
+const ENCODER_STREAM_CHUNK_SIZE: u32 = 64 * 1024;
+
+#[napi]
+pub struct EncoderStream {
+  encoder: StreamEncoder,
+  rows_per_chunk: u32,
+  /// Inserted into the first chunk
+  metadata: Option<StreamMetadata>,
+  finished: bool,
+}
+
+enum StreamMetadata {
+  /// The first chunk contains the IHDR chunk
+  Png(PngMetadata),
+  /// The headers may span several chunks, they are complete once the SOS marker is written
+  Jpeg(JpegMetadata),
+}
+
+#[napi]
+impl EncoderStream {
+  /// Returns the next encoded chunk, or `null` after the whole image is encoded
+  #[napi]
+  pub fn next(&mut self) -> Result<Option<Buffer>> {
+    if self.finished {
+      return Ok(None);
+    }
+    let (mut chunk, mut finished) = self.encode_rows()?;
+    while chunk.is_empty() && !finished {
+      (chunk, finished) = self.encode_rows()?;
+    }
+    match self.metadata.take() {
+      Some(StreamMetadata::Png(metadata)) => chunk = png::insert_metadata(&chunk, &metadata)?,
+      Some(StreamMetadata::Jpeg(metadata)) => {
+        while !finished && jpeg::read_segments(&chunk).is_err() {
+          let (rest, rest_finished) = self.encode_rows()?;
+          chunk.extend_from_slice(&rest);
+          finished = rest_finished;
+        }
+        chunk = jpeg::insert_metadata(&chunk, &metadata)?;
+      }
+      None => {}
+    }
+    self.finished = finished;
+    // The last rows may be flushed by the previous chunk, an empty Buffer would stall the Readable
+    if chunk.is_empty() {
+      return Ok(None);
+    }
+    Ok(Some(chunk.into()))
+  }
+}
+
+impl EncoderStream {
+  /// zlib and libjpeg buffer their output, so the rows may not produce any bytes yet
+  fn encode_rows(&mut self) -> Result<(Vec<u8>, bool)> {
+    self
+      .encoder
+      .encode_rows(self.rows_per_chunk)
+      .ok_or_else(|| Error::new(Status::GenericFailure, "Encode rows failed".to_owned()))
+  }
+}
+
 #[napi(object)]
 pub struct ContextAttr {
   pub alpha: Option<bool>,
 }
 
-fn get_data_ref(
-  ctx2d: &Context,
-  mime: &str,
-  quality_or_config: &Either3<u32, AvifConfig, Unknown>,
-) -> Result<ContextOutputData> {
-  let surface_ref = ctx2d.surface.reference();
-  let quality = quality_or_config.to_quality(mime);
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct WebpConfig {
+  /// 0-100 scale, default is 80
+  pub quality: Option<u32>,
+  /// Encode without any loss, `quality` is ignored
+  pub lossless: Option<bool>,
+  /// 0 (fastest) - 6 (slowest but smallest), only used by the lossless compression, default is 4
+  pub effort: Option<u32>,
+}
 
-  if let Some(data_ref) = match mime {
-    MIME_WEBP => surface_ref.encode_data(sk::SkEncodedImageFormat::Webp, quality),
-    MIME_JPEG => surface_ref.encode_data(sk::SkEncodedImageFormat::Jpeg, quality),
-    MIME_PNG => surface_ref.png_data(),
-    MIME_AVIF => {
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct PngConfig {
+  /// zlib compression level, 0 (fastest) - 9 (smallest), default is 6
+  pub compression_level: Option<u32>,
+  /// Row filters the encoder could choose from, 'none' | 'sub' | 'up' | 'avg' | 'paeth' | 'all', default is ['all']
+  pub filters: Option<Vec<String>>,
+  /// Pixels per inch, written to the pHYs chunk
+  pub density: Option<f64>,
+  /// Written to tEXt chunks, or iTXt chunks if the value isn't Latin-1
+  pub text: Option<HashMap<String, String>>,
+  /// Encode as an indexed color PNG with at most this many colors, 2 - 256
+  pub colors: Option<u32>,
+  /// 'none' | 'ordered' | 'floyd-steinberg', used with `colors`, default is 'none'
+  pub dither: Option<String>,
+  /// 8 or 16 bits per channel, default is 16 for the `float16` canvas and 8 for the others
+  pub bit_depth: Option<u32>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct JpegConfig {
+  /// 0-100 scale, default is 92
+  pub quality: Option<u32>,
+  /// Skia only writes baseline JPEG, accepted for the compatibility with node-canvas and ignored
+  pub progressive: Option<bool>,
+  /// Use 4:2:0 chroma subsampling, or 4:4:4 if false. Default is true
+  pub chroma_subsampling: Option<bool>,
+  /// CSS color the transparent pixels are composited over, they become black without it
+  pub background: Option<String>,
+  /// Pixels per inch, written to the JFIF header and the EXIF X/Y resolution
+  pub density: Option<f64>,
+  /// EXIF orientation, 1 - 8
+  pub orientation: Option<u32>,
+  /// EXIF Software
+  pub software: Option<String>,
+  /// EXIF Artist
+  pub artist: Option<String>,
+  /// EXIF Copyright
+  pub copyright: Option<String>,
+  /// EXIF ImageDescription
+  pub description: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct SaveAsConfig {
+  /// 'png' | 'jpeg' | 'webp' | 'avif' | 'raw', or the mime type
+  pub format: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct RawConfig {
+  /// 'rgba', 'bgra' or 'rgba16f' of the half floats, default is 'rgba'
+  pub color_type: Option<String>,
+  /// Keep the color components multiplied by alpha, default is false
+  pub premultiplied: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct YuvConfig {
+  /// 'i420' | 'nv12', default is 'i420'
+  pub format: Option<String>,
+  /// 'bt601' | 'bt709', default is 'bt601'
+  pub matrix: Option<String>,
+  /// Use 0 - 255 instead of the limited range, default is false
+  pub full_range: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct ExportSizeConfig {
+  /// The other side keeps the aspect ratio of the canvas if only one of `width` and `height` is set
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  /// 'fill' | 'contain' | 'cover', default is 'fill'
+  pub fit: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct PixelsOptions {
+  /// 'rgba' | 'bgra', default is 'rgba'
+  pub color_type: Option<String>,
+  /// Whether the colors are multiplied by the alpha, default is false
+  pub premultiplied: Option<bool>,
+  /// 'srgb' | 'display-p3', the color space of the pixels and the canvas
+  pub color_space: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResizeOptions {
+  /// Copy the pixels to the new surface and keep the states, instead of clearing and resetting the context
+  pub preserve_contents: Option<bool>,
+  /// Scale the preserved pixels to the new size with the image smoothing of the context, instead of copying them to the top left corner
+  pub scale: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct IccConfig {
+  /// Embedded in the PNG, JPEG and WebP output. The profile of the canvas color space is embedded by default if it isn't sRGB
+  pub icc_profile: Option<Buffer>,
+}
+
+/// Unpremultiplied RGBA pixels of the canvas
+fn read_rgba(ctx2d: &Context) -> Result<Vec<u8>> {
+  ctx2d
+    .surface
+    .reference()
+    .read_pixels_as(
+      ctx2d.width,
+      ctx2d.height,
+      ColorType::RGBA8888,
+      AlphaType::Unpremultiplied,
+    )
+    .ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Read pixels from surface failed".to_owned(),
+      )
+    })
+}
+
+/// Returns the ICC profile to embed, `None` if the format doesn't support it or the canvas is sRGB without a profile supplied
+fn icc_profile(
+  env: &Env,
+  format: &str,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+  ctx2d: &Context,
+) -> Result<Option<Vec<u8>>> {
+  if !matches!(format, MIME_PNG | MIME_JPEG | MIME_WEBP) {
+    return Ok(None);
+  }
+  let config: IccConfig = parse_config(env, quality_or_config)?;
+  match config.icc_profile {
+    Some(profile) if profile.is_empty() => Err(Error::new(
+      Status::InvalidArg,
+      "The ICC profile is empty".to_owned(),
+    )),
+    Some(profile) => Ok(Some(profile.to_vec())),
+    None if ctx2d.color_space != ColorSpace::Srgb => Ok(
+      ctx2d
+        .color_space
+        .icc_profile()
+        .map(|profile| profile.slice().to_vec()),
+    ),
+    None => Ok(None),
+  }
+}
+
+/// Returns the size to resize to before encoding, `None` if the config doesn't set any
+fn export_size(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+  ctx2d: &Context,
+) -> Result<Option<ExportSize>> {
+  let config: ExportSizeConfig = parse_config(env, quality_or_config)?;
+  let (width, height) = match (config.width, config.height) {
+    (None, None) => return Ok(None),
+    (Some(width), Some(height)) => (width, height),
+    (Some(width), None) => (
+      width,
+      ((width as f64 * ctx2d.height as f64 / ctx2d.width as f64).round() as u32).max(1),
+    ),
+    (None, Some(height)) => (
+      ((height as f64 * ctx2d.width as f64 / ctx2d.height as f64).round() as u32).max(1),
+      height,
+    ),
+  };
+  if width == 0 || height == 0 {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "The width and height must be greater than 0".to_owned(),
+    ));
+  }
+  Ok(Some(ExportSize {
+    width,
+    height,
+    fit: config
+      .fit
+      .map(|fit| ResizeFit::from_str(&fit))
+      .transpose()?
+      .unwrap_or_default(),
+  }))
+}
+
+/// Read the format specific config object, the defaults are used if a quality number is passed
+fn parse_config<T: FromNapiValue + Default>(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<T> {
+  if let Either3::B(config) = quality_or_config {
+    unsafe { T::from_napi_value(env.raw(), config.raw()) }
+  } else {
+    Ok(T::default())
+  }
+}
+
+fn raw_options(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<(ColorType, AlphaType)> {
+  let config: RawConfig = parse_config(env, quality_or_config)?;
+  let color_type = match config.color_type.as_deref() {
+    None | Some("rgba") => ColorType::RGBA8888,
+    Some("bgra") => ColorType::BGRA8888,
+    Some("rgba16f") => ColorType::RGBAF16,
+    Some(color_type) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{} is not valid raw color type", color_type),
+      ))
+    }
+  };
+  let alpha_type = if config.premultiplied.unwrap_or(false) {
+    AlphaType::Premultiplied
+  } else {
+    AlphaType::Unpremultiplied
+  };
+  Ok((color_type, alpha_type))
+}
+
+fn png_options(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<(PngEncodeOptions, PngMetadata, Option<PaletteOptions>)> {
+  let config: PngConfig = parse_config(env, quality_or_config)?;
+  let mut options = PngEncodeOptions::default();
+  if let Some(level) = config.compression_level {
+    options.set_zlib_level(level);
+  }
+  if let Some(filters) = config.filters {
+    let mut flags = 0;
+    for filter in filters.iter() {
+      flags |= PngEncodeOptions::filter_flag(filter).ok_or_else(|| {
+        Error::new(
+          Status::InvalidArg,
+          format!("{} is not valid png filter", filter),
+        )
+      })?;
+    }
+    options.set_filter_flags(flags);
+  }
+  match config.bit_depth {
+    Some(16) if config.colors.is_some() => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The indexed color png is 8-bit".to_owned(),
+      ))
+    }
+    Some(bit_depth @ (8 | 16)) => options.set_bit_depth(bit_depth as u8),
+    Some(bit_depth) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("The png bit depth must be 8 or 16, got {}", bit_depth),
+      ))
+    }
+    None => {}
+  }
+  let mut text = config
+    .text
+    .unwrap_or_default()
+    .into_iter()
+    .collect::<Vec<_>>();
+  // the order of the object keys is lost in the HashMap
+  text.sort();
+  let metadata = PngMetadata {
+    density: config.density,
+    text,
+  };
+  let palette = match config.colors {
+    Some(colors) if (2..=256).contains(&colors) => Some(PaletteOptions {
+      max_colors: colors,
+      dither: config
+        .dither
+        .map(|dither| Dither::from_str(&dither))
+        .transpose()?
+        .unwrap_or_default(),
+    }),
+    Some(colors) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("The png colors must be between 2 and 256, got {}", colors),
+      ))
+    }
+    None => None,
+  };
+  Ok((options, metadata, palette))
+}
+
+fn jpeg_options(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<(JpegEncodeOptions, JpegMetadata)> {
+  let config = match quality_or_config {
+    Either3::A(quality) => JpegConfig {
+      quality: Some(*quality),
+      ..Default::default()
+    },
+    _ => parse_config::<JpegConfig>(env, quality_or_config)?,
+  };
+  let mut options = JpegEncodeOptions::new(
+    // Skia encoders expect the quality in 0..=100, `as u8` alone would wrap the larger values
+    config
+      .quality
+      .map(|q| q.min(100) as u8)
+      // https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/toDataURL
+      .unwrap_or(DEFAULT_JPEG_QUALITY),
+    config.chroma_subsampling.unwrap_or(true),
+  );
+  if let Some(background) = config.background {
+    let rgba = parse_color(&background)?;
+    options.set_background(Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha).0);
+  }
+  if let Some(orientation) = config.orientation.filter(|o| !(1..=8).contains(o)) {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("Invalid jpeg orientation: {}", orientation),
+    ));
+  }
+  if let Some(density) = config
+    .density
+    .filter(|d| !d.is_finite() || *d <= 0.0 || *d > u16::MAX as f64)
+  {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("Invalid jpeg density: {}", density),
+    ));
+  }
+  let metadata = JpegMetadata {
+    density: config.density,
+    orientation: config.orientation,
+    software: config.software,
+    artist: config.artist,
+    copyright: config.copyright,
+    description: config.description,
+  };
+  Ok((options, metadata))
+}
+
+/// Returns the quality passed to Skia and whether to use the lossless compression
+fn webp_options(
+  env: &Env,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<(u8, bool)> {
+  let config = match quality_or_config {
+    Either3::A(quality) => WebpConfig {
+      quality: Some(*quality),
+      ..Default::default()
+    },
+    _ => parse_config::<WebpConfig>(env, quality_or_config)?,
+  };
+  if config.lossless.unwrap_or(false) {
+    // Skia treats the quality as the compression effort of the lossless encoder
+    let effort = config.effort.unwrap_or(4).min(6);
+    Ok(((effort * 100 / 6) as u8, true))
+  } else {
+    let quality = config
+      .quality
+      .map(|q| q.min(100) as u8)
+      .unwrap_or(DEFAULT_WEBP_QUALITY);
+    Ok((quality, false))
+  }
+}
+
+fn get_data_ref(
+  env: &Env,
+  ctx2d: &Context,
+  mime: &str,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<ContextOutputData> {
+  let output = encode_surface(env, ctx2d, mime, quality_or_config)?;
+  match icc_profile(env, mime, quality_or_config, ctx2d)? {
+    Some(profile) => Ok(ContextOutputData::Buffer(ctx::embed_icc(
+      output.as_slice(),
+      &profile,
+    )?)),
+    None => Ok(output),
+  }
+}
+
+fn encode_surface(
+  env: &Env,
+  ctx2d: &Context,
+  mime: &str,
+  quality_or_config: &Either3<u32, JsObject, Unknown>,
+) -> Result<ContextOutputData> {
+  let (resized, width, height) = match export_size(env, quality_or_config, ctx2d)? {
+    Some(size) => (
+      Some(size.resize(
+        &ctx2d.surface.reference(),
+        ctx2d.width,
+        ctx2d.height,
+        ctx2d.color_space,
+      )?),
+      size.width,
+      size.height,
+    ),
+    // the GPU surface has no addressable pixels
+    None if ctx2d.surface.is_gpu() => (
+      Some(ctx2d.surface.try_clone(ctx2d.color_space).ok_or_else(|| {
+        Error::new(
+          Status::GenericFailure,
+          "Copy the canvas pixels failed".to_owned(),
+        )
+      })?),
+      ctx2d.width,
+      ctx2d.height,
+    ),
+    None => (None, ctx2d.width, ctx2d.height),
+  };
+  let surface_ref = resized.as_ref().unwrap_or(&ctx2d.surface).reference();
+
+  if let Some(data_ref) = match mime {
+    MIME_WEBP => {
+      let (quality, lossless) = webp_options(env, quality_or_config)?;
+      surface_ref.encode_webp(quality, lossless)
+    }
+    MIME_JPEG => {
+      let (options, metadata) = jpeg_options(env, quality_or_config)?;
+      match surface_ref.encode_jpeg(options) {
+        Some(data_ref) if !metadata.is_empty() => {
+          let output = jpeg::insert_metadata(data_ref.slice(), &metadata)?;
+          return Ok(ContextOutputData::Buffer(output));
+        }
+        data_ref => data_ref,
+      }
+    }
+    MIME_PNG => {
+      let (options, metadata, palette) = png_options(env, quality_or_config)?;
+      if let Some(palette) = palette {
+        let mut output = ctx::encode_palette_png(&surface_ref, &options, &palette)?;
+        if !metadata.is_empty() {
+          output = png::insert_metadata(&output, &metadata)?;
+        }
+        return Ok(ContextOutputData::Buffer(output));
+      }
+      match surface_ref.encode_png(options) {
+        Some(data_ref) if !metadata.is_empty() => {
+          let output = png::insert_metadata(data_ref.slice(), &metadata)?;
+          return Ok(ContextOutputData::Buffer(output));
+        }
+        data_ref => data_ref,
+      }
+    }
+    MIME_AVIF => {
       let (data, size) = surface_ref.data().ok_or_else(|| {
         Error::new(
           Status::GenericFailure,
           "Encode to avif error, failed to get surface pixels".to_owned(),
         )
       })?;
-      let config = AvifConfig::from(quality_or_config).into();
+      let config = parse_config::<AvifConfig>(env, quality_or_config)?.into();
       let output = avif::encode(
         unsafe { slice::from_raw_parts(data, size) },
-        ctx2d.width,
-        ctx2d.height,
+        width,
+        height,
         &config,
       )
       .map_err(|e| Error::new(Status::GenericFailure, format!("{}", e)))?;
@@ -348,14 +1458,7 @@ impl Task for AsyncDataUrl {
 
   fn compute(&mut self) -> Result<Self::Output> {
     let mut output = format!("data:{};base64,", &self.mime);
-    match &self.surface_data {
-      ContextOutputData::Skia(data_ref) => {
-        base64::encode_config_buf(data_ref.slice(), base64::STANDARD, &mut output);
-      }
-      ContextOutputData::Avif(o) => {
-        base64::encode_config_buf(o.as_slice(), base64::STANDARD, &mut output);
-      }
-    }
+    base64::encode_config_buf(self.surface_data.as_slice(), base64::STANDARD, &mut output);
     Ok(output)
   }
 
@@ -364,26 +1467,28 @@ impl Task for AsyncDataUrl {
   }
 }
 
-trait ToQuality {
-  fn to_quality(&self, mime: &str) -> u8;
+pub struct SaveAs {
+  context_data: ContextData,
+  path: String,
 }
 
-impl ToQuality for &Either3<u32, AvifConfig, Unknown> {
-  fn to_quality(&self, mime_or_format: &str) -> u8 {
-    if let Either3::A(q) = &self {
-      *q as u8
-    } else {
-      match mime_or_format {
-        MIME_WEBP | "webp" => DEFAULT_WEBP_QUALITY,
-        _ => DEFAULT_JPEG_QUALITY, // https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/toDataURL
-      }
-    }
+#[napi]
+impl Task for SaveAs {
+  type Output = ();
+  type JsValue = ();
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let output = self.context_data.compute()?;
+    fs::write(&self.path, output.as_slice()).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Write {} failed: {}", self.path, e),
+      )
+    })
   }
-}
 
-impl ToQuality for Either3<u32, AvifConfig, Unknown> {
-  fn to_quality(&self, mime: &str) -> u8 {
-    ToQuality::to_quality(&self, mime)
+  fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+    Ok(())
   }
 }
 
@@ -402,23 +1507,37 @@ impl SVGCanvas {
     mut this: This,
     width: u32,
     height: u32,
-    flag: SvgExportFlag,
+    flag: Option<SvgExportFlag>,
+    path: Option<String>,
+    config: Option<SvgConfig>,
   ) -> Result<Self> {
+    let text_policy = config
+      .and_then(|c| c.text)
+      .map(|text| SvgTextPolicy::from_str(&text))
+      .transpose()?
+      .unwrap_or_default();
     let ctx = CanvasRenderingContext2D::into_instance(
       CanvasRenderingContext2D {
-        context: Context::new_svg(width, height, flag.into(), ColorSpace::default())?,
+        context: Context::new_svg(
+          width,
+          height,
+          flag.map(|flag| flag.into()),
+          text_policy,
+          ColorSpace::default(),
+          path.as_deref(),
+        )?,
       },
       env,
     )?;
     ctx.as_object(env).define_properties(&[
       Property::new(FILL_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
+        .with_value(&env.create_string("#000000")?)
         .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
       Property::new(STROKE_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
+        .with_value(&env.create_string("#000000")?)
         .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
     ])?;
-    env.adjust_external_memory((width * height * 4) as i64)?;
+    env.adjust_external_memory(width as i64 * height as i64 * 4)?;
     this.define_properties(&[Property::new("ctx")?
       .with_value(&ctx)
       .with_property_attributes(PropertyAttributes::Default)])?;
@@ -439,7 +1558,8 @@ impl SVGCanvas {
       ));
     }
     let context_2d = &mut self.ctx.context;
-    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) {
+    // the SVG document has no pixels, the white background is drawn once instead
+    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) && context_2d.alpha {
       let mut fill_paint = context_2d.fill_paint()?;
       fill_paint.set_color(255, 255, 255, 255);
       context_2d.alpha = false;
@@ -461,15 +1581,340 @@ impl SVGCanvas {
 
   #[napi]
   pub fn get_content(&self, env: Env) -> Result<JsBuffer> {
-    let svg_data_stream = self.ctx.context.stream.as_ref().unwrap();
+    let svg_data_stream = self.memory_stream()?;
     let svg_data = svg_data_stream.data(self.ctx.context.width, self.ctx.context.height);
-    unsafe {
-      env
-        .create_buffer_with_borrowed_data(svg_data.0.ptr, svg_data.0.size, svg_data, |d, _| {
-          mem::drop(d)
-        })
-        .map(|b| b.into_raw())
+    self.with_font_faces(&env, svg_data)
+  }
+
+  /// The complete SVG document, unlike `getContent` it can be called repeatedly while drawing
+  #[napi]
+  pub fn to_buffer(&self, env: Env) -> Result<JsBuffer> {
+    self.with_font_faces(&env, self.memory_stream()?.snapshot())
+  }
+
+  /// The part of the document written since the last call, used to stream to a Writable
+  #[napi]
+  pub fn take_content(&self, env: Env) -> Result<JsBuffer> {
+    sk_data_to_buffer(&env, self.memory_stream()?.take())
+  }
+
+  /// Close the document written to the file passed to the constructor
+  #[napi]
+  pub fn finish_file(&mut self) -> Result<()> {
+    let context = &mut self.ctx.context;
+    let style = context.svg_fonts.as_ref().map(|fonts| fonts.style());
+    let file_stream = context.file_stream.as_mut().ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "The SVG canvas isn't written to a file".to_owned(),
+      )
+    })?;
+    let style = style.as_ref().map(|s| s.slice()).unwrap_or_default();
+    if !file_stream.finish_svg(style) {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "Finish the SVG file failed".to_owned(),
+      ));
     }
+    Ok(())
+  }
+}
+
+impl SVGCanvas {
+  /// Insert the `@font-face` rules before the closing tag of the document if the fonts are embedded
+  fn with_font_faces(&self, env: &Env, svg_data: SkiaDataRef) -> Result<JsBuffer> {
+    let fonts = match self.ctx.context.svg_fonts.as_ref() {
+      Some(fonts) => fonts,
+      None => return sk_data_to_buffer(env, svg_data),
+    };
+    let svg = svg_data.slice();
+    let style = fonts.style();
+    let close_tag_start = svg.len().saturating_sub(b"</svg>".len());
+    let content = [
+      &svg[..close_tag_start],
+      style.slice(),
+      &svg[close_tag_start..],
+    ]
+    .concat();
+    env.create_buffer_with_data(content).map(|b| b.into_raw())
+  }
+
+  fn memory_stream(&self) -> Result<&SkWMemoryStream> {
+    self.ctx.context.stream.as_ref().ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "The SVG canvas is written to a file".to_owned(),
+      )
+    })
+  }
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct SvgConfig {
+  /// 'family' | 'embed' | 'paths', default is 'family'.
+  /// `embed` writes the fonts as `@font-face` data urls, `paths` outlines the glyphs
+  pub text: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct PdfConfig {
+  pub title: Option<String>,
+  pub author: Option<String>,
+  pub subject: Option<String>,
+  pub keywords: Option<String>,
+  /// The application created the content, default is '@napi-rs/canvas'
+  pub creator: Option<String>,
+  /// The application converted the content to PDF, default is the version of Skia
+  pub producer: Option<String>,
+  /// Milliseconds since the Unix epoch, `Date` is converted in JavaScript
+  pub creation_date: Option<f64>,
+  pub modified_date: Option<f64>,
+  /// Conform to PDF/A-2b for archiving, default is false
+  pub pdf_a: Option<bool>,
+  /// Draw the text as paths instead of the selectable text, default is false
+  pub text_to_paths: Option<bool>,
+}
+
+fn pdf_metadata(config: PdfConfig) -> Result<PdfMetadata> {
+  Ok(PdfMetadata {
+    title: config.title,
+    author: config.author,
+    subject: config.subject,
+    keywords: config.keywords,
+    creator: config.creator,
+    producer: config.producer,
+    creation_date: config
+      .creation_date
+      .map(PdfDateTime::from_timestamp)
+      .transpose()?,
+    modified_date: config
+      .modified_date
+      .map(PdfDateTime::from_timestamp)
+      .transpose()?,
+    pdf_a: config.pdf_a.unwrap_or(false),
+  })
+}
+
+#[napi(js_name = "PDFCanvas")]
+pub struct PDFCanvas {
+  pub width: u32,
+  pub height: u32,
+  pub(crate) ctx: ClassInstance<CanvasRenderingContext2D>,
+  to_file: bool,
+}
+
+#[napi]
+impl PDFCanvas {
+  #[napi(constructor)]
+  pub fn new(
+    mut env: Env,
+    mut this: This,
+    width: u32,
+    height: u32,
+    path: Option<String>,
+    config: Option<PdfConfig>,
+  ) -> Result<Self> {
+    let config = config.unwrap_or_default();
+    let text_to_paths = config.text_to_paths.unwrap_or(false);
+    let metadata = pdf_metadata(config)?;
+    let ctx = CanvasRenderingContext2D::into_instance(
+      CanvasRenderingContext2D {
+        context: Context::new_pdf(
+          width,
+          height,
+          ColorSpace::default(),
+          path.as_deref(),
+          &metadata,
+          text_to_paths,
+        )?,
+      },
+      env,
+    )?;
+    ctx.as_object(env).define_properties(&[
+      Property::new(FILL_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+      Property::new(STROKE_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+    ])?;
+    env.adjust_external_memory(width as i64 * height as i64 * 4)?;
+    this.define_properties(&[Property::new("ctx")?
+      .with_value(&ctx)
+      .with_property_attributes(PropertyAttributes::Default)])?;
+    Ok(Self {
+      width,
+      height,
+      ctx,
+      to_file: path.is_some(),
+    })
+  }
+
+  #[napi]
+  pub fn get_context(
+    &mut self,
+    this: This,
+    context_type: String,
+    attrs: Option<CanvasRenderingContext2DAttributes>,
+  ) -> Result<Unknown> {
+    if context_type != "2d" {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{context_type} is not supported"),
+      ));
+    }
+    let color_space = attrs
+      .and_then(|a| a.color_space)
+      .and_then(|cs| ColorSpace::from_str(&cs).ok())
+      .unwrap_or_default();
+    self.ctx.context.color_space = color_space;
+    this.get_named_property("ctx")
+  }
+
+  /// Close the document and return the whole PDF, the later draws are ignored
+  #[napi]
+  pub fn to_buffer(&mut self, env: Env) -> Result<JsBuffer> {
+    if self.to_file {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "The PDF canvas is written to a file".to_owned(),
+      ));
+    }
+    self.finish()?;
+    sk_data_to_buffer(&env, self.document()?.snapshot())
+  }
+
+  /// The part of the document written since the last call, used to stream to a Writable
+  #[napi]
+  pub fn take_content(&self, env: Env) -> Result<JsBuffer> {
+    sk_data_to_buffer(&env, self.document()?.take())
+  }
+
+  /// Close the document, the file passed to the constructor is completed
+  #[napi]
+  pub fn finish(&mut self) -> Result<()> {
+    let context = &mut self.ctx.context;
+    if let Some(pdf) = context.pdf.as_mut() {
+      if !pdf.is_closed() {
+        pdf.close(&mut context.surface);
+      }
+    }
+    Ok(())
+  }
+}
+
+impl PDFCanvas {
+  fn document(&self) -> Result<&PdfDocument> {
+    self.ctx.context.pdf.as_ref().ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "The PDF document is released".to_owned(),
+      )
+    })
+  }
+}
+
+/// Records the draw commands into a `SkPicture`, it can be replayed at any scale without rasterizing
+#[napi]
+pub struct PictureCanvas {
+  pub width: u32,
+  pub height: u32,
+  pub(crate) ctx: ClassInstance<CanvasRenderingContext2D>,
+}
+
+#[napi]
+impl PictureCanvas {
+  #[napi(constructor)]
+  pub fn new(mut env: Env, mut this: This, width: u32, height: u32) -> Result<Self> {
+    let ctx = CanvasRenderingContext2D::into_instance(
+      CanvasRenderingContext2D {
+        context: Context::new_picture(width, height, ColorSpace::default())?,
+      },
+      env,
+    )?;
+    ctx.as_object(env).define_properties(&[
+      Property::new(FILL_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+      Property::new(STROKE_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+    ])?;
+    env.adjust_external_memory(width as i64 * height as i64 * 4)?;
+    this.define_properties(&[Property::new("ctx")?
+      .with_value(&ctx)
+      .with_property_attributes(PropertyAttributes::Default)])?;
+    Ok(Self { width, height, ctx })
+  }
+
+  #[napi]
+  pub fn get_context(
+    &mut self,
+    this: This,
+    context_type: String,
+    attrs: Option<CanvasRenderingContext2DAttributes>,
+  ) -> Result<Unknown> {
+    if context_type != "2d" {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{context_type} is not supported"),
+      ));
+    }
+    let color_space = attrs
+      .and_then(|a| a.color_space)
+      .and_then(|cs| ColorSpace::from_str(&cs).ok())
+      .unwrap_or_default();
+    self.ctx.context.color_space = color_space;
+    this.get_named_property("ctx")
+  }
+
+  /// Finish the recording and serialize it as `.skp` bytes, the later draws are ignored
+  #[napi]
+  pub fn to_buffer(&mut self, env: Env) -> Result<JsBuffer> {
+    let picture = self.finish()?;
+    sk_data_to_buffer(&env, picture.inner.serialize())
+  }
+
+  /// Finish the recording and return the picture without serializing it, the later draws are ignored
+  #[napi]
+  pub fn to_picture(&mut self) -> Result<Picture> {
+    self.finish()
+  }
+}
+
+impl PictureCanvas {
+  fn finish(&mut self) -> Result<Picture> {
+    let context = &mut self.ctx.context;
+    let recorder = context.picture.as_mut().ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "The picture recorder is released".to_owned(),
+      )
+    })?;
+    if recorder.picture().is_none() {
+      recorder.finish(&mut context.surface);
+    }
+    let inner = recorder.picture().ok_or_else(|| {
+      Error::new(
+        Status::GenericFailure,
+        "Finish the picture recording failed".to_owned(),
+      )
+    })?;
+    Ok(Picture { inner })
+  }
+}
+
+/// Empty `SkData` has no bytes to borrow
+fn sk_data_to_buffer(env: &Env, data: SkiaDataRef) -> Result<JsBuffer> {
+  if data.0.size == 0 {
+    return env.create_buffer(0).map(|b| b.into_raw());
+  }
+  unsafe {
+    env
+      .create_buffer_with_borrowed_data(data.0.ptr, data.0.size, data, |d, _| mem::drop(d))
+      .map(|b| b.into_raw())
   }
 }
 
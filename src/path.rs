@@ -1,7 +1,11 @@
+use std::result;
+
 use napi::{bindgen_prelude::*, JsString};
 
+use crate::ctx::RectInit;
+use crate::error::SkError;
 use crate::sk::{
-  FillType as SkFillType, Matrix as SkMatrix, Path as SkPath, PathOp as SkPathOp,
+  FillType as SkFillType, Matrix as SkMatrix, Path as SkPath, PathEffect, PathOp as SkPathOp,
   StrokeCap as SkStrokeCap, StrokeJoin as SkStrokeJoin,
 };
 
@@ -15,6 +19,21 @@ pub struct Matrix {
   pub f: f64,
 }
 
+#[napi(object)]
+pub struct Point {
+  pub x: f64,
+  pub y: f64,
+}
+
+/// The `DOMPointInit` radius of the corner of `roundRect`
+#[napi(object)]
+pub struct CornerRadius {
+  pub x: Option<f64>,
+  pub y: Option<f64>,
+}
+
+pub type RoundRectRadii = Either3<f64, CornerRadius, Vec<Either<f64, CornerRadius>>>;
+
 #[napi]
 pub enum PathOp {
   Difference,        // subtract the op path from the first path
@@ -120,6 +139,9 @@ pub struct StrokeOptions {
   pub miter_limit: Option<f64>,
   pub cap: Option<StrokeCap>,
   pub join: Option<StrokeJoin>,
+  /// The dash list like `setLineDash`, the odd list is repeated
+  pub dash: Option<Vec<f64>>,
+  pub dash_offset: Option<f64>,
 }
 
 #[napi]
@@ -132,12 +154,7 @@ impl Path {
   #[napi(constructor)]
   pub fn new(path: Option<Either3<String, &mut Path, Unknown>>) -> Result<Self> {
     let inner = match &path {
-      Some(Either3::A(path)) => SkPath::from_svg_path(path).ok_or_else(|| {
-        Error::new(
-          Status::InvalidArg,
-          "Create path from provided path string failed.".to_owned(),
-        )
-      })?,
+      Some(Either3::A(path)) => SkPath::from_svg_path(path),
       Some(Either3::B(path)) => path.inner.clone(),
       Some(Either3::C(c)) => {
         return Err(Error::new(
@@ -155,6 +172,11 @@ impl Path {
 
   #[napi]
   pub fn add_path(&mut self, sub_path: &Path, matrix: Option<Matrix>) {
+    if let Some(m) = &matrix {
+      if !all_finite(&[m.a, m.b, m.c, m.d, m.e, m.f]) {
+        return;
+      }
+    }
     let transform = matrix
       .map(|m| {
         SkMatrix::new(
@@ -172,16 +194,25 @@ impl Path {
 
   #[napi]
   pub fn move_to(&mut self, x: f64, y: f64) {
+    if !all_finite(&[x, y]) {
+      return;
+    }
     self.inner.move_to(x as f32, y as f32);
   }
 
   #[napi]
   pub fn line_to(&mut self, x: f64, y: f64) {
+    if !all_finite(&[x, y]) {
+      return;
+    }
     self.inner.line_to(x as f32, y as f32);
   }
 
   #[napi]
   pub fn bezier_curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+    if !all_finite(&[cp1x, cp1y, cp2x, cp2y, x, y]) {
+      return;
+    }
     self.inner.cubic_to(
       cp1x as f32,
       cp1y as f32,
@@ -194,6 +225,9 @@ impl Path {
 
   #[napi]
   pub fn quadratic_curve_to(&mut self, cpx: f64, cpy: f64, x: f64, y: f64) {
+    if !all_finite(&[cpx, cpy, x, y]) {
+      return;
+    }
     self
       .inner
       .quad_to(cpx as f32, cpy as f32, x as f32, y as f32);
@@ -208,7 +242,10 @@ impl Path {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !radius_args_valid(&[x, y, start_angle, end_angle], &[radius])? {
+      return Ok(());
+    }
     self.inner.arc(
       x as f32,
       y as f32,
@@ -217,13 +254,17 @@ impl Path {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
-  pub fn arc_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) {
-    self
-      .inner
-      .arc_to_tangent(x1 as f32, y1 as f32, x2 as f32, y2 as f32, radius as f32);
+  pub fn arc_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) -> Result<()> {
+    if radius_args_valid(&[x1, y1, x2, y2], &[radius])? {
+      self
+        .inner
+        .arc_to_tangent(x1 as f32, y1 as f32, x2 as f32, y2 as f32, radius as f32);
+    }
+    Ok(())
   }
 
   #[napi]
@@ -237,7 +278,13 @@ impl Path {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !radius_args_valid(
+      &[x, y, rotation, start_angle, end_angle],
+      &[radius_x, radius_y],
+    )? {
+      return Ok(());
+    }
     self.inner.ellipse(
       x as f32,
       y as f32,
@@ -248,19 +295,44 @@ impl Path {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
   pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+    if !all_finite(&[x, y, width, height]) {
+      return;
+    }
     self
       .inner
       .add_rect(x as f32, y as f32, width as f32, height as f32);
   }
 
   #[napi]
-  pub fn op(&mut self, other: &Path, op: PathOp) -> &Self {
-    self.inner.op(&other.inner, op.into());
-    self
+  pub fn round_rect(
+    &mut self,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    radii: Option<RoundRectRadii>,
+  ) -> Result<()> {
+    if let Some(radii) = round_rect_radii(&[x, y, width, height], radii)? {
+      self
+        .inner
+        .add_round_rect(x as f32, y as f32, width as f32, height as f32, radii);
+    }
+    Ok(())
+  }
+
+  #[napi]
+  pub fn op(&mut self, other: &Path, op: Either<PathOp, String>) -> Result<&Self> {
+    let op: SkPathOp = match op {
+      Either::A(op) => op.into(),
+      Either::B(op) => op.parse()?,
+    };
+    self.inner.op(&other.inner, op);
+    Ok(self)
   }
 
   #[napi(js_name = "toSVGString")]
@@ -301,11 +373,19 @@ impl Path {
 
   #[napi]
   pub fn stroke(&mut self, options: StrokeOptions) -> &Self {
+    let intervals = dash_intervals(&options.dash.unwrap_or_default());
+    // the invalid dash list, like the negative or zero intervals, strokes the solid outline
+    let path_effect = if intervals.is_empty() {
+      None
+    } else {
+      PathEffect::new_dash_path(&intervals, options.dash_offset.unwrap_or(0.0) as f32)
+    };
     self.inner.stroke(
       options.cap.unwrap_or_default().into(),
       options.join.unwrap_or_default().into(),
       options.width.unwrap_or(1.0) as f32,
       options.miter_limit.unwrap_or(4.0) as f32,
+      path_effect.as_ref(),
     );
     self
   }
@@ -322,6 +402,47 @@ impl Path {
     vec![ltrb.0 as f64, ltrb.1 as f64, ltrb.2 as f64, ltrb.3 as f64]
   }
 
+  /// The bounds of the points, or of the curves if `tight` is true
+  #[napi]
+  pub fn bounds(&self, tight: Option<bool>) -> RectInit {
+    let (left, top, right, bottom) = if tight.unwrap_or(false) {
+      self.inner.compute_tight_bounds()
+    } else {
+      self.inner.get_bounds()
+    };
+    RectInit {
+      x: left as f64,
+      y: top as f64,
+      width: (right - left) as f64,
+      height: (bottom - top) as f64,
+    }
+  }
+
+  #[napi]
+  pub fn length(&self) -> f64 {
+    self.inner.length() as f64
+  }
+
+  /// The point at the distance along the path, `null` if the path has no length
+  #[napi]
+  pub fn point_at(&self, distance: f64) -> Option<Point> {
+    let ((x, y), _) = self.inner.get_pos_tan(distance as f32)?;
+    Some(Point {
+      x: x as f64,
+      y: y as f64,
+    })
+  }
+
+  /// The unit tangent at the distance along the path, `null` if the path has no length
+  #[napi]
+  pub fn tangent_at(&self, distance: f64) -> Option<Point> {
+    let (_, (x, y)) = self.inner.get_pos_tan(distance as f32)?;
+    Some(Point {
+      x: x as f64,
+      y: y as f64,
+    })
+  }
+
   #[napi]
   pub fn transform(&mut self, matrix: Matrix) -> &Self {
     let trans = SkMatrix::new(
@@ -344,9 +465,21 @@ impl Path {
     self
   }
 
+  /// `dash(intervals, phase?)` or `dash(on, off, phase)`, the path is kept if the intervals are invalid
   #[napi]
-  pub fn dash(&mut self, on: f64, off: f64, phase: f64) -> &Self {
-    self.inner.dash(on as f32, off as f32, phase as f32);
+  pub fn dash(
+    &mut self,
+    intervals: Either<Vec<f64>, f64>,
+    off_or_phase: Option<f64>,
+    phase: Option<f64>,
+  ) -> &Self {
+    let (intervals, phase) = match intervals {
+      Either::A(intervals) => (dash_intervals(&intervals), off_or_phase),
+      Either::B(on) => (dash_intervals(&[on, off_or_phase.unwrap_or(on)]), phase),
+    };
+    if !intervals.is_empty() {
+      self.inner.dash(&intervals, phase.unwrap_or(0.0) as f32);
+    }
     self
   }
 
@@ -363,4 +496,106 @@ impl Path {
       fill_type.unwrap_or(FillType::Winding).into(),
     )
   }
+
+  /// Whether the point is inside of the path, by `fillRule` or else the fill type of the path
+  #[napi]
+  pub fn contains(&mut self, x: f64, y: f64, fill_rule: Option<String>) -> Result<bool> {
+    if !all_finite(&[x, y]) {
+      return Ok(false);
+    }
+    let fill_type: SkFillType = match fill_rule {
+      Some(fill_rule) => fill_rule.parse()?,
+      None => self.get_fill_type().into(),
+    };
+    Ok(self.inner.hit_test(x as f32, y as f32, fill_type))
+  }
+}
+
+/// The path methods ignore the calls with the infinite or NaN arguments, like `CanvasRenderingContext2D`
+pub(crate) fn all_finite(values: &[f64]) -> bool {
+  values.iter().all(|value| value.is_finite())
+}
+
+/// `false` if the path method is ignored for the infinite or NaN arguments,
+/// the negative radii throw the `IndexSizeError`
+pub(crate) fn radius_args_valid(args: &[f64], radii: &[f64]) -> result::Result<bool, SkError> {
+  if !all_finite(args) || !all_finite(radii) {
+    return Ok(false);
+  }
+  if let Some(radius) = radii.iter().find(|radius| **radius < 0.0) {
+    return Err(SkError::IndexSizeError(format!(
+      "The radius provided ({}) is negative.",
+      radius
+    )));
+  }
+  Ok(true)
+}
+
+/// The odd dash list is repeated to be even, like `setLineDash`
+fn dash_intervals(dash: &[f64]) -> Vec<f32> {
+  let mut intervals = dash.iter().map(|v| *v as f32).collect::<Vec<_>>();
+  if intervals.len() & 1 != 0 {
+    intervals.extend_from_within(..);
+  }
+  intervals
+}
+
+/// The radii of the upper left, upper right, lower right and lower left corners,
+/// `None` if `roundRect` is ignored for the infinite or NaN arguments.
+/// https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-roundrect
+pub(crate) fn round_rect_radii(
+  rect: &[f64; 4],
+  radii: Option<RoundRectRadii>,
+) -> result::Result<Option<[(f32, f32); 4]>, SkError> {
+  if !all_finite(rect) {
+    return Ok(None);
+  }
+  let radii = match radii {
+    None => vec![Either::A(0.0)],
+    Some(Either3::A(radius)) => vec![Either::A(radius)],
+    Some(Either3::B(radius)) => vec![Either::B(radius)],
+    Some(Either3::C(radii)) => radii,
+  };
+  if radii.is_empty() || radii.len() > 4 {
+    return Err(SkError::RangeError(format!(
+      "{} radii provided. Between one and four radii are necessary.",
+      radii.len()
+    )));
+  }
+  let mut corners = Vec::with_capacity(radii.len());
+  for radius in radii {
+    let (x, y) = match radius {
+      Either::A(radius) => (radius, radius),
+      Either::B(radius) => (radius.x.unwrap_or(0.0), radius.y.unwrap_or(0.0)),
+    };
+    if !all_finite(&[x, y]) {
+      return Ok(None);
+    }
+    if x < 0.0 || y < 0.0 {
+      return Err(SkError::RangeError(format!(
+        "Radius value {} is negative.",
+        x.min(y)
+      )));
+    }
+    corners.push((x as f32, y as f32));
+  }
+  Ok(Some(match corners[..] {
+    [all] => [all; 4],
+    [upper_left_lower_right, upper_right_lower_left] => [
+      upper_left_lower_right,
+      upper_right_lower_left,
+      upper_left_lower_right,
+      upper_right_lower_left,
+    ],
+    [upper_left, upper_right_lower_left, lower_right] => [
+      upper_left,
+      upper_right_lower_left,
+      lower_right,
+      upper_right_lower_left,
+    ],
+    [upper_left, upper_right, lower_right, lower_left] => {
+      [upper_left, upper_right, lower_right, lower_left]
+    }
+    _ => unreachable!(),
+  }))
 }
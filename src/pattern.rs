@@ -7,37 +7,67 @@ use crate::ctx::TransformObject;
 use crate::error::SkError;
 use crate::gradient::Gradient;
 use crate::image::{Image, ImageData};
+use crate::shader::Effect;
 use crate::sk::{AlphaType, Bitmap, ColorType, ImagePattern, TileMode, Transform};
 use crate::{CanvasElement, SVGCanvas};
 
 #[derive(Debug, Clone)]
 pub enum Pattern {
-  Color(RGBA, String),
+  Color(RGBA),
   Gradient(Gradient),
   Image(ImagePattern),
+  Shader(Effect),
 }
 
 impl Default for Pattern {
   fn default() -> Self {
-    Self::Color(RGBA::new(0, 0, 0, 255), "#000".to_owned())
+    Self::Color(RGBA::new(0, 0, 0, 255))
   }
 }
 
 impl Pattern {
-  pub fn from_color(color_str: &str) -> StdResult<Self, SkError> {
-    let mut parser_input = ParserInput::new(color_str);
-    let mut parser = Parser::new(&mut parser_input);
-    let color = CSSColor::parse(&mut parser)
-      .map_err(|e| SkError::Generic(format!("Parse color [{}] error: {:?}", color_str, e)))?;
-    match color {
-      CSSColor::CurrentColor => Err(SkError::Generic(
-        "Color should not be `currentcolor` keyword".to_owned(),
-      )),
-      CSSColor::RGBA(rgba) => Ok(Pattern::Color(rgba, color_str.to_owned())),
-    }
+  pub fn from_color(color_str: &str, current_color: Option<RGBA>) -> StdResult<Self, SkError> {
+    Ok(Pattern::Color(parse_color_with(color_str, current_color)?))
+  }
+}
+
+pub(crate) fn parse_color(color_str: &str) -> StdResult<RGBA, SkError> {
+  parse_color_with(color_str, None)
+}
+
+/// `currentcolor` is resolved to `current_color`, it's an error without it
+pub(crate) fn parse_color_with(
+  color_str: &str,
+  current_color: Option<RGBA>,
+) -> StdResult<RGBA, SkError> {
+  let mut parser_input = ParserInput::new(color_str);
+  let mut parser = Parser::new(&mut parser_input);
+  let color = CSSColor::parse(&mut parser)
+    .map_err(|e| SkError::Generic(format!("Parse color [{}] error: {:?}", color_str, e)))?;
+  match color {
+    CSSColor::CurrentColor => current_color
+      .ok_or_else(|| SkError::Generic("Color should not be `currentcolor` keyword".to_owned())),
+    CSSColor::RGBA(rgba) => Ok(rgba),
   }
 }
 
+/// Serialize the color like the browsers, `#rrggbb` if it's opaque, otherwise `rgba(r, g, b, a)`
+pub(crate) fn serialize_color(color: &RGBA) -> String {
+  if color.alpha == 255 {
+    return format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue);
+  }
+  // the alpha is rounded to 2 decimals if it still maps to the same byte, otherwise 3
+  let alpha = color.alpha_f32();
+  let mut rounded = (alpha * 100.0).round() / 100.0;
+  if (rounded * 255.0).round() as u8 != color.alpha {
+    rounded = (alpha * 1000.0).round() / 1000.0;
+  }
+  format!(
+    "rgba({}, {}, {}, {})",
+    color.red, color.green, color.blue, rounded
+  )
+}
+
 #[napi]
 pub struct CanvasPattern {
   pub(crate) inner: Pattern,
@@ -121,3 +151,18 @@ impl CanvasPattern {
     }
   }
 }
+
+#[test]
+fn test_serialize_color() {
+  assert_eq!(serialize_color(&RGBA::new(255, 105, 180, 255)), "#ff69b4");
+  assert_eq!(
+    serialize_color(&RGBA::new(255, 0, 0, 128)),
+    "rgba(255, 0, 0, 0.5)"
+  );
+  assert_eq!(serialize_color(&RGBA::new(0, 0, 0, 0)), "rgba(0, 0, 0, 0)");
+  // 0.3 would be 77, not 76
+  assert_eq!(
+    serialize_color(&RGBA::new(0, 0, 255, 76)),
+    "rgba(0, 0, 255, 0.298)"
+  );
+}
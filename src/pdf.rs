@@ -0,0 +1,127 @@
+// Document information of the PDF canvas
+// https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf 14.3.3
+
+use crate::error::SkError;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// UTC time written to the `CreationDate` and `ModDate` entries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PdfDateTime {
+  pub year: u16,
+  /// 1 - 12
+  pub month: u8,
+  /// 0 is Sunday
+  pub day_of_week: u8,
+  /// 1 - 31
+  pub day: u8,
+  pub hour: u8,
+  pub minute: u8,
+  pub second: u8,
+}
+
+impl PdfDateTime {
+  /// `timestamp` is the milliseconds since the Unix epoch, like `Date.prototype.getTime`
+  pub fn from_timestamp(timestamp: f64) -> Result<Self, SkError> {
+    if !timestamp.is_finite() {
+      return Err(SkError::Generic(format!("Invalid date: {}", timestamp)));
+    }
+    let millis = timestamp.floor() as i64;
+    let days = millis.div_euclid(MILLIS_PER_DAY);
+    let seconds = millis.rem_euclid(MILLIS_PER_DAY) / 1000;
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+      return Err(SkError::Generic(format!(
+        "The year of the date must be in 0 - 9999, but got {}",
+        year
+      )));
+    }
+    Ok(Self {
+      year: year as u16,
+      month,
+      // 1970-01-01 is Thursday
+      day_of_week: (days + 4).rem_euclid(7) as u8,
+      day,
+      hour: (seconds / 3600) as u8,
+      minute: (seconds / 60 % 60) as u8,
+      second: (seconds % 60) as u8,
+    })
+  }
+}
+
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let day_of_era = z.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PdfMetadata {
+  pub title: Option<String>,
+  pub author: Option<String>,
+  pub subject: Option<String>,
+  pub keywords: Option<String>,
+  pub creator: Option<String>,
+  pub producer: Option<String>,
+  pub creation_date: Option<PdfDateTime>,
+  pub modified_date: Option<PdfDateTime>,
+  /// Write the XMP metadata and the output intent required by PDF/A-2b
+  pub pdf_a: bool,
+}
+
+#[test]
+fn test_epoch() {
+  assert_eq!(
+    PdfDateTime::from_timestamp(0.0).unwrap(),
+    PdfDateTime {
+      year: 1970,
+      month: 1,
+      day_of_week: 4,
+      day: 1,
+      hour: 0,
+      minute: 0,
+      second: 0,
+    }
+  );
+}
+
+#[test]
+fn test_leap_day() {
+  // 2024-02-29T13:45:30.500Z
+  assert_eq!(
+    PdfDateTime::from_timestamp(1_709_214_330_500.0).unwrap(),
+    PdfDateTime {
+      year: 2024,
+      month: 2,
+      day_of_week: 4,
+      day: 29,
+      hour: 13,
+      minute: 45,
+      second: 30,
+    }
+  );
+  // 1969-12-31T23:59:59.000Z
+  let before_epoch = PdfDateTime::from_timestamp(-1000.0).unwrap();
+  assert_eq!(
+    (before_epoch.year, before_epoch.month, before_epoch.day),
+    (1969, 12, 31)
+  );
+  assert_eq!(before_epoch.day_of_week, 3);
+  assert_eq!(before_epoch.second, 59);
+}
+
+#[test]
+fn test_invalid_date() {
+  assert!(PdfDateTime::from_timestamp(f64::NAN).is_err());
+  // 10000-01-01
+  assert!(PdfDateTime::from_timestamp(253_402_300_800_000.0).is_err());
+}
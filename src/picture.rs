@@ -0,0 +1,42 @@
+use napi::{bindgen_prelude::*, JsBuffer};
+
+use crate::sk::Picture as SkPicture;
+
+/// The recorded draw commands, replayed by `drawPicture`
+#[napi]
+pub struct Picture {
+  pub(crate) inner: SkPicture,
+}
+
+#[napi]
+impl Picture {
+  /// Load the `.skp` bytes serialized by `PictureCanvas.toBuffer`, only load the pictures from the trusted sources
+  #[napi(constructor)]
+  pub fn new(data: Buffer) -> Result<Self> {
+    let inner = SkPicture::from_data(&data).ok_or_else(|| {
+      Error::new(
+        Status::InvalidArg,
+        "The data is not a valid skp picture".to_owned(),
+      )
+    })?;
+    Ok(Self { inner })
+  }
+
+  #[napi(getter)]
+  pub fn get_width(&self) -> f64 {
+    let (left, _, right, _) = self.inner.cull_rect();
+    (right - left) as f64
+  }
+
+  #[napi(getter)]
+  pub fn get_height(&self) -> f64 {
+    let (_, top, _, bottom) = self.inner.cull_rect();
+    (bottom - top) as f64
+  }
+
+  /// The `.skp` bytes
+  #[napi]
+  pub fn to_buffer(&self, env: Env) -> Result<JsBuffer> {
+    crate::sk_data_to_buffer(&env, self.inner.serialize())
+  }
+}
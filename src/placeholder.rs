@@ -0,0 +1,347 @@
+// Compact placeholder hashes of the unpremultiplied RGBA pixels, shown while the full image is loading.
+// https://github.com/woltapp/blurhash/blob/master/Algorithm.md
+// https://evanw.github.io/thumbhash/
+
+use std::f64::consts::PI;
+
+use crate::error::SkError;
+use crate::resize::{resize_rgba, ResizeFilter, ResizeFit};
+
+const BASE83_CHARS: &[u8] =
+  b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+// ThumbHash is designed for images up to 100x100
+const THUMBHASH_MAX_SIZE: u32 = 100;
+
+/// `components_x` and `components_y` are 1 - 9
+pub fn blurhash(
+  pixels: &[u8],
+  width: u32,
+  height: u32,
+  components_x: u32,
+  components_y: u32,
+) -> Result<String, SkError> {
+  if !(1..=9).contains(&components_x) || !(1..=9).contains(&components_y) {
+    return Err(SkError::Generic(format!(
+      "The blurhash components must be between 1 and 9, got {}x{}",
+      components_x, components_y
+    )));
+  }
+  let (width, height) = (width as usize, height as usize);
+  let (components_x, components_y) = (components_x as usize, components_y as usize);
+  let linear = pixels
+    .chunks_exact(4)
+    .map(|p| {
+      [
+        srgb_to_linear(p[0]),
+        srgb_to_linear(p[1]),
+        srgb_to_linear(p[2]),
+      ]
+    })
+    .collect::<Vec<[f64; 3]>>();
+  let basis_x = cosine_table(components_x, width, 0.0);
+  let basis_y = cosine_table(components_y, height, 0.0);
+
+  let mut factors = Vec::with_capacity(components_x * components_y);
+  for j in 0..components_y {
+    for i in 0..components_x {
+      let mut factor = [0f64; 3];
+      for y in 0..height {
+        let by = basis_y[j * height + y];
+        for x in 0..width {
+          let basis = basis_x[i * width + x] * by;
+          let pixel = &linear[y * width + x];
+          for c in 0..3 {
+            factor[c] += basis * pixel[c];
+          }
+        }
+      }
+      let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+      let scale = normalisation / (width * height).max(1) as f64;
+      factors.push(factor.map(|f| f * scale));
+    }
+  }
+
+  let dc = factors[0];
+  let ac = &factors[1..];
+  let mut hash = String::with_capacity(6 + ac.len() * 2);
+  encode_base83(
+    (components_x - 1 + (components_y - 1) * 9) as u32,
+    1,
+    &mut hash,
+  );
+  let maximum = if ac.is_empty() {
+    encode_base83(0, 1, &mut hash);
+    1.0
+  } else {
+    let actual_maximum = ac
+      .iter()
+      .flat_map(|factor| factor.iter())
+      .fold(0f64, |max, f| max.max(f.abs()));
+    let quantised = (actual_maximum * 166.0 - 0.5).floor().clamp(0.0, 82.0);
+    encode_base83(quantised as u32, 1, &mut hash);
+    (quantised + 1.0) / 166.0
+  };
+  let dc_value =
+    (linear_to_srgb(dc[0]) << 16) + (linear_to_srgb(dc[1]) << 8) + linear_to_srgb(dc[2]);
+  encode_base83(dc_value, 4, &mut hash);
+  for factor in ac {
+    let quantise = |value: f64| {
+      (sign_pow(value / maximum, 0.5) * 9.0 + 9.5)
+        .floor()
+        .clamp(0.0, 18.0) as u32
+    };
+    let ac_value = quantise(factor[0]) * 19 * 19 + quantise(factor[1]) * 19 + quantise(factor[2]);
+    encode_base83(ac_value, 2, &mut hash);
+  }
+  Ok(hash)
+}
+
+/// The pixels are downscaled first if the image is larger than 100x100
+pub fn thumbhash(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, SkError> {
+  if width == 0 || height == 0 {
+    return Err(SkError::Generic(
+      "The thumbhash image must not be empty".to_owned(),
+    ));
+  }
+  if width > THUMBHASH_MAX_SIZE || height > THUMBHASH_MAX_SIZE {
+    let scale = THUMBHASH_MAX_SIZE as f64 / width.max(height) as f64;
+    let scaled_width = ((width as f64 * scale).round() as u32).clamp(1, THUMBHASH_MAX_SIZE);
+    let scaled_height = ((height as f64 * scale).round() as u32).clamp(1, THUMBHASH_MAX_SIZE);
+    let scaled = resize_rgba(
+      pixels,
+      width,
+      height,
+      scaled_width,
+      scaled_height,
+      ResizeFit::Fill,
+      ResizeFilter::Lanczos,
+    );
+    return Ok(encode_thumbhash(&scaled, scaled_width, scaled_height));
+  }
+  Ok(encode_thumbhash(pixels, width, height))
+}
+
+fn encode_thumbhash(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+  let (w, h) = (width as usize, height as usize);
+  // the average color weighted by alpha
+  let (mut avg_r, mut avg_g, mut avg_b, mut avg_a) = (0f64, 0f64, 0f64, 0f64);
+  for p in pixels.chunks_exact(4) {
+    let alpha = p[3] as f64 / 255.0;
+    avg_r += alpha / 255.0 * p[0] as f64;
+    avg_g += alpha / 255.0 * p[1] as f64;
+    avg_b += alpha / 255.0 * p[2] as f64;
+    avg_a += alpha;
+  }
+  if avg_a > 0.0 {
+    avg_r /= avg_a;
+    avg_g /= avg_a;
+    avg_b /= avg_a;
+  }
+
+  let has_alpha = avg_a < (w * h) as f64;
+  // fewer luminance bits if there is alpha
+  let l_limit = if has_alpha { 5.0 } else { 7.0 };
+  let max_side = w.max(h) as f64;
+  let lx = (js_round(l_limit * w as f64 / max_side) as usize).max(1);
+  let ly = (js_round(l_limit * h as f64 / max_side) as usize).max(1);
+
+  // luminance, yellow - blue, red - green and alpha, composited atop the average color
+  let mut l = Vec::with_capacity(w * h);
+  let mut p = Vec::with_capacity(w * h);
+  let mut q = Vec::with_capacity(w * h);
+  let mut a = Vec::with_capacity(w * h);
+  for pixel in pixels.chunks_exact(4) {
+    let alpha = pixel[3] as f64 / 255.0;
+    let r = avg_r * (1.0 - alpha) + alpha / 255.0 * pixel[0] as f64;
+    let g = avg_g * (1.0 - alpha) + alpha / 255.0 * pixel[1] as f64;
+    let b = avg_b * (1.0 - alpha) + alpha / 255.0 * pixel[2] as f64;
+    l.push((r + g + b) / 3.0);
+    p.push((r + g) / 2.0 - b);
+    q.push(r - g);
+    a.push(alpha);
+  }
+
+  let (l_dc, l_ac, l_scale) = encode_channel(&l, w, h, lx.max(3), ly.max(3));
+  let (p_dc, p_ac, p_scale) = encode_channel(&p, w, h, 3, 3);
+  let (q_dc, q_ac, q_scale) = encode_channel(&q, w, h, 3, 3);
+
+  let is_landscape = w > h;
+  let header24 = js_round(63.0 * l_dc) as u32
+    | (js_round(31.5 + 31.5 * p_dc) as u32) << 6
+    | (js_round(31.5 + 31.5 * q_dc) as u32) << 12
+    | (js_round(31.0 * l_scale) as u32) << 18
+    | (has_alpha as u32) << 23;
+  let header16 = (if is_landscape { ly } else { lx }) as u32
+    | (js_round(63.0 * p_scale) as u32) << 3
+    | (js_round(63.0 * q_scale) as u32) << 9
+    | (is_landscape as u32) << 15;
+  let mut hash = vec![
+    header24 as u8,
+    (header24 >> 8) as u8,
+    (header24 >> 16) as u8,
+    header16 as u8,
+    (header16 >> 8) as u8,
+  ];
+  let mut channels = vec![l_ac, p_ac, q_ac];
+  if has_alpha {
+    let (a_dc, a_ac, a_scale) = encode_channel(&a, w, h, 5, 5);
+    hash.push(js_round(15.0 * a_dc) as u8 | (js_round(15.0 * a_scale) as u8) << 4);
+    channels.push(a_ac);
+  }
+  // two factors in every byte, the low nibble first
+  for (i, factor) in channels.iter().flatten().enumerate() {
+    let nibble = js_round(15.0 * factor) as u8;
+    if i % 2 == 0 {
+      hash.push(nibble);
+    } else {
+      *hash.last_mut().unwrap() |= nibble << 4;
+    }
+  }
+  hash
+}
+
+/// Returns the DC term, the AC terms normalized to 0 - 1 and the scale of the AC terms
+fn encode_channel(
+  channel: &[f64],
+  w: usize,
+  h: usize,
+  nx: usize,
+  ny: usize,
+) -> (f64, Vec<f64>, f64) {
+  let basis_x = cosine_table(nx, w, 0.5);
+  let basis_y = cosine_table(ny, h, 0.5);
+  let mut dc = 0.0;
+  let mut ac = Vec::new();
+  let mut scale = 0f64;
+  for cy in 0..ny {
+    let mut cx = 0;
+    // the triangle of the low frequencies
+    while cx * ny < nx * (ny - cy) {
+      let mut f = 0.0;
+      for y in 0..h {
+        let fy = basis_y[cy * h + y];
+        for x in 0..w {
+          f += channel[x + y * w] * basis_x[cx * w + x] * fy;
+        }
+      }
+      f /= (w * h) as f64;
+      if cx > 0 || cy > 0 {
+        ac.push(f);
+        scale = scale.max(f.abs());
+      } else {
+        dc = f;
+      }
+      cx += 1;
+    }
+  }
+  if scale > 0.0 {
+    for f in ac.iter_mut() {
+      *f = 0.5 + 0.5 / scale * *f;
+    }
+  }
+  (dc, ac, scale)
+}
+
+/// `cos(PI * component * (x + offset) / size)` for every component and position
+fn cosine_table(components: usize, size: usize, offset: f64) -> Vec<f64> {
+  (0..components)
+    .flat_map(|component| {
+      (0..size).map(move |x| (PI * component as f64 * (x as f64 + offset) / size as f64).cos())
+    })
+    .collect()
+}
+
+/// `Math.round` of JavaScript, which rounds half up, to match the reference implementation
+fn js_round(value: f64) -> f64 {
+  (value + 0.5).floor()
+}
+
+fn srgb_to_linear(value: u8) -> f64 {
+  let v = value as f64 / 255.0;
+  if v <= 0.04045 {
+    v / 12.92
+  } else {
+    ((v + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+fn linear_to_srgb(value: f64) -> u32 {
+  let v = value.clamp(0.0, 1.0);
+  if v <= 0.0031308 {
+    (v * 12.92 * 255.0 + 0.5) as u32
+  } else {
+    ((1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u32
+  }
+}
+
+fn sign_pow(value: f64, exp: f64) -> f64 {
+  value.abs().powf(exp).copysign(value)
+}
+
+fn encode_base83(value: u32, length: u32, output: &mut String) {
+  for i in 1..=length {
+    let digit = (value / 83u32.pow(length - i)) % 83;
+    output.push(BASE83_CHARS[digit as usize] as char);
+  }
+}
+
+#[test]
+fn test_blurhash_solid_color() {
+  let pixels = [255u8, 0, 0, 255].repeat(8 * 6);
+  let hash = blurhash(&pixels, 8, 6, 4, 3).unwrap();
+  // sizeFlag of 4x3 and the red DC term
+  assert_eq!(hash.len(), 6 + 11 * 2);
+  assert_eq!(&hash[..1], "L");
+  assert_eq!(&hash[2..6], "TI:j");
+  assert!(blurhash(&pixels, 8, 6, 0, 3).is_err());
+  assert!(blurhash(&pixels, 8, 6, 4, 10).is_err());
+}
+
+#[test]
+fn test_blurhash_gradient() {
+  let pixels = (0..16u32)
+    .flat_map(|x| {
+      let v = (x * 17) as u8;
+      [v, v, v, 255]
+    })
+    .collect::<Vec<u8>>()
+    .repeat(4);
+  let hash = blurhash(&pixels, 16, 4, 2, 1).unwrap();
+  assert_eq!(hash.len(), 8);
+  // sizeFlag of 2x1
+  assert_eq!(&hash[..1], "1");
+  // the horizontal AC term is negative, dark on the left
+  let ac = BASE83_CHARS
+    .iter()
+    .position(|c| *c == hash.as_bytes()[6])
+    .unwrap()
+    * 83
+    + BASE83_CHARS
+      .iter()
+      .position(|c| *c == hash.as_bytes()[7])
+      .unwrap();
+  assert!(ac / (19 * 19) < 9);
+}
+
+#[test]
+fn test_thumbhash() {
+  let white = [255u8; 4].repeat(4 * 4);
+  let hash = thumbhash(&white, 4, 4).unwrap();
+  // 27 luminance and 5 + 5 chroma AC terms
+  assert_eq!(hash.len(), 5 + 19);
+  assert_eq!(&hash[..5], &[0x3f, 0x08, 0x02, 0x07, 0x00]);
+
+  let mut transparent = white.clone();
+  transparent[3] = 0;
+  let hash = thumbhash(&transparent, 4, 4).unwrap();
+  // the alpha byte, 14 luminance, 5 + 5 chroma and 14 alpha AC terms
+  assert_eq!(hash.len(), 6 + 19);
+  assert_eq!(hash[2] >> 7, 1);
+
+  // downscaled to 100x50
+  let large = [0u8, 0, 255, 255].repeat(400 * 200);
+  let hash = thumbhash(&large, 400, 200).unwrap();
+  assert_eq!(hash[4] >> 7, 1);
+  assert!(thumbhash(&[], 0, 0).is_err());
+}
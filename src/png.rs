@@ -0,0 +1,407 @@
+// Chunks Skia's PNG encoder doesn't write, the metadata chunks are inserted after the IHDR chunk of the encoded data,
+// and the frames of an animation are combined into an APNG.
+// http://www.libpng.org/pub/png/spec/1.2/PNG-Chunks.html
+
+use crate::error::SkError;
+
+pub(crate) const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, Default, Clone)]
+pub struct PngMetadata {
+  /// Pixels per inch
+  pub density: Option<f64>,
+  pub text: Vec<(String, String)>,
+}
+
+impl PngMetadata {
+  pub fn is_empty(&self) -> bool {
+    self.density.is_none() && self.text.is_empty()
+  }
+
+  fn to_chunks(&self) -> Result<Vec<u8>, SkError> {
+    let mut chunks = Vec::new();
+    if let Some(density) = self.density {
+      if !density.is_finite() || density <= 0.0 {
+        return Err(SkError::Generic(format!(
+          "Invalid png density: {}",
+          density
+        )));
+      }
+      // pHYs is in pixels per meter
+      let ppm = ((density / 0.0254).round() as u32).to_be_bytes();
+      let mut data = Vec::with_capacity(9);
+      data.extend_from_slice(&ppm);
+      data.extend_from_slice(&ppm);
+      data.push(1);
+      write_chunk(&mut chunks, b"pHYs", &data);
+    }
+    for (keyword, text) in self.text.iter() {
+      if keyword.is_empty()
+        || keyword.len() > 79
+        || !keyword
+          .chars()
+          .all(|c| matches!(c as u32, 0x20..=0x7e | 0xa1..=0xff))
+      {
+        return Err(SkError::Generic(format!(
+          "Invalid png text keyword: {}",
+          keyword
+        )));
+      }
+      let mut data = latin1(keyword);
+      data.push(0);
+      if text.chars().all(|c| (c as u32) < 0x100) {
+        data.extend(latin1(text));
+        write_chunk(&mut chunks, b"tEXt", &data);
+      } else {
+        // not compressed, empty language tag and translated keyword
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(text.as_bytes());
+        write_chunk(&mut chunks, b"iTXt", &data);
+      }
+    }
+    Ok(chunks)
+  }
+}
+
+/// Returns `png` with the metadata chunks inserted after IHDR
+pub fn insert_metadata(png: &[u8], metadata: &PngMetadata) -> Result<Vec<u8>, SkError> {
+  if !png.starts_with(SIGNATURE) || png.len() < SIGNATURE.len() + 8 {
+    return Err(SkError::Generic("Invalid png data".to_owned()));
+  }
+  let ihdr_length = u32::from_be_bytes([png[8], png[9], png[10], png[11]]) as usize;
+  let ihdr_end = SIGNATURE.len() + 12 + ihdr_length;
+  if png.len() < ihdr_end {
+    return Err(SkError::Generic("Invalid png data".to_owned()));
+  }
+  let chunks = metadata.to_chunks()?;
+  let mut output = Vec::with_capacity(png.len() + chunks.len());
+  output.extend_from_slice(&png[..ihdr_end]);
+  output.extend_from_slice(&chunks);
+  output.extend_from_slice(&png[ihdr_end..]);
+  Ok(output)
+}
+
+/// Combine the PNG `frames` into an APNG, every frame is shown for `delay_num / delay_den` seconds.
+/// The frames must have the same size, `plays` 0 means looping forever.
+/// https://wiki.mozilla.org/APNG_Specification
+pub fn assemble_apng(
+  frames: &[Vec<u8>],
+  delay_num: u16,
+  delay_den: u16,
+  plays: u32,
+) -> Result<Vec<u8>, SkError> {
+  let first = frames
+    .first()
+    .ok_or_else(|| SkError::Generic("The animation has no frames".to_owned()))?;
+  let first_chunks = read_chunks(first)?;
+  let ihdr = first_chunks[0].1;
+  let mut output = SIGNATURE.to_vec();
+  let mut sequence = 0u32;
+  for (i, frame) in frames.iter().enumerate() {
+    let chunks = if i == 0 {
+      first_chunks.clone()
+    } else {
+      read_chunks(frame)?
+    };
+    // the width and height are the first 8 bytes of IHDR
+    if chunks[0].1[..8] != ihdr[..8] {
+      return Err(SkError::Generic(format!(
+        "The size of frame {} is different from the first frame",
+        i
+      )));
+    }
+    let mut fctl = Vec::with_capacity(26);
+    fctl.extend_from_slice(&sequence.to_be_bytes());
+    // width, height
+    fctl.extend_from_slice(&ihdr[..8]);
+    // x and y offset
+    fctl.extend_from_slice(&[0; 8]);
+    fctl.extend_from_slice(&delay_num.to_be_bytes());
+    fctl.extend_from_slice(&delay_den.to_be_bytes());
+    // APNG_DISPOSE_OP_NONE, APNG_BLEND_OP_SOURCE
+    fctl.extend_from_slice(&[0, 0]);
+    sequence += 1;
+    if i == 0 {
+      for (chunk_type, data) in chunks.iter() {
+        match chunk_type {
+          b"IHDR" => {
+            write_chunk(&mut output, b"IHDR", data);
+            let mut actl = Vec::with_capacity(8);
+            actl.extend_from_slice(&(frames.len() as u32).to_be_bytes());
+            actl.extend_from_slice(&plays.to_be_bytes());
+            write_chunk(&mut output, b"acTL", &actl);
+          }
+          b"IDAT" => {
+            if !fctl.is_empty() {
+              write_chunk(&mut output, b"fcTL", &fctl);
+              fctl.clear();
+            }
+            write_chunk(&mut output, b"IDAT", data);
+          }
+          b"IEND" => {}
+          _ => write_chunk(&mut output, chunk_type, data),
+        }
+      }
+    } else {
+      write_chunk(&mut output, b"fcTL", &fctl);
+      for (_, data) in chunks
+        .iter()
+        .filter(|(chunk_type, _)| chunk_type == b"IDAT")
+      {
+        let mut fdat = Vec::with_capacity(data.len() + 4);
+        fdat.extend_from_slice(&sequence.to_be_bytes());
+        fdat.extend_from_slice(data);
+        write_chunk(&mut output, b"fdAT", &fdat);
+        sequence += 1;
+      }
+    }
+  }
+  write_chunk(&mut output, b"IEND", &[]);
+  Ok(output)
+}
+
+/// Encode the palette `indices` as an indexed color PNG, the bit depth is the smallest one the palette fits in.
+/// `deflate` compresses the scanlines into a zlib stream.
+pub fn encode_indexed(
+  width: u32,
+  height: u32,
+  palette: &[[u8; 4]],
+  indices: &[u8],
+  deflate: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Result<Vec<u8>, SkError> {
+  if palette.is_empty() || palette.len() > 256 || indices.len() != (width * height) as usize {
+    return Err(SkError::Generic("Invalid palette data".to_owned()));
+  }
+  let bit_depth: u8 = match palette.len() {
+    1..=2 => 1,
+    3..=4 => 2,
+    5..=16 => 4,
+    _ => 8,
+  };
+  let pixels_per_byte = (8 / bit_depth) as usize;
+  let row_bytes = (width as usize + pixels_per_byte - 1) / pixels_per_byte;
+  let mut scanlines = Vec::with_capacity((row_bytes + 1) * height as usize);
+  for row in indices.chunks_exact(width as usize) {
+    // filter type None, the filters rarely help the indexed color images
+    scanlines.push(0);
+    for packed in row.chunks(pixels_per_byte) {
+      let mut byte = 0u8;
+      for (i, index) in packed.iter().enumerate() {
+        byte |= index << (8 - bit_depth * (i as u8 + 1));
+      }
+      scanlines.push(byte);
+    }
+  }
+  let compressed =
+    deflate(&scanlines).ok_or_else(|| SkError::Generic("Compress png data failed".to_owned()))?;
+
+  let mut output = SIGNATURE.to_vec();
+  let mut ihdr = Vec::with_capacity(13);
+  ihdr.extend_from_slice(&width.to_be_bytes());
+  ihdr.extend_from_slice(&height.to_be_bytes());
+  // color type 3, deflate compression, adaptive filtering, no interlace
+  ihdr.extend_from_slice(&[bit_depth, 3, 0, 0, 0]);
+  write_chunk(&mut output, b"IHDR", &ihdr);
+  let plte = palette
+    .iter()
+    .flat_map(|color| [color[0], color[1], color[2]])
+    .collect::<Vec<u8>>();
+  write_chunk(&mut output, b"PLTE", &plte);
+  // the entries after the last translucent color are opaque
+  if let Some(last) = palette.iter().rposition(|color| color[3] != 255) {
+    let trns = palette[..=last]
+      .iter()
+      .map(|color| color[3])
+      .collect::<Vec<u8>>();
+    write_chunk(&mut output, b"tRNS", &trns);
+  }
+  write_chunk(&mut output, b"IDAT", &compressed);
+  write_chunk(&mut output, b"IEND", &[]);
+  Ok(output)
+}
+
+/// Type and data
+pub(crate) type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+/// Returns every chunk, the first one is always IHDR
+pub(crate) fn read_chunks(png: &[u8]) -> Result<Vec<Chunk<'_>>, SkError> {
+  if !png.starts_with(SIGNATURE) {
+    return Err(SkError::Generic("Invalid png data".to_owned()));
+  }
+  let mut chunks = Vec::new();
+  let mut offset = SIGNATURE.len();
+  while offset + 12 <= png.len() {
+    let length = u32::from_be_bytes([
+      png[offset],
+      png[offset + 1],
+      png[offset + 2],
+      png[offset + 3],
+    ]) as usize;
+    let data_start = offset + 8;
+    if data_start + length + 4 > png.len() {
+      return Err(SkError::Generic("Invalid png data".to_owned()));
+    }
+    let mut chunk_type = [0u8; 4];
+    chunk_type.copy_from_slice(&png[offset + 4..data_start]);
+    chunks.push((chunk_type, &png[data_start..data_start + length]));
+    offset = data_start + length + 4;
+  }
+  match chunks.first() {
+    Some((chunk_type, data)) if chunk_type == b"IHDR" && data.len() == 13 => Ok(chunks),
+    _ => Err(SkError::Generic("Invalid png data".to_owned())),
+  }
+}
+
+fn latin1(value: &str) -> Vec<u8> {
+  value.chars().map(|c| c as u8).collect()
+}
+
+pub(crate) fn write_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+  output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  let start = output.len();
+  output.extend_from_slice(chunk_type);
+  output.extend_from_slice(data);
+  let crc = crc32(&output[start..]);
+  output.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+  let mut crc = 0xffff_ffffu32;
+  for byte in data {
+    crc ^= *byte as u32;
+    for _ in 0..8 {
+      crc = if crc & 1 != 0 {
+        (crc >> 1) ^ 0xedb8_8320
+      } else {
+        crc >> 1
+      };
+    }
+  }
+  !crc
+}
+
+#[cfg(test)]
+fn minimal_png() -> Vec<u8> {
+  let mut png = SIGNATURE.to_vec();
+  write_chunk(&mut png, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+  write_chunk(&mut png, b"IEND", &[]);
+  png
+}
+
+#[test]
+fn test_crc32() {
+  assert_eq!(crc32(b"IEND"), 0xae42_6082);
+}
+
+#[test]
+fn test_insert_density() {
+  let png = minimal_png();
+  let metadata = PngMetadata {
+    density: Some(72.0),
+    ..Default::default()
+  };
+  let output = insert_metadata(&png, &metadata).unwrap();
+  assert_eq!(output.len(), png.len() + 21);
+  assert_eq!(&output[33..41], b"\x00\x00\x00\x09pHYs");
+  // 72 dpi = 2835 pixels per meter
+  assert_eq!(&output[41..50], &[0, 0, 11, 19, 0, 0, 11, 19, 1]);
+  assert_eq!(&output[54..], &png[33..]);
+}
+
+#[test]
+fn test_insert_text() {
+  let png = minimal_png();
+  let metadata = PngMetadata {
+    density: None,
+    text: vec![
+      ("Software".to_owned(), "canvas".to_owned()),
+      ("Title".to_owned(), "你好".to_owned()),
+    ],
+  };
+  let output = insert_metadata(&png, &metadata).unwrap();
+  assert_eq!(&output[37..41], b"tEXt");
+  assert_eq!(&output[41..56], b"Software\0canvas");
+  assert_eq!(&output[64..68], b"iTXt");
+  assert_eq!(&output[68..78], b"Title\0\0\0\0\0");
+  assert_eq!(&output[78..84], "你好".as_bytes());
+}
+
+#[test]
+fn test_reject_invalid_metadata() {
+  let png = minimal_png();
+  let keyword = PngMetadata {
+    density: None,
+    text: vec![("".to_owned(), "value".to_owned())],
+  };
+  assert!(insert_metadata(&png, &keyword).is_err());
+  let density = PngMetadata {
+    density: Some(-1.0),
+    ..Default::default()
+  };
+  assert!(insert_metadata(&png, &density).is_err());
+  assert!(insert_metadata(b"not a png", &PngMetadata::default()).is_err());
+}
+
+#[cfg(test)]
+fn frame_png(width: u8, idat: &[&[u8]]) -> Vec<u8> {
+  let mut png = SIGNATURE.to_vec();
+  write_chunk(
+    &mut png,
+    b"IHDR",
+    &[0, 0, 0, width, 0, 0, 0, 1, 8, 6, 0, 0, 0],
+  );
+  for data in idat {
+    write_chunk(&mut png, b"IDAT", data);
+  }
+  write_chunk(&mut png, b"IEND", &[]);
+  png
+}
+
+#[test]
+fn test_assemble_apng() {
+  let frames = vec![frame_png(1, &[b"a"]), frame_png(1, &[b"b", b"c"])];
+  let output = assemble_apng(&frames, 1, 30, 0).unwrap();
+  let chunks = read_chunks(&output).unwrap();
+  let types = chunks
+    .iter()
+    .map(|(chunk_type, _)| std::str::from_utf8(chunk_type).unwrap())
+    .collect::<Vec<&str>>();
+  assert_eq!(
+    types,
+    vec!["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fdAT", "IEND"]
+  );
+  // 2 frames, loop forever
+  assert_eq!(chunks[1].1, &[0, 0, 0, 2, 0, 0, 0, 0]);
+  assert_eq!(&chunks[2].1[..4], &[0, 0, 0, 0]);
+  // delay 1/30s
+  assert_eq!(&chunks[2].1[20..24], &[0, 1, 0, 30]);
+  assert_eq!(chunks[3].1, b"a");
+  assert_eq!(&chunks[4].1[..4], &[0, 0, 0, 1]);
+  assert_eq!(chunks[5].1, b"\0\0\0\x02b");
+  assert_eq!(chunks[6].1, b"\0\0\0\x03c");
+}
+
+#[test]
+fn test_reject_invalid_apng_frames() {
+  assert!(assemble_apng(&[], 1, 30, 0).is_err());
+  let frames = vec![frame_png(1, &[b"a"]), frame_png(2, &[b"b"])];
+  assert!(assemble_apng(&frames, 1, 30, 0).is_err());
+  assert!(assemble_apng(&[b"not a png".to_vec()], 1, 30, 0).is_err());
+}
+
+#[test]
+fn test_encode_indexed() {
+  let palette = [[0, 0, 0, 0], [255, 0, 0, 128], [0, 0, 255, 255]];
+  // 2 bits per pixel, each 3-pixel row packs into one byte after the filter byte
+  let indices = [0, 1, 2, 2, 1, 0];
+  let output = encode_indexed(3, 2, &palette, &indices, |data| Some(data.to_vec())).unwrap();
+  let chunks = read_chunks(&output).unwrap();
+  assert_eq!(&chunks[0].1[8..10], &[2, 3]);
+  assert_eq!(&chunks[1].0, b"PLTE");
+  assert_eq!(chunks[1].1, &[0, 0, 0, 255, 0, 0, 0, 0, 255]);
+  assert_eq!(&chunks[2].0, b"tRNS");
+  assert_eq!(chunks[2].1, &[0, 128]);
+  assert_eq!(&chunks[3].0, b"IDAT");
+  assert_eq!(chunks[3].1, &[0, 0b0001_1000, 0, 0b1001_0000]);
+  assert!(encode_indexed(3, 2, &palette, &indices[..5], |data| Some(data.to_vec())).is_err());
+}
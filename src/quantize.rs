@@ -0,0 +1,309 @@
+// Reduce the colors of RGBA pixels to a palette for the indexed color PNG output.
+// The palette is built with median cut, the exact colors are kept if there are not more than the max colors.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::error::SkError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+  None,
+  /// 4x4 Bayer matrix
+  Ordered,
+  FloydSteinberg,
+}
+
+impl Default for Dither {
+  fn default() -> Self {
+    Self::None
+  }
+}
+
+impl FromStr for Dither {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "none" => Ok(Self::None),
+      "ordered" => Ok(Self::Ordered),
+      "floyd-steinberg" => Ok(Self::FloydSteinberg),
+      _ => Err(SkError::Generic(format!("Invalid dither: {}", value))),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteOptions {
+  /// 2 - 256
+  pub max_colors: u32,
+  pub dither: Dither,
+}
+
+pub struct Quantized {
+  /// The colors with alpha come first, so the tRNS chunk could be shorter
+  pub palette: Vec<[u8; 4]>,
+  pub indices: Vec<u8>,
+}
+
+const BAYER_4X4: [[f32; 4]; 4] = [
+  [0.0, 8.0, 2.0, 10.0],
+  [12.0, 4.0, 14.0, 6.0],
+  [3.0, 11.0, 1.0, 9.0],
+  [15.0, 7.0, 13.0, 5.0],
+];
+
+/// Map the unpremultiplied RGBA `pixels` to a palette of at most `options.max_colors` colors
+pub fn quantize(pixels: &[u8], width: u32, height: u32, options: &PaletteOptions) -> Quantized {
+  let max_colors = options.max_colors.clamp(2, 256) as usize;
+  let mut histogram: HashMap<[u8; 4], u32> = HashMap::new();
+  for pixel in pixels.chunks_exact(4) {
+    *histogram.entry(normalize(pixel)).or_insert(0) += 1;
+  }
+  let exact = histogram.len() <= max_colors;
+  let mut palette = if exact {
+    histogram.keys().copied().collect::<Vec<[u8; 4]>>()
+  } else {
+    median_cut(histogram.into_iter().collect(), max_colors)
+  };
+  palette.sort_by_key(|color| (color[3] == 255, *color));
+  // dithering doesn't change anything if all the colors are in the palette
+  let dither = if exact { Dither::None } else { options.dither };
+  let indices = map_to_palette(pixels, width, height, &palette, dither);
+  Quantized { palette, indices }
+}
+
+/// Returns the index of the palette color for every pixel
+fn map_to_palette(
+  pixels: &[u8],
+  width: u32,
+  height: u32,
+  palette: &[[u8; 4]],
+  dither: Dither,
+) -> Vec<u8> {
+  let mut nearest_cache: HashMap<[u8; 4], u8> = HashMap::new();
+  let mut nearest = |color: [u8; 4]| -> u8 {
+    *nearest_cache
+      .entry(color)
+      .or_insert_with(|| nearest_index(palette, color))
+  };
+  let (width, height) = (width as usize, height as usize);
+  let mut indices = Vec::with_capacity(width * height);
+  match dither {
+    Dither::None => indices.extend(pixels.chunks_exact(4).map(|p| nearest(normalize(p)))),
+    Dither::Ordered => {
+      // about the distance between the palette colors on every channel
+      let spread = 255.0 / (palette.len() as f32).cbrt();
+      for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+        let offset = ((BAYER_4X4[(i / width) % 4][(i % width) % 4] + 0.5) / 16.0 - 0.5) * spread;
+        let mut color = normalize(pixel);
+        if color[3] != 0 {
+          for c in color.iter_mut().take(3) {
+            *c = (*c as f32 + offset).round().clamp(0.0, 255.0) as u8;
+          }
+        }
+        indices.push(nearest(color));
+      }
+    }
+    Dither::FloydSteinberg => {
+      let mut errors = vec![[0f32; 4]; width * 2];
+      for y in 0..height {
+        let (current, next) = errors.split_at_mut(width);
+        for x in 0..width {
+          let i = y * width + x;
+          let mut target = [0f32; 4];
+          let mut color = [0u8; 4];
+          for c in 0..4 {
+            target[c] = (pixels[i * 4 + c] as f32 + current[x][c]).clamp(0.0, 255.0);
+            color[c] = target[c].round() as u8;
+          }
+          let index = nearest(normalize(&color));
+          indices.push(index);
+          let chosen = palette[index as usize];
+          for c in 0..4 {
+            let error = target[c] - chosen[c] as f32;
+            if x + 1 < width {
+              current[x + 1][c] += error * 7.0 / 16.0;
+              next[x + 1][c] += error / 16.0;
+            }
+            if x > 0 {
+              next[x - 1][c] += error * 3.0 / 16.0;
+            }
+            next[x][c] += error * 5.0 / 16.0;
+          }
+        }
+        // the next row becomes the current row
+        errors.rotate_left(width);
+        errors[width..].iter_mut().for_each(|e| *e = [0.0; 4]);
+      }
+    }
+  }
+  indices
+}
+
+/// The color of the fully transparent pixels doesn't matter
+fn normalize(pixel: &[u8]) -> [u8; 4] {
+  if pixel[3] == 0 {
+    [0, 0, 0, 0]
+  } else {
+    [pixel[0], pixel[1], pixel[2], pixel[3]]
+  }
+}
+
+fn nearest_index(palette: &[[u8; 4]], color: [u8; 4]) -> u8 {
+  let mut best = 0;
+  let mut best_distance = u32::MAX;
+  for (i, candidate) in palette.iter().enumerate() {
+    let distance = candidate
+      .iter()
+      .zip(color.iter())
+      .map(|(a, b)| {
+        let d = *a as i32 - *b as i32;
+        (d * d) as u32
+      })
+      .sum::<u32>();
+    if distance < best_distance {
+      best = i;
+      best_distance = distance;
+    }
+  }
+  best as u8
+}
+
+/// Split the box with the widest channel range at the weighted median until there are `max_colors` boxes,
+/// every box becomes the weighted average of its colors
+fn median_cut(colors: Vec<([u8; 4], u32)>, max_colors: usize) -> Vec<[u8; 4]> {
+  let mut boxes = vec![colors];
+  while boxes.len() < max_colors {
+    let widest = boxes
+      .iter()
+      .enumerate()
+      .filter(|(_, colors)| colors.len() > 1)
+      .map(|(i, colors)| {
+        let (channel, range) = widest_channel(colors);
+        (i, channel, range)
+      })
+      .max_by_key(|(_, _, range)| *range);
+    let (i, channel) = match widest {
+      Some((i, channel, _)) => (i, channel),
+      None => break,
+    };
+    let mut colors = boxes.swap_remove(i);
+    colors.sort_unstable_by_key(|(color, _)| color[channel]);
+    let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+    let mut accumulated = 0u64;
+    let mut split = 1;
+    for (j, (_, count)) in colors.iter().enumerate() {
+      accumulated += *count as u64;
+      if accumulated * 2 >= total {
+        split = j + 1;
+        break;
+      }
+    }
+    let split = split.clamp(1, colors.len() - 1);
+    let rest = colors.split_off(split);
+    boxes.push(colors);
+    boxes.push(rest);
+  }
+  boxes
+    .iter()
+    .map(|colors| {
+      let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+      let mut sum = [0u64; 4];
+      for (color, count) in colors.iter() {
+        for c in 0..4 {
+          sum[c] += color[c] as u64 * *count as u64;
+        }
+      }
+      let mut average = [0u8; 4];
+      for c in 0..4 {
+        average[c] = ((sum[c] + total / 2) / total) as u8;
+      }
+      average
+    })
+    .collect()
+}
+
+fn widest_channel(colors: &[([u8; 4], u32)]) -> (usize, u8) {
+  (0..4)
+    .map(|c| {
+      let min = colors.iter().map(|(color, _)| color[c]).min().unwrap_or(0);
+      let max = colors.iter().map(|(color, _)| color[c]).max().unwrap_or(0);
+      (c, max - min)
+    })
+    .max_by_key(|(_, range)| *range)
+    .unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+fn gradient(width: u32) -> Vec<u8> {
+  (0..width)
+    .flat_map(|x| {
+      let v = (x * 255 / (width - 1)) as u8;
+      [v, v, v, 255]
+    })
+    .collect()
+}
+
+#[test]
+fn test_exact_palette() {
+  let pixels = [
+    255u8, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255, 10, 20, 30, 0,
+  ];
+  let options = PaletteOptions {
+    max_colors: 4,
+    dither: Dither::FloydSteinberg,
+  };
+  let quantized = quantize(&pixels, 4, 1, &options);
+  assert_eq!(
+    quantized.palette,
+    vec![[0, 0, 0, 0], [0, 0, 255, 255], [255, 0, 0, 255]]
+  );
+  assert_eq!(quantized.indices, vec![2, 1, 2, 0]);
+}
+
+#[test]
+fn test_median_cut() {
+  let pixels = gradient(256);
+  for dither in [Dither::None, Dither::Ordered, Dither::FloydSteinberg] {
+    let options = PaletteOptions {
+      max_colors: 16,
+      dither,
+    };
+    let quantized = quantize(&pixels, 256, 1, &options);
+    assert_eq!(quantized.palette.len(), 16);
+    assert!(quantized.indices.iter().all(|i| (*i as usize) < 16));
+  }
+  let options = PaletteOptions {
+    max_colors: 2,
+    dither: Dither::None,
+  };
+  let quantized = quantize(&pixels, 256, 1, &options);
+  // the dark half and the light half
+  assert!(quantized.palette[0][0] < 128 && quantized.palette[1][0] > 128);
+  assert_eq!(quantized.indices[0], 0);
+  assert_eq!(quantized.indices[255], 1);
+}
+
+#[test]
+fn test_dither_keeps_average() {
+  // 50% gray with a black and white palette
+  let pixels = [128u8, 128, 128, 255].repeat(16 * 16);
+  let palette = [[0, 0, 0, 255], [255, 255, 255, 255]];
+  let none = map_to_palette(&pixels, 16, 16, &palette, Dither::None);
+  assert!(none.iter().all(|i| *i == 1));
+  for dither in [Dither::Ordered, Dither::FloydSteinberg] {
+    let indices = map_to_palette(&pixels, 16, 16, &palette, dither);
+    let white = indices.iter().filter(|i| **i == 1).count();
+    assert!((112..=144).contains(&white));
+  }
+}
+
+#[test]
+fn test_parse_dither() {
+  assert_eq!(
+    Dither::from_str("floyd-steinberg").ok(),
+    Some(Dither::FloydSteinberg)
+  );
+  assert!(Dither::from_str("atkinson").is_err());
+}
@@ -0,0 +1,104 @@
+use napi::bindgen_prelude::*;
+
+use crate::image::ImageData;
+use crate::sk::{ColorSpace, PixelsReadback as SkPixelsReadback, Surface};
+
+enum Pixels {
+  Pending(SkPixelsReadback),
+  /// The rect outside of the canvas is read synchronously, like `getImageData`
+  Ready(Vec<u8>),
+}
+
+/// The canvas pixels read without blocking the thread on the GPU, polled by `getImageDataAsync` and `encodeAsync`
+#[napi]
+pub struct PixelsReadback {
+  /// `None` after the pixels are taken
+  pixels: Option<Pixels>,
+  width: u32,
+  height: u32,
+  color_space: ColorSpace,
+}
+
+impl PixelsReadback {
+  pub(crate) fn new(
+    surface: &Surface,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+  ) -> Result<Self> {
+    let inside =
+      x.saturating_add(width) <= surface.width() && y.saturating_add(height) <= surface.height();
+    let pixels = if inside {
+      Pixels::Pending(SkPixelsReadback::new(
+        surface,
+        x,
+        y,
+        width,
+        height,
+        color_space,
+      ))
+    } else {
+      Pixels::Ready(
+        surface
+          .read_pixels(x, y, width, height, color_space)
+          .ok_or_else(read_failed)?,
+      )
+    };
+    Ok(Self {
+      pixels: Some(pixels),
+      width,
+      height,
+      color_space,
+    })
+  }
+
+  /// The raster copy of the pixels, encoded instead of the GPU canvas
+  pub(crate) fn take_surface(&mut self) -> Result<Surface> {
+    let surface = match &self.pixels {
+      Some(Pixels::Pending(readback)) => readback.to_surface(self.color_space),
+      _ => None,
+    };
+    self.pixels = None;
+    surface.ok_or_else(read_failed)
+  }
+}
+
+#[napi]
+impl PixelsReadback {
+  /// Whether the pixels are ready to be taken, or taking them fails without waiting
+  #[napi]
+  pub fn poll(&self) -> bool {
+    match &self.pixels {
+      Some(Pixels::Pending(readback)) => readback.poll(),
+      Some(Pixels::Ready(_)) => true,
+      // already taken, `takeImageData` rejects
+      None => true,
+    }
+  }
+
+  #[napi]
+  pub fn take_image_data(&mut self, env: Env) -> Result<ClassInstance<ImageData>> {
+    let pixels = match self.pixels.take() {
+      Some(Pixels::Pending(readback)) => readback.pixels(),
+      Some(Pixels::Ready(pixels)) => Some(pixels),
+      None => None,
+    }
+    .ok_or_else(read_failed)?;
+    ImageData::from_pixels(
+      env,
+      pixels,
+      self.width as usize,
+      self.height as usize,
+      self.color_space,
+    )
+  }
+}
+
+fn read_failed() -> Error {
+  Error::new(
+    Status::GenericFailure,
+    "Read pixels from canvas failed".to_owned(),
+  )
+}
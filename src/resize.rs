@@ -0,0 +1,315 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use crate::error::SkError;
+
+/// The resized images larger than this are rejected, 16384 x 16384
+pub const MAX_RESIZE_PIXELS: usize = 1 << 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeFilter {
+  Nearest,
+  Linear,
+  Mitchell,
+  Lanczos,
+}
+
+impl Default for ResizeFilter {
+  fn default() -> Self {
+    Self::Lanczos
+  }
+}
+
+impl FromStr for ResizeFilter {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "nearest" => Ok(Self::Nearest),
+      "linear" => Ok(Self::Linear),
+      "mitchell" => Ok(Self::Mitchell),
+      "lanczos" => Ok(Self::Lanczos),
+      _ => Err(SkError::Generic(format!(
+        "Invalid resize filter: {}",
+        value
+      ))),
+    }
+  }
+}
+
+impl ResizeFilter {
+  fn support(&self) -> f32 {
+    match self {
+      Self::Nearest => 0.5,
+      Self::Linear => 1.0,
+      Self::Mitchell => 2.0,
+      Self::Lanczos => 3.0,
+    }
+  }
+
+  fn weight(&self, x: f32) -> f32 {
+    let x = x.abs();
+    match self {
+      Self::Nearest => {
+        if x <= 0.5 {
+          1.0
+        } else {
+          0.0
+        }
+      }
+      Self::Linear => (1.0 - x).max(0.0),
+      Self::Mitchell => {
+        // B = C = 1/3
+        const B: f32 = 1.0 / 3.0;
+        const C: f32 = 1.0 / 3.0;
+        if x < 1.0 {
+          ((12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B))
+            / 6.0
+        } else if x < 2.0 {
+          ((-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+        } else {
+          0.0
+        }
+      }
+      Self::Lanczos => {
+        if x < f32::EPSILON {
+          1.0
+        } else if x < 3.0 {
+          let px = PI * x;
+          3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+        } else {
+          0.0
+        }
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeFit {
+  /// Stretch to the target size, ignoring the aspect ratio
+  Fill,
+  /// Keep the aspect ratio and letterbox with transparent pixels
+  Contain,
+  /// Keep the aspect ratio and crop the overflowing part
+  Cover,
+}
+
+impl Default for ResizeFit {
+  fn default() -> Self {
+    Self::Fill
+  }
+}
+
+impl FromStr for ResizeFit {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "fill" => Ok(Self::Fill),
+      "contain" => Ok(Self::Contain),
+      "cover" => Ok(Self::Cover),
+      _ => Err(SkError::Generic(format!("Invalid resize fit: {}", value))),
+    }
+  }
+}
+
+/// The byte length of the RGBA pixels, `None` if it overflows or the size is larger than `MAX_RESIZE_PIXELS`
+pub fn rgba_len(width: u32, height: u32) -> Option<usize> {
+  (width as usize)
+    .checked_mul(height as usize)
+    .filter(|pixels| *pixels <= MAX_RESIZE_PIXELS)
+    .and_then(|pixels| pixels.checked_mul(4))
+}
+
+/// Resize the unpremultiplied RGBA pixels to `width` x `height`.
+/// The size must be checked by `rgba_len` first.
+/// The filter kernel is widened when downscaling, so every source pixel contributes to the output.
+pub fn resize_rgba(
+  pixels: &[u8],
+  src_width: u32,
+  src_height: u32,
+  width: u32,
+  height: u32,
+  fit: ResizeFit,
+  filter: ResizeFilter,
+) -> Vec<u8> {
+  let mut output = vec![0u8; width as usize * height as usize * 4];
+  if src_width == 0 || src_height == 0 || width == 0 || height == 0 {
+    return output;
+  }
+  let (sx, sy, sw, sh) = if fit == ResizeFit::Cover {
+    let scale = (width as f32 / src_width as f32).max(height as f32 / src_height as f32);
+    let sw = (width as f32 / scale).min(src_width as f32);
+    let sh = (height as f32 / scale).min(src_height as f32);
+    (
+      (src_width as f32 - sw) / 2.0,
+      (src_height as f32 - sh) / 2.0,
+      sw,
+      sh,
+    )
+  } else {
+    (0.0, 0.0, src_width as f32, src_height as f32)
+  };
+  let (dx, dy, dw, dh) = if fit == ResizeFit::Contain {
+    let scale = (width as f32 / src_width as f32).min(height as f32 / src_height as f32);
+    let dw = ((src_width as f32 * scale).round() as u32).clamp(1, width);
+    let dh = ((src_height as f32 * scale).round() as u32).clamp(1, height);
+    ((width - dw) / 2, (height - dh) / 2, dw, dh)
+  } else {
+    (0, 0, width, height)
+  };
+
+  let premultiplied = pixels
+    .chunks_exact(4)
+    .flat_map(|p| {
+      let alpha = p[3] as f32 / 255.0;
+      [
+        p[0] as f32 * alpha,
+        p[1] as f32 * alpha,
+        p[2] as f32 * alpha,
+        p[3] as f32,
+      ]
+    })
+    .collect::<Vec<f32>>();
+
+  // horizontal pass
+  let x_weights = compute_weights(sx, sw, src_width, dw, filter);
+  let mut horizontal = vec![0f32; dw as usize * src_height as usize * 4];
+  for y in 0..src_height as usize {
+    let src_row = &premultiplied[y * src_width as usize * 4..(y + 1) * src_width as usize * 4];
+    for (x, (start, weights)) in x_weights.iter().enumerate() {
+      let target = (y * dw as usize + x) * 4;
+      for (i, weight) in weights.iter().enumerate() {
+        let source = (start + i) * 4;
+        for c in 0..4 {
+          horizontal[target + c] += src_row[source + c] * weight;
+        }
+      }
+    }
+  }
+
+  // vertical pass
+  let y_weights = compute_weights(sy, sh, src_height, dh, filter);
+  for (y, (start, weights)) in y_weights.iter().enumerate() {
+    for x in 0..dw as usize {
+      let mut rgba = [0f32; 4];
+      for (i, weight) in weights.iter().enumerate() {
+        let source = ((start + i) * dw as usize + x) * 4;
+        for (c, value) in rgba.iter_mut().enumerate() {
+          *value += horizontal[source + c] * weight;
+        }
+      }
+      let alpha = rgba[3].clamp(0.0, 255.0);
+      let target = ((y + dy as usize) * width as usize + x + dx as usize) * 4;
+      if alpha > 0.0 {
+        let unpremultiply = 255.0 / alpha;
+        for c in 0..3 {
+          output[target + c] = (rgba[c] * unpremultiply).round().clamp(0.0, 255.0) as u8;
+        }
+      }
+      output[target + 3] = alpha.round() as u8;
+    }
+  }
+  output
+}
+
+/// Returns the first source index and the normalized weights for every output pixel
+fn compute_weights(
+  src_offset: f32,
+  src_size: f32,
+  src_length: u32,
+  dst_size: u32,
+  filter: ResizeFilter,
+) -> Vec<(usize, Vec<f32>)> {
+  let scale = src_size / dst_size as f32;
+  let filter_scale = scale.max(1.0);
+  let support = filter.support() * filter_scale;
+  (0..dst_size)
+    .map(|i| {
+      let center = src_offset + (i as f32 + 0.5) * scale;
+      let start = ((center - support).floor().max(0.0) as usize).min(src_length as usize - 1);
+      let end = ((center + support).ceil() as usize).clamp(start + 1, src_length as usize);
+      let mut weights = (start..end)
+        .map(|j| filter.weight((j as f32 + 0.5 - center) / filter_scale))
+        .collect::<Vec<f32>>();
+      let sum: f32 = weights.iter().sum();
+      if sum.abs() > f32::EPSILON {
+        weights.iter_mut().for_each(|w| *w /= sum);
+      } else {
+        // the kernel doesn't cover any pixel center, fallback to the nearest pixel
+        weights.iter_mut().for_each(|w| *w = 0.0);
+        let nearest = (center.floor() as usize).clamp(start, end - 1);
+        weights[nearest - start] = 1.0;
+      }
+      (start, weights)
+    })
+    .collect()
+}
+
+#[test]
+fn test_resize_solid_color() {
+  let pixels = [10u8, 20, 30, 255].repeat(16 * 16);
+  for filter in [
+    ResizeFilter::Nearest,
+    ResizeFilter::Linear,
+    ResizeFilter::Mitchell,
+    ResizeFilter::Lanczos,
+  ] {
+    let output = resize_rgba(&pixels, 16, 16, 5, 3, ResizeFit::Fill, filter);
+    assert_eq!(output, [10u8, 20, 30, 255].repeat(5 * 3));
+  }
+}
+
+#[test]
+fn test_resize_premultiplied() {
+  // the color of the transparent pixel must not bleed into the result
+  let pixels = [255u8, 0, 0, 255, 0, 255, 0, 0];
+  let output = resize_rgba(&pixels, 2, 1, 1, 1, ResizeFit::Fill, ResizeFilter::Linear);
+  assert_eq!(output, vec![255, 0, 0, 128]);
+}
+
+#[test]
+fn test_resize_fit() {
+  let pixels = [255u8; 4 * 4 * 2];
+  let contain = resize_rgba(
+    &pixels,
+    4,
+    2,
+    4,
+    4,
+    ResizeFit::Contain,
+    ResizeFilter::Lanczos,
+  );
+  let alpha = contain.chunks_exact(4).map(|p| p[3]).collect::<Vec<u8>>();
+  assert_eq!(
+    alpha,
+    vec![0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 0]
+  );
+  let cover = resize_rgba(&pixels, 4, 2, 4, 4, ResizeFit::Cover, ResizeFilter::Lanczos);
+  assert!(cover.iter().all(|v| *v == 255));
+}
+
+#[test]
+fn test_parse_resize_options() {
+  assert_eq!(
+    ResizeFilter::from_str("lanczos").ok(),
+    Some(ResizeFilter::Lanczos)
+  );
+  assert_eq!(ResizeFit::from_str("cover").ok(), Some(ResizeFit::Cover));
+  assert!(ResizeFilter::from_str("bicubic").is_err());
+}
+
+#[test]
+fn test_rgba_len() {
+  assert_eq!(rgba_len(3, 2), Some(24));
+  assert_eq!(rgba_len(16384, 16384), Some(1 << 30));
+  assert_eq!(rgba_len(16384, 16385), None);
+  assert_eq!(rgba_len(u32::MAX, u32::MAX), None);
+}
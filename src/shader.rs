@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use napi::bindgen_prelude::*;
+
+use crate::error::SkError;
+use crate::sk::{FilterQuality, RuntimeEffect, RuntimeEffectUniform, Shader, Surface, Transform};
+
+#[derive(Debug, Clone)]
+pub struct Effect {
+  /// Shared by the fill and stroke styles it's assigned to
+  runtime_effect: Rc<RuntimeEffect>,
+  /// The int uniforms are stored as their bits
+  uniforms: Vec<f32>,
+}
+
+impl Effect {
+  /// The first child shader samples the pixels of `input`, the others are transparent
+  pub(crate) fn get_shader(&self, input: Option<&Surface>) -> Option<Shader> {
+    let input = match input {
+      Some(surface) => Some(Shader::new_from_surface_image(
+        surface,
+        Transform::default(),
+        FilterQuality::None,
+      )?),
+      None => None,
+    };
+    self
+      .runtime_effect
+      .make_shader(&self.uniforms, input.as_ref())
+  }
+}
+
+/// The SkSL shader created by `createShaderEffect`, it's assigned to `fillStyle` and `strokeStyle`
+/// or filters the canvas by `applyShaderEffect`
+#[napi]
+pub struct ShaderEffect {
+  pub(crate) inner: Effect,
+  layout: Vec<RuntimeEffectUniform>,
+}
+
+impl ShaderEffect {
+  pub(crate) fn new(
+    sksl: &str,
+    uniforms: Option<HashMap<String, Either<f64, Vec<f64>>>>,
+  ) -> Result<Self> {
+    let runtime_effect = RuntimeEffect::new(sksl)?;
+    let mut effect = Self {
+      layout: runtime_effect.uniforms(),
+      inner: Effect {
+        uniforms: vec![0.0; runtime_effect.uniform_size()],
+        runtime_effect: Rc::new(runtime_effect),
+      },
+    };
+    if let Some(uniforms) = uniforms {
+      effect.set_uniforms(uniforms)?;
+    }
+    Ok(effect)
+  }
+}
+
+#[napi]
+impl ShaderEffect {
+  /// Update the uniforms by the names, the styles assigned before are not changed
+  #[napi]
+  pub fn set_uniforms(&mut self, uniforms: HashMap<String, Either<f64, Vec<f64>>>) -> Result<()> {
+    for (name, value) in uniforms {
+      let values = match value {
+        Either::A(value) => vec![value],
+        Either::B(values) => values,
+      };
+      write_uniform(&self.layout, &mut self.inner.uniforms, &name, &values)
+        .map_err(|err| Error::new(Status::InvalidArg, err.to_string()))?;
+    }
+    Ok(())
+  }
+}
+
+/// The values of the uniform are written in place, the unset uniforms are zeros
+fn write_uniform(
+  layout: &[RuntimeEffectUniform],
+  uniforms: &mut [f32],
+  name: &str,
+  values: &[f64],
+) -> std::result::Result<(), SkError> {
+  let uniform = layout
+    .iter()
+    .find(|uniform| uniform.name == name)
+    .ok_or_else(|| SkError::Generic(format!("{} is not a uniform of the shader effect", name)))?;
+  if values.len() != uniform.count {
+    return Err(SkError::Generic(format!(
+      "The uniform {} has {} values, but {} are given",
+      name,
+      uniform.count,
+      values.len()
+    )));
+  }
+  for (target, value) in uniforms[uniform.offset..uniform.offset + uniform.count]
+    .iter_mut()
+    .zip(values)
+  {
+    *target = if uniform.is_int {
+      f32::from_bits(*value as i32 as u32)
+    } else {
+      *value as f32
+    };
+  }
+  Ok(())
+}
+
+#[test]
+fn test_write_uniform() {
+  // uniform float time; uniform float2 resolution; uniform float4 colors[2];
+  let layout = [
+    RuntimeEffectUniform {
+      name: "time".to_owned(),
+      offset: 0,
+      count: 1,
+      is_int: false,
+    },
+    RuntimeEffectUniform {
+      name: "resolution".to_owned(),
+      offset: 1,
+      count: 2,
+      is_int: false,
+    },
+    RuntimeEffectUniform {
+      name: "colors".to_owned(),
+      offset: 3,
+      count: 8,
+      is_int: false,
+    },
+  ];
+  let mut uniforms = vec![0.0; 11];
+  write_uniform(&layout, &mut uniforms, "resolution", &[640.0, 480.0]).unwrap();
+  write_uniform(&layout, &mut uniforms, "time", &[0.5]).unwrap();
+  assert_eq!(&uniforms[..4], &[0.5, 640.0, 480.0, 0.0]);
+  write_uniform(&layout, &mut uniforms, "colors", &[1.0; 8]).unwrap();
+  assert_eq!(&uniforms[3..], &[1.0; 8]);
+}
+
+#[test]
+fn test_write_int_uniform() {
+  // uniform int2 steps;
+  let layout = [RuntimeEffectUniform {
+    name: "steps".to_owned(),
+    offset: 0,
+    count: 2,
+    is_int: true,
+  }];
+  let mut uniforms = vec![0.0; 2];
+  write_uniform(&layout, &mut uniforms, "steps", &[3.0, -1.0]).unwrap();
+  let bits: Vec<i32> = uniforms.iter().map(|v| v.to_bits() as i32).collect();
+  assert_eq!(bits, vec![3, -1]);
+}
+
+#[test]
+fn test_write_invalid_uniform() {
+  let layout = [RuntimeEffectUniform {
+    name: "resolution".to_owned(),
+    offset: 0,
+    count: 2,
+    is_int: false,
+  }];
+  let mut uniforms = vec![0.0; 2];
+  assert!(write_uniform(&layout, &mut uniforms, "time", &[0.5]).is_err());
+  assert!(write_uniform(&layout, &mut uniforms, "resolution", &[640.0]).is_err());
+  assert_eq!(uniforms, vec![0.0; 2]);
+}
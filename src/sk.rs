@@ -2,6 +2,7 @@ use std::convert::TryFrom;
 use std::f32::consts::PI;
 use std::ffi::{c_void, CStr, CString, NulError};
 use std::fmt;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_char;
 use std::ptr;
@@ -10,7 +11,10 @@ use std::str::FromStr;
 
 use crate::error::SkError;
 use crate::font::{FontStretch, FontStyle};
+use crate::gpu::GpuDevice;
 use crate::image::ImageData;
+use crate::pdf::{PdfDateTime, PdfMetadata};
+use crate::svg_path::{self, Segment};
 
 pub mod ffi {
   use std::ffi::c_void;
@@ -30,6 +34,63 @@ pub mod ffi {
     _unused: [u8; 0],
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_w_file_stream {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_pdf_document {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_svg_font_recorder {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_picture_recorder {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_picture {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_gpu_context {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_pixels_readback {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_runtime_effect {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_runtime_effect_uniform {
+    pub name: *const c_char,
+    pub offset: usize,
+    pub count: usize,
+    pub is_int: bool,
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_svg_surface {
@@ -38,6 +99,51 @@ pub mod ffi {
     pub canvas: *mut skiac_canvas,
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug, Default)]
+  pub struct skiac_pdf_date_time {
+    pub time_zone_minutes: i16,
+    pub year: u16,
+    pub month: u8,
+    pub day_of_week: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_pdf_metadata {
+    pub title: *const c_char,
+    pub author: *const c_char,
+    pub subject: *const c_char,
+    pub keywords: *const c_char,
+    pub creator: *const c_char,
+    pub producer: *const c_char,
+    pub has_creation: bool,
+    pub creation: skiac_pdf_date_time,
+    pub has_modified: bool,
+    pub modified: skiac_pdf_date_time,
+    pub pdf_a: bool,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_pdf_surface {
+    pub document: *mut skiac_pdf_document,
+    pub surface: *mut skiac_surface,
+    pub canvas: *mut skiac_canvas,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_picture_surface {
+    pub recorder: *mut skiac_picture_recorder,
+    pub surface: *mut skiac_surface,
+    pub canvas: *mut skiac_canvas,
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_canvas {
@@ -86,6 +192,12 @@ pub mod ffi {
     _unused: [u8; 0],
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_color_filter {
+    _unused: [u8; 0],
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_data {
@@ -118,6 +230,26 @@ pub mod ffi {
     _unused: [u8; 0],
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_surface_props {
+    pub sample_count: i32,
+    pub pixel_geometry: u8,
+    pub color_type: i32,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_external_texture {
+    pub kind: u8,
+    pub fd: i32,
+    pub fourcc: u32,
+    pub stride: u32,
+    pub offset: u32,
+    pub modifier: u64,
+    pub io_surface: u32,
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_rect {
@@ -145,6 +277,33 @@ pub mod ffi {
     pub y: f32,
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_png_options {
+    pub zlib_level: i32,
+    pub filter_flags: i32,
+    pub bit_depth: i32,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_jpeg_options {
+    pub quality: i32,
+    pub downsample: i32,
+    pub has_background: bool,
+    pub background: u32,
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_sampling_options {
+    pub filter_mode: i32,
+    pub mipmap_mode: i32,
+    pub use_cubic: bool,
+    pub cubic_b: f32,
+    pub cubic_c: f32,
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_surface_data {
@@ -196,6 +355,26 @@ pub mod ffi {
     _unused: [u8; 0],
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_image_decoder {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_stream_encoder {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Debug, Clone, Copy, Default)]
+  pub struct skiac_decode_progress {
+    pub width: i32,
+    pub height: i32,
+    pub decoded_rows: i32,
+  }
+
   #[repr(C)]
   #[derive(Debug, Clone, Copy)]
   pub struct skiac_mapped_point {
@@ -263,8 +442,69 @@ pub mod ffi {
       cs: u8,
     );
 
+    pub fn skiac_surface_create_pdf(
+      c_surface: *mut skiac_pdf_surface,
+      width: i32,
+      height: i32,
+      alphaType: i32,
+      cs: u8,
+      path: *const c_char,
+      c_metadata: *const skiac_pdf_metadata,
+      text_to_paths: bool,
+    );
+
+    pub fn skiac_surface_create_picture(
+      c_surface: *mut skiac_picture_surface,
+      width: i32,
+      height: i32,
+      alphaType: i32,
+      cs: u8,
+    );
+
+    pub fn skiac_surface_create_svg_file(
+      c_surface: *mut skiac_svg_surface,
+      width: i32,
+      height: i32,
+      alphaType: i32,
+      flag: u32,
+      cs: u8,
+      path: *const c_char,
+    ) -> *mut skiac_w_file_stream;
+
     pub fn skiac_surface_create_rgba(width: i32, height: i32, cs: u8) -> *mut skiac_surface;
 
+    pub fn skiac_surface_create_with_props(
+      width: i32,
+      height: i32,
+      cs: u8,
+      props: skiac_surface_props,
+    ) -> *mut skiac_surface;
+
+    pub fn skiac_surface_create_gpu(
+      width: i32,
+      height: i32,
+      cs: u8,
+      backend: u8,
+      device_index: i32,
+      device_uuid: *const u8,
+      props: skiac_surface_props,
+    ) -> *mut skiac_surface;
+
+    pub fn skiac_surface_create_external(
+      width: i32,
+      height: i32,
+      cs: u8,
+      texture: skiac_external_texture,
+      props: skiac_surface_props,
+    ) -> *mut skiac_surface;
+
+    pub fn skiac_surface_is_gpu(surface: *mut skiac_surface) -> bool;
+
+    pub fn skiac_surface_export_texture(
+      surface: *mut skiac_surface,
+      texture: *mut skiac_external_texture,
+    ) -> bool;
+
     pub fn skiac_surface_destroy(surface: *mut skiac_surface);
 
     pub fn skiac_surface_copy_rgba(
@@ -276,6 +516,26 @@ pub mod ffi {
       cs: u8,
     ) -> *mut skiac_surface;
 
+    pub fn skiac_surface_make_raster_copy(surface: *mut skiac_surface) -> *mut skiac_surface;
+
+    pub fn skiac_surface_make_opaque_copy(surface: *mut skiac_surface) -> *mut skiac_surface;
+
+    pub fn skiac_surface_make_crop(
+      surface: *mut skiac_surface,
+      x: i32,
+      y: i32,
+      width: i32,
+      height: i32,
+    ) -> *mut skiac_surface;
+
+    pub fn skiac_surface_make_resized(
+      surface: *mut skiac_surface,
+      width: i32,
+      height: i32,
+      contents: i32,
+      sampling: skiac_sampling_options,
+    ) -> *mut skiac_surface;
+
     pub fn skiac_surface_save(c_surface: *mut skiac_surface, path: *const c_char) -> bool;
 
     pub fn skiac_surface_get_canvas(surface: *mut skiac_surface) -> *mut skiac_canvas;
@@ -294,6 +554,25 @@ pub mod ffi {
       w: i32,
       h: i32,
       color_space: u8,
+      color_type: i32,
+      alpha_type: i32,
+    ) -> bool;
+
+    pub fn skiac_surface_write_pixels(
+      surface: *mut skiac_surface,
+      data: *const u8,
+      w: i32,
+      h: i32,
+      color_space: u8,
+      color_type: i32,
+      alpha_type: i32,
+    ) -> bool;
+
+    pub fn skiac_surface_read_pixels_as(
+      surface: *mut skiac_surface,
+      data: *mut u8,
+      color_type: i32,
+      alpha_type: i32,
     ) -> bool;
 
     pub fn skiac_surface_png_data(surface: *mut skiac_surface, data: *mut skiac_sk_data);
@@ -305,8 +584,29 @@ pub mod ffi {
       quality: i32,
     );
 
+    pub fn skiac_surface_encode_png(
+      surface: *mut skiac_surface,
+      data: *mut skiac_sk_data,
+      options: skiac_png_options,
+    );
+
+    pub fn skiac_surface_encode_jpeg(
+      surface: *mut skiac_surface,
+      data: *mut skiac_sk_data,
+      options: skiac_jpeg_options,
+    );
+
+    pub fn skiac_surface_encode_webp(
+      surface: *mut skiac_surface,
+      data: *mut skiac_sk_data,
+      quality: f32,
+      lossless: bool,
+    );
+
     pub fn skiac_surface_get_alpha_type(surface: *mut skiac_surface) -> i32;
 
+    pub fn skiac_surface_get_bytes_per_pixel(surface: *mut skiac_surface) -> i32;
+
     pub fn skiac_surface_draw_svg(
       surface: *mut skiac_surface,
       paint: *mut skiac_paint,
@@ -318,6 +618,45 @@ pub mod ffi {
 
     pub fn skiac_surface_get_bitmap(surface: *mut skiac_surface, info: *mut skiac_bitmap_info);
 
+    // GrDirectContext
+    pub fn skiac_gpu_context_get(
+      backend: u8,
+      device_index: i32,
+      device_uuid: *const u8,
+    ) -> *mut skiac_gpu_context;
+
+    pub fn skiac_gpu_context_get_resource_cache_limit(context: *mut skiac_gpu_context) -> usize;
+
+    pub fn skiac_gpu_context_set_resource_cache_limit(
+      context: *mut skiac_gpu_context,
+      bytes: usize,
+    );
+
+    pub fn skiac_gpu_context_get_resource_cache_usage(context: *mut skiac_gpu_context) -> usize;
+
+    pub fn skiac_gpu_context_free_resources(context: *mut skiac_gpu_context);
+
+    // SkSurface::asyncRescaleAndReadPixels
+    pub fn skiac_surface_read_pixels_async(
+      surface: *mut skiac_surface,
+      x: i32,
+      y: i32,
+      w: i32,
+      h: i32,
+      cs: u8,
+    ) -> *mut skiac_pixels_readback;
+
+    pub fn skiac_pixels_readback_poll(readback: *mut skiac_pixels_readback) -> bool;
+
+    pub fn skiac_pixels_readback_copy(readback: *mut skiac_pixels_readback, data: *mut u8) -> bool;
+
+    pub fn skiac_pixels_readback_make_surface(
+      readback: *mut skiac_pixels_readback,
+      cs: u8,
+    ) -> *mut skiac_surface;
+
+    pub fn skiac_pixels_readback_destroy(readback: *mut skiac_pixels_readback);
+
     // SkCanvas
     pub fn skiac_canvas_clear(canvas: *mut skiac_canvas, color: u32);
 
@@ -348,8 +687,34 @@ pub mod ffi {
       dy: f32,
       d_width: f32,
       d_height: f32,
-      enable_smoothing: bool,
-      filter_quality: i32,
+      sampling: skiac_sampling_options,
+      paint: *mut skiac_paint,
+    );
+
+    pub fn skiac_canvas_draw_image_nine(
+      canvas: *mut skiac_canvas,
+      bitmap: *mut skiac_bitmap,
+      center_x: i32,
+      center_y: i32,
+      center_width: i32,
+      center_height: i32,
+      dx: f32,
+      dy: f32,
+      d_width: f32,
+      d_height: f32,
+      filter_mode: i32,
+      paint: *mut skiac_paint,
+    );
+
+    pub fn skiac_canvas_draw_atlas(
+      canvas: *mut skiac_canvas,
+      bitmap: *mut skiac_bitmap,
+      xforms: *const f32,
+      src_rects: *const f32,
+      colors: *const u32,
+      count: i32,
+      blend_mode: i32,
+      sampling: skiac_sampling_options,
       paint: *mut skiac_paint,
     );
 
@@ -375,7 +740,7 @@ pub mod ffi {
       top: f32,
       alpha: u8,
       blend_mode: i32,
-      filter_quality: i32,
+      sampling: skiac_sampling_options,
     );
 
     pub fn skiac_canvas_draw_surface_rect(
@@ -389,7 +754,7 @@ pub mod ffi {
       dy: f32,
       dw: f32,
       dh: f32,
-      filter_quality: i32,
+      sampling: skiac_sampling_options,
     );
 
     pub fn skiac_canvas_get_line_metrics_or_draw_text(
@@ -417,36 +782,87 @@ pub mod ffi {
 
     pub fn skiac_canvas_clip_rect(canvas: *mut skiac_canvas, x: f32, y: f32, w: f32, h: f32);
 
-    pub fn skiac_canvas_clip_path(canvas: *mut skiac_canvas, path: *mut skiac_path);
-
-    pub fn skiac_canvas_save(canvas: *mut skiac_canvas);
-
-    pub fn skiac_canvas_restore(canvas: *mut skiac_canvas);
-
-    pub fn skiac_canvas_reset(canvas: *mut skiac_canvas);
+    pub fn skiac_canvas_clip_path(canvas: *mut skiac_canvas, path: *mut skiac_path, aa: bool);
 
-    pub fn skiac_canvas_write_pixels(
+    pub fn skiac_canvas_draw_picture(
       canvas: *mut skiac_canvas,
-      width: i32,
-      height: i32,
-      pixels: *const u8,
-      row_bytes: usize,
-      x: i32,
-      y: i32,
+      picture: *mut skiac_picture,
+      matrix: *mut skiac_matrix,
+      paint: *mut skiac_paint,
     );
 
-    pub fn skiac_canvas_write_pixels_dirty(
+    pub fn skiac_canvas_draw_svg(
       canvas: *mut skiac_canvas,
-      width: i32,
-      height: i32,
-      pixels: *const u8,
-      row_bytes: usize,
+      data: *const u8,
       length: usize,
+      dx: f32,
+      dy: f32,
+      dw: f32,
+      dh: f32,
+      paint: *mut skiac_paint,
+      font_collection: *mut skiac_font_collection,
+    ) -> bool;
+
+    pub fn skiac_canvas_annotate_link_to_url(
+      canvas: *mut skiac_canvas,
       x: f32,
       y: f32,
-      dirty_x: f32,
-      dirty_y: f32,
-      dirty_width: f32,
+      w: f32,
+      h: f32,
+      url: *const c_char,
+    );
+
+    pub fn skiac_canvas_annotate_link_to_destination(
+      canvas: *mut skiac_canvas,
+      x: f32,
+      y: f32,
+      w: f32,
+      h: f32,
+      name: *const c_char,
+    );
+
+    pub fn skiac_canvas_annotate_named_destination(
+      canvas: *mut skiac_canvas,
+      x: f32,
+      y: f32,
+      name: *const c_char,
+    );
+
+    pub fn skiac_canvas_save(canvas: *mut skiac_canvas);
+
+    pub fn skiac_canvas_save_layer(
+      canvas: *mut skiac_canvas,
+      paint: *mut skiac_paint,
+      bounds: *mut skiac_rect,
+    );
+
+    pub fn skiac_canvas_restore(canvas: *mut skiac_canvas);
+
+    pub fn skiac_canvas_reset(canvas: *mut skiac_canvas);
+
+    pub fn skiac_canvas_write_pixels(
+      canvas: *mut skiac_canvas,
+      width: i32,
+      height: i32,
+      pixels: *const u8,
+      row_bytes: usize,
+      x: i32,
+      y: i32,
+      color_space: u8,
+    );
+
+    pub fn skiac_canvas_write_pixels_dirty(
+      canvas: *mut skiac_canvas,
+      width: i32,
+      height: i32,
+      pixels: *const u8,
+      row_bytes: usize,
+      length: usize,
+      x: f32,
+      y: f32,
+      dirty_x: f32,
+      dirty_y: f32,
+      dirty_width: f32,
       dirty_height: f32,
       color_space: u8,
     );
@@ -467,6 +883,8 @@ pub mod ffi {
 
     pub fn skiac_paint_set_anti_alias(paint: *mut skiac_paint, aa: bool);
 
+    pub fn skiac_paint_get_anti_alias(paint: *mut skiac_paint) -> bool;
+
     pub fn skiac_paint_set_blend_mode(paint: *mut skiac_paint, blend_mode: i32);
 
     pub fn skiac_paint_get_blend_mode(paint: *mut skiac_paint) -> i32;
@@ -504,9 +922,12 @@ pub mod ffi {
       image_filter: *mut skiac_image_filter,
     );
 
-    pub fn skiac_path_create() -> *mut skiac_path;
+    pub fn skiac_paint_set_color_filter(
+      paint: *mut skiac_paint,
+      color_filter: *mut skiac_color_filter,
+    );
 
-    pub fn skiac_path_from_svg(svg_path: *mut std::os::raw::c_char) -> *mut skiac_path;
+    pub fn skiac_path_create() -> *mut skiac_path;
 
     pub fn skiac_path_clone(path: *mut skiac_path) -> *mut skiac_path;
 
@@ -531,12 +952,22 @@ pub mod ffi {
       join: u8,
       width: f32,
       miter_limit: f32,
+      path_effect: *mut skiac_path_effect,
     ) -> bool;
 
     pub fn skiac_path_get_bounds(path: *mut skiac_path, c_rect: *mut skiac_rect);
 
     pub fn skiac_path_compute_tight_bounds(path: *mut skiac_path, c_rect: *mut skiac_rect);
 
+    pub fn skiac_path_length(path: *mut skiac_path) -> f32;
+
+    pub fn skiac_path_get_pos_tan(
+      path: *mut skiac_path,
+      distance: f32,
+      position: *mut skiac_point,
+      tangent: *mut skiac_point,
+    ) -> bool;
+
     pub fn skiac_path_trim(
       path: *mut skiac_path,
       start_t: f32,
@@ -544,7 +975,12 @@ pub mod ffi {
       is_complement: bool,
     ) -> bool;
 
-    pub fn skiac_path_dash(path: *mut skiac_path, on: f32, off: f32, phase: f32) -> bool;
+    pub fn skiac_path_dash(
+      path: *mut skiac_path,
+      intervals: *const f32,
+      count: i32,
+      phase: f32,
+    ) -> bool;
 
     pub fn skiac_path_equals(path: *mut skiac_path, other: *mut skiac_path) -> bool;
 
@@ -576,6 +1012,17 @@ pub mod ffi {
       radius: f32,
     );
 
+    pub fn skiac_path_svg_arc_to(
+      path: *mut skiac_path,
+      rx: f32,
+      ry: f32,
+      x_axis_rotate: f32,
+      large_arc: bool,
+      sweep: bool,
+      x: f32,
+      y: f32,
+    );
+
     pub fn skiac_path_move_to(path: *mut skiac_path, x: f32, y: f32);
 
     pub fn skiac_path_line_to(path: *mut skiac_path, x: f32, y: f32);
@@ -596,6 +1043,15 @@ pub mod ffi {
 
     pub fn skiac_path_add_rect(path: *mut skiac_path, l: f32, t: f32, r: f32, b: f32);
 
+    pub fn skiac_path_add_round_rect(
+      path: *mut skiac_path,
+      x: f32,
+      y: f32,
+      width: f32,
+      height: f32,
+      radii: *const skiac_point,
+    );
+
     pub fn skiac_path_add_circle(path: *mut skiac_path, x: f32, y: f32, r: f32);
 
     pub fn skiac_path_transform(
@@ -663,6 +1119,29 @@ pub mod ffi {
 
     pub fn skiac_shader_destroy(shader: *mut skiac_shader);
 
+    pub fn skiac_runtime_effect_make(
+      sksl: *const c_char,
+      error: *mut SkiaString,
+    ) -> *mut skiac_runtime_effect;
+
+    pub fn skiac_runtime_effect_get_uniform_count(effect: *mut skiac_runtime_effect) -> usize;
+
+    pub fn skiac_runtime_effect_get_uniform(
+      effect: *mut skiac_runtime_effect,
+      index: usize,
+      uniform: *mut skiac_runtime_effect_uniform,
+    );
+
+    pub fn skiac_runtime_effect_get_uniform_size(effect: *mut skiac_runtime_effect) -> usize;
+
+    pub fn skiac_runtime_effect_make_shader(
+      effect: *mut skiac_runtime_effect,
+      uniforms: *const f32,
+      input: *mut skiac_shader,
+    ) -> *mut skiac_shader;
+
+    pub fn skiac_runtime_effect_destroy(effect: *mut skiac_runtime_effect);
+
     pub fn skiac_matrix_create() -> *mut skiac_matrix;
 
     pub fn skiac_matrix_new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> *mut skiac_matrix;
@@ -763,13 +1242,74 @@ pub mod ffi {
       c_image_filter: *mut skiac_image_filter,
     ) -> *mut skiac_image_filter;
 
+    pub fn skiac_image_filter_make_offset(
+      dx: f32,
+      dy: f32,
+      chained_filter: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
+    pub fn skiac_image_filter_make_color_filter(
+      color_filter: *mut skiac_color_filter,
+      chained_filter: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
+    pub fn skiac_image_filter_make_merge(
+      image_filters: *mut *mut skiac_image_filter,
+      count: usize,
+    ) -> *mut skiac_image_filter;
+
+    pub fn skiac_image_filter_make_compose(
+      outer: *mut skiac_image_filter,
+      inner: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
+    pub fn skiac_image_filter_make_displacement_map(
+      x_channel: i32,
+      y_channel: i32,
+      scale: f32,
+      displacement: *mut skiac_image_filter,
+      chained_filter: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
     pub fn skiac_image_filter_ref(image_filter: *mut skiac_image_filter);
 
     pub fn skiac_image_filter_destroy(image_filter: *mut skiac_image_filter);
 
+    pub fn skiac_color_filter_make_matrix(row_major: *const f32) -> *mut skiac_color_filter;
+
+    pub fn skiac_color_filter_make_blend(color: u32, blend_mode: i32) -> *mut skiac_color_filter;
+
+    pub fn skiac_color_filter_make_lighting(mul: u32, add: u32) -> *mut skiac_color_filter;
+
+    pub fn skiac_color_filter_make_luma() -> *mut skiac_color_filter;
+
+    pub fn skiac_color_filter_compose(
+      outer: *mut skiac_color_filter,
+      inner: *mut skiac_color_filter,
+    ) -> *mut skiac_color_filter;
+
+    pub fn skiac_color_filter_ref(color_filter: *mut skiac_color_filter);
+
+    pub fn skiac_color_filter_destroy(color_filter: *mut skiac_color_filter);
+
     pub fn skiac_sk_data_destroy(c_data: *mut skiac_data);
 
-    pub fn skiac_bitmap_make_from_buffer(ptr: *mut u8, size: usize, info: *mut skiac_bitmap_info);
+    pub fn skiac_bitmap_make_from_buffer(
+      ptr: *mut u8,
+      size: usize,
+      width: i32,
+      height: i32,
+      cs: u8,
+      color_space_conversion: bool,
+      info: *mut skiac_bitmap_info,
+    );
+
+    pub fn skiac_bitmap_make_from_pixels(
+      ptr: *const u8,
+      width: i32,
+      height: i32,
+      info: *mut skiac_bitmap_info,
+    );
 
     pub fn skiac_bitmap_make_from_svg(
       data: *const u8,
@@ -790,6 +1330,12 @@ pub mod ffi {
       alpha_type: i32,
     ) -> *mut skiac_bitmap;
 
+    pub fn skiac_bitmap_read_pixels(
+      c_bitmap: *mut skiac_bitmap,
+      data: *mut u8,
+      row_bytes: usize,
+    ) -> bool;
+
     pub fn skiac_bitmap_get_width(c_bitmap: *mut skiac_bitmap) -> usize;
 
     pub fn skiac_bitmap_get_height(c_bitmap: *mut skiac_bitmap) -> usize;
@@ -852,8 +1398,125 @@ pub mod ffi {
       h: i32,
     );
 
+    pub fn skiac_sk_w_stream_take(
+      c_w_memory_stream: *mut skiac_w_memory_stream,
+      sk_data: *mut skiac_sk_data,
+    );
+
+    pub fn skiac_sk_w_stream_snapshot(
+      c_w_memory_stream: *mut skiac_w_memory_stream,
+      sk_data: *mut skiac_sk_data,
+    );
+
     pub fn skiac_sk_w_stream_destroy(c_w_memory_stream: *mut skiac_w_memory_stream);
 
+    // SkPDF
+    pub fn skiac_pdf_document_add_page(
+      c_document: *mut skiac_pdf_document,
+      width: f32,
+      height: f32,
+    ) -> *mut skiac_canvas;
+
+    pub fn skiac_pdf_document_close(c_document: *mut skiac_pdf_document) -> *mut skiac_canvas;
+
+    pub fn skiac_pdf_document_take(
+      c_document: *mut skiac_pdf_document,
+      sk_data: *mut skiac_sk_data,
+    );
+
+    pub fn skiac_pdf_document_snapshot(
+      c_document: *mut skiac_pdf_document,
+      sk_data: *mut skiac_sk_data,
+    );
+
+    pub fn skiac_pdf_document_destroy(c_document: *mut skiac_pdf_document);
+
+    // SkPicture
+    pub fn skiac_picture_recorder_finish(
+      c_recorder: *mut skiac_picture_recorder,
+    ) -> *mut skiac_canvas;
+
+    pub fn skiac_picture_recorder_get_picture(
+      c_recorder: *mut skiac_picture_recorder,
+    ) -> *mut skiac_picture;
+
+    pub fn skiac_picture_recorder_destroy(c_recorder: *mut skiac_picture_recorder);
+
+    pub fn skiac_picture_make_from_data(data: *const u8, length: usize) -> *mut skiac_picture;
+
+    pub fn skiac_picture_get_cull_rect(c_picture: *mut skiac_picture, c_rect: *mut skiac_rect);
+
+    pub fn skiac_picture_serialize(c_picture: *mut skiac_picture, sk_data: *mut skiac_sk_data);
+
+    pub fn skiac_picture_destroy(c_picture: *mut skiac_picture);
+
+    // SkiacFontRecordingCanvas
+    pub fn skiac_svg_font_recorder_create(
+      c_canvas: *mut skiac_canvas,
+    ) -> *mut skiac_svg_font_recorder;
+
+    pub fn skiac_svg_font_recorder_get_canvas(
+      c_recorder: *mut skiac_svg_font_recorder,
+    ) -> *mut skiac_canvas;
+
+    pub fn skiac_svg_font_recorder_get_style(
+      c_recorder: *mut skiac_svg_font_recorder,
+      sk_data: *mut skiac_sk_data,
+    );
+
+    pub fn skiac_svg_font_recorder_destroy(c_recorder: *mut skiac_svg_font_recorder);
+
+    // SkiacFileWStream
+    pub fn skiac_w_file_stream_finish_svg(
+      c_w_file_stream: *mut skiac_w_file_stream,
+      style: *const u8,
+      style_size: usize,
+    ) -> bool;
+
+    pub fn skiac_w_file_stream_destroy(c_w_file_stream: *mut skiac_w_file_stream);
+
+    // ImageDecoder
+    pub fn skiac_image_decoder_create() -> *mut skiac_image_decoder;
+
+    pub fn skiac_image_decoder_push(
+      c_decoder: *mut skiac_image_decoder,
+      ptr: *const u8,
+      size: usize,
+      finished: bool,
+      progress: *mut skiac_decode_progress,
+    ) -> i32;
+
+    pub fn skiac_image_decoder_get_bitmap(
+      c_decoder: *mut skiac_image_decoder,
+      bitmap_info: *mut skiac_bitmap_info,
+    );
+
+    pub fn skiac_image_decoder_destroy(c_decoder: *mut skiac_image_decoder);
+
+    // StreamEncoder
+    pub fn skiac_stream_encoder_create_png(
+      c_surface: *mut skiac_surface,
+      options: skiac_png_options,
+    ) -> *mut skiac_stream_encoder;
+
+    pub fn skiac_stream_encoder_create_jpeg(
+      c_surface: *mut skiac_surface,
+      options: skiac_jpeg_options,
+    ) -> *mut skiac_stream_encoder;
+
+    pub fn skiac_stream_encoder_encode_rows(
+      c_encoder: *mut skiac_stream_encoder,
+      rows: i32,
+      data: *mut *const u8,
+      size: *mut usize,
+    ) -> i32;
+
+    pub fn skiac_stream_encoder_destroy(c_encoder: *mut skiac_stream_encoder);
+
+    pub fn skiac_color_space_icc(cs: u8, data: *mut skiac_sk_data);
+
+    pub fn skiac_deflate(data: *const u8, size: usize, level: i32, output_data: *mut skiac_sk_data);
+
     // SkSVG
     pub fn skiac_svg_text_to_path(
       data: *const u8,
@@ -916,6 +1579,20 @@ pub enum ColorType {
   R16G16B16A16Unorm,
 }
 
+impl ColorType {
+  /// `SkColorTypeBytesPerPixel`
+  pub fn bytes_per_pixel(&self) -> usize {
+    match self {
+      Self::Unknown => 0,
+      Self::Alpha8 | Self::Gray8 => 1,
+      Self::RGB565 | Self::ARGB4444 | Self::R8G8Unorm | Self::A16Float | Self::A16Unorm => 2,
+      Self::RGBAF16Norm | Self::RGBAF16 | Self::R16G16B16A16Unorm => 8,
+      Self::RGBAF32 => 16,
+      _ => 4,
+    }
+  }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(u8)]
 pub enum ColorSpace {
@@ -941,87 +1618,279 @@ impl FromStr for ColorSpace {
   }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub enum PaintStyle {
-  Fill = 0,
-  Stroke = 1,
+impl ColorSpace {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Srgb => "srgb",
+      Self::DisplayP3 => "display-p3",
+    }
+  }
+
+  /// ICC profile generated by Skia
+  pub fn icc_profile(&self) -> Option<SkiaDataRef> {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_color_space_icc(*self as u8, &mut data) };
+    if data.ptr.is_null() {
+      None
+    } else {
+      Some(SkiaDataRef(data))
+    }
+  }
 }
 
+/// The subpixel layout of the LCD text, same as `SkPixelGeometry`
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub enum FillType {
-  Winding = 0,
-  EvenOdd = 1,
-  InverseWinding = 2,
-  InverseEvenOdd = 3,
+#[repr(u8)]
+pub enum PixelGeometry {
+  Unknown,
+  RgbH,
+  BgrH,
+  RgbV,
+  BgrV,
 }
 
-impl From<u32> for FillType {
-  fn from(value: u32) -> Self {
-    match value {
-      0 => Self::Winding,
-      1 => Self::EvenOdd,
-      2 => Self::InverseWinding,
-      3 => Self::InverseEvenOdd,
-      _ => unreachable!(),
-    }
+impl Default for PixelGeometry {
+  fn default() -> Self {
+    Self::Unknown
   }
 }
 
-impl FromStr for FillType {
+impl FromStr for PixelGeometry {
   type Err = SkError;
 
+  /// The names of the `rgba` setting of fontconfig
   fn from_str(value: &str) -> Result<Self, SkError> {
     match value {
-      "nonzero" => Ok(FillType::Winding),
-      "evenodd" => Ok(FillType::EvenOdd),
-      _ => Err(SkError::StringToFillRuleError(value.to_owned())),
+      "unknown" => Ok(Self::Unknown),
+      "rgb" => Ok(Self::RgbH),
+      "bgr" => Ok(Self::BgrH),
+      "vrgb" => Ok(Self::RgbV),
+      "vbgr" => Ok(Self::BgrV),
+      _ => Err(SkError::StringToPixelGeometryError(value.to_owned())),
     }
   }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub enum StrokeCap {
-  Butt = 0,
-  Round = 1,
-  Square = 2,
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SurfaceProps {
+  /// MSAA samples of the GPU surface, 1 is off
+  pub sample_count: u32,
+  pub pixel_geometry: PixelGeometry,
+  /// `RGBA8888`, `RGBAF16` for the HDR pixels or `Alpha8` for the masks, the external texture keeps the format of it
+  pub color_type: ColorType,
 }
 
-impl StrokeCap {
-  pub fn from_raw(cap: i32) -> Result<Self, SkError> {
-    match cap {
-      0 => Ok(Self::Butt),
-      1 => Ok(Self::Round),
-      2 => Ok(Self::Square),
-      _ => Err(SkError::Generic(format!(
-        "{} is not valid StrokeCap value",
-        cap
-      ))),
+impl Default for SurfaceProps {
+  fn default() -> Self {
+    Self {
+      sample_count: 1,
+      pixel_geometry: PixelGeometry::default(),
+      color_type: ColorType::RGBA8888,
     }
   }
+}
 
-  pub fn as_str(&self) -> &str {
-    match self {
-      Self::Butt => "butt",
-      Self::Round => "round",
-      Self::Square => "square",
+impl From<SurfaceProps> for ffi::skiac_surface_props {
+  fn from(props: SurfaceProps) -> Self {
+    Self {
+      sample_count: props.sample_count as i32,
+      pixel_geometry: props.pixel_geometry as u8,
+      color_type: props.color_type as i32,
     }
   }
 }
 
-impl FromStr for StrokeCap {
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+pub enum GpuBackend {
+  /// Metal on macOS, OpenGL on the others, then Vulkan if OpenGL is unavailable
+  Auto,
+  Gl,
+  Metal,
+  Vulkan,
+}
+
+impl Default for GpuBackend {
+  fn default() -> Self {
+    Self::Auto
+  }
+}
+
+impl FromStr for GpuBackend {
   type Err = SkError;
 
-  fn from_str(value: &str) -> Result<StrokeCap, Self::Err> {
+  fn from_str(value: &str) -> Result<Self, SkError> {
     match value {
-      "butt" => Ok(Self::Butt),
-      "round" => Ok(Self::Round),
-      "square" => Ok(Self::Square),
-      _ => Err(SkError::StringToStrokeCapError(value.to_owned())),
+      "auto" => Ok(Self::Auto),
+      "gl" => Ok(Self::Gl),
+      "metal" => Ok(Self::Metal),
+      "vulkan" => Ok(Self::Vulkan),
+      _ => Err(SkError::StringToGpuBackendError(value.to_owned())),
     }
   }
 }
 
-#[repr(u8)]
+/// `DRM_FORMAT_MOD_INVALID`, the layout of the dmabuf is implied by the driver
+pub const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
+
+/// The GPU memory shared with the other processes and APIs without copying it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExternalTexture {
+  /// The single plane dmabuf on Linux, imported and exported by the GL backend
+  DmaBuf {
+    fd: i32,
+    fourcc: u32,
+    stride: u32,
+    offset: u32,
+    modifier: u64,
+  },
+  /// The BGRA IOSurface on macOS, imported and exported by the Metal backend
+  IoSurface(u32),
+}
+
+impl ExternalTexture {
+  pub fn backend(&self) -> GpuBackend {
+    match self {
+      Self::DmaBuf { .. } => GpuBackend::Gl,
+      Self::IoSurface(_) => GpuBackend::Metal,
+    }
+  }
+}
+
+impl From<ExternalTexture> for ffi::skiac_external_texture {
+  fn from(texture: ExternalTexture) -> Self {
+    let mut c_texture = ffi::skiac_external_texture {
+      kind: 0,
+      fd: -1,
+      fourcc: 0,
+      stride: 0,
+      offset: 0,
+      modifier: DRM_FORMAT_MOD_INVALID,
+      io_surface: 0,
+    };
+    match texture {
+      ExternalTexture::DmaBuf {
+        fd,
+        fourcc,
+        stride,
+        offset,
+        modifier,
+      } => {
+        c_texture.fd = fd;
+        c_texture.fourcc = fourcc;
+        c_texture.stride = stride;
+        c_texture.offset = offset;
+        c_texture.modifier = modifier;
+      }
+      ExternalTexture::IoSurface(id) => {
+        c_texture.kind = 1;
+        c_texture.io_surface = id;
+      }
+    }
+    c_texture
+  }
+}
+
+impl From<ffi::skiac_external_texture> for ExternalTexture {
+  fn from(c_texture: ffi::skiac_external_texture) -> Self {
+    match c_texture.kind {
+      1 => Self::IoSurface(c_texture.io_surface),
+      _ => Self::DmaBuf {
+        fd: c_texture.fd,
+        fourcc: c_texture.fourcc,
+        stride: c_texture.stride,
+        offset: c_texture.offset,
+        modifier: c_texture.modifier,
+      },
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PaintStyle {
+  Fill = 0,
+  Stroke = 1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum FillType {
+  Winding = 0,
+  EvenOdd = 1,
+  InverseWinding = 2,
+  InverseEvenOdd = 3,
+}
+
+impl From<u32> for FillType {
+  fn from(value: u32) -> Self {
+    match value {
+      0 => Self::Winding,
+      1 => Self::EvenOdd,
+      2 => Self::InverseWinding,
+      3 => Self::InverseEvenOdd,
+      _ => unreachable!(),
+    }
+  }
+}
+
+impl FromStr for FillType {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, SkError> {
+    match value {
+      "nonzero" => Ok(FillType::Winding),
+      "evenodd" => Ok(FillType::EvenOdd),
+      _ => Err(SkError::StringToFillRuleError(value.to_owned())),
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StrokeCap {
+  Butt = 0,
+  Round = 1,
+  Square = 2,
+}
+
+impl StrokeCap {
+  pub fn from_raw(cap: i32) -> Result<Self, SkError> {
+    match cap {
+      0 => Ok(Self::Butt),
+      1 => Ok(Self::Round),
+      2 => Ok(Self::Square),
+      _ => Err(SkError::Generic(format!(
+        "{} is not valid StrokeCap value",
+        cap
+      ))),
+    }
+  }
+
+  pub fn as_str(&self) -> &str {
+    match self {
+      Self::Butt => "butt",
+      Self::Round => "round",
+      Self::Square => "square",
+    }
+  }
+}
+
+impl FromStr for StrokeCap {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<StrokeCap, Self::Err> {
+    match value {
+      "butt" => Ok(Self::Butt),
+      "round" => Ok(Self::Round),
+      "square" => Ok(Self::Square),
+      _ => Err(SkError::StringToStrokeCapError(value.to_owned())),
+    }
+  }
+}
+
+#[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum StrokeJoin {
   Miter,
@@ -1064,6 +1933,32 @@ impl FromStr for StrokeJoin {
   }
 }
 
+#[repr(i32)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ColorChannel {
+  R = 0,
+  G = 1,
+  B = 2,
+  A = 3,
+}
+
+impl FromStr for ColorChannel {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "r" => Ok(Self::R),
+      "g" => Ok(Self::G),
+      "b" => Ok(Self::B),
+      "a" => Ok(Self::A),
+      _ => Err(SkError::TypeError(format!(
+        "{} is not a color channel, it's one of r, g, b and a",
+        value
+      ))),
+    }
+  }
+}
+
 #[repr(i32)]
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TileMode {
@@ -1293,6 +2188,144 @@ impl FromStr for FilterQuality {
   }
 }
 
+/// Mirror of `SkPngEncoder::Options`
+#[derive(Copy, Clone, Debug)]
+pub struct PngEncodeOptions(ffi::skiac_png_options);
+
+impl Default for PngEncodeOptions {
+  fn default() -> Self {
+    PngEncodeOptions(ffi::skiac_png_options {
+      zlib_level: 6,
+      filter_flags: Self::filter_flag("all").unwrap(),
+      bit_depth: 0,
+    })
+  }
+}
+
+impl PngEncodeOptions {
+  /// `SkPngEncoder::FilterFlag`
+  pub fn filter_flag(filter: &str) -> Option<i32> {
+    match filter {
+      "none" => Some(0x08),
+      "sub" => Some(0x10),
+      "up" => Some(0x20),
+      "avg" => Some(0x40),
+      "paeth" => Some(0x80),
+      "all" => Some(0x08 | 0x10 | 0x20 | 0x40 | 0x80),
+      _ => None,
+    }
+  }
+
+  pub fn zlib_level(&self) -> i32 {
+    self.0.zlib_level
+  }
+
+  /// zlib compression level, 0 (fastest) - 9 (smallest)
+  pub fn set_zlib_level(&mut self, level: u32) {
+    self.0.zlib_level = level.min(9) as i32;
+  }
+
+  pub fn set_filter_flags(&mut self, flags: i32) {
+    self.0.filter_flags = flags;
+  }
+
+  /// 8 or 16 bits per channel, the half float surface is 16-bit by default
+  pub fn set_bit_depth(&mut self, bit_depth: u8) {
+    self.0.bit_depth = bit_depth as i32;
+  }
+}
+
+/// Mirror of `SkJpegEncoder::Options`
+#[derive(Copy, Clone, Debug)]
+pub struct JpegEncodeOptions(ffi::skiac_jpeg_options);
+
+impl JpegEncodeOptions {
+  /// `chroma_subsampling` uses 4:2:0 if true, otherwise 4:4:4
+  pub fn new(quality: u8, chroma_subsampling: bool) -> Self {
+    JpegEncodeOptions(ffi::skiac_jpeg_options {
+      quality: quality.min(100) as i32,
+      // SkJpegEncoder::Downsample::k420 and k444
+      downsample: if chroma_subsampling { 0 } else { 2 },
+      has_background: false,
+      background: 0,
+    })
+  }
+
+  /// The transparent pixels are composited over `color` before encoding
+  pub fn set_background(&mut self, color: u32) {
+    self.0.has_background = true;
+    self.0.background = color;
+  }
+
+  /// Rows of a MCU, libjpeg only outputs bytes once a whole MCU row is buffered
+  pub fn mcu_height(&self) -> u32 {
+    if self.0.downsample == 0 {
+      16
+    } else {
+      8
+    }
+  }
+}
+
+/// Mirror of `SkSamplingOptions`
+#[derive(Copy, Clone, Debug)]
+pub struct SamplingOptions(ffi::skiac_sampling_options);
+
+impl SamplingOptions {
+  pub fn nearest() -> Self {
+    Self::filter(0, 0)
+  }
+
+  /// `SkFilterMode` and `SkMipmapMode`
+  fn filter(filter_mode: i32, mipmap_mode: i32) -> Self {
+    SamplingOptions(ffi::skiac_sampling_options {
+      filter_mode,
+      mipmap_mode,
+      use_cubic: false,
+      cubic_b: 0.0,
+      cubic_c: 0.0,
+    })
+  }
+
+  /// `scale` is the ratio between the destination size on device and the source size.
+  /// The cubic resampler doesn't use mipmaps, so it aliases when the image is shrunk a lot,
+  /// the trilinear filtering is used instead in this case.
+  pub fn from_quality(quality: FilterQuality, enable_smoothing: bool, scale: f32) -> Self {
+    if !enable_smoothing {
+      return Self::nearest();
+    }
+    match quality {
+      FilterQuality::None => Self::nearest(),
+      FilterQuality::Low => Self::filter(1, 0),
+      FilterQuality::Medium => Self::filter(1, 1),
+      FilterQuality::High => {
+        if scale < 0.5 {
+          Self::filter(1, 2)
+        } else {
+          SamplingOptions(ffi::skiac_sampling_options {
+            filter_mode: 0,
+            mipmap_mode: 0,
+            use_cubic: true,
+            cubic_b: 1.0 / 3.0,
+            cubic_c: 1.0 / 3.0,
+          })
+        }
+      }
+    }
+  }
+}
+
+/// The pixels of the resized surface
+#[derive(Copy, Clone, Debug)]
+pub enum ResizeContents {
+  /// Transparent, or white on the opaque surface
+  Clear,
+  /// The old pixels at the top left corner, unscaled
+  Copy,
+  /// The old pixels scaled to the new size
+  Scale(SamplingOptions),
+}
+
 /// Describes how to interpret the alpha component of a pixel.
 ///
 /// A pixel may be opaque, or alpha, describing multiple levels of transparency.
@@ -1338,6 +2371,21 @@ impl From<i32> for PathOp {
   }
 }
 
+impl FromStr for PathOp {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, SkError> {
+    match value {
+      "difference" => Ok(Self::Difference),
+      "intersect" => Ok(Self::Intersect),
+      "union" => Ok(Self::Union),
+      "xor" => Ok(Self::Xor),
+      "reverseDifference" => Ok(Self::ReverseDifference),
+      _ => Err(SkError::StringToPathOpError(value.to_owned())),
+    }
+  }
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy)]
 pub enum TextAlign {
@@ -1503,6 +2551,39 @@ pub enum SvgExportFlag {
   RelativePathEncoding = 0x04,
 }
 
+/// How the SVG canvas writes the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgTextPolicy {
+  /// `<text>` referencing the font family names
+  Family,
+  /// `<text>` with the fonts embedded as `@font-face` data urls
+  Embed,
+  /// The glyphs outlined to paths, like `SvgExportFlag::ConvertTextToPaths`
+  Paths,
+}
+
+impl Default for SvgTextPolicy {
+  fn default() -> Self {
+    Self::Family
+  }
+}
+
+impl FromStr for SvgTextPolicy {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "family" => Ok(Self::Family),
+      "embed" => Ok(Self::Embed),
+      "paths" => Ok(Self::Paths),
+      _ => Err(SkError::Generic(format!(
+        "Invalid svg text policy: {}",
+        value
+      ))),
+    }
+  }
+}
+
 impl TryFrom<u32> for SvgExportFlag {
   type Error = SkError;
 
@@ -1516,6 +2597,52 @@ impl TryFrom<u32> for SvgExportFlag {
   }
 }
 
+/// `(device_index, device_uuid)` of the C functions, -1 and null are the first usable device
+fn device_args(device: &Option<GpuDevice>) -> (i32, *const u8) {
+  match device {
+    Some(GpuDevice::Index(index)) => (*index as i32, ptr::null()),
+    Some(GpuDevice::Uuid(uuid)) => (-1, uuid.as_ptr()),
+    None => (-1, ptr::null()),
+  }
+}
+
+/// The GPU context shared by the GPU surfaces of the backend and device on the current thread.
+/// It's owned by the thread, so it's not destroyed on drop
+#[derive(Debug)]
+pub struct DirectContext(*mut ffi::skiac_gpu_context);
+
+impl DirectContext {
+  /// `None` if there is no GPU, or the library is built without the `backend`
+  pub fn get(backend: GpuBackend, device: Option<GpuDevice>) -> Option<DirectContext> {
+    let (device_index, device_uuid) = device_args(&device);
+    let ptr = unsafe { ffi::skiac_gpu_context_get(backend as u8, device_index, device_uuid) };
+    if ptr.is_null() {
+      None
+    } else {
+      Some(DirectContext(ptr))
+    }
+  }
+
+  /// Budget of the cached GPU resources in bytes
+  pub fn resource_cache_limit(&self) -> usize {
+    unsafe { ffi::skiac_gpu_context_get_resource_cache_limit(self.0) }
+  }
+
+  pub fn set_resource_cache_limit(&self, bytes: usize) {
+    unsafe { ffi::skiac_gpu_context_set_resource_cache_limit(self.0, bytes) }
+  }
+
+  /// Bytes of the GPU resources in the cache, including the ones used by the surfaces
+  pub fn resource_cache_usage(&self) -> usize {
+    unsafe { ffi::skiac_gpu_context_get_resource_cache_usage(self.0) }
+  }
+
+  /// Free the cached GPU resources not used by the surfaces
+  pub fn free_resources(&self) {
+    unsafe { ffi::skiac_gpu_context_free_resources(self.0) }
+  }
+}
+
 pub struct Surface {
   ptr: *mut ffi::skiac_surface,
   pub(crate) canvas: Canvas,
@@ -1546,11 +2673,73 @@ impl Surface {
     }
   }
 
+  /// Premultiplied raster surface, the sample count of `props` is ignored
+  pub fn new_with_props(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    props: SurfaceProps,
+  ) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_create_with_props(
+        width as i32,
+        height as i32,
+        color_space as u8,
+        props.into(),
+      ))
+    }
+  }
+
+  /// Premultiplied surface rendered on the GPU, `None` if there is no GPU,
+  /// or the library is built without the `backend`.
+  /// `device` is only selected by the Vulkan backend, `None` is the first usable device
+  pub fn new_gpu(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    backend: GpuBackend,
+    device: Option<GpuDevice>,
+    props: SurfaceProps,
+  ) -> Option<Surface> {
+    let (device_index, device_uuid) = device_args(&device);
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_create_gpu(
+        width as i32,
+        height as i32,
+        color_space as u8,
+        backend as u8,
+        device_index,
+        device_uuid,
+        props.into(),
+      ))
+    }
+  }
+
+  /// Draw into the external texture without copying it
+  pub fn new_external(
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    texture: ExternalTexture,
+    props: SurfaceProps,
+  ) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_create_external(
+        width as i32,
+        height as i32,
+        color_space as u8,
+        texture.into(),
+        props.into(),
+      ))
+    }
+  }
+
+  /// `flags` are the bits of `SvgExportFlag`
   pub fn new_svg(
     width: u32,
     height: u32,
     alpha_type: AlphaType,
-    flag: SvgExportFlag,
+    flags: u32,
     color_space: ColorSpace,
   ) -> Option<(Surface, SkWMemoryStream)> {
     let mut svg_surface = ffi::skiac_svg_surface {
@@ -1564,7 +2753,7 @@ impl Surface {
         width as i32,
         height as i32,
         alpha_type as i32,
-        flag as u32,
+        flags,
         color_space as u8,
       );
     };
@@ -1580,29 +2769,182 @@ impl Surface {
     ))
   }
 
-  unsafe fn from_ptr(ptr: *mut ffi::skiac_surface) -> Option<Surface> {
-    if ptr.is_null() {
-      None
-    } else {
-      Some(Surface {
-        ptr,
-        canvas: Canvas(ffi::skiac_surface_get_canvas(ptr)),
-      })
-    }
-  }
-
-  pub fn copy_rgba(
-    &self,
-    x: u32,
-    y: u32,
+  /// The SVG document is written to `path` while drawing
+  pub fn new_svg_file(
     width: u32,
     height: u32,
+    alpha_type: AlphaType,
+    flags: u32,
     color_space: ColorSpace,
-  ) -> Option<Surface> {
-    unsafe {
-      Self::from_ptr(ffi::skiac_surface_copy_rgba(
-        self.ptr,
-        x,
+    path: &str,
+  ) -> Option<(Surface, SkWFileStream)> {
+    let c_path = std::ffi::CString::new(path).ok()?;
+    let mut svg_surface = ffi::skiac_svg_surface {
+      stream: ptr::null_mut(),
+      surface: ptr::null_mut(),
+      canvas: ptr::null_mut(),
+    };
+    let stream = unsafe {
+      ffi::skiac_surface_create_svg_file(
+        &mut svg_surface,
+        width as i32,
+        height as i32,
+        alpha_type as i32,
+        flags,
+        color_space as u8,
+        c_path.as_ptr(),
+      )
+    };
+    if stream.is_null() {
+      return None;
+    }
+    Some((
+      Self {
+        ptr: svg_surface.surface,
+        canvas: Canvas(svg_surface.canvas),
+      },
+      SkWFileStream(stream),
+    ))
+  }
+
+  /// The first page of the PDF document has the size of the surface,
+  /// the document is kept in memory if `path` is `None`.
+  /// The text is written as text objects with the fonts embedded, unless `text_to_paths` is set
+  pub fn new_pdf(
+    width: u32,
+    height: u32,
+    alpha_type: AlphaType,
+    color_space: ColorSpace,
+    path: Option<&str>,
+    metadata: &PdfMetadata,
+    text_to_paths: bool,
+  ) -> Option<(Surface, PdfDocument)> {
+    let c_path = path.map(CString::new).transpose().ok()?;
+    let c_string = |s: &Option<String>| s.as_deref().map(CString::new).transpose();
+    let title = c_string(&metadata.title).ok()?;
+    let author = c_string(&metadata.author).ok()?;
+    let subject = c_string(&metadata.subject).ok()?;
+    let keywords = c_string(&metadata.keywords).ok()?;
+    let creator = c_string(&metadata.creator).ok()?;
+    let producer = c_string(&metadata.producer).ok()?;
+    let c_ptr = |s: &Option<CString>| s.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null());
+    let c_date_time = |date_time: &Option<PdfDateTime>| {
+      date_time
+        .map(|d| ffi::skiac_pdf_date_time {
+          time_zone_minutes: 0,
+          year: d.year,
+          month: d.month,
+          day_of_week: d.day_of_week,
+          day: d.day,
+          hour: d.hour,
+          minute: d.minute,
+          second: d.second,
+        })
+        .unwrap_or_default()
+    };
+    let c_metadata = ffi::skiac_pdf_metadata {
+      title: c_ptr(&title),
+      author: c_ptr(&author),
+      subject: c_ptr(&subject),
+      keywords: c_ptr(&keywords),
+      creator: c_ptr(&creator),
+      producer: c_ptr(&producer),
+      has_creation: metadata.creation_date.is_some(),
+      creation: c_date_time(&metadata.creation_date),
+      has_modified: metadata.modified_date.is_some(),
+      modified: c_date_time(&metadata.modified_date),
+      pdf_a: metadata.pdf_a,
+    };
+    let mut pdf_surface = ffi::skiac_pdf_surface {
+      document: ptr::null_mut(),
+      surface: ptr::null_mut(),
+      canvas: ptr::null_mut(),
+    };
+    unsafe {
+      ffi::skiac_surface_create_pdf(
+        &mut pdf_surface,
+        width as i32,
+        height as i32,
+        alpha_type as i32,
+        color_space as u8,
+        c_ptr(&c_path),
+        &c_metadata,
+        text_to_paths,
+      );
+    };
+    if pdf_surface.surface.is_null() {
+      return None;
+    }
+    Some((
+      Self {
+        ptr: pdf_surface.surface,
+        canvas: Canvas(pdf_surface.canvas),
+      },
+      PdfDocument {
+        ptr: pdf_surface.document,
+        closed: false,
+      },
+    ))
+  }
+
+  /// Record the draws into a `SkPicture` instead of rasterizing them
+  pub fn new_picture(
+    width: u32,
+    height: u32,
+    alpha_type: AlphaType,
+    color_space: ColorSpace,
+  ) -> Option<(Surface, PictureRecorder)> {
+    let mut picture_surface = ffi::skiac_picture_surface {
+      recorder: ptr::null_mut(),
+      surface: ptr::null_mut(),
+      canvas: ptr::null_mut(),
+    };
+    unsafe {
+      ffi::skiac_surface_create_picture(
+        &mut picture_surface,
+        width as i32,
+        height as i32,
+        alpha_type as i32,
+        color_space as u8,
+      );
+    };
+    if picture_surface.surface.is_null() {
+      return None;
+    }
+    Some((
+      Self {
+        ptr: picture_surface.surface,
+        canvas: Canvas(picture_surface.canvas),
+      },
+      PictureRecorder {
+        ptr: picture_surface.recorder,
+      },
+    ))
+  }
+
+  unsafe fn from_ptr(ptr: *mut ffi::skiac_surface) -> Option<Surface> {
+    if ptr.is_null() {
+      None
+    } else {
+      Some(Surface {
+        ptr,
+        canvas: Canvas(ffi::skiac_surface_get_canvas(ptr)),
+      })
+    }
+  }
+
+  pub fn copy_rgba(
+    &self,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+  ) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_copy_rgba(
+        self.ptr,
+        x,
         y,
         width,
         height,
@@ -1624,6 +2966,47 @@ impl Surface {
     }
   }
 
+  /// The premultiplied raster surface with the pixels and the props of it, like the GPU surface on the CPU
+  pub fn to_raster(&self) -> Option<Surface> {
+    unsafe { Self::from_ptr(ffi::skiac_surface_make_raster_copy(self.ptr)) }
+  }
+
+  /// The pixels can't be transparent on the copy, the GPU surface stays on the GPU
+  pub fn to_opaque(&self) -> Option<Surface> {
+    unsafe { Self::from_ptr(ffi::skiac_surface_make_opaque_copy(self.ptr)) }
+  }
+
+  /// A new surface of the pixels in the rect on the same backend, the parts outside of the surface are cleared
+  pub fn crop(&self, x: i32, y: i32, width: u32, height: u32) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_make_crop(
+        self.ptr,
+        x,
+        y,
+        width as i32,
+        height as i32,
+      ))
+    }
+  }
+
+  /// A new surface of the size on the same backend, the pixels are cleared or copied with `contents`
+  pub fn resized(&self, width: u32, height: u32, contents: ResizeContents) -> Option<Surface> {
+    let (contents, sampling) = match contents {
+      ResizeContents::Clear => (0, SamplingOptions::nearest()),
+      ResizeContents::Copy => (1, SamplingOptions::nearest()),
+      ResizeContents::Scale(sampling) => (2, sampling),
+    };
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_make_resized(
+        self.ptr,
+        width as i32,
+        height as i32,
+        contents,
+        sampling.0,
+      ))
+    }
+  }
+
   pub fn save_png(&self, path: &str) -> bool {
     let c_path = std::ffi::CString::new(path).unwrap();
     unsafe { ffi::skiac_surface_save(self.ptr, c_path.as_ptr()) }
@@ -1633,10 +3016,30 @@ impl Surface {
     unsafe { ffi::skiac_surface_get_width(self.ptr) as u32 }
   }
 
+  /// The pixels of the GPU surface are not addressable, and must only be accessed on the thread created it
+  pub fn is_gpu(&self) -> bool {
+    unsafe { ffi::skiac_surface_is_gpu(self.ptr) }
+  }
+
+  /// Finish the draws and share the texture of the GPU surface, the exported dmabuf fd is owned by the caller
+  pub fn export_texture(&self) -> Option<ExternalTexture> {
+    let mut c_texture: ffi::skiac_external_texture = ExternalTexture::IoSurface(0).into();
+    if unsafe { ffi::skiac_surface_export_texture(self.ptr, &mut c_texture) } {
+      Some(c_texture.into())
+    } else {
+      None
+    }
+  }
+
   pub fn height(&self) -> u32 {
     unsafe { ffi::skiac_surface_get_height(self.ptr) as u32 }
   }
 
+  /// 1 for the alpha only surface, 8 for the half float one, 4 for the others
+  pub fn bytes_per_pixel(&self) -> u32 {
+    unsafe { ffi::skiac_surface_get_bytes_per_pixel(self.ptr) as u32 }
+  }
+
   pub fn alpha_type(&self) -> AlphaType {
     let kind = unsafe { ffi::skiac_surface_get_alpha_type(self.ptr) };
     match kind {
@@ -1655,6 +3058,28 @@ impl Surface {
     width: u32,
     height: u32,
     color_space: ColorSpace,
+  ) -> Option<Vec<u8>> {
+    self.read_pixels_rect(
+      x,
+      y,
+      width,
+      height,
+      color_space,
+      ColorType::RGBA8888,
+      AlphaType::Unpremultiplied,
+    )
+  }
+
+  /// The 4 bytes per pixel color types, `RGBA8888` or `BGRA8888`
+  pub fn read_pixels_rect(
+    &self,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    color_type: ColorType,
+    alpha_type: AlphaType,
   ) -> Option<Vec<u8>> {
     let mut result = vec![0; (width * height * 4) as usize];
     let status = unsafe {
@@ -1666,6 +3091,8 @@ impl Surface {
         width as i32,
         height as i32,
         color_space as u8,
+        color_type as i32,
+        alpha_type as i32,
       )
     };
     if status {
@@ -1675,6 +3102,30 @@ impl Surface {
     }
   }
 
+  /// Replace the pixels at the top left corner, `pixels` is `width * height * 4` bytes of `RGBA8888` or `BGRA8888`
+  pub fn write_pixels(
+    &mut self,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    color_type: ColorType,
+    alpha_type: AlphaType,
+  ) -> bool {
+    debug_assert!(pixels.len() >= (width * height * 4) as usize);
+    unsafe {
+      ffi::skiac_surface_write_pixels(
+        self.ptr,
+        pixels.as_ptr(),
+        width as i32,
+        height as i32,
+        color_space as u8,
+        color_type as i32,
+        alpha_type as i32,
+      )
+    }
+  }
+
   pub fn data(&self) -> Option<SurfaceData> {
     unsafe {
       let mut data = ffi::skiac_surface_data {
@@ -1821,6 +3272,89 @@ impl SurfaceRef {
     }
   }
 
+  /// Read the whole surface converted to `color_type` and `alpha_type`, 4 bytes per pixel
+  pub fn read_pixels_as(
+    &self,
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    alpha_type: AlphaType,
+  ) -> Option<Vec<u8>> {
+    let mut pixels = vec![0u8; (width * height) as usize * color_type.bytes_per_pixel()];
+    if unsafe {
+      ffi::skiac_surface_read_pixels_as(
+        self.0,
+        pixels.as_mut_ptr(),
+        color_type as i32,
+        alpha_type as i32,
+      )
+    } {
+      Some(pixels)
+    } else {
+      None
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    unsafe { ffi::skiac_surface_get_width(self.0) as u32 }
+  }
+
+  pub fn height(&self) -> u32 {
+    unsafe { ffi::skiac_surface_get_height(self.0) as u32 }
+  }
+
+  pub fn encode_jpeg(&self, options: JpegEncodeOptions) -> Option<SkiaDataRef> {
+    unsafe {
+      let mut data = ffi::skiac_sk_data {
+        ptr: ptr::null_mut(),
+        size: 0,
+        data: ptr::null_mut(),
+      };
+      ffi::skiac_surface_encode_jpeg(self.0, &mut data, options.0);
+
+      if data.ptr.is_null() {
+        None
+      } else {
+        Some(SkiaDataRef(data))
+      }
+    }
+  }
+
+  pub fn encode_png(&self, options: PngEncodeOptions) -> Option<SkiaDataRef> {
+    unsafe {
+      let mut data = ffi::skiac_sk_data {
+        ptr: ptr::null_mut(),
+        size: 0,
+        data: ptr::null_mut(),
+      };
+      ffi::skiac_surface_encode_png(self.0, &mut data, options.0);
+
+      if data.ptr.is_null() {
+        None
+      } else {
+        Some(SkiaDataRef(data))
+      }
+    }
+  }
+
+  /// `quality` is the compression effort when `lossless` is true
+  pub fn encode_webp(&self, quality: u8, lossless: bool) -> Option<SkiaDataRef> {
+    unsafe {
+      let mut data = ffi::skiac_sk_data {
+        ptr: ptr::null_mut(),
+        size: 0,
+        data: ptr::null_mut(),
+      };
+      ffi::skiac_surface_encode_webp(self.0, &mut data, quality as f32, lossless);
+
+      if data.ptr.is_null() {
+        None
+      } else {
+        Some(SkiaDataRef(data))
+      }
+    }
+  }
+
   pub fn svg(&self, width: f32, height: f32, flag: SvgExportFlag) -> Option<SkiaDataRef> {
     let mut data = ffi::skiac_sk_data {
       ptr: ptr::null_mut(),
@@ -1848,6 +3382,9 @@ impl SurfaceRef {
 unsafe impl Send for SurfaceRef {}
 unsafe impl Sync for SurfaceRef {}
 
+// the owned copies of the canvas pixels are only used by one thread at a time
+unsafe impl Send for Surface {}
+
 pub struct SurfaceData<'a> {
   slice: &'a [u8],
 }
@@ -1877,6 +3414,10 @@ pub struct SkiaDataRef(pub(crate) ffi::skiac_sk_data);
 
 impl SkiaDataRef {
   pub fn slice(&self) -> &'static [u8] {
+    // the empty `SkData` has no bytes
+    if self.0.size == 0 {
+      return &[];
+    }
     unsafe { slice::from_raw_parts(self.0.ptr, self.0.size) }
   }
 }
@@ -1997,36 +3538,81 @@ impl Canvas {
     dy: f32,
     d_width: f32,
     d_height: f32,
-    enable_smoothing: bool,
-    quality: FilterQuality,
+    sampling: SamplingOptions,
     paint: &Paint,
   ) {
     unsafe {
       ffi::skiac_canvas_draw_image(
+        self.0, image, sx, sy, s_width, s_height, dx, dy, d_width, d_height, sampling.0, paint.0,
+      );
+    }
+  }
+
+  /// The center rect is stretched, the corners keep their size and the edges are stretched in one direction
+  pub fn draw_image_nine(
+    &mut self,
+    image: *mut ffi::skiac_bitmap,
+    center: (i32, i32, i32, i32),
+    dx: f32,
+    dy: f32,
+    d_width: f32,
+    d_height: f32,
+    enable_smoothing: bool,
+    paint: &Paint,
+  ) {
+    let (center_x, center_y, center_width, center_height) = center;
+    unsafe {
+      ffi::skiac_canvas_draw_image_nine(
         self.0,
         image,
-        sx,
-        sy,
-        s_width,
-        s_height,
+        center_x,
+        center_y,
+        center_width,
+        center_height,
         dx,
         dy,
         d_width,
         d_height,
-        enable_smoothing,
-        quality as i32,
+        enable_smoothing as i32,
         paint.0,
       );
     }
   }
 
-  pub fn draw_path(&mut self, path: &Path, paint: &Paint) {
+  /// `xforms` are `[scos, ssin, tx, ty]` and `src_rects` are `[x, y, width, height]` for every sprite,
+  /// `colors` are blended with the sprites by `blend_mode`
+  pub fn draw_atlas(
+    &mut self,
+    image: *mut ffi::skiac_bitmap,
+    xforms: &[f32],
+    src_rects: &[f32],
+    colors: Option<&[u32]>,
+    blend_mode: BlendMode,
+    sampling: SamplingOptions,
+    paint: &Paint,
+  ) {
     unsafe {
-      ffi::skiac_canvas_draw_path(self.0, path.0, paint.0);
+      ffi::skiac_canvas_draw_atlas(
+        self.0,
+        image,
+        xforms.as_ptr(),
+        src_rects.as_ptr(),
+        colors.map(|c| c.as_ptr()).unwrap_or(ptr::null()),
+        (xforms.len() / 4) as i32,
+        blend_mode as i32,
+        sampling.0,
+        paint.0,
+      );
     }
   }
 
-  pub fn draw_rect(&mut self, x: f32, y: f32, w: f32, h: f32, paint: &Paint) {
+  pub fn draw_path(&mut self, path: &Path, paint: &Paint) {
+    unsafe {
+      ffi::skiac_canvas_draw_path(self.0, path.0, paint.0);
+    }
+  }
+
+  pub fn draw_rect(&mut self, x: f32, y: f32, w: f32, h: f32, paint: &Paint) {
     unsafe {
       ffi::skiac_canvas_draw_rect(self.0, x, y, w, h, paint.0);
     }
@@ -2129,7 +3715,7 @@ impl Canvas {
     top: f32,
     alpha: u8,
     blend_mode: BlendMode,
-    filter_quality: FilterQuality,
+    sampling: SamplingOptions,
   ) {
     unsafe {
       ffi::skiac_canvas_draw_surface(
@@ -2139,7 +3725,7 @@ impl Canvas {
         top,
         alpha,
         blend_mode as i32,
-        filter_quality as i32,
+        sampling.0,
       );
     }
   }
@@ -2155,7 +3741,7 @@ impl Canvas {
     dy: f32,
     dw: f32,
     dh: f32,
-    filter_quality: FilterQuality,
+    sampling: SamplingOptions,
   ) {
     unsafe {
       ffi::skiac_canvas_draw_surface_rect(
@@ -2169,7 +3755,7 @@ impl Canvas {
         dy,
         dw,
         dh,
-        filter_quality as i32,
+        sampling.0,
       );
     }
   }
@@ -2180,9 +3766,82 @@ impl Canvas {
     }
   }
 
-  pub fn set_clip_path(&mut self, path: &Path) {
+  /// Only the PDF canvas writes the link
+  pub fn annotate_link_to_url(
+    &mut self,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    url: &str,
+  ) -> Result<(), NulError> {
+    let c_url = CString::new(url)?;
+    unsafe { ffi::skiac_canvas_annotate_link_to_url(self.0, x, y, w, h, c_url.as_ptr()) };
+    Ok(())
+  }
+
+  /// Link to the destination added by `annotate_named_destination`
+  pub fn annotate_link_to_destination(
+    &mut self,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    name: &str,
+  ) -> Result<(), NulError> {
+    let c_name = CString::new(name)?;
+    unsafe { ffi::skiac_canvas_annotate_link_to_destination(self.0, x, y, w, h, c_name.as_ptr()) };
+    Ok(())
+  }
+
+  pub fn annotate_named_destination(&mut self, x: f32, y: f32, name: &str) -> Result<(), NulError> {
+    let c_name = CString::new(name)?;
+    unsafe { ffi::skiac_canvas_annotate_named_destination(self.0, x, y, c_name.as_ptr()) };
+    Ok(())
+  }
+
+  /// `matrix` is concatenated to the current transform
+  pub fn draw_picture(&mut self, picture: &Picture, matrix: Option<&Matrix>, paint: &Paint) {
+    unsafe {
+      ffi::skiac_canvas_draw_picture(
+        self.0,
+        picture.0,
+        matrix.map(|m| m.0).unwrap_or(ptr::null_mut()),
+        paint.0,
+      )
+    }
+  }
+
+  /// Render the SVG document at `(dx, dy)`, scaled to `dw` x `dh` if they are positive.
+  /// Returns `false` if the SVG can't be parsed or has no size
+  pub fn draw_svg(
+    &mut self,
+    svg: &[u8],
+    dx: f32,
+    dy: f32,
+    dw: f32,
+    dh: f32,
+    paint: &Paint,
+    fc: &FontCollection,
+  ) -> bool {
+    unsafe {
+      ffi::skiac_canvas_draw_svg(
+        self.0,
+        svg.as_ptr(),
+        svg.len(),
+        dx,
+        dy,
+        dw,
+        dh,
+        paint.0,
+        fc.0,
+      )
+    }
+  }
+
+  pub fn set_clip_path(&mut self, path: &Path, anti_alias: bool) {
     unsafe {
-      ffi::skiac_canvas_clip_path(self.0, path.0);
+      ffi::skiac_canvas_clip_path(self.0, path.0, anti_alias);
     }
   }
 
@@ -2192,6 +3851,26 @@ impl Canvas {
     }
   }
 
+  /// The bounds are the left, top, right and bottom in the local coordinates
+  pub fn save_layer(&mut self, paint: &Paint, bounds: Option<(f32, f32, f32, f32)>) {
+    let mut rect = bounds.map(|(left, top, right, bottom)| ffi::skiac_rect {
+      left,
+      top,
+      right,
+      bottom,
+    });
+    unsafe {
+      ffi::skiac_canvas_save_layer(
+        self.0,
+        paint.0,
+        rect
+          .as_mut()
+          .map(|r| r as *mut ffi::skiac_rect)
+          .unwrap_or(ptr::null_mut()),
+      );
+    }
+  }
+
   pub fn restore(&mut self) {
     unsafe {
       ffi::skiac_canvas_restore(self.0);
@@ -2204,7 +3883,8 @@ impl Canvas {
     }
   }
 
-  pub fn write_pixels(&mut self, image: &ImageData, x: u32, y: u32) {
+  /// The pixels in `color_space` are converted into the color space of the canvas
+  pub fn write_pixels(&mut self, image: &ImageData, x: u32, y: u32, color_space: ColorSpace) {
     unsafe {
       ffi::skiac_canvas_write_pixels(
         self.0,
@@ -2214,6 +3894,7 @@ impl Canvas {
         (image.width * 4) as usize,
         x as i32,
         y as i32,
+        color_space as u8,
       );
     }
   }
@@ -2291,6 +3972,10 @@ impl Paint {
     }
   }
 
+  pub fn get_anti_alias(&self) -> bool {
+    unsafe { ffi::skiac_paint_get_anti_alias(self.0) }
+  }
+
   pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
     unsafe {
       ffi::skiac_paint_set_blend_mode(self.0, blend_mode as i32);
@@ -2364,6 +4049,12 @@ impl Paint {
       ffi::skiac_paint_set_image_filter(self.0, image_filter.0);
     }
   }
+
+  pub fn set_color_filter(&mut self, color_filter: &ColorFilter) {
+    unsafe {
+      ffi::skiac_paint_set_color_filter(self.0, color_filter.0);
+    }
+  }
 }
 
 impl Default for Paint {
@@ -2409,14 +4100,28 @@ impl Path {
     unsafe { ffi::skiac_path_swap(self.0, other.0) }
   }
 
-  pub fn from_svg_path(path: &str) -> Option<Path> {
-    let path_str = CString::new(path).ok()?;
-    let p = unsafe { ffi::skiac_path_from_svg(path_str.into_raw()) };
-    if p.is_null() {
-      None
-    } else {
-      Some(Path(p))
+  /// The invalid part of the path data and the segments after it are ignored
+  pub fn from_svg_path(data: &str) -> Path {
+    let mut path = Path::new();
+    for segment in svg_path::parse(data) {
+      match segment {
+        Segment::MoveTo(x, y) => path.move_to(x, y),
+        Segment::LineTo(x, y) => path.line_to(x, y),
+        Segment::CubicTo(x1, y1, x2, y2, x3, y3) => path.cubic_to(x1, y1, x2, y2, x3, y3),
+        Segment::QuadTo(x1, y1, x2, y2) => path.quad_to(x1, y1, x2, y2),
+        Segment::ArcTo {
+          rx,
+          ry,
+          rotation,
+          large_arc,
+          sweep,
+          x,
+          y,
+        } => path.svg_arc_to(rx, ry, rotation, large_arc, sweep, x, y),
+        Segment::Close => path.close(),
+      }
     }
+    path
   }
 
   pub fn add_path(&mut self, sub_path: &Path, transform: &Matrix) {
@@ -2565,6 +4270,22 @@ impl Path {
     unsafe { ffi::skiac_path_arc_to_tangent(self.0, x1, y1, x2, y2, radius) }
   }
 
+  /// The elliptical arc of the SVG path data, the radii are scaled up if they are too small
+  pub fn svg_arc_to(
+    &mut self,
+    rx: f32,
+    ry: f32,
+    x_axis_rotate: f32,
+    large_arc: bool,
+    sweep: bool,
+    x: f32,
+    y: f32,
+  ) {
+    unsafe {
+      ffi::skiac_path_svg_arc_to(self.0, rx, ry, x_axis_rotate, large_arc, sweep, x, y);
+    }
+  }
+
   pub fn move_to(&mut self, x: f32, y: f32) {
     unsafe {
       ffi::skiac_path_move_to(self.0, x, y);
@@ -2604,6 +4325,36 @@ impl Path {
     }
   }
 
+  /// The radii are of the upper left, upper right, lower right and lower left corners,
+  /// they're swapped like the rect is flipped by the negative width or height
+  pub fn add_round_rect(
+    &mut self,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    radii: [(f32, f32); 4],
+  ) {
+    let [mut upper_left, mut upper_right, mut lower_right, mut lower_left] = radii;
+    let (mut left, mut top) = (x, y);
+    if width < 0.0 {
+      left += width;
+      mem::swap(&mut upper_left, &mut upper_right);
+      mem::swap(&mut lower_left, &mut lower_right);
+    }
+    if height < 0.0 {
+      top += height;
+      mem::swap(&mut upper_left, &mut lower_left);
+      mem::swap(&mut upper_right, &mut lower_right);
+    }
+    let radii =
+      [upper_left, upper_right, lower_right, lower_left].map(|(x, y)| ffi::skiac_point { x, y });
+    unsafe {
+      ffi::skiac_path_add_round_rect(self.0, left, top, width.abs(), height.abs(), radii.as_ptr());
+    }
+    self.move_to(x, y);
+  }
+
   pub fn push_circle(&mut self, x: f32, y: f32, r: f32) {
     unsafe {
       ffi::skiac_path_add_circle(self.0, x, y, r);
@@ -2648,8 +4399,26 @@ impl Path {
     unsafe { ffi::skiac_path_as_winding(self.0) }
   }
 
-  pub fn stroke(&mut self, cap: StrokeCap, join: StrokeJoin, width: f32, miter_limit: f32) -> bool {
-    unsafe { ffi::skiac_path_stroke(self.0, cap as i32, join as u8, width, miter_limit) }
+  /// Replace the path with the outline of its stroke, dashed by `path_effect` first
+  pub fn stroke(
+    &mut self,
+    cap: StrokeCap,
+    join: StrokeJoin,
+    width: f32,
+    miter_limit: f32,
+    path_effect: Option<&PathEffect>,
+  ) -> bool {
+    let path_effect = path_effect.map_or(ptr::null_mut(), |effect| effect.0);
+    unsafe {
+      ffi::skiac_path_stroke(
+        self.0,
+        cap as i32,
+        join as u8,
+        width,
+        miter_limit,
+        path_effect,
+      )
+    }
   }
 
   pub fn compute_tight_bounds(&self) -> (f32, f32, f32, f32) {
@@ -2674,12 +4443,30 @@ impl Path {
     (rect.left, rect.top, rect.right, rect.bottom)
   }
 
+  /// The total length of the contours
+  pub fn length(&self) -> f32 {
+    unsafe { ffi::skiac_path_length(self.0) }
+  }
+
+  /// The position and the unit tangent at the distance along the contours,
+  /// the distance out of the path is pinned to its ends
+  pub fn get_pos_tan(&self, distance: f32) -> Option<((f32, f32), (f32, f32))> {
+    let mut position = ffi::skiac_point { x: 0.0, y: 0.0 };
+    let mut tangent = ffi::skiac_point { x: 0.0, y: 0.0 };
+    if unsafe { ffi::skiac_path_get_pos_tan(self.0, distance, &mut position, &mut tangent) } {
+      Some(((position.x, position.y), (tangent.x, tangent.y)))
+    } else {
+      None
+    }
+  }
+
   pub fn trim(&mut self, start: f32, end: f32, is_complement: bool) -> bool {
     unsafe { ffi::skiac_path_trim(self.0, start, end, is_complement) }
   }
 
-  pub fn dash(&mut self, on: f32, off: f32, phase: f32) -> bool {
-    unsafe { ffi::skiac_path_dash(self.0, on, off, phase) }
+  /// The intervals are the lengths of the on and off dashes in turn, the count must be even
+  pub fn dash(&mut self, intervals: &[f32], phase: f32) -> bool {
+    unsafe { ffi::skiac_path_dash(self.0, intervals.as_ptr(), intervals.len() as i32, phase) }
   }
 
   fn ellipse_helper(&mut self, x: f32, y: f32, rx: f32, ry: f32, start_angle: f32, end_angle: f32) {
@@ -3317,6 +5104,75 @@ impl ImageFilter {
     }
     Some(ImageFilter(raw_ptr))
   }
+
+  pub fn make_offset(dx: f32, dy: f32, chained_filter: Option<&ImageFilter>) -> Option<Self> {
+    let raw_ptr = unsafe {
+      ffi::skiac_image_filter_make_offset(
+        dx,
+        dy,
+        chained_filter.map(|c| c.0).unwrap_or(ptr::null_mut()),
+      )
+    };
+    Self::from_ptr(raw_ptr)
+  }
+
+  pub fn make_color_filter(
+    color_filter: &ColorFilter,
+    chained_filter: Option<&ImageFilter>,
+  ) -> Option<Self> {
+    let raw_ptr = unsafe {
+      ffi::skiac_image_filter_make_color_filter(
+        color_filter.0,
+        chained_filter.map(|c| c.0).unwrap_or(ptr::null_mut()),
+      )
+    };
+    Self::from_ptr(raw_ptr)
+  }
+
+  /// The `None` filters are the source
+  pub fn make_merge(filters: &[Option<&ImageFilter>]) -> Option<Self> {
+    let mut raw_filters = filters
+      .iter()
+      .map(|filter| filter.map(|f| f.0).unwrap_or(ptr::null_mut()))
+      .collect::<Vec<_>>();
+    let raw_ptr =
+      unsafe { ffi::skiac_image_filter_make_merge(raw_filters.as_mut_ptr(), raw_filters.len()) };
+    Self::from_ptr(raw_ptr)
+  }
+
+  /// `inner` is applied first
+  pub fn make_compose(outer: &ImageFilter, inner: &ImageFilter) -> Option<Self> {
+    let raw_ptr = unsafe { ffi::skiac_image_filter_make_compose(outer.0, inner.0) };
+    Self::from_ptr(raw_ptr)
+  }
+
+  /// Move the pixels of the input by the channels of the displacement
+  pub fn make_displacement_map(
+    x_channel: ColorChannel,
+    y_channel: ColorChannel,
+    scale: f32,
+    displacement: &ImageFilter,
+    chained_filter: Option<&ImageFilter>,
+  ) -> Option<Self> {
+    let raw_ptr = unsafe {
+      ffi::skiac_image_filter_make_displacement_map(
+        x_channel as i32,
+        y_channel as i32,
+        scale,
+        displacement.0,
+        chained_filter.map(|c| c.0).unwrap_or(ptr::null_mut()),
+      )
+    };
+    Self::from_ptr(raw_ptr)
+  }
+
+  fn from_ptr(raw_ptr: *mut ffi::skiac_image_filter) -> Option<Self> {
+    if raw_ptr.is_null() {
+      None
+    } else {
+      Some(ImageFilter(raw_ptr))
+    }
+  }
 }
 
 impl Drop for ImageFilter {
@@ -3327,20 +5183,123 @@ impl Drop for ImageFilter {
   }
 }
 
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ColorFilter(*mut ffi::skiac_color_filter);
+
+impl Clone for ColorFilter {
+  fn clone(&self) -> Self {
+    unsafe {
+      ffi::skiac_color_filter_ref(self.0);
+    };
+    Self(self.0)
+  }
+}
+
+impl ColorFilter {
+  /// The 4x5 row major matrix of the unpremultiplied colors in 0 ~ 1
+  pub fn make_matrix(row_major: &[f32; 20]) -> Option<Self> {
+    unsafe { Self::from_ptr(ffi::skiac_color_filter_make_matrix(row_major.as_ptr())) }
+  }
+
+  pub fn make_blend(color: u32, blend_mode: BlendMode) -> Option<Self> {
+    unsafe { Self::from_ptr(ffi::skiac_color_filter_make_blend(color, blend_mode as i32)) }
+  }
+
+  /// The RGB are multiplied by `mul` then added with `add`, the alpha is not changed
+  pub fn make_lighting(mul: u32, add: u32) -> Option<Self> {
+    unsafe { Self::from_ptr(ffi::skiac_color_filter_make_lighting(mul, add)) }
+  }
+
+  /// The luminance of the color is the alpha, the RGB are black
+  pub fn make_luma() -> Option<Self> {
+    unsafe { Self::from_ptr(ffi::skiac_color_filter_make_luma()) }
+  }
+
+  /// `inner` is applied first
+  pub fn compose(&self, inner: &ColorFilter) -> Option<Self> {
+    unsafe { Self::from_ptr(ffi::skiac_color_filter_compose(self.0, inner.0)) }
+  }
+
+  unsafe fn from_ptr(ptr: *mut ffi::skiac_color_filter) -> Option<Self> {
+    if ptr.is_null() {
+      None
+    } else {
+      Some(ColorFilter(ptr))
+    }
+  }
+}
+
+impl Drop for ColorFilter {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_color_filter_destroy(self.0) };
+  }
+}
+
 #[repr(transparent)]
 #[derive(Debug)]
 pub(crate) struct Bitmap(pub(crate) ffi::skiac_bitmap_info);
 
 impl Bitmap {
-  pub fn from_buffer(ptr: *mut u8, size: usize) -> Self {
+  /// Decode the encoded image with Skia codecs.
+  /// `width` and `height` are only hints, they are used to select the closest embedded image in ICO files.
+  /// The pixels are converted from the embedded ICC profile into `color_space` if `color_space_conversion` is true.
+  pub fn from_buffer(
+    ptr: *mut u8,
+    size: usize,
+    width: i32,
+    height: i32,
+    color_space: ColorSpace,
+    color_space_conversion: bool,
+  ) -> Option<Self> {
+    let mut bitmap_info = ffi::skiac_bitmap_info {
+      bitmap: ptr::null_mut(),
+      width: 0,
+      height: 0,
+    };
+    unsafe {
+      ffi::skiac_bitmap_make_from_buffer(
+        ptr,
+        size,
+        width,
+        height,
+        color_space as u8,
+        color_space_conversion,
+        &mut bitmap_info,
+      );
+
+      if bitmap_info.bitmap.is_null() {
+        return None;
+      }
+      Some(Bitmap(bitmap_info))
+    }
+  }
+
+  /// Copy the unpremultiplied RGBA pixels into a new Bitmap
+  pub fn from_pixels(pixels: &[u8], width: u32, height: u32) -> Option<Self> {
+    let length = (width as usize)
+      .checked_mul(height as usize)
+      .and_then(|pixels| pixels.checked_mul(4));
+    if length != Some(pixels.len()) {
+      return None;
+    }
     let mut bitmap_info = ffi::skiac_bitmap_info {
       bitmap: ptr::null_mut(),
       width: 0,
       height: 0,
     };
     unsafe {
-      ffi::skiac_bitmap_make_from_buffer(ptr, size, &mut bitmap_info);
-      Bitmap(bitmap_info)
+      ffi::skiac_bitmap_make_from_pixels(
+        pixels.as_ptr(),
+        width as i32,
+        height as i32,
+        &mut bitmap_info,
+      );
+
+      if bitmap_info.bitmap.is_null() {
+        return None;
+      }
+      Some(Bitmap(bitmap_info))
     }
   }
 
@@ -3415,6 +5374,18 @@ impl Bitmap {
       height: (size / row_bytes / 4) as i32,
     })
   }
+
+  /// Read the pixels as unpremultiplied RGBA, returns (width, height, pixels)
+  pub fn read_pixels(&self) -> Option<(u32, u32, Vec<u8>)> {
+    let width = unsafe { ffi::skiac_bitmap_get_width(self.0.bitmap) };
+    let height = unsafe { ffi::skiac_bitmap_get_height(self.0.bitmap) };
+    let mut pixels = vec![0u8; width * height * 4];
+    if unsafe { ffi::skiac_bitmap_read_pixels(self.0.bitmap, pixels.as_mut_ptr(), width * 4) } {
+      Some((width as u32, height as u32, pixels))
+    } else {
+      None
+    }
+  }
 }
 
 impl Drop for Bitmap {
@@ -3446,6 +5417,88 @@ impl ImagePattern {
   }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeEffectUniform {
+  pub name: String,
+  /// Offset and count in 4 bytes values
+  pub offset: usize,
+  pub count: usize,
+  /// `int` to `int4`, the other uniforms are floats
+  pub is_int: bool,
+}
+
+/// The compiled SkSL shader
+#[derive(Debug)]
+pub struct RuntimeEffect(*mut ffi::skiac_runtime_effect);
+
+impl RuntimeEffect {
+  /// The error is the message of the SkSL compiler
+  pub fn new(sksl: &str) -> Result<Self, SkError> {
+    let sksl = CString::new(sksl)?;
+    let mut error = SkiaString {
+      ptr: ptr::null_mut(),
+      length: 0,
+      sk_string: ptr::null_mut(),
+    };
+    let ptr = unsafe { ffi::skiac_runtime_effect_make(sksl.as_ptr(), &mut error) };
+    if ptr.is_null() {
+      let message = unsafe { slice::from_raw_parts(error.ptr as *const u8, error.length) };
+      return Err(SkError::Generic(format!(
+        "Compile the shader effect failed: {}",
+        String::from_utf8_lossy(message)
+      )));
+    }
+    Ok(Self(ptr))
+  }
+
+  pub fn uniforms(&self) -> Vec<RuntimeEffectUniform> {
+    let count = unsafe { ffi::skiac_runtime_effect_get_uniform_count(self.0) };
+    (0..count)
+      .map(|index| {
+        let mut uniform = ffi::skiac_runtime_effect_uniform {
+          name: ptr::null(),
+          offset: 0,
+          count: 0,
+          is_int: false,
+        };
+        unsafe { ffi::skiac_runtime_effect_get_uniform(self.0, index, &mut uniform) };
+        RuntimeEffectUniform {
+          name: unsafe { CStr::from_ptr(uniform.name) }
+            .to_string_lossy()
+            .into_owned(),
+          offset: uniform.offset,
+          count: uniform.count,
+          is_int: uniform.is_int,
+        }
+      })
+      .collect()
+  }
+
+  /// In 4 bytes values
+  pub fn uniform_size(&self) -> usize {
+    unsafe { ffi::skiac_runtime_effect_get_uniform_size(self.0) }
+  }
+
+  /// `uniforms` has `uniform_size` values, the ints are stored as their bits.
+  /// `input` is sampled by the first child shader
+  pub fn make_shader(&self, uniforms: &[f32], input: Option<&Shader>) -> Option<Shader> {
+    debug_assert_eq!(uniforms.len(), self.uniform_size());
+    unsafe {
+      Shader::from_ptr(ffi::skiac_runtime_effect_make_shader(
+        self.0,
+        uniforms.as_ptr(),
+        input.map(|shader| shader.0).unwrap_or(ptr::null_mut()),
+      ))
+    }
+  }
+}
+
+impl Drop for RuntimeEffect {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_runtime_effect_destroy(self.0) }
+  }
+}
+
 #[repr(C)]
 #[derive(Clone, Debug)]
 pub struct SkiaString {
@@ -3599,6 +5652,28 @@ impl SkWMemoryStream {
     unsafe { ffi::skiac_sk_w_stream_get(self.0, &mut data, w as i32, h as i32) };
     SkiaDataRef(data)
   }
+
+  /// The bytes written since the last call, without closing the SVG document
+  pub fn take(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_sk_w_stream_take(self.0, &mut data) };
+    SkiaDataRef(data)
+  }
+
+  /// The SVG document drawn so far, the stream is left untouched so it can be called repeatedly
+  pub fn snapshot(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_sk_w_stream_snapshot(self.0, &mut data) };
+    SkiaDataRef(data)
+  }
 }
 
 impl Drop for SkWMemoryStream {
@@ -3607,6 +5682,357 @@ impl Drop for SkWMemoryStream {
   }
 }
 
+#[derive(Debug)]
+pub struct SkWFileStream(*mut ffi::skiac_w_file_stream);
+
+impl SkWFileStream {
+  /// Close the SVG document and the file, the later draws are not written.
+  /// `style` is written before the closing tag
+  pub fn finish_svg(&mut self, style: &[u8]) -> bool {
+    unsafe { ffi::skiac_w_file_stream_finish_svg(self.0, style.as_ptr(), style.len()) }
+  }
+}
+
+/// Records the typefaces of the text drawn on the SVG surface, to embed them as `@font-face`
+#[derive(Debug)]
+pub struct SvgFontRecorder(*mut ffi::skiac_svg_font_recorder);
+
+impl SvgFontRecorder {
+  /// The draws on `surface` go through the recorder afterwards
+  pub fn new(surface: &mut Surface) -> Self {
+    let recorder = unsafe { ffi::skiac_svg_font_recorder_create(surface.canvas.0) };
+    surface.canvas = Canvas(unsafe { ffi::skiac_svg_font_recorder_get_canvas(recorder) });
+    Self(recorder)
+  }
+
+  /// The `<style>` element with the `@font-face` rules, empty if no text is drawn
+  pub fn style(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_svg_font_recorder_get_style(self.0, &mut data) };
+    SkiaDataRef(data)
+  }
+}
+
+impl Drop for SvgFontRecorder {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_svg_font_recorder_destroy(self.0) }
+  }
+}
+
+impl Drop for SkWFileStream {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_w_file_stream_destroy(self.0) }
+  }
+}
+
+/// The canvas of the surface created with `Surface::new_pdf` is owned by the document,
+/// so it's swapped by `add_page` and `close`
+#[derive(Debug)]
+pub struct PdfDocument {
+  ptr: *mut ffi::skiac_pdf_document,
+  closed: bool,
+}
+
+impl PdfDocument {
+  /// End the current page and draw the surface on a new one, returns false if the document is closed
+  pub fn add_page(&mut self, surface: &mut Surface, width: f32, height: f32) -> bool {
+    let canvas = unsafe { ffi::skiac_pdf_document_add_page(self.ptr, width, height) };
+    if canvas.is_null() {
+      return false;
+    }
+    surface.canvas = Canvas(canvas);
+    true
+  }
+
+  /// Write the rest of the document, the later draws on the surface are ignored
+  pub fn close(&mut self, surface: &mut Surface) {
+    surface.canvas = Canvas(unsafe { ffi::skiac_pdf_document_close(self.ptr) });
+    self.closed = true;
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  /// The bytes written since the last call, always empty if the document is written to a file
+  pub fn take(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_pdf_document_take(self.ptr, &mut data) };
+    SkiaDataRef(data)
+  }
+
+  /// The bytes written so far, always empty if the document is written to a file
+  pub fn snapshot(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_pdf_document_snapshot(self.ptr, &mut data) };
+    SkiaDataRef(data)
+  }
+}
+
+impl Drop for PdfDocument {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_pdf_document_destroy(self.ptr) }
+  }
+}
+
+pub struct PictureRecorder {
+  ptr: *mut ffi::skiac_picture_recorder,
+}
+
+impl PictureRecorder {
+  /// End the recording, the later draws on the surface are ignored
+  pub fn finish(&mut self, surface: &mut Surface) {
+    surface.canvas = Canvas(unsafe { ffi::skiac_picture_recorder_finish(self.ptr) });
+  }
+
+  /// `None` until the recording is finished
+  pub fn picture(&self) -> Option<Picture> {
+    let picture = unsafe { ffi::skiac_picture_recorder_get_picture(self.ptr) };
+    (!picture.is_null()).then(|| Picture(picture))
+  }
+}
+
+impl Drop for PictureRecorder {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_picture_recorder_destroy(self.ptr) }
+  }
+}
+
+#[derive(Debug)]
+pub struct Picture(*mut ffi::skiac_picture);
+
+impl Picture {
+  /// `None` if the data isn't a valid `.skp`, only load the pictures from the trusted sources
+  pub fn from_data(data: &[u8]) -> Option<Picture> {
+    let picture = unsafe { ffi::skiac_picture_make_from_data(data.as_ptr(), data.len()) };
+    (!picture.is_null()).then(|| Picture(picture))
+  }
+
+  /// The bounds of the recording, `(left, top, right, bottom)`
+  pub fn cull_rect(&self) -> (f32, f32, f32, f32) {
+    let mut rect = ffi::skiac_rect {
+      left: 0.0f32,
+      top: 0.0f32,
+      right: 0.0f32,
+      bottom: 0.0f32,
+    };
+    unsafe { ffi::skiac_picture_get_cull_rect(self.0, &mut rect) };
+    (rect.left, rect.top, rect.right, rect.bottom)
+  }
+
+  /// The `.skp` bytes
+  pub fn serialize(&self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_picture_serialize(self.0, &mut data) };
+    SkiaDataRef(data)
+  }
+}
+
+impl Drop for Picture {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_picture_destroy(self.0) }
+  }
+}
+
+/// The unpremultiplied RGBA pixels read without waiting for the GPU
+#[derive(Debug)]
+pub struct PixelsReadback {
+  ptr: *mut ffi::skiac_pixels_readback,
+  width: u32,
+  height: u32,
+}
+
+impl PixelsReadback {
+  /// The rect must be inside of the surface
+  pub fn new(
+    surface: &Surface,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+  ) -> Self {
+    let ptr = unsafe {
+      ffi::skiac_surface_read_pixels_async(
+        surface.ptr,
+        x as i32,
+        y as i32,
+        width as i32,
+        height as i32,
+        color_space as u8,
+      )
+    };
+    Self { ptr, width, height }
+  }
+
+  /// Whether the read is completed, it's never blocked by the GPU
+  pub fn poll(&self) -> bool {
+    unsafe { ffi::skiac_pixels_readback_poll(self.ptr) }
+  }
+
+  /// `None` if it's pending or failed
+  pub fn pixels(&self) -> Option<Vec<u8>> {
+    if !self.poll() {
+      return None;
+    }
+    let mut pixels = vec![0u8; (self.width * self.height * 4) as usize];
+    if unsafe { ffi::skiac_pixels_readback_copy(self.ptr, pixels.as_mut_ptr()) } {
+      Some(pixels)
+    } else {
+      None
+    }
+  }
+
+  /// The raster copy of the pixels, `None` if it's pending or failed
+  pub fn to_surface(&self, color_space: ColorSpace) -> Option<Surface> {
+    if !self.poll() {
+      return None;
+    }
+    unsafe {
+      Surface::from_ptr(ffi::skiac_pixels_readback_make_surface(
+        self.ptr,
+        color_space as u8,
+      ))
+    }
+  }
+}
+
+impl Drop for PixelsReadback {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_pixels_readback_destroy(self.ptr) }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeStatus {
+  Incomplete,
+  Complete,
+}
+
+/// Decodes an encoded image from chunks of data as they arrive.
+/// Codecs which support incremental decoding fill the rows progressively.
+#[derive(Debug)]
+pub struct ImageDecoder(*mut ffi::skiac_image_decoder);
+
+impl ImageDecoder {
+  pub fn new() -> Self {
+    ImageDecoder(unsafe { ffi::skiac_image_decoder_create() })
+  }
+
+  pub fn push(
+    &mut self,
+    chunk: &[u8],
+    finished: bool,
+  ) -> Option<(DecodeStatus, ffi::skiac_decode_progress)> {
+    let mut progress = ffi::skiac_decode_progress::default();
+    let status = unsafe {
+      ffi::skiac_image_decoder_push(self.0, chunk.as_ptr(), chunk.len(), finished, &mut progress)
+    };
+    match status {
+      0 => Some((DecodeStatus::Incomplete, progress)),
+      1 => Some((DecodeStatus::Complete, progress)),
+      _ => None,
+    }
+  }
+
+  /// Take the decoded Bitmap, return `None` if the decoding is not completed
+  pub fn take_bitmap(&mut self) -> Option<Bitmap> {
+    let mut bitmap_info = ffi::skiac_bitmap_info {
+      bitmap: ptr::null_mut(),
+      width: 0,
+      height: 0,
+    };
+    unsafe { ffi::skiac_image_decoder_get_bitmap(self.0, &mut bitmap_info) };
+    if bitmap_info.bitmap.is_null() {
+      return None;
+    }
+    Some(Bitmap(bitmap_info))
+  }
+}
+
+impl Drop for ImageDecoder {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_image_decoder_destroy(self.0) }
+  }
+}
+
+/// Encodes a snapshot of the surface a few rows at a time
+pub struct StreamEncoder(*mut ffi::skiac_stream_encoder);
+
+impl StreamEncoder {
+  pub fn png(surface: &SurfaceRef, options: PngEncodeOptions) -> Option<Self> {
+    let encoder = unsafe { ffi::skiac_stream_encoder_create_png(surface.0, options.0) };
+    if encoder.is_null() {
+      None
+    } else {
+      Some(StreamEncoder(encoder))
+    }
+  }
+
+  pub fn jpeg(surface: &SurfaceRef, options: JpegEncodeOptions) -> Option<Self> {
+    let encoder = unsafe { ffi::skiac_stream_encoder_create_jpeg(surface.0, options.0) };
+    if encoder.is_null() {
+      None
+    } else {
+      Some(StreamEncoder(encoder))
+    }
+  }
+
+  /// Encode the next `rows` rows, returns the bytes produced and whether all the rows are encoded
+  pub fn encode_rows(&mut self, rows: u32) -> Option<(Vec<u8>, bool)> {
+    let mut data = ptr::null();
+    let mut size = 0;
+    let rows_left =
+      unsafe { ffi::skiac_stream_encoder_encode_rows(self.0, rows as i32, &mut data, &mut size) };
+    if rows_left < 0 {
+      return None;
+    }
+    let bytes = if size == 0 {
+      Vec::new()
+    } else {
+      unsafe { slice::from_raw_parts(data, size) }.to_vec()
+    };
+    Some((bytes, rows_left == 0))
+  }
+}
+
+impl Drop for StreamEncoder {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_stream_encoder_destroy(self.0) }
+  }
+}
+
+/// Compress `data` into a zlib stream
+pub fn deflate(data: &[u8], level: i32) -> Option<SkiaDataRef> {
+  let mut output = ffi::skiac_sk_data {
+    ptr: ptr::null_mut(),
+    size: 0,
+    data: ptr::null_mut(),
+  };
+  unsafe { ffi::skiac_deflate(data.as_ptr(), data.len(), level, &mut output) };
+  if output.ptr.is_null() {
+    None
+  } else {
+    Some(SkiaDataRef(output))
+  }
+}
+
 #[inline(always)]
 pub(crate) fn radians_to_degrees(rad: f32) -> f32 {
   rad / PI * 180.0
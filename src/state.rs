@@ -1,6 +1,6 @@
 use cssparser::RGBA;
 
-use crate::sk::{ImageFilter, Matrix};
+use crate::sk::{ColorFilter, ImageFilter, Matrix, Path};
 
 use super::{
   font::Font,
@@ -17,7 +17,6 @@ pub struct Context2dRenderingState {
   pub shadow_offset_y: f32,
   pub shadow_blur: f32,
   pub shadow_color: RGBA,
-  pub shadow_color_string: String,
   pub global_alpha: f32,
   pub line_dash_offset: f32,
   pub image_smoothing_enabled: bool,
@@ -30,7 +29,15 @@ pub struct Context2dRenderingState {
   pub text_direction: TextDirection,
   pub transform: Matrix,
   pub filter: Option<ImageFilter>,
-  pub filters_string: String,
+  /// The CSS filter, `None` if the filter is an `ImageFilter`
+  pub filters_string: Option<String>,
+  pub color_filter: Option<ColorFilter>,
+  /// The clip paths in the device coordinates with their anti alias,
+  /// to apply them again when the surface is replaced
+  pub clips: Vec<(Path, bool)>,
+  /// The paint and the bounds of the layer on the state pushed by `saveLayer`,
+  /// to save the layer again when the surface is replaced
+  pub layer: Option<(Paint, Option<(f32, f32, f32, f32)>)>,
 }
 
 impl Default for Context2dRenderingState {
@@ -42,8 +49,7 @@ impl Default for Context2dRenderingState {
       shadow_offset_x: 0f32,
       shadow_offset_y: 0f32,
       shadow_blur: 0f32,
-      shadow_color: RGBA::new(0, 0, 0, 255),
-      shadow_color_string: "#000000".to_owned(),
+      shadow_color: RGBA::new(0, 0, 0, 0),
       /// 0.0 ~ 1.0
       global_alpha: 1.0,
       /// A float specifying the amount of the line dash offset. The default value is 0.0.
@@ -58,7 +64,10 @@ impl Default for Context2dRenderingState {
       text_direction: TextDirection::default(),
       transform: Matrix::identity(),
       filter: None,
-      filters_string: "none".to_owned(),
+      filters_string: Some("none".to_owned()),
+      color_filter: None,
+      clips: vec![],
+      layer: None,
     }
   }
 }
@@ -0,0 +1,353 @@
+// The SVG path data of `new Path2D(d)`, https://www.w3.org/TR/SVG2/paths.html#PathDataBNF
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+  MoveTo(f32, f32),
+  LineTo(f32, f32),
+  CubicTo(f32, f32, f32, f32, f32, f32),
+  QuadTo(f32, f32, f32, f32),
+  ArcTo {
+    rx: f32,
+    ry: f32,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    x: f32,
+    y: f32,
+  },
+  Close,
+}
+
+/// The segments in the absolute coordinates, the ones after the first error are dropped,
+/// so the valid part of the path is still drawn like the browsers do
+pub fn parse(data: &str) -> Vec<Segment> {
+  let mut segments = Vec::new();
+  let mut parser = Parser { data, pos: 0 };
+  parser.skip_whitespace();
+  let mut command = match parser.command() {
+    Some(command @ (b'M' | b'm')) => command,
+    _ => return segments,
+  };
+  let (mut x, mut y) = (0f32, 0f32);
+  let (mut start_x, mut start_y) = (0f32, 0f32);
+  // the control points reflected by `S` and `T`
+  let mut last_cubic: Option<(f32, f32)> = None;
+  let mut last_quad: Option<(f32, f32)> = None;
+  loop {
+    let (ox, oy) = if command.is_ascii_lowercase() {
+      (x, y)
+    } else {
+      (0.0, 0.0)
+    };
+    let segment = match command.to_ascii_uppercase() {
+      b'M' => parser.numbers().map(|[x1, y1]| {
+        start_x = x1 + ox;
+        start_y = y1 + oy;
+        Segment::MoveTo(start_x, start_y)
+      }),
+      b'L' => parser
+        .numbers()
+        .map(|[x1, y1]| Segment::LineTo(x1 + ox, y1 + oy)),
+      b'H' => parser.numbers().map(|[x1]| Segment::LineTo(x1 + ox, y)),
+      b'V' => parser.numbers().map(|[y1]| Segment::LineTo(x, y1 + oy)),
+      b'C' => parser.numbers().map(|[x1, y1, x2, y2, x3, y3]| {
+        Segment::CubicTo(x1 + ox, y1 + oy, x2 + ox, y2 + oy, x3 + ox, y3 + oy)
+      }),
+      b'S' => parser.numbers().map(|[x2, y2, x3, y3]| {
+        let (x1, y1) = last_cubic.map_or((x, y), |(cx, cy)| (2.0 * x - cx, 2.0 * y - cy));
+        Segment::CubicTo(x1, y1, x2 + ox, y2 + oy, x3 + ox, y3 + oy)
+      }),
+      b'Q' => parser
+        .numbers()
+        .map(|[x1, y1, x2, y2]| Segment::QuadTo(x1 + ox, y1 + oy, x2 + ox, y2 + oy)),
+      b'T' => parser.numbers().map(|[x2, y2]| {
+        let (x1, y1) = last_quad.map_or((x, y), |(cx, cy)| (2.0 * x - cx, 2.0 * y - cy));
+        Segment::QuadTo(x1, y1, x2 + ox, y2 + oy)
+      }),
+      b'A' => parser.arc().map(
+        |(rx, ry, rotation, large_arc, sweep, x1, y1)| Segment::ArcTo {
+          rx,
+          ry,
+          rotation,
+          large_arc,
+          sweep,
+          x: x1 + ox,
+          y: y1 + oy,
+        },
+      ),
+      _ => Some(Segment::Close),
+    };
+    let segment = match segment {
+      Some(segment) => segment,
+      None => break,
+    };
+    last_cubic = None;
+    last_quad = None;
+    match segment {
+      Segment::MoveTo(x1, y1) | Segment::LineTo(x1, y1) | Segment::ArcTo { x: x1, y: y1, .. } => {
+        x = x1;
+        y = y1;
+      }
+      Segment::CubicTo(_, _, x2, y2, x3, y3) => {
+        last_cubic = Some((x2, y2));
+        x = x3;
+        y = y3;
+      }
+      Segment::QuadTo(x1, y1, x2, y2) => {
+        last_quad = Some((x1, y1));
+        x = x2;
+        y = y2;
+      }
+      Segment::Close => {
+        x = start_x;
+        y = start_y;
+      }
+    }
+    segments.push(segment);
+    parser.skip_whitespace();
+    if parser.is_end() {
+      break;
+    }
+    command = match parser.command() {
+      Some(next) => next,
+      // the coordinates after `Z` are invalid
+      None if command == b'Z' || command == b'z' => break,
+      // the repeated coordinates after `M` are the implicit `L`
+      None if command == b'M' => b'L',
+      None if command == b'm' => b'l',
+      None => command,
+    };
+  }
+  segments
+}
+
+struct Parser<'a> {
+  data: &'a str,
+  pos: usize,
+}
+
+impl Parser<'_> {
+  fn peek(&self) -> Option<u8> {
+    self.data.as_bytes().get(self.pos).copied()
+  }
+
+  fn is_end(&self) -> bool {
+    self.pos >= self.data.len()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while let Some(b' ' | b'\t' | b'\n' | b'\r' | b'\x0C') = self.peek() {
+      self.pos += 1;
+    }
+  }
+
+  /// The whitespace and at most one comma between the numbers
+  fn skip_separator(&mut self) {
+    self.skip_whitespace();
+    if self.peek() == Some(b',') {
+      self.pos += 1;
+      self.skip_whitespace();
+    }
+  }
+
+  fn command(&mut self) -> Option<u8> {
+    match self.peek() {
+      Some(
+        command @ (b'M' | b'm' | b'L' | b'l' | b'H' | b'h' | b'V' | b'v' | b'C' | b'c' | b'S'
+        | b's' | b'Q' | b'q' | b'T' | b't' | b'A' | b'a' | b'Z' | b'z'),
+      ) => {
+        self.pos += 1;
+        Some(command)
+      }
+      _ => None,
+    }
+  }
+
+  fn number(&mut self) -> Option<f32> {
+    self.skip_whitespace();
+    let bytes = self.data.as_bytes();
+    let start = self.pos;
+    let mut end = start;
+    if let Some(b'+' | b'-') = bytes.get(end) {
+      end += 1;
+    }
+    let integer_start = end;
+    while bytes.get(end).map_or(false, u8::is_ascii_digit) {
+      end += 1;
+    }
+    let mut digits = end - integer_start;
+    if bytes.get(end) == Some(&b'.') {
+      end += 1;
+      let fraction_start = end;
+      while bytes.get(end).map_or(false, u8::is_ascii_digit) {
+        end += 1;
+      }
+      digits += end - fraction_start;
+    }
+    if digits == 0 {
+      return None;
+    }
+    if let Some(b'e' | b'E') = bytes.get(end) {
+      let mut exponent_end = end + 1;
+      if let Some(b'+' | b'-') = bytes.get(exponent_end) {
+        exponent_end += 1;
+      }
+      let exponent_start = exponent_end;
+      while bytes.get(exponent_end).map_or(false, u8::is_ascii_digit) {
+        exponent_end += 1;
+      }
+      if exponent_end > exponent_start {
+        end = exponent_end;
+      }
+    }
+    let value = self.data[start..end].parse::<f32>().ok()?;
+    self.pos = end;
+    self.skip_separator();
+    value.is_finite().then(|| value)
+  }
+
+  /// The flags are single digits, they don't need the separators like `a1 1 0 00 1 1`
+  fn flag(&mut self) -> Option<bool> {
+    self.skip_whitespace();
+    let flag = match self.peek()? {
+      b'0' => false,
+      b'1' => true,
+      _ => return None,
+    };
+    self.pos += 1;
+    self.skip_separator();
+    Some(flag)
+  }
+
+  fn numbers<const N: usize>(&mut self) -> Option<[f32; N]> {
+    let mut numbers = [0f32; N];
+    for number in numbers.iter_mut() {
+      *number = self.number()?;
+    }
+    Some(numbers)
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn arc(&mut self) -> Option<(f32, f32, f32, bool, bool, f32, f32)> {
+    let [rx, ry, rotation] = self.numbers()?;
+    let large_arc = self.flag()?;
+    let sweep = self.flag()?;
+    let [x, y] = self.numbers()?;
+    Some((rx, ry, rotation, large_arc, sweep, x, y))
+  }
+}
+
+#[test]
+fn test_parse_absolute() {
+  assert_eq!(
+    parse("M10 10 H 90 V 90 Z"),
+    vec![
+      Segment::MoveTo(10.0, 10.0),
+      Segment::LineTo(90.0, 10.0),
+      Segment::LineTo(90.0, 90.0),
+      Segment::Close,
+    ]
+  );
+}
+
+#[test]
+fn test_parse_relative() {
+  assert_eq!(
+    parse("m10,10 l5-5 h10 v10 z m1 1"),
+    vec![
+      Segment::MoveTo(10.0, 10.0),
+      Segment::LineTo(15.0, 5.0),
+      Segment::LineTo(25.0, 5.0),
+      Segment::LineTo(25.0, 15.0),
+      Segment::Close,
+      Segment::MoveTo(11.0, 11.0),
+    ]
+  );
+}
+
+#[test]
+fn test_parse_implicit_commands() {
+  assert_eq!(
+    parse("M0 0 10 0 10 10L0 10 0 20"),
+    vec![
+      Segment::MoveTo(0.0, 0.0),
+      Segment::LineTo(10.0, 0.0),
+      Segment::LineTo(10.0, 10.0),
+      Segment::LineTo(0.0, 10.0),
+      Segment::LineTo(0.0, 20.0),
+    ]
+  );
+}
+
+#[test]
+fn test_parse_numbers() {
+  assert_eq!(
+    parse("M.5.5-1e1+1E+1"),
+    vec![Segment::MoveTo(0.5, 0.5), Segment::LineTo(-10.0, 10.0)]
+  );
+}
+
+#[test]
+fn test_parse_smooth_curves() {
+  assert_eq!(
+    parse("M0 0C0 10 10 10 10 0S20-10 20 0Q25 5 30 0T40 0"),
+    vec![
+      Segment::MoveTo(0.0, 0.0),
+      Segment::CubicTo(0.0, 10.0, 10.0, 10.0, 10.0, 0.0),
+      Segment::CubicTo(10.0, -10.0, 20.0, -10.0, 20.0, 0.0),
+      Segment::QuadTo(25.0, 5.0, 30.0, 0.0),
+      Segment::QuadTo(35.0, -5.0, 40.0, 0.0),
+    ]
+  );
+  // without the previous curve, the control point is the current point
+  assert_eq!(
+    parse("M0 0S10 10 20 0"),
+    vec![
+      Segment::MoveTo(0.0, 0.0),
+      Segment::CubicTo(0.0, 0.0, 10.0, 10.0, 20.0, 0.0),
+    ]
+  );
+}
+
+#[test]
+fn test_parse_arc() {
+  assert_eq!(
+    parse("M0 0a10 10 0 0110 10"),
+    vec![
+      Segment::MoveTo(0.0, 0.0),
+      Segment::ArcTo {
+        rx: 10.0,
+        ry: 10.0,
+        rotation: 0.0,
+        large_arc: false,
+        sweep: true,
+        x: 10.0,
+        y: 10.0,
+      },
+    ]
+  );
+}
+
+#[test]
+fn test_parse_error() {
+  // the segments before the error are kept
+  assert_eq!(
+    parse("M10 10 L20 20 L30"),
+    vec![Segment::MoveTo(10.0, 10.0), Segment::LineTo(20.0, 20.0)]
+  );
+  assert_eq!(
+    parse("M10 10 L20 20 X 30 30"),
+    vec![Segment::MoveTo(10.0, 10.0), Segment::LineTo(20.0, 20.0)]
+  );
+  assert_eq!(
+    parse("M10 10 Z 20 20"),
+    vec![Segment::MoveTo(10.0, 10.0), Segment::Close]
+  );
+  assert_eq!(
+    parse("M0 0 A10 10 0 2 0 10 10"),
+    vec![Segment::MoveTo(0.0, 0.0)]
+  );
+  // the path must start with `M`
+  assert!(parse("L10 10").is_empty());
+  assert!(parse("").is_empty());
+}
@@ -0,0 +1,247 @@
+// Skia doesn't ship a TGA codec, this is a small decoder for the Truevision TGA format.
+// http://www.dca.fbk.eu/sites/default/files/tga_spec.pdf
+
+const HEADER_SIZE: usize = 18;
+/// The header is not trusted, the larger images are rejected before allocating the pixels
+const MAX_PIXELS: usize = 1 << 28;
+/// An RLE packet of 1 + bytes per pixel bytes repeats the pixel at most 128 times
+const MAX_RLE_COUNT: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageType {
+  ColorMapped,
+  TrueColor,
+  Grayscale,
+}
+
+pub struct TgaImage {
+  pub width: u32,
+  pub height: u32,
+  /// Unpremultiplied RGBA pixels
+  pub pixels: Vec<u8>,
+}
+
+/// TGA has no magic number, so the header is validated as strictly as possible
+/// to avoid treating arbitrary binary data as an image.
+pub fn decode(data: &[u8]) -> Option<TgaImage> {
+  if data.len() < HEADER_SIZE {
+    return None;
+  }
+  let id_length = data[0] as usize;
+  let color_map_type = data[1];
+  let (image_type, rle) = match data[2] {
+    1 => (ImageType::ColorMapped, false),
+    2 => (ImageType::TrueColor, false),
+    3 => (ImageType::Grayscale, false),
+    9 => (ImageType::ColorMapped, true),
+    10 => (ImageType::TrueColor, true),
+    11 => (ImageType::Grayscale, true),
+    _ => return None,
+  };
+  if color_map_type > 1 || (image_type == ImageType::ColorMapped) != (color_map_type == 1) {
+    return None;
+  }
+  let color_map_first = u16::from_le_bytes([data[3], data[4]]) as usize;
+  let color_map_length = u16::from_le_bytes([data[5], data[6]]) as usize;
+  let color_map_depth = data[7];
+  let width = u16::from_le_bytes([data[12], data[13]]) as u32;
+  let height = u16::from_le_bytes([data[14], data[15]]) as u32;
+  let pixel_depth = data[16];
+  let descriptor = data[17];
+  if width == 0 || height == 0 || descriptor & 0b1100_0000 != 0 {
+    return None;
+  }
+  let valid_depth = match image_type {
+    ImageType::ColorMapped => matches!(pixel_depth, 8 | 16),
+    ImageType::TrueColor => matches!(pixel_depth, 15 | 16 | 24 | 32),
+    ImageType::Grayscale => matches!(pixel_depth, 8 | 16),
+  };
+  if !valid_depth {
+    return None;
+  }
+  let has_alpha = descriptor & 0b1111 != 0;
+  let right_to_left = descriptor & 0b1_0000 != 0;
+  let top_to_bottom = descriptor & 0b10_0000 != 0;
+
+  let mut offset = HEADER_SIZE + id_length;
+  let mut palette = Vec::new();
+  if color_map_type == 1 {
+    if !matches!(color_map_depth, 15 | 16 | 24 | 32) {
+      return None;
+    }
+    let entry_size = (color_map_depth as usize + 7) / 8;
+    let palette_end = offset + color_map_length * entry_size;
+    let palette_data = data.get(offset..palette_end)?;
+    palette = palette_data
+      .chunks_exact(entry_size)
+      .map(|entry| read_true_color(entry, has_alpha || color_map_depth == 32))
+      .collect();
+    offset = palette_end;
+  }
+
+  let bytes_per_pixel = (pixel_depth as usize + 7) / 8;
+  let pixel_count = width as usize * height as usize;
+  if pixel_count > MAX_PIXELS {
+    return None;
+  }
+  let raw = if rle {
+    decode_rle(data.get(offset..)?, bytes_per_pixel, pixel_count)?
+  } else {
+    data
+      .get(offset..offset + pixel_count * bytes_per_pixel)?
+      .to_vec()
+  };
+
+  let mut pixels = vec![0u8; pixel_count * 4];
+  for (i, source) in raw.chunks_exact(bytes_per_pixel).enumerate() {
+    let rgba = match image_type {
+      ImageType::ColorMapped => {
+        let index = if bytes_per_pixel == 1 {
+          source[0] as usize
+        } else {
+          u16::from_le_bytes([source[0], source[1]]) as usize
+        };
+        *palette.get(index.checked_sub(color_map_first)?)?
+      }
+      ImageType::TrueColor => read_true_color(source, has_alpha),
+      ImageType::Grayscale => {
+        let alpha = if bytes_per_pixel == 2 { source[1] } else { 255 };
+        [source[0], source[0], source[0], alpha]
+      }
+    };
+    let (width, height) = (width as usize, height as usize);
+    let row = i / width;
+    let column = i % width;
+    let x = if right_to_left {
+      width - 1 - column
+    } else {
+      column
+    };
+    let y = if top_to_bottom { row } else { height - 1 - row };
+    let target = (y * width + x) * 4;
+    pixels[target..target + 4].copy_from_slice(&rgba);
+  }
+
+  Some(TgaImage {
+    width,
+    height,
+    pixels,
+  })
+}
+
+fn read_true_color(source: &[u8], has_alpha: bool) -> [u8; 4] {
+  match source.len() {
+    2 => {
+      // A1R5G5B5
+      let value = u16::from_le_bytes([source[0], source[1]]);
+      let expand = |c: u16| ((c << 3) | (c >> 2)) as u8;
+      let alpha = if has_alpha && value & 0x8000 == 0 {
+        0
+      } else {
+        255
+      };
+      [
+        expand((value >> 10) & 0x1f),
+        expand((value >> 5) & 0x1f),
+        expand(value & 0x1f),
+        alpha,
+      ]
+    }
+    3 => [source[2], source[1], source[0], 255],
+    _ => [
+      source[2],
+      source[1],
+      source[0],
+      if has_alpha { source[3] } else { 255 },
+    ],
+  }
+}
+
+fn decode_rle(data: &[u8], bytes_per_pixel: usize, pixel_count: usize) -> Option<Vec<u8>> {
+  let expected = pixel_count * bytes_per_pixel;
+  // the header may claim more pixels than the packets can produce
+  let mut output = Vec::with_capacity(expected.min(data.len() * MAX_RLE_COUNT * bytes_per_pixel));
+  let mut offset = 0;
+  while output.len() < expected {
+    let packet = *data.get(offset)?;
+    offset += 1;
+    let count = (packet & 0x7f) as usize + 1;
+    if packet & 0x80 != 0 {
+      let value = data.get(offset..offset + bytes_per_pixel)?;
+      for _ in 0..count {
+        output.extend_from_slice(value);
+      }
+      offset += bytes_per_pixel;
+    } else {
+      let length = count * bytes_per_pixel;
+      output.extend_from_slice(data.get(offset..offset + length)?);
+      offset += length;
+    }
+  }
+  output.truncate(expected);
+  Some(output)
+}
+
+#[cfg(test)]
+fn header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+  let mut header = vec![0u8; HEADER_SIZE];
+  header[2] = image_type;
+  header[12..14].copy_from_slice(&width.to_le_bytes());
+  header[14..16].copy_from_slice(&height.to_le_bytes());
+  header[16] = depth;
+  header[17] = descriptor;
+  header
+}
+
+#[test]
+fn test_decode_true_color_bottom_up() {
+  let mut data = header(2, 2, 2, 24, 0);
+  // bottom row first, BGR
+  data.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]);
+  let image = decode(&data).unwrap();
+  assert_eq!((image.width, image.height), (2, 2));
+  assert_eq!(
+    image.pixels,
+    vec![
+      255, 0, 0, 255, 255, 255, 255, 255, //
+      0, 0, 255, 255, 0, 255, 0, 255,
+    ]
+  );
+}
+
+#[test]
+fn test_decode_rle_with_alpha() {
+  let mut data = header(10, 3, 1, 32, 0b10_1000);
+  // run of 2 pixels, then a raw packet of 1 pixel
+  data.extend_from_slice(&[0x81, 1, 2, 3, 128, 0x00, 4, 5, 6, 0]);
+  let image = decode(&data).unwrap();
+  assert_eq!(image.pixels, vec![3, 2, 1, 128, 3, 2, 1, 128, 6, 5, 4, 0]);
+}
+
+#[test]
+fn test_decode_color_mapped() {
+  let mut data = header(1, 2, 1, 8, 0b10_0000);
+  data[1] = 1;
+  data[5..7].copy_from_slice(&2u16.to_le_bytes());
+  data[7] = 24;
+  data.extend_from_slice(&[0, 0, 255, 255, 0, 0]);
+  data.extend_from_slice(&[1, 0]);
+  let image = decode(&data).unwrap();
+  assert_eq!(image.pixels, vec![0, 0, 255, 255, 255, 0, 0, 255]);
+}
+
+#[test]
+fn test_reject_invalid() {
+  assert!(decode(b"\x89PNG\r\n\x1a\n0000000000000000").is_none());
+  assert!(decode(&header(2, 0, 1, 24, 0)).is_none());
+  // truncated pixel data
+  assert!(decode(&header(2, 4, 4, 24, 0)).is_none());
+  // larger than the maximum
+  let mut data = header(10, 65535, 65535, 32, 0);
+  data.extend_from_slice(&[0xff, 1, 2, 3, 4]);
+  assert!(decode(&data).is_none());
+  // the RLE packets produce less pixels than the header claims
+  let mut data = header(10, 8192, 8192, 32, 0);
+  data.extend_from_slice(&[0xff, 1, 2, 3, 4]);
+  assert!(decode(&data).is_none());
+}
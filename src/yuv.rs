@@ -0,0 +1,217 @@
+// Convert RGBA pixels to the planar YUV 4:2:0 layouts taken by video encoders.
+// https://www.itu.int/rec/R-REC-BT.601
+// https://www.itu.int/rec/R-REC-BT.709
+
+use std::str::FromStr;
+
+use crate::error::SkError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvFormat {
+  /// Y plane, U plane and V plane, `yuv420p` of ffmpeg
+  I420,
+  /// Y plane and the interleaved UV plane
+  Nv12,
+}
+
+impl Default for YuvFormat {
+  fn default() -> Self {
+    Self::I420
+  }
+}
+
+impl FromStr for YuvFormat {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "i420" => Ok(Self::I420),
+      "nv12" => Ok(Self::Nv12),
+      _ => Err(SkError::Generic(format!("Invalid yuv format: {}", value))),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvMatrix {
+  Bt601,
+  Bt709,
+}
+
+impl Default for YuvMatrix {
+  fn default() -> Self {
+    Self::Bt601
+  }
+}
+
+impl FromStr for YuvMatrix {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "bt601" => Ok(Self::Bt601),
+      "bt709" => Ok(Self::Bt709),
+      _ => Err(SkError::Generic(format!("Invalid yuv matrix: {}", value))),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct YuvOptions {
+  pub format: YuvFormat,
+  pub matrix: YuvMatrix,
+  /// 0 - 255 instead of 16 - 235 for luma and 16 - 240 for chroma
+  pub full_range: bool,
+}
+
+struct Coefficients {
+  kr: f32,
+  kb: f32,
+  y_scale: f32,
+  y_offset: f32,
+  c_scale: f32,
+}
+
+impl Coefficients {
+  fn new(options: &YuvOptions) -> Self {
+    let (kr, kb) = match options.matrix {
+      YuvMatrix::Bt601 => (0.299, 0.114),
+      YuvMatrix::Bt709 => (0.2126, 0.0722),
+    };
+    let (y_scale, y_offset, c_scale) = if options.full_range {
+      (255.0, 0.0, 255.0)
+    } else {
+      (219.0, 16.0, 224.0)
+    };
+    Self {
+      kr,
+      kb,
+      y_scale,
+      y_offset,
+      c_scale,
+    }
+  }
+
+  /// Luma of the color components in 0 - 1
+  fn luma(&self, r: f32, g: f32, b: f32) -> f32 {
+    self.kr * r + (1.0 - self.kr - self.kb) * g + self.kb * b
+  }
+
+  fn y(&self, r: f32, g: f32, b: f32) -> u8 {
+    (self.y_offset + self.y_scale * self.luma(r, g, b))
+      .round()
+      .clamp(0.0, 255.0) as u8
+  }
+
+  fn uv(&self, r: f32, g: f32, b: f32) -> (u8, u8) {
+    let luma = self.luma(r, g, b);
+    let u = (b - luma) / (2.0 * (1.0 - self.kb));
+    let v = (r - luma) / (2.0 * (1.0 - self.kr));
+    let quantize = |c: f32| (128.0 + self.c_scale * c).round().clamp(0.0, 255.0) as u8;
+    (quantize(u), quantize(v))
+  }
+}
+
+/// The size of the chroma planes, odd sizes are rounded up
+fn chroma_size(width: u32, height: u32) -> (u32, u32) {
+  ((width + 1) / 2, (height + 1) / 2)
+}
+
+/// `pixels` are RGBA with premultiplied alpha, the transparent pixels become black.
+/// Every chroma sample is the average of the 2x2 block of pixels
+pub fn rgba_to_yuv(pixels: &[u8], width: u32, height: u32, options: &YuvOptions) -> Vec<u8> {
+  let coefficients = Coefficients::new(options);
+  let (w, h) = (width as usize, height as usize);
+  let (chroma_width, chroma_height) = chroma_size(width, height);
+  let (cw, ch) = (chroma_width as usize, chroma_height as usize);
+  let mut output = vec![0u8; w * h + cw * ch * 2];
+  let (luma_plane, chroma_planes) = output.split_at_mut(w * h);
+
+  let component = |index: usize| pixels[index] as f32 / 255.0;
+  for (i, y) in luma_plane.iter_mut().enumerate() {
+    *y = coefficients.y(component(i * 4), component(i * 4 + 1), component(i * 4 + 2));
+  }
+
+  for cy in 0..ch {
+    for cx in 0..cw {
+      let mut sum = [0f32; 3];
+      let mut count = 0.0;
+      for y in (cy * 2)..(cy * 2 + 2).min(h) {
+        for x in (cx * 2)..(cx * 2 + 2).min(w) {
+          let offset = (y * w + x) * 4;
+          for (c, s) in sum.iter_mut().enumerate() {
+            *s += component(offset + c);
+          }
+          count += 1.0;
+        }
+      }
+      let (u, v) = coefficients.uv(sum[0] / count, sum[1] / count, sum[2] / count);
+      let index = cy * cw + cx;
+      match options.format {
+        YuvFormat::I420 => {
+          chroma_planes[index] = u;
+          chroma_planes[cw * ch + index] = v;
+        }
+        YuvFormat::Nv12 => {
+          chroma_planes[index * 2] = u;
+          chroma_planes[index * 2 + 1] = v;
+        }
+      }
+    }
+  }
+  output
+}
+
+#[test]
+fn test_limited_range_bt601() {
+  // white, black, red and green
+  let pixels = [
+    255u8, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255, 0, 255, 0, 255,
+  ];
+  let yuv = rgba_to_yuv(&pixels, 4, 1, &YuvOptions::default());
+  assert_eq!(&yuv[..4], &[235, 16, 81, 145]);
+  // the chroma of white and black averages to gray
+  assert_eq!(yuv.len(), 4 + 2 * 2);
+  assert_eq!(yuv[4], 128);
+  assert_eq!(yuv[6], 128);
+  let red = rgba_to_yuv(&[255, 0, 0, 255], 1, 1, &YuvOptions::default());
+  assert_eq!(red, vec![81, 90, 240]);
+}
+
+#[test]
+fn test_full_range_bt709() {
+  let options = YuvOptions {
+    matrix: YuvMatrix::Bt709,
+    full_range: true,
+    ..Default::default()
+  };
+  assert_eq!(rgba_to_yuv(&[255; 4], 1, 1, &options), vec![255, 128, 128]);
+  assert_eq!(
+    rgba_to_yuv(&[0, 0, 255, 255], 1, 1, &options),
+    vec![18, 255, 116]
+  );
+}
+
+#[test]
+fn test_nv12_layout() {
+  // 3x3 with the chroma planes rounded up to 2x2
+  let mut pixels = [0u8, 0, 0, 255].repeat(9);
+  // the bottom right pixel is red
+  pixels[32] = 255;
+  let i420 = rgba_to_yuv(&pixels, 3, 3, &YuvOptions::default());
+  assert_eq!(i420.len(), 9 + 4 + 4);
+  assert_eq!(&i420[9..13], &[128, 128, 128, 90]);
+  assert_eq!(&i420[13..17], &[128, 128, 128, 240]);
+  let nv12 = rgba_to_yuv(
+    &pixels,
+    3,
+    3,
+    &YuvOptions {
+      format: YuvFormat::Nv12,
+      ..Default::default()
+    },
+  );
+  assert_eq!(&nv12[..9], &i420[..9]);
+  assert_eq!(&nv12[9..], &[128, 128, 128, 128, 128, 128, 90, 240]);
+  assert!(YuvFormat::from_str("yv12").is_err());
+}